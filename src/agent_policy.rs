@@ -3,8 +3,12 @@ use crate::grok_client::SearchMode;
 use crate::protocol::ChatMessage;
 use std::path::Path;
 
-pub(crate) fn build_system_prompt(cwd: &Path) -> String {
-    let custom = load_custom_instructions(cwd)
+pub(crate) fn build_system_prompt(
+    cwd: &Path,
+    repo_map: Option<&str>,
+    project_instructions: bool,
+) -> String {
+    let custom = load_custom_instructions(cwd, project_instructions)
         .map(|instructions| {
             format!(
                 "\n\nCUSTOM INSTRUCTIONS:\n{}\n\nFollow the custom instructions above while respecting the tool safety constraints below.\n",
@@ -12,25 +16,43 @@ pub(crate) fn build_system_prompt(cwd: &Path) -> String {
             )
         })
         .unwrap_or_default();
+    let repo_map = repo_map
+        .map(|map| {
+            format!(
+                "\nREPOSITORY MAP (most referenced files and their definitions with start lines; it can lag behind edits, so confirm with view_file):\n{map}\n"
+            )
+        })
+        .unwrap_or_default();
 
     format!(
         "You are Grok CLI, an AI coding assistant in a terminal environment.{custom}
 You can use these tools:
-- view_file: Read file contents or list directories.
-- create_file: Create a new file.
+- view_file: Read file contents page by page, or list directories (recursive: true for a tree).
+- create_file: Create a new file (pass overwrite: true to replace an existing one).
 - str_replace_editor: Replace text in an existing file.
+- delete_file / move_file / copy_file: Delete, move/rename or copy files inside the project.
 - bash: Run shell commands.
+- bash_background / read_process_output / list_processes / kill_process: Run and manage long-lived commands such as dev servers and watchers.
 - search: Find text and files.
+- code_outline: List definitions in a file or directory with line ranges.
+- find_definition: Locate where a symbol is defined.
+- goto_definition / find_references / hover: Ask the language server about the symbol at a file position.
 - create_todo_list: Create a todo checklist.
 - update_todo_list: Update todo checklist items.
 
 Important behavior:
 - Use view_file before editing when practical.
 - Use str_replace_editor for existing files instead of create_file.
+- Use delete_file, move_file and copy_file instead of rm, mv and cp in bash.
 - Keep responses concise and directly tied to the task.
 - Use bash for file discovery and command execution when useful.
+- Use bash_background for servers and watchers that never exit, and kill them when done.
 - Use search for broad text or file discovery across the workspace.
-
+- Check the repository map below before exploring; it may already point at the right file.
+- Use code_outline or find_definition to locate code, then view_file with the reported start_line/end_line.
+- Fix diagnostics reported after an edit before moving on.
+- Paths written as @path in user messages refer to files relative to the current working directory.
+{repo_map}
 Current working directory: {}",
         cwd.display()
     )
@@ -104,7 +126,7 @@ fn estimate_chars_tokens(char_count: usize) -> usize {
 
 #[cfg(test)]
 mod tests {
-    use super::search_mode_for;
+    use super::{build_system_prompt, search_mode_for};
     use crate::grok_client::SearchMode;
 
     #[test]
@@ -119,6 +141,17 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn system_prompt_includes_repo_map_when_available() {
+        let cwd = std::env::temp_dir().join("codetoad-no-such-project");
+        let with_map = build_system_prompt(&cwd, Some("src/lib.rs:\n  fn run (3)"), true);
+        assert!(with_map.contains("REPOSITORY MAP"));
+        assert!(with_map.contains("src/lib.rs:\n  fn run (3)\n\nCurrent working directory:"));
+
+        let without_map = build_system_prompt(&cwd, None, true);
+        assert!(!without_map.contains("REPOSITORY MAP"));
+    }
+
     #[test]
     fn search_mode_is_off_for_regular_code_prompt() {
         assert!(matches!(
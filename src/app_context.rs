@@ -1,4 +1,5 @@
 use crate::agent::Agent;
+use crate::permissions::PermissionMode;
 use crate::session_store::save_session;
 use crate::settings::SettingsManager;
 use anyhow::Result;
@@ -9,7 +10,7 @@ use tokio::sync::Mutex;
 
 #[derive(Debug, Clone, Copy, Default)]
 pub struct RuntimeFlags {
-    pub auto_edit: bool,
+    pub permission_mode: PermissionMode,
 }
 
 #[derive(Clone)]
@@ -23,11 +24,14 @@ pub struct AppContext {
 
 impl AppContext {
     pub fn new(cwd: PathBuf, agent: Agent, settings: SettingsManager) -> Self {
+        let runtime_flags = RuntimeFlags {
+            permission_mode: agent.permission_mode(),
+        };
         Self {
             cwd,
             agent: Arc::new(Mutex::new(agent)),
             settings: Arc::new(Mutex::new(settings)),
-            runtime_flags: Arc::new(Mutex::new(RuntimeFlags::default())),
+            runtime_flags: Arc::new(Mutex::new(runtime_flags)),
             active_session_name: Arc::new(Mutex::new(None)),
         }
     }
@@ -44,22 +48,22 @@ impl AppContext {
         self.settings.clone()
     }
 
-    pub async fn auto_edit_enabled(&self) -> bool {
-        self.runtime_flags.lock().await.auto_edit
+    pub async fn permission_mode(&self) -> PermissionMode {
+        self.runtime_flags.lock().await.permission_mode
     }
 
-    pub async fn set_auto_edit_enabled(&self, enabled: bool) {
+    pub async fn set_permission_mode(&self, mode: PermissionMode) {
         {
             let mut flags = self.runtime_flags.lock().await;
-            flags.auto_edit = enabled;
+            flags.permission_mode = mode;
         }
-        self.agent.lock().await.set_auto_edit_enabled(enabled);
+        self.agent.lock().await.set_permission_mode(mode);
     }
 
-    pub async fn sync_auto_edit_from_agent(&self) {
-        let enabled = self.agent.lock().await.auto_edit_enabled();
+    pub async fn sync_permission_mode_from_agent(&self) {
+        let mode = self.agent.lock().await.permission_mode();
         let mut flags = self.runtime_flags.lock().await;
-        flags.auto_edit = enabled;
+        flags.permission_mode = mode;
     }
 
     pub async fn active_session_name(&self) -> Option<String> {
@@ -97,6 +101,7 @@ fn default_session_name() -> String {
 mod tests {
     use super::{AppContext, default_session_name};
     use crate::agent::Agent;
+    use crate::permissions::PermissionMode;
     use crate::settings::SettingsManager;
     use std::fs;
     use std::path::{Path, PathBuf};
@@ -110,8 +115,8 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn set_auto_edit_enabled_updates_runtime_and_agent_flags() {
-        let temp = TempDir::new("app-context-auto-edit");
+    async fn set_permission_mode_updates_runtime_and_agent_flags() {
+        let temp = TempDir::new("app-context-permission-mode");
         let settings = SettingsManager::load_with_home(temp.path(), temp.path()).expect("settings");
         let agent = Agent::new(
             "test-key".to_string(),
@@ -123,13 +128,21 @@ mod tests {
         .expect("agent");
         let app = AppContext::new(temp.path().to_path_buf(), agent, settings);
 
-        app.set_auto_edit_enabled(true).await;
-        assert!(app.auto_edit_enabled().await);
-        assert!(app.agent().lock().await.auto_edit_enabled());
-
-        app.set_auto_edit_enabled(false).await;
-        assert!(!app.auto_edit_enabled().await);
-        assert!(!app.agent().lock().await.auto_edit_enabled());
+        assert_eq!(app.permission_mode().await, PermissionMode::Default);
+
+        app.set_permission_mode(PermissionMode::AcceptEdits).await;
+        assert_eq!(app.permission_mode().await, PermissionMode::AcceptEdits);
+        assert_eq!(
+            app.agent().lock().await.permission_mode(),
+            PermissionMode::AcceptEdits
+        );
+
+        app.agent()
+            .lock()
+            .await
+            .set_permission_mode(PermissionMode::Plan);
+        app.sync_permission_mode_from_agent().await;
+        assert_eq!(app.permission_mode().await, PermissionMode::Plan);
     }
 
     #[tokio::test]
@@ -0,0 +1,476 @@
+use crate::permissions::{PermissionDecision, PermissionMode};
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub const DEFAULT_AUDIT_DIR: &str = ".grok/audit";
+const AUDIT_FILE_EXTENSION: &str = "jsonl";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuditConfig {
+    pub enabled: bool,
+    pub dir: PathBuf,
+}
+
+/// How a tool call got to run, or not.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditDecision {
+    /// Ran without asking.
+    Allowed,
+    /// Ran after the user confirmed it.
+    Approved,
+    /// Refused by a rule or the permission mode.
+    Denied,
+    /// Refused by the user at the confirmation prompt.
+    Rejected,
+}
+
+impl AuditDecision {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Allowed => "allowed",
+            Self::Approved => "approved",
+            Self::Denied => "denied",
+            Self::Rejected => "rejected",
+        }
+    }
+}
+
+/// Who or what made the decision.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Approver {
+    User,
+    Rule {
+        source: String,
+        rule: String,
+    },
+    Mode {
+        mode: PermissionMode,
+    },
+    /// The tool's default, e.g. reads and read-only commands.
+    Default,
+    /// Nobody was there to ask, as in headless runs.
+    Unattended,
+}
+
+impl Approver {
+    pub fn from_decision(decision: &PermissionDecision) -> Self {
+        match (decision.mode, &decision.rule) {
+            (Some(mode), _) => Self::Mode { mode },
+            (None, Some((source, rule))) => Self::Rule {
+                source: source.as_str().to_string(),
+                rule: rule.clone(),
+            },
+            (None, None) => Self::Default,
+        }
+    }
+
+    pub fn label(&self) -> String {
+        match self {
+            Self::User => "user".to_string(),
+            Self::Rule { source, rule } => format!("rule {rule} ({source})"),
+            Self::Mode { mode } => format!("mode {}", mode.as_str()),
+            Self::Default => "default".to_string(),
+            Self::Unattended => "unattended".to_string(),
+        }
+    }
+}
+
+/// One tool call as the agent saw it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolCallRecord {
+    pub tool_call_id: String,
+    pub tool: String,
+    pub arguments: Value,
+    pub decision: AuditDecision,
+    pub approver: Approver,
+    pub success: bool,
+    pub duration_ms: u64,
+    /// Secrets masked in the output sent to the model.
+    pub redactions: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub timestamp: String,
+    pub timestamp_ms: u64,
+    pub session: String,
+    pub model: String,
+    #[serde(flatten)]
+    pub call: ToolCallRecord,
+}
+
+/// Appends one JSON line per tool call to `<dir>/<session>.jsonl`.
+#[derive(Debug, Clone)]
+pub struct AuditLog {
+    dir: PathBuf,
+    session: String,
+}
+
+impl AuditLog {
+    pub fn new(dir: PathBuf) -> Self {
+        Self {
+            dir,
+            session: format!("session-{}", epoch_millis()),
+        }
+    }
+
+    pub fn session(&self) -> &str {
+        &self.session
+    }
+
+    pub fn record(&self, model: &str, call: ToolCallRecord) -> Result<()> {
+        let timestamp_ms = epoch_millis();
+        let entry = AuditEntry {
+            timestamp: format_timestamp(timestamp_ms),
+            timestamp_ms,
+            session: self.session.clone(),
+            model: model.to_string(),
+            call,
+        };
+        let mut line = serde_json::to_string(&entry).context("Failed encoding audit entry")?;
+        line.push('\n');
+        std::fs::create_dir_all(&self.dir)
+            .with_context(|| format!("Failed creating audit dir {}", self.dir.display()))?;
+        let path = self
+            .dir
+            .join(format!("{}.{AUDIT_FILE_EXTENSION}", self.session));
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .and_then(|mut file| file.write_all(line.as_bytes()))
+            .with_context(|| format!("Failed writing audit log {}", path.display()))
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct AuditQuery {
+    pub since_ms: Option<u64>,
+    pub until_ms: Option<u64>,
+    pub tool: Option<String>,
+    pub session: Option<String>,
+}
+
+impl AuditQuery {
+    fn matches(&self, entry: &AuditEntry) -> bool {
+        self.since_ms
+            .is_none_or(|since| entry.timestamp_ms >= since)
+            && self.until_ms.is_none_or(|until| entry.timestamp_ms < until)
+            && self
+                .tool
+                .as_deref()
+                .is_none_or(|tool| entry.call.tool == tool)
+            && self
+                .session
+                .as_deref()
+                .is_none_or(|session| entry.session == session)
+    }
+}
+
+/// Entries from every log in `dir` that match `query`, oldest first. Lines
+/// that do not parse are skipped.
+pub fn read_entries(dir: &Path, query: &AuditQuery) -> Result<Vec<AuditEntry>> {
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    let mut files = std::fs::read_dir(dir)
+        .with_context(|| format!("Failed reading {}", dir.display()))?
+        .map(|file| file.map(|file| file.path()))
+        .collect::<std::io::Result<Vec<_>>>()?;
+    // Entries written in the same millisecond keep their file order.
+    files.sort();
+    let mut entries = Vec::new();
+    for path in files {
+        if path.extension().and_then(|ext| ext.to_str()) != Some(AUDIT_FILE_EXTENSION) {
+            continue;
+        }
+        let content = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed reading {}", path.display()))?;
+        entries.extend(
+            content
+                .lines()
+                .filter_map(|line| serde_json::from_str::<AuditEntry>(line).ok())
+                .filter(|entry| query.matches(entry)),
+        );
+    }
+    entries.sort_by_key(|entry| entry.timestamp_ms);
+    Ok(entries)
+}
+
+/// One aligned line per entry, arguments shortened.
+pub fn format_entry(entry: &AuditEntry) -> String {
+    let mut arguments = entry.call.arguments.to_string();
+    if let Some((cut, _)) = arguments.char_indices().nth(120) {
+        arguments.truncate(cut);
+        arguments.push_str("...");
+    }
+    format!(
+        "{}  {}  {}  {} by {}  {}  {}ms  {}  {}",
+        entry.timestamp.get(..19).unwrap_or(&entry.timestamp),
+        entry.session,
+        entry.call.tool,
+        entry.call.decision.as_str(),
+        entry.call.approver.label(),
+        if entry.call.success { "ok" } else { "failed" },
+        entry.call.duration_ms,
+        entry.model,
+        arguments
+    )
+}
+
+/// Accepts epoch milliseconds, `YYYY-MM-DD`, `YYYY-MM-DDTHH:MM[:SS]` (UTC) or
+/// a duration before now such as `90s`, `30m`, `2h` or `7d`.
+pub fn parse_time(value: &str) -> Result<u64> {
+    let value = value.trim();
+    if let Ok(millis) = value.parse::<u64>() {
+        return Ok(millis);
+    }
+    if let Some(unit) = value.chars().last()
+        && let Some(seconds) = match unit {
+            's' => Some(1),
+            'm' => Some(60),
+            'h' => Some(3600),
+            'd' => Some(86_400),
+            _ => None,
+        }
+        && let Ok(amount) = value[..value.len() - 1].parse::<u64>()
+    {
+        return Ok(epoch_millis().saturating_sub(amount * seconds * 1000));
+    }
+    parse_utc_datetime(value).with_context(|| {
+        format!("Invalid time `{value}`: expected YYYY-MM-DD[THH:MM[:SS]], epoch ms or 30m/2h/7d")
+    })
+}
+
+fn parse_utc_datetime(value: &str) -> Result<u64> {
+    let (date, time) = value.split_once(['T', ' ']).unwrap_or((value, "00:00:00"));
+    let date_parts = date
+        .split('-')
+        .map(str::parse::<u32>)
+        .collect::<Result<Vec<_>, _>>()?;
+    let time_parts = time
+        .trim_end_matches('Z')
+        .split(':')
+        .map(str::parse::<u32>)
+        .collect::<Result<Vec<_>, _>>()?;
+    let ([year, month, day], [hour, minute, rest @ ..]) =
+        (date_parts.as_slice(), time_parts.as_slice())
+    else {
+        bail!("malformed date or time");
+    };
+    let second = rest.first().copied().unwrap_or(0);
+    if !(1..=12).contains(month)
+        || !(1..=31).contains(day)
+        || *hour > 23
+        || *minute > 59
+        || second > 59
+    {
+        bail!("date or time out of range");
+    }
+    let days = days_from_civil(*year as i64, *month, *day);
+    let seconds = days * 86_400 + (*hour as i64) * 3600 + (*minute as i64) * 60 + second as i64;
+    u64::try_from(seconds * 1000).context("dates before 1970 are not supported")
+}
+
+fn format_timestamp(millis: u64) -> String {
+    let seconds = millis / 1000;
+    let (year, month, day) = civil_from_days((seconds / 86_400) as i64);
+    let of_day = seconds % 86_400;
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}.{:03}Z",
+        of_day / 3600,
+        of_day % 3600 / 60,
+        of_day % 60,
+        millis % 1000
+    )
+}
+
+// Howard Hinnant's algorithms for the proleptic Gregorian calendar.
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month = month as i64;
+    let day_of_year = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+fn epoch_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        Approver, AuditDecision, AuditLog, AuditQuery, ToolCallRecord, format_entry,
+        format_timestamp, parse_time, read_entries,
+    };
+    use crate::permissions::PermissionMode;
+    use serde_json::json;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    fn record(tool: &str, decision: AuditDecision, approver: Approver) -> ToolCallRecord {
+        ToolCallRecord {
+            tool_call_id: "call_1".to_string(),
+            tool: tool.to_string(),
+            arguments: json!({ "command": "cargo test" }),
+            decision,
+            approver,
+            success: decision == AuditDecision::Allowed,
+            duration_ms: 42,
+            redactions: 0,
+        }
+    }
+
+    #[test]
+    fn records_and_filters_entries_across_sessions() {
+        let temp = TempDir::new("audit-log");
+        let first = AuditLog::new(temp.path().join("audit"));
+        first
+            .record(
+                "grok-code-fast-1",
+                record("bash", AuditDecision::Allowed, Approver::Unattended),
+            )
+            .expect("record");
+        first
+            .record(
+                "grok-code-fast-1",
+                record(
+                    "bash",
+                    AuditDecision::Denied,
+                    Approver::Mode {
+                        mode: PermissionMode::Plan,
+                    },
+                ),
+            )
+            .expect("record");
+        let second = AuditLog {
+            dir: temp.path().join("audit"),
+            session: "session-other".to_string(),
+        };
+        second
+            .record(
+                "grok-4",
+                record("view_file", AuditDecision::Approved, Approver::User),
+            )
+            .expect("record");
+        fs::write(temp.path().join("audit/notes.txt"), "ignored").expect("write");
+
+        let dir = temp.path().join("audit");
+        let all = read_entries(&dir, &AuditQuery::default()).expect("read");
+        assert_eq!(all.len(), 3);
+        assert_eq!(all[0].call.approver, Approver::Unattended);
+
+        let bash = read_entries(
+            &dir,
+            &AuditQuery {
+                tool: Some("bash".to_string()),
+                session: Some(first.session().to_string()),
+                ..AuditQuery::default()
+            },
+        )
+        .expect("read");
+        assert_eq!(bash.len(), 2);
+        assert!(format_entry(&bash[1]).contains("bash  denied by mode plan  failed  42ms"));
+
+        let future = read_entries(
+            &dir,
+            &AuditQuery {
+                since_ms: Some(all[2].timestamp_ms + 1),
+                ..AuditQuery::default()
+            },
+        )
+        .expect("read");
+        assert!(future.is_empty());
+        assert!(
+            read_entries(&temp.path().join("missing"), &AuditQuery::default())
+                .expect("read")
+                .is_empty()
+        );
+
+        let line = fs::read_to_string(dir.join("session-other.jsonl")).expect("read log");
+        let value: serde_json::Value = serde_json::from_str(line.trim()).expect("json");
+        assert_eq!(value["approver"], json!({ "kind": "user" }));
+        assert_eq!(value["decision"], "approved");
+        assert_eq!(value["model"], "grok-4");
+    }
+
+    #[test]
+    fn parses_and_formats_times() {
+        assert_eq!(
+            parse_time("1700000000000").expect("epoch"),
+            1_700_000_000_000
+        );
+        assert_eq!(parse_time("2024-02-29").expect("date"), 1_709_164_800_000);
+        assert_eq!(
+            parse_time("2024-02-29T13:45").expect("datetime"),
+            1_709_214_300_000
+        );
+        assert_eq!(
+            parse_time("1970-01-02 00:00:01Z").expect("datetime"),
+            86_401_000
+        );
+        assert!(parse_time("2h").expect("relative") < parse_time("1h").expect("relative"));
+        assert!(parse_time("yesterday").is_err());
+        assert!(parse_time("2024-13-01").is_err());
+        assert_eq!(
+            format_timestamp(1_709_214_300_123),
+            "2024-02-29T13:45:00.123Z"
+        );
+        assert_eq!(format_timestamp(0), "1970-01-01T00:00:00.000Z");
+    }
+
+    struct TempDir {
+        path: PathBuf,
+    }
+
+    impl TempDir {
+        fn new(prefix: &str) -> Self {
+            let nonce = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .expect("clock should be after epoch")
+                .as_nanos();
+            let pid = std::process::id();
+            let path = std::env::temp_dir().join(format!("codetoad-{prefix}-{pid}-{nonce}"));
+            fs::create_dir_all(&path).expect("create temp dir");
+            Self { path }
+        }
+
+        fn path(&self) -> &Path {
+            &self.path
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.path);
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use clap::{Parser, Subcommand, ValueEnum};
+use clap::{Args, Parser, Subcommand, ValueEnum};
 use std::path::PathBuf;
 
 #[derive(Debug, Parser)]
@@ -29,6 +29,9 @@ pub struct Cli {
     #[arg(long = "max-tool-rounds", default_value_t = 400, global = true)]
     pub max_tool_rounds: usize,
 
+    #[arg(long = "permission-mode", value_enum, global = true)]
+    pub permission_mode: Option<PermissionModeArg>,
+
     #[command(subcommand)]
     pub command: Option<Commands>,
 
@@ -42,6 +45,42 @@ pub enum Commands {
         #[command(subcommand)]
         command: GitCommands,
     },
+    /// Show logged tool calls, oldest first.
+    Audit(AuditArgs),
+    /// Trust the current directory, or change a saved trust decision.
+    Trust(TrustArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct TrustArgs {
+    /// Mark the directory as untrusted instead.
+    #[arg(long = "deny", conflicts_with = "forget")]
+    pub deny: bool,
+
+    /// Forget the saved decision so the next launch asks again.
+    #[arg(long = "forget")]
+    pub forget: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct AuditArgs {
+    /// Start of the range: YYYY-MM-DD[THH:MM[:SS]] (UTC), epoch ms, or a duration ago such as 2h or 7d.
+    #[arg(long = "since")]
+    pub since: Option<String>,
+
+    /// End of the range (exclusive), in the same formats as --since.
+    #[arg(long = "until")]
+    pub until: Option<String>,
+
+    #[arg(long = "tool")]
+    pub tool: Option<String>,
+
+    #[arg(long = "session")]
+    pub session: Option<String>,
+
+    /// Print matching entries as JSON lines.
+    #[arg(long = "json")]
+    pub json: bool,
 }
 
 #[derive(Debug, Subcommand)]
@@ -55,9 +94,18 @@ pub enum ApiKeyStorageArg {
     Plaintext,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+#[value(rename_all = "snake_case")]
+pub enum PermissionModeArg {
+    Default,
+    AcceptEdits,
+    Plan,
+    Bypass,
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{ApiKeyStorageArg, Cli, Commands, GitCommands};
+    use super::{ApiKeyStorageArg, Cli, Commands, GitCommands, PermissionModeArg};
     use clap::Parser;
 
     #[test]
@@ -101,6 +149,32 @@ mod tests {
         }
     }
 
+    #[test]
+    fn parses_audit_subcommand_filters() {
+        let cli = Cli::parse_from([
+            "codetoad",
+            "audit",
+            "--since",
+            "2h",
+            "--tool",
+            "bash",
+            "--session",
+            "session-1",
+            "--json",
+        ]);
+
+        match cli.command {
+            Some(Commands::Audit(args)) => {
+                assert_eq!(args.since.as_deref(), Some("2h"));
+                assert_eq!(args.until, None);
+                assert_eq!(args.tool.as_deref(), Some("bash"));
+                assert_eq!(args.session.as_deref(), Some("session-1"));
+                assert!(args.json);
+            }
+            _ => panic!("expected audit command"),
+        }
+    }
+
     #[test]
     fn parses_api_key_storage_value_enum() {
         let keychain = Cli::parse_from(["codetoad", "--api-key-storage", "keychain"]);
@@ -115,4 +189,12 @@ mod tests {
             Some(ApiKeyStorageArg::Plaintext)
         ));
     }
+
+    #[test]
+    fn parses_permission_mode_value_enum() {
+        let cli = Cli::parse_from(["codetoad", "--permission-mode", "accept_edits"]);
+        assert_eq!(cli.permission_mode, Some(PermissionModeArg::AcceptEdits));
+        assert!(Cli::try_parse_from(["codetoad", "--permission-mode", "yolo"]).is_err());
+        assert_eq!(Cli::parse_from(["codetoad"]).permission_mode, None);
+    }
 }
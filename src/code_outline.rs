@@ -0,0 +1,439 @@
+use anyhow::{Context, Result, anyhow};
+use std::path::Path;
+use tree_sitter::{Node, Parser};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SourceLanguage {
+    Rust,
+    TypeScript,
+    Tsx,
+    JavaScript,
+    Python,
+    Go,
+}
+
+impl SourceLanguage {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "rs" => Some(Self::Rust),
+            "ts" | "mts" | "cts" => Some(Self::TypeScript),
+            "tsx" => Some(Self::Tsx),
+            "js" | "jsx" | "mjs" | "cjs" => Some(Self::JavaScript),
+            "py" | "pyi" => Some(Self::Python),
+            "go" => Some(Self::Go),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Rust => "rust",
+            Self::TypeScript => "typescript",
+            Self::Tsx => "tsx",
+            Self::JavaScript => "javascript",
+            Self::Python => "python",
+            Self::Go => "go",
+        }
+    }
+
+    fn grammar(self) -> tree_sitter::Language {
+        match self {
+            Self::Rust => tree_sitter_rust::LANGUAGE.into(),
+            Self::TypeScript => tree_sitter_typescript::LANGUAGE_TYPESCRIPT.into(),
+            Self::Tsx => tree_sitter_typescript::LANGUAGE_TSX.into(),
+            Self::JavaScript => tree_sitter_javascript::LANGUAGE.into(),
+            Self::Python => tree_sitter_python::LANGUAGE.into(),
+            Self::Go => tree_sitter_go::LANGUAGE.into(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymbolKind {
+    Function,
+    Method,
+    Struct,
+    Enum,
+    Trait,
+    Impl,
+    Class,
+    Interface,
+    Type,
+    Module,
+    Constant,
+    Macro,
+}
+
+impl SymbolKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Function => "fn",
+            Self::Method => "method",
+            Self::Struct => "struct",
+            Self::Enum => "enum",
+            Self::Trait => "trait",
+            Self::Impl => "impl",
+            Self::Class => "class",
+            Self::Interface => "interface",
+            Self::Type => "type",
+            Self::Module => "mod",
+            Self::Constant => "const",
+            Self::Macro => "macro",
+        }
+    }
+
+    fn is_container(self) -> bool {
+        matches!(
+            self,
+            Self::Impl | Self::Trait | Self::Class | Self::Interface | Self::Module
+        )
+    }
+}
+
+/// A definition found in a source file. Lines are 1-based and inclusive.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Symbol {
+    pub name: String,
+    pub kind: SymbolKind,
+    pub start_line: usize,
+    pub end_line: usize,
+    /// Nesting level: methods inside an `impl` or class have depth 1.
+    pub depth: usize,
+    /// Type or module the definition belongs to, e.g. the `Foo` of `impl Foo`.
+    pub container: Option<String>,
+}
+
+impl Symbol {
+    /// Go methods are declared at the top level, so their receiver is shown as a qualifier.
+    pub fn display_name(&self) -> String {
+        match (&self.kind, &self.container) {
+            (SymbolKind::Method | SymbolKind::Function, Some(container)) if self.depth == 0 => {
+                format!("{container}.{}", self.name)
+            }
+            _ => self.name.clone(),
+        }
+    }
+}
+
+pub struct OutlineParser {
+    parser: Parser,
+}
+
+impl Default for OutlineParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl OutlineParser {
+    pub fn new() -> Self {
+        Self {
+            parser: Parser::new(),
+        }
+    }
+
+    pub fn outline(&mut self, language: SourceLanguage, source: &str) -> Result<Vec<Symbol>> {
+        self.parser
+            .set_language(&language.grammar())
+            .with_context(|| format!("Failed loading the {} grammar", language.name()))?;
+        let tree = self
+            .parser
+            .parse(source, None)
+            .ok_or_else(|| anyhow!("Failed parsing {} source", language.name()))?;
+
+        let mut symbols = Vec::new();
+        collect_symbols(
+            tree.root_node(),
+            source.as_bytes(),
+            language,
+            0,
+            None,
+            &mut symbols,
+        );
+        Ok(symbols)
+    }
+}
+
+struct Definition {
+    kind: SymbolKind,
+    name: String,
+    container: Option<String>,
+    /// Name nested definitions are grouped under (the type of an `impl`).
+    scope: Option<String>,
+}
+
+fn collect_symbols(
+    node: Node<'_>,
+    source: &[u8],
+    language: SourceLanguage,
+    depth: usize,
+    container: Option<&str>,
+    out: &mut Vec<Symbol>,
+) {
+    let mut cursor = node.walk();
+    for child in node.named_children(&mut cursor) {
+        let Some(definition) = classify(child, source, language, container) else {
+            collect_symbols(child, source, language, depth, container, out);
+            continue;
+        };
+        let kind = definition.kind;
+        let scope = definition
+            .scope
+            .clone()
+            .unwrap_or_else(|| definition.name.clone());
+        out.push(Symbol {
+            name: definition.name,
+            kind,
+            start_line: child.start_position().row + 1,
+            end_line: child.end_position().row + 1,
+            depth,
+            container: definition.container,
+        });
+        if kind.is_container() {
+            collect_symbols(child, source, language, depth + 1, Some(&scope), out);
+        }
+    }
+}
+
+fn classify(
+    node: Node<'_>,
+    source: &[u8],
+    language: SourceLanguage,
+    container: Option<&str>,
+) -> Option<Definition> {
+    let function_kind = if container.is_some() {
+        SymbolKind::Method
+    } else {
+        SymbolKind::Function
+    };
+    let kind = match language {
+        SourceLanguage::Rust => match node.kind() {
+            "function_item" | "function_signature_item" => function_kind,
+            "struct_item" | "union_item" => SymbolKind::Struct,
+            "enum_item" => SymbolKind::Enum,
+            "trait_item" => SymbolKind::Trait,
+            "mod_item" => SymbolKind::Module,
+            "type_item" => SymbolKind::Type,
+            "const_item" | "static_item" => SymbolKind::Constant,
+            "macro_definition" => SymbolKind::Macro,
+            "impl_item" => return rust_impl(node, source, container),
+            _ => return None,
+        },
+        SourceLanguage::Python => match node.kind() {
+            "function_definition" => function_kind,
+            "class_definition" => SymbolKind::Class,
+            _ => return None,
+        },
+        SourceLanguage::Go => match node.kind() {
+            "function_declaration" => SymbolKind::Function,
+            "method_declaration" => return go_method(node, source),
+            "type_spec" | "type_alias" => match node.child_by_field_name("type")?.kind() {
+                "struct_type" => SymbolKind::Struct,
+                "interface_type" => SymbolKind::Interface,
+                _ => SymbolKind::Type,
+            },
+            _ => return None,
+        },
+        SourceLanguage::TypeScript | SourceLanguage::Tsx | SourceLanguage::JavaScript => {
+            match node.kind() {
+                "function_declaration"
+                | "generator_function_declaration"
+                | "function_signature" => SymbolKind::Function,
+                "class_declaration" | "abstract_class_declaration" | "class" => SymbolKind::Class,
+                "method_definition" | "method_signature" | "abstract_method_signature" => {
+                    SymbolKind::Method
+                }
+                "interface_declaration" => SymbolKind::Interface,
+                "type_alias_declaration" => SymbolKind::Type,
+                "enum_declaration" => SymbolKind::Enum,
+                "internal_module" | "module" => SymbolKind::Module,
+                "variable_declarator" => {
+                    let value = node.child_by_field_name("value")?;
+                    if !matches!(
+                        value.kind(),
+                        "arrow_function" | "function_expression" | "function"
+                    ) {
+                        return None;
+                    }
+                    function_kind
+                }
+                _ => return None,
+            }
+        }
+    };
+
+    let name = node_text(node.child_by_field_name("name")?, source);
+    Some(Definition {
+        kind,
+        name,
+        container: container.map(str::to_string),
+        scope: None,
+    })
+}
+
+fn rust_impl(node: Node<'_>, source: &[u8], container: Option<&str>) -> Option<Definition> {
+    let type_node = node.child_by_field_name("type")?;
+    let type_name = base_type_name(&node_text(type_node, source));
+    let name = match node.child_by_field_name("trait") {
+        Some(trait_node) => format!(
+            "{} for {}",
+            node_text(trait_node, source),
+            node_text(type_node, source)
+        ),
+        None => node_text(type_node, source),
+    };
+    Some(Definition {
+        kind: SymbolKind::Impl,
+        name,
+        container: container.map(str::to_string),
+        scope: Some(type_name),
+    })
+}
+
+fn go_method(node: Node<'_>, source: &[u8]) -> Option<Definition> {
+    let name = node_text(node.child_by_field_name("name")?, source);
+    let receiver = node
+        .child_by_field_name("receiver")
+        .map(|receiver| node_text(receiver, source))
+        .unwrap_or_default();
+    let receiver_type = receiver
+        .trim_matches(|ch| ch == '(' || ch == ')')
+        .split_whitespace()
+        .last()
+        .map(|text| base_type_name(text.trim_start_matches('*')))
+        .filter(|text| !text.is_empty());
+    Some(Definition {
+        kind: SymbolKind::Method,
+        name,
+        container: receiver_type,
+        scope: None,
+    })
+}
+
+/// Strips generic arguments and path qualifiers: `crate::a::Foo<T>` -> `Foo`.
+fn base_type_name(text: &str) -> String {
+    let without_generics = text.split(['<', '[']).next().unwrap_or(text);
+    without_generics
+        .rsplit("::")
+        .next()
+        .unwrap_or(without_generics)
+        .trim()
+        .to_string()
+}
+
+fn node_text(node: Node<'_>, source: &[u8]) -> String {
+    node.utf8_text(source).unwrap_or_default().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{OutlineParser, SourceLanguage, Symbol, SymbolKind};
+    use std::path::Path;
+
+    fn outline(
+        language: SourceLanguage,
+        source: &str,
+    ) -> Vec<(String, &'static str, usize, usize)> {
+        OutlineParser::new()
+            .outline(language, source)
+            .expect("outline")
+            .into_iter()
+            .map(|symbol| {
+                (
+                    symbol.display_name(),
+                    symbol.kind.label(),
+                    symbol.start_line,
+                    symbol.depth,
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn detects_languages_from_extensions() {
+        assert_eq!(
+            SourceLanguage::from_path(Path::new("src/lib.rs")),
+            Some(SourceLanguage::Rust)
+        );
+        assert_eq!(
+            SourceLanguage::from_path(Path::new("app/View.TSX")),
+            Some(SourceLanguage::Tsx)
+        );
+        assert_eq!(SourceLanguage::from_path(Path::new("README.md")), None);
+    }
+
+    #[test]
+    fn outlines_rust_items_with_impl_methods() {
+        let source = "struct Foo;\n\nimpl Display for Foo {\n    fn fmt(&self) {}\n}\n\nimpl<T> Bar<T> {\n    pub fn new() -> Self {\n        todo!()\n    }\n}\n\nfn main() {\n    fn nested() {}\n}\n";
+        let symbols = OutlineParser::new()
+            .outline(SourceLanguage::Rust, source)
+            .expect("outline");
+        let names = symbols
+            .iter()
+            .map(|symbol| (symbol.kind, symbol.name.as_str(), symbol.depth))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            vec![
+                (SymbolKind::Struct, "Foo", 0),
+                (SymbolKind::Impl, "Display for Foo", 0),
+                (SymbolKind::Method, "fmt", 1),
+                (SymbolKind::Impl, "Bar<T>", 0),
+                (SymbolKind::Method, "new", 1),
+                (SymbolKind::Function, "main", 0),
+            ]
+        );
+        let new = &symbols[4];
+        assert_eq!(new.container.as_deref(), Some("Bar"));
+        assert_eq!((new.start_line, new.end_line), (8, 10));
+    }
+
+    #[test]
+    fn outlines_typescript_python_and_go() {
+        let ts = "export interface Props { id: string }\nexport class View {\n  render() {}\n}\nexport const handler = async () => {};\n";
+        assert_eq!(
+            outline(SourceLanguage::TypeScript, ts),
+            vec![
+                ("Props".to_string(), "interface", 1, 0),
+                ("View".to_string(), "class", 2, 0),
+                ("render".to_string(), "method", 3, 1),
+                ("handler".to_string(), "fn", 5, 0),
+            ]
+        );
+
+        let py =
+            "@decorator\ndef top():\n    pass\n\nclass Model:\n    def save(self):\n        pass\n";
+        assert_eq!(
+            outline(SourceLanguage::Python, py),
+            vec![
+                ("top".to_string(), "fn", 2, 0),
+                ("Model".to_string(), "class", 5, 0),
+                ("save".to_string(), "method", 6, 1),
+            ]
+        );
+
+        let go = "package main\n\ntype Server struct{}\n\nfunc (s *Server) Start() error { return nil }\n\nfunc main() {}\n";
+        assert_eq!(
+            outline(SourceLanguage::Go, go),
+            vec![
+                ("Server".to_string(), "struct", 3, 0),
+                ("Server.Start".to_string(), "method", 5, 0),
+                ("main".to_string(), "fn", 7, 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn display_name_qualifies_only_top_level_methods() {
+        let method = Symbol {
+            name: "save".to_string(),
+            kind: SymbolKind::Method,
+            start_line: 1,
+            end_line: 2,
+            depth: 1,
+            container: Some("Model".to_string()),
+        };
+        assert_eq!(method.display_name(), "save");
+    }
+}
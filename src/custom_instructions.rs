@@ -1,9 +1,11 @@
 use std::fs;
 use std::path::Path;
 
-pub fn load_custom_instructions(cwd: &Path) -> Option<String> {
+/// Project instructions are skipped for untrusted workspaces; the global ones
+/// in the home directory always apply.
+pub fn load_custom_instructions(cwd: &Path, include_project: bool) -> Option<String> {
     let project_path = cwd.join(".grok").join("GROK.md");
-    if let Ok(content) = fs::read_to_string(project_path) {
+    if include_project && let Ok(content) = fs::read_to_string(project_path) {
         let trimmed = content.trim();
         if !trimmed.is_empty() {
             return Some(trimmed.to_string());
@@ -41,7 +43,7 @@ mod tests {
         )
         .expect("write project GROK.md");
 
-        let loaded = load_custom_instructions(temp.path());
+        let loaded = load_custom_instructions(temp.path(), true);
         assert_eq!(
             loaded.as_deref(),
             Some("Keep responses short and actionable.")
@@ -56,8 +58,10 @@ mod tests {
         fs::write(project_grok_dir.join("GROK.md"), "project instructions")
             .expect("write project GROK.md");
 
-        let loaded = load_custom_instructions(temp.path());
+        let loaded = load_custom_instructions(temp.path(), true);
         assert_eq!(loaded.as_deref(), Some("project instructions"));
+        let untrusted = load_custom_instructions(temp.path(), false);
+        assert_ne!(untrusted.as_deref(), Some("project instructions"));
     }
 
     struct TempDir {
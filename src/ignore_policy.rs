@@ -0,0 +1,138 @@
+use anyhow::{Context, Result, bail};
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+pub const IGNORE_FILE_NAME: &str = ".grokignore";
+
+/// Paths hidden from every tool: the project's `.grokignore` plus the
+/// `ignorePatterns` deny list from settings, both in gitignore syntax and
+/// relative to the project root.
+#[derive(Debug, Clone, Default)]
+pub struct IgnorePolicy {
+    root: PathBuf,
+    matcher: Option<Arc<Gitignore>>,
+}
+
+impl IgnorePolicy {
+    pub fn load(root: &Path, patterns: &[String]) -> Result<Self> {
+        let mut builder = GitignoreBuilder::new(root);
+        let file = root.join(IGNORE_FILE_NAME);
+        // Invalid lines are skipped; the valid ones still apply.
+        if file.is_file() {
+            let _ = builder.add(&file);
+        }
+        for pattern in patterns {
+            builder
+                .add_line(None, pattern)
+                .with_context(|| format!("Invalid ignore pattern `{pattern}` in settings"))?;
+        }
+        let matcher = builder
+            .build()
+            .with_context(|| format!("Failed to load {}", file.display()))?;
+        Ok(Self {
+            root: root.to_path_buf(),
+            matcher: (!matcher.is_empty()).then(|| Arc::new(matcher)),
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.matcher.is_none()
+    }
+
+    /// Whether `path` or one of its parent directories matches. The project
+    /// root itself and paths outside it never do.
+    pub fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        let Some(matcher) = &self.matcher else {
+            return false;
+        };
+        match path.strip_prefix(&self.root) {
+            Ok(relative) if !relative.as_os_str().is_empty() => matcher
+                .matched_path_or_any_parents(relative, is_dir)
+                .is_ignore(),
+            _ => false,
+        }
+    }
+
+    pub fn check(&self, path: &Path) -> Result<()> {
+        if self.is_ignored(path, path.is_dir()) {
+            bail!(
+                "path is ignored by policy: {}",
+                path.strip_prefix(&self.root).unwrap_or(path).display()
+            );
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{IGNORE_FILE_NAME, IgnorePolicy};
+    use std::fs;
+    use std::path::{Path, PathBuf};
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    #[test]
+    fn combines_ignore_file_and_settings_patterns() {
+        let temp = TempDir::new("ignore-policy");
+        let root = temp.path();
+        fs::write(
+            root.join(IGNORE_FILE_NAME),
+            "# secrets\n.env\n*.pem\ndumps/\n!dumps/README.md\n",
+        )
+        .expect("write ignore file");
+        fs::create_dir_all(root.join("dumps")).expect("mkdir");
+        let policy = IgnorePolicy::load(root, &["customers/**/*.csv".to_string()]).expect("policy");
+
+        assert!(policy.is_ignored(&root.join(".env"), false));
+        assert!(policy.is_ignored(&root.join("config/.env"), false));
+        assert!(policy.is_ignored(&root.join("keys/server.pem"), false));
+        assert!(policy.is_ignored(&root.join("dumps"), true));
+        assert!(policy.is_ignored(&root.join("dumps/2024.sql"), false));
+        assert!(policy.is_ignored(&root.join("customers/eu/list.csv"), false));
+        assert!(!policy.is_ignored(&root.join("src/main.rs"), false));
+        assert!(!policy.is_ignored(&root.join(".env.example"), false));
+        assert!(!policy.is_ignored(root, true));
+        assert!(!policy.is_ignored(Path::new("/elsewhere/.env"), false));
+
+        let err = policy.check(&root.join(".env")).expect_err("ignored");
+        assert_eq!(err.to_string(), "path is ignored by policy: .env");
+        assert!(policy.check(&root.join("src")).is_ok());
+    }
+
+    #[test]
+    fn empty_without_ignore_file_or_patterns() {
+        let temp = TempDir::new("ignore-policy-empty");
+        let policy = IgnorePolicy::load(temp.path(), &[]).expect("policy");
+        assert!(policy.is_empty());
+        assert!(!policy.is_ignored(&temp.path().join(".env"), false));
+        assert!(IgnorePolicy::load(temp.path(), &["{a,b".to_string()]).is_err());
+    }
+
+    struct TempDir {
+        path: PathBuf,
+    }
+
+    impl TempDir {
+        fn new(prefix: &str) -> Self {
+            let nonce = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .expect("clock should be after epoch")
+                .as_nanos();
+            let pid = std::process::id();
+            let path = std::env::temp_dir().join(format!("codetoad-{prefix}-{pid}-{nonce}"));
+            fs::create_dir_all(&path).expect("create temp dir");
+            Self { path }
+        }
+
+        fn path(&self) -> &Path {
+            &self.path
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.path);
+        }
+    }
+}
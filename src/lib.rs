@@ -2,20 +2,30 @@ pub mod agent;
 pub mod agent_policy;
 pub mod agent_stream;
 pub mod app_context;
+pub mod audit;
 pub mod cli;
+pub mod code_outline;
 pub mod confirmation;
 pub mod custom_instructions;
 pub mod git_ops;
 pub mod grok_client;
+pub mod ignore_policy;
 pub mod image_input;
+pub mod lsp;
 pub mod message_projection;
 pub mod model_client;
 pub mod onboarding;
+pub mod path_index;
+pub mod permissions;
 pub mod protocol;
 pub mod provider;
+pub mod redaction;
+pub mod repo_map;
 pub mod responses_adapter;
+pub mod sandbox;
 pub mod session_store;
 pub mod settings;
+pub mod shell;
 pub mod slash_commands;
 pub mod tool_catalog;
 pub mod tool_context;
@@ -0,0 +1,1069 @@
+use crate::ignore_policy::IgnorePolicy;
+use crate::settings::LanguageServerConfig;
+use anyhow::{Context, Result, anyhow, bail};
+use serde_json::{Value, json};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::{Arc, Mutex as StdMutex, PoisonError};
+use std::time::Duration;
+use tokio::io::{
+    AsyncBufRead, AsyncBufReadExt, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader,
+};
+use tokio::process::{Child, ChildStdin, Command};
+use tokio::sync::{Mutex, Notify, oneshot};
+
+const INITIALIZE_TIMEOUT: Duration = Duration::from_secs(30);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+/// How long a write waits for the server to publish fresh diagnostics.
+const DIAGNOSTICS_TIMEOUT: Duration = Duration::from_secs(3);
+/// Extra time after the first publish for follow-up passes (e.g. cargo check on save).
+const DIAGNOSTICS_SETTLE: Duration = Duration::from_millis(400);
+const MAX_REPORTED_DIAGNOSTICS: usize = 20;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Diagnostic {
+    /// 1-based line and column.
+    pub line: usize,
+    pub column: usize,
+    /// LSP severity: 1 error, 2 warning, 3 information, 4 hint.
+    pub severity: u8,
+    pub message: String,
+}
+
+impl Diagnostic {
+    fn from_value(value: &Value) -> Option<Self> {
+        let start = value.get("range")?.get("start")?;
+        Some(Self {
+            line: start.get("line")?.as_u64()? as usize + 1,
+            column: start.get("character")?.as_u64()? as usize + 1,
+            severity: value
+                .get("severity")
+                .and_then(Value::as_u64)
+                .unwrap_or(1)
+                .clamp(1, 4) as u8,
+            message: value.get("message")?.as_str()?.trim().to_string(),
+        })
+    }
+
+    fn label(&self) -> &'static str {
+        match self.severity {
+            1 => "error",
+            2 => "warning",
+            3 => "info",
+            _ => "hint",
+        }
+    }
+
+    /// Line numbers shift with edits, so "same problem" ignores the position.
+    fn same_problem(&self, other: &Self) -> bool {
+        self.severity == other.severity && self.message == other.message
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Location {
+    pub path: PathBuf,
+    pub line: usize,
+    pub column: usize,
+}
+
+#[derive(Debug, Default)]
+struct DiagnosticsStore {
+    by_uri: HashMap<String, Vec<Diagnostic>>,
+    generations: HashMap<String, u64>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct OpenDocument {
+    version: i64,
+    content_hash: u64,
+}
+
+type PendingRequests = Arc<StdMutex<HashMap<i64, oneshot::Sender<Result<Value, String>>>>>;
+
+/// One running language server, talking JSON-RPC over the child's stdio.
+pub(crate) struct LspClient {
+    name: String,
+    config: LanguageServerConfig,
+    stdin: Arc<Mutex<ChildStdin>>,
+    pending: PendingRequests,
+    next_id: AtomicI64,
+    diagnostics: Arc<StdMutex<DiagnosticsStore>>,
+    diagnostics_changed: Arc<Notify>,
+    documents: Mutex<HashMap<String, OpenDocument>>,
+    _child: Child,
+}
+
+impl LspClient {
+    pub(crate) async fn start(
+        name: &str,
+        config: &LanguageServerConfig,
+        root: &Path,
+    ) -> Result<Self> {
+        let mut child = Command::new(&config.command)
+            .args(&config.args)
+            .current_dir(root)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .kill_on_drop(true)
+            .spawn()
+            .with_context(|| format!("Failed to start language server '{}'", config.command))?;
+        let stdin = child
+            .stdin
+            .take()
+            .context("Language server stdin unavailable")?;
+        let stdout = child
+            .stdout
+            .take()
+            .context("Language server stdout unavailable")?;
+
+        let client = Self {
+            name: name.to_string(),
+            config: config.clone(),
+            stdin: Arc::new(Mutex::new(stdin)),
+            pending: Arc::new(StdMutex::new(HashMap::new())),
+            next_id: AtomicI64::new(1),
+            diagnostics: Arc::new(StdMutex::new(DiagnosticsStore::default())),
+            diagnostics_changed: Arc::new(Notify::new()),
+            documents: Mutex::new(HashMap::new()),
+            _child: child,
+        };
+        tokio::spawn(read_loop(
+            BufReader::new(stdout),
+            Arc::clone(&client.stdin),
+            Arc::clone(&client.pending),
+            Arc::clone(&client.diagnostics),
+            Arc::clone(&client.diagnostics_changed),
+        ));
+
+        let root_uri = path_to_uri(root);
+        let params = json!({
+            "processId": std::process::id(),
+            "rootUri": root_uri,
+            "workspaceFolders": [{ "uri": root_uri, "name": root.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default() }],
+            "initializationOptions": config.initialization_options,
+            "capabilities": {
+                "general": { "positionEncodings": ["utf-16"] },
+                "workspace": {
+                    "configuration": true,
+                    "workspaceFolders": true,
+                    "didChangeWatchedFiles": { "dynamicRegistration": false }
+                },
+                "textDocument": {
+                    "synchronization": { "didSave": true, "dynamicRegistration": false },
+                    "publishDiagnostics": { "relatedInformation": false, "versionSupport": true },
+                    "definition": { "linkSupport": true },
+                    "references": {},
+                    "hover": { "contentFormat": ["markdown", "plaintext"] }
+                }
+            }
+        });
+        client
+            .request_with_timeout("initialize", params, INITIALIZE_TIMEOUT)
+            .await
+            .with_context(|| format!("Language server '{name}' failed to initialize"))?;
+        client.notify("initialized", json!({})).await?;
+        Ok(client)
+    }
+
+    pub(crate) fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub(crate) async fn request(&self, method: &str, params: Value) -> Result<Value> {
+        self.request_with_timeout(method, params, REQUEST_TIMEOUT)
+            .await
+    }
+
+    async fn request_with_timeout(
+        &self,
+        method: &str,
+        params: Value,
+        timeout: Duration,
+    ) -> Result<Value> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let (sender, receiver) = oneshot::channel();
+        lock(&self.pending).insert(id, sender);
+        let message = json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params });
+        if let Err(err) = write_message(&mut *self.stdin.lock().await, &message).await {
+            lock(&self.pending).remove(&id);
+            return Err(err);
+        }
+
+        match tokio::time::timeout(timeout, receiver).await {
+            Ok(Ok(Ok(result))) => Ok(result),
+            Ok(Ok(Err(message))) => bail!("{} {method} failed: {message}", self.name),
+            Ok(Err(_)) => bail!("Language server '{}' exited", self.name),
+            Err(_) => {
+                lock(&self.pending).remove(&id);
+                bail!(
+                    "{} did not answer {method} within {}s",
+                    self.name,
+                    timeout.as_secs()
+                )
+            }
+        }
+    }
+
+    async fn notify(&self, method: &str, params: Value) -> Result<()> {
+        let message = json!({ "jsonrpc": "2.0", "method": method, "params": params });
+        write_message(&mut *self.stdin.lock().await, &message).await
+    }
+
+    /// Opens the document or sends its full new text. Returns false when the
+    /// server already has this exact content.
+    pub(crate) async fn sync_document(&self, path: &Path, text: &str) -> Result<bool> {
+        let uri = path_to_uri(path);
+        let content_hash = {
+            let mut hasher = DefaultHasher::new();
+            text.hash(&mut hasher);
+            hasher.finish()
+        };
+        let mut documents = self.documents.lock().await;
+        match documents.get_mut(&uri) {
+            Some(document) if document.content_hash == content_hash => Ok(false),
+            Some(document) => {
+                document.version += 1;
+                document.content_hash = content_hash;
+                let version = document.version;
+                drop(documents);
+                self.notify(
+                    "textDocument/didChange",
+                    json!({
+                        "textDocument": { "uri": uri, "version": version },
+                        "contentChanges": [{ "text": text }]
+                    }),
+                )
+                .await?;
+                self.notify(
+                    "textDocument/didSave",
+                    json!({ "textDocument": { "uri": uri } }),
+                )
+                .await?;
+                Ok(true)
+            }
+            None => {
+                documents.insert(
+                    uri.clone(),
+                    OpenDocument {
+                        version: 1,
+                        content_hash,
+                    },
+                );
+                drop(documents);
+                self.notify(
+                    "textDocument/didOpen",
+                    json!({
+                        "textDocument": {
+                            "uri": uri,
+                            "languageId": language_id(path, &self.config, &self.name),
+                            "version": 1,
+                            "text": text
+                        }
+                    }),
+                )
+                .await?;
+                Ok(true)
+            }
+        }
+    }
+
+    /// Closes open documents at or under `path`, which no longer exists,
+    /// reports it deleted, and drops the diagnostics kept for it.
+    async fn forget_path(&self, path: &Path) -> Result<()> {
+        let under_path = |uri: &str| uri_to_path(uri).is_some_and(|file| file.starts_with(path));
+        let closed = {
+            let mut documents = self.documents.lock().await;
+            let closed = documents
+                .keys()
+                .filter(|uri| under_path(uri))
+                .cloned()
+                .collect::<Vec<_>>();
+            for uri in &closed {
+                documents.remove(uri);
+            }
+            closed
+        };
+        {
+            let mut store = lock(&self.diagnostics);
+            store.by_uri.retain(|uri, _| !under_path(uri));
+        }
+
+        let path_uri = path_to_uri(path);
+        let mut changes = vec![json!({ "uri": path_uri, "type": 3 })];
+        for uri in &closed {
+            self.notify(
+                "textDocument/didClose",
+                json!({ "textDocument": { "uri": uri } }),
+            )
+            .await?;
+            if *uri != path_uri {
+                changes.push(json!({ "uri": uri, "type": 3 }));
+            }
+        }
+        self.notify(
+            "workspace/didChangeWatchedFiles",
+            json!({ "changes": changes }),
+        )
+        .await
+    }
+
+    pub(crate) async fn is_open(&self, path: &Path) -> bool {
+        self.documents.lock().await.contains_key(&path_to_uri(path))
+    }
+
+    fn diagnostics_snapshot(&self) -> HashMap<String, Vec<Diagnostic>> {
+        lock(&self.diagnostics).by_uri.clone()
+    }
+
+    fn generation(&self, uri: &str) -> u64 {
+        lock(&self.diagnostics)
+            .generations
+            .get(uri)
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// Waits until diagnostics for `uri` are published past `generation`, then
+    /// briefly for any follow-up publish.
+    async fn wait_for_diagnostics(&self, uri: &str, generation: u64) {
+        let first = tokio::time::timeout(DIAGNOSTICS_TIMEOUT, async {
+            loop {
+                let notified = self.diagnostics_changed.notified();
+                if self.generation(uri) > generation {
+                    return;
+                }
+                notified.await;
+            }
+        })
+        .await;
+        if first.is_ok() {
+            let settled = self.generation(uri);
+            let _ = tokio::time::timeout(DIAGNOSTICS_SETTLE, async {
+                loop {
+                    let notified = self.diagnostics_changed.notified();
+                    if self.generation(uri) > settled {
+                        return;
+                    }
+                    notified.await;
+                }
+            })
+            .await;
+        }
+    }
+}
+
+/// Starts configured servers on first use and keeps them for the session.
+pub(crate) struct LspManager {
+    root: PathBuf,
+    configs: BTreeMap<String, LanguageServerConfig>,
+    clients: HashMap<String, Arc<LspClient>>,
+    /// Servers that failed to start, with the reason, so they are not retried.
+    failed: HashMap<String, String>,
+}
+
+impl LspManager {
+    pub(crate) fn new(root: PathBuf, configs: BTreeMap<String, LanguageServerConfig>) -> Self {
+        Self {
+            root,
+            configs,
+            clients: HashMap::new(),
+            failed: HashMap::new(),
+        }
+    }
+
+    pub(crate) fn configure(&mut self, configs: BTreeMap<String, LanguageServerConfig>) {
+        self.configs = configs;
+        self.clients.clear();
+        self.failed.clear();
+    }
+
+    fn server_name_for(&self, path: &Path) -> Option<String> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        self.configs
+            .iter()
+            .find(|(_, config)| {
+                config.extensions.iter().any(|candidate| {
+                    candidate
+                        .trim_start_matches('.')
+                        .eq_ignore_ascii_case(&extension)
+                })
+            })
+            .map(|(name, _)| name.clone())
+    }
+
+    /// Returns the running server for `path`, starting it if needed. `Ok(None)`
+    /// means no server is configured for the file type.
+    pub(crate) async fn client_for(&mut self, path: &Path) -> Result<Option<Arc<LspClient>>> {
+        let Some(name) = self.server_name_for(path) else {
+            return Ok(None);
+        };
+        if let Some(client) = self.clients.get(&name) {
+            return Ok(Some(Arc::clone(client)));
+        }
+        if let Some(reason) = self.failed.get(&name) {
+            bail!("{reason}");
+        }
+
+        match LspClient::start(&name, &self.configs[&name], &self.root).await {
+            Ok(client) => {
+                let client = Arc::new(client);
+                self.clients.insert(name, Arc::clone(&client));
+                Ok(Some(client))
+            }
+            Err(err) => {
+                let reason = format!("{err:#}");
+                self.failed.insert(name, reason.clone());
+                Err(anyhow!(reason))
+            }
+        }
+    }
+
+    /// Tells running servers that `path` was deleted or moved away, so they
+    /// stop reporting problems for it.
+    pub(crate) async fn path_removed(&self, path: &Path) {
+        for client in self.clients.values() {
+            let _ = client.forget_path(path).await;
+        }
+    }
+
+    /// Syncs a file a tool just wrote and reports diagnostics that appeared
+    /// because of it: new errors and warnings in the file itself, and new
+    /// errors elsewhere. The first time a file is seen, only its errors count.
+    pub(crate) async fn diagnostics_after_write(
+        &mut self,
+        path: &Path,
+        ignore: &IgnorePolicy,
+    ) -> Option<String> {
+        let client = match self.client_for(path).await {
+            Ok(client) => client?,
+            Err(err) => return Some(format!("Language server unavailable: {err:#}")),
+        };
+        let text = std::fs::read_to_string(path).ok()?;
+        let uri = path_to_uri(path);
+        let was_open = client.is_open(path).await;
+        let before = client.diagnostics_snapshot();
+        let generation = client.generation(&uri);
+        if !client.sync_document(path, &text).await.ok()? {
+            return None;
+        }
+        client.wait_for_diagnostics(&uri, generation).await;
+        let after = client.diagnostics_snapshot();
+
+        let mut lines = Vec::new();
+        let empty = Vec::new();
+        let mut uris = after.keys().collect::<Vec<_>>();
+        uris.sort_by_key(|candidate| (*candidate != &uri, (*candidate).clone()));
+        for candidate in uris {
+            if uri_to_path(candidate).is_some_and(|file| ignore.is_ignored(&file, false)) {
+                continue;
+            }
+            let own_file = *candidate == uri;
+            let previous = before.get(candidate).unwrap_or(&empty);
+            let display = uri_to_path(candidate)
+                .map(|file| {
+                    file.strip_prefix(&self.root)
+                        .unwrap_or(&file)
+                        .display()
+                        .to_string()
+                })
+                .unwrap_or_else(|| candidate.clone());
+            for diagnostic in &after[candidate] {
+                let max_severity = if own_file && was_open { 2 } else { 1 };
+                if diagnostic.severity > max_severity
+                    || previous.iter().any(|old| old.same_problem(diagnostic))
+                {
+                    continue;
+                }
+                lines.push(format!(
+                    "  {display}:{}:{} {}: {}",
+                    diagnostic.line,
+                    diagnostic.column,
+                    diagnostic.label(),
+                    first_line(&diagnostic.message)
+                ));
+            }
+        }
+        if lines.is_empty() {
+            return None;
+        }
+
+        let total = lines.len();
+        lines.truncate(MAX_REPORTED_DIAGNOSTICS);
+        let mut report = format!(
+            "New diagnostics from {}:\n{}",
+            client.name(),
+            lines.join("\n")
+        );
+        if total > MAX_REPORTED_DIAGNOSTICS {
+            report.push_str(&format!(
+                "\n  ... {} more",
+                total - MAX_REPORTED_DIAGNOSTICS
+            ));
+        }
+        Some(report)
+    }
+}
+
+async fn read_loop<R: AsyncBufRead + Unpin>(
+    mut reader: R,
+    stdin: Arc<Mutex<ChildStdin>>,
+    pending: PendingRequests,
+    diagnostics: Arc<StdMutex<DiagnosticsStore>>,
+    diagnostics_changed: Arc<Notify>,
+) {
+    while let Ok(Some(message)) = read_message(&mut reader).await {
+        let method = message.get("method").and_then(Value::as_str);
+        let id = message.get("id").cloned();
+        match (method, id) {
+            (None, Some(id)) => {
+                let Some(sender) = id.as_i64().and_then(|id| lock(&pending).remove(&id)) else {
+                    continue;
+                };
+                let outcome = match message.get("error") {
+                    Some(error) => Err(error
+                        .get("message")
+                        .and_then(Value::as_str)
+                        .unwrap_or("unknown error")
+                        .to_string()),
+                    None => Ok(message.get("result").cloned().unwrap_or(Value::Null)),
+                };
+                let _ = sender.send(outcome);
+            }
+            (Some(method), Some(id)) => {
+                let reply = json!({
+                    "jsonrpc": "2.0",
+                    "id": id,
+                    "result": server_request_result(method, message.get("params"))
+                });
+                if write_message(&mut *stdin.lock().await, &reply)
+                    .await
+                    .is_err()
+                {
+                    break;
+                }
+            }
+            (Some("textDocument/publishDiagnostics"), None) => {
+                let Some(params) = message.get("params") else {
+                    continue;
+                };
+                let Some(uri) = params.get("uri").and_then(Value::as_str) else {
+                    continue;
+                };
+                let published = params
+                    .get("diagnostics")
+                    .and_then(Value::as_array)
+                    .map(|items| items.iter().filter_map(Diagnostic::from_value).collect())
+                    .unwrap_or_default();
+                let mut store = lock(&diagnostics);
+                store.by_uri.insert(uri.to_string(), published);
+                *store.generations.entry(uri.to_string()).or_default() += 1;
+                drop(store);
+                diagnostics_changed.notify_waiters();
+            }
+            _ => {}
+        }
+    }
+
+    for (_, sender) in lock(&pending).drain() {
+        let _ = sender.send(Err("language server exited".to_string()));
+    }
+}
+
+/// Minimal answers to requests servers send the client during normal operation.
+fn server_request_result(method: &str, params: Option<&Value>) -> Value {
+    match method {
+        "workspace/configuration" => {
+            let items = params
+                .and_then(|params| params.get("items"))
+                .and_then(Value::as_array)
+                .map_or(0, Vec::len);
+            Value::Array(vec![Value::Null; items])
+        }
+        "workspace/workspaceFolders" => Value::Array(Vec::new()),
+        _ => Value::Null,
+    }
+}
+
+async fn read_message<R: AsyncBufRead + Unpin>(reader: &mut R) -> Result<Option<Value>> {
+    let mut content_length = None;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header).await? == 0 {
+            return Ok(None);
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':')
+            && name.eq_ignore_ascii_case("Content-Length")
+        {
+            content_length = Some(value.trim().parse::<usize>()?);
+        }
+    }
+    let length = content_length.context("Language server message without Content-Length")?;
+    let mut body = vec![0u8; length];
+    reader.read_exact(&mut body).await?;
+    Ok(Some(serde_json::from_slice(&body)?))
+}
+
+async fn write_message<W: AsyncWrite + Unpin>(writer: &mut W, message: &Value) -> Result<()> {
+    let body = serde_json::to_string(message)?;
+    writer
+        .write_all(format!("Content-Length: {}\r\n\r\n{body}", body.len()).as_bytes())
+        .await?;
+    writer.flush().await?;
+    Ok(())
+}
+
+fn lock<T>(mutex: &StdMutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+fn language_id(path: &Path, config: &LanguageServerConfig, name: &str) -> String {
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    let known = match extension.as_str() {
+        "rs" => Some("rust"),
+        "ts" | "mts" | "cts" => Some("typescript"),
+        "tsx" => Some("typescriptreact"),
+        "js" | "mjs" | "cjs" => Some("javascript"),
+        "jsx" => Some("javascriptreact"),
+        "py" | "pyi" => Some("python"),
+        "go" => Some("go"),
+        _ => None,
+    };
+    known
+        .map(str::to_string)
+        .or_else(|| config.language_id.clone())
+        .unwrap_or_else(|| name.to_string())
+}
+
+pub(crate) fn path_to_uri(path: &Path) -> String {
+    let mut uri = String::from("file://");
+    let text = path.to_string_lossy().replace('\\', "/");
+    if !text.starts_with('/') {
+        uri.push('/');
+    }
+    for byte in text.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'/' | b'-' | b'_' | b'.' | b'~' | b':' => {
+                uri.push(byte as char)
+            }
+            _ => uri.push_str(&format!("%{byte:02X}")),
+        }
+    }
+    uri
+}
+
+pub(crate) fn uri_to_path(uri: &str) -> Option<PathBuf> {
+    let encoded = uri.strip_prefix("file://")?;
+    let bytes = encoded.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        if bytes[index] == b'%'
+            && index + 2 < bytes.len()
+            && let Ok(byte) = u8::from_str_radix(&encoded[index + 1..index + 3], 16)
+        {
+            decoded.push(byte);
+            index += 3;
+            continue;
+        }
+        decoded.push(bytes[index]);
+        index += 1;
+    }
+    let text = String::from_utf8(decoded).ok()?;
+    // Windows URIs look like file:///C:/dir; drop the slash before the drive.
+    let text = match text.as_bytes() {
+        [b'/', drive, b':', ..] if drive.is_ascii_alphabetic() => text[1..].to_string(),
+        _ => text,
+    };
+    Some(PathBuf::from(text))
+}
+
+/// Parses a definition/references result: `Location`, `Location[]` or `LocationLink[]`.
+pub(crate) fn parse_locations(value: &Value) -> Vec<Location> {
+    let items = match value {
+        Value::Array(items) => items.iter().collect::<Vec<_>>(),
+        Value::Null => Vec::new(),
+        single => vec![single],
+    };
+    items
+        .into_iter()
+        .filter_map(|item| {
+            let (uri, range) = match item.get("targetUri") {
+                Some(uri) => (
+                    uri,
+                    item.get("targetSelectionRange")
+                        .or_else(|| item.get("targetRange"))?,
+                ),
+                None => (item.get("uri")?, item.get("range")?),
+            };
+            let start = range.get("start")?;
+            Some(Location {
+                path: uri_to_path(uri.as_str()?)?,
+                line: start.get("line")?.as_u64()? as usize + 1,
+                column: start.get("character")?.as_u64()? as usize + 1,
+            })
+        })
+        .collect()
+}
+
+/// Flattens hover contents (`MarkupContent`, `MarkedString` or an array of them).
+pub(crate) fn hover_text(value: &Value) -> String {
+    let Some(contents) = value.get("contents") else {
+        return String::new();
+    };
+    let parts = match contents {
+        Value::Array(items) => items.iter().collect::<Vec<_>>(),
+        single => vec![single],
+    };
+    parts
+        .into_iter()
+        .filter_map(|part| match part {
+            Value::String(text) => Some(text.clone()),
+            Value::Object(_) => part.get("value").and_then(Value::as_str).map(|text| {
+                match part.get("language").and_then(Value::as_str) {
+                    Some(language) => format!("```{language}\n{text}\n```"),
+                    None => text.to_string(),
+                }
+            }),
+            _ => None,
+        })
+        .map(|text| text.trim().to_string())
+        .filter(|text| !text.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+/// Converts a 1-based character column into a 0-based UTF-16 offset.
+pub(crate) fn utf16_offset(line_text: &str, column: usize) -> usize {
+    line_text
+        .chars()
+        .take(column.saturating_sub(1))
+        .map(char::len_utf16)
+        .sum()
+}
+
+/// Converts a 0-based UTF-16 offset back into a 1-based character column.
+pub(crate) fn char_column(line_text: &str, utf16: usize) -> usize {
+    let mut consumed = 0;
+    let mut column = 1;
+    for ch in line_text.chars() {
+        if consumed >= utf16 {
+            break;
+        }
+        consumed += ch.len_utf16();
+        column += 1;
+    }
+    column
+}
+
+fn first_line(text: &str) -> &str {
+    text.lines().next().unwrap_or(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        Diagnostic, LspManager, char_column, hover_text, parse_locations, path_to_uri,
+        read_message, uri_to_path, utf16_offset, write_message,
+    };
+    use crate::ignore_policy::IgnorePolicy;
+    use crate::settings::LanguageServerConfig;
+    use serde_json::json;
+    use std::collections::BTreeMap;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+    use std::time::{SystemTime, UNIX_EPOCH};
+    use tokio::io::BufReader;
+
+    #[tokio::test]
+    async fn messages_round_trip_through_content_length_framing() {
+        let mut buffer = Vec::new();
+        write_message(&mut buffer, &json!({ "id": 1, "result": "é" }))
+            .await
+            .expect("write");
+        write_message(&mut buffer, &json!({ "method": "x" }))
+            .await
+            .expect("write");
+        assert!(buffer.starts_with(b"Content-Length: 22\r\n\r\n"));
+
+        let mut reader = BufReader::new(buffer.as_slice());
+        let first = read_message(&mut reader)
+            .await
+            .expect("read")
+            .expect("message");
+        assert_eq!(first["result"], "é");
+        let second = read_message(&mut reader)
+            .await
+            .expect("read")
+            .expect("message");
+        assert_eq!(second["method"], "x");
+        assert!(read_message(&mut reader).await.expect("read").is_none());
+    }
+
+    #[test]
+    fn uris_round_trip_with_escaping() {
+        let path = Path::new("/tmp/my project/src/lib#1.rs");
+        let uri = path_to_uri(path);
+        assert_eq!(uri, "file:///tmp/my%20project/src/lib%231.rs");
+        assert_eq!(uri_to_path(&uri).as_deref(), Some(path));
+        assert_eq!(uri_to_path("https://example.com"), None);
+    }
+
+    #[test]
+    fn parses_locations_links_and_hover_contents() {
+        let single =
+            json!({ "uri": "file:///a.rs", "range": { "start": { "line": 4, "character": 2 } } });
+        assert_eq!(parse_locations(&single)[0].line, 5);
+        let links = json!([{
+            "targetUri": "file:///b.rs",
+            "targetRange": { "start": { "line": 0, "character": 0 } },
+            "targetSelectionRange": { "start": { "line": 9, "character": 7 } }
+        }]);
+        let parsed = parse_locations(&links);
+        assert_eq!(parsed[0].path, PathBuf::from("/b.rs"));
+        assert_eq!((parsed[0].line, parsed[0].column), (10, 8));
+        assert!(parse_locations(&json!(null)).is_empty());
+
+        let markup = json!({ "contents": { "kind": "markdown", "value": " fn open() -> Store " } });
+        assert_eq!(hover_text(&markup), "fn open() -> Store");
+        let marked =
+            json!({ "contents": [{ "language": "rust", "value": "struct Store" }, "Docs"] });
+        assert_eq!(hover_text(&marked), "```rust\nstruct Store\n```\n\nDocs");
+    }
+
+    #[test]
+    fn utf16_columns_convert_both_ways() {
+        let line = "let 🙂 = ok;";
+        assert_eq!(utf16_offset(line, 1), 0);
+        assert_eq!(utf16_offset(line, 6), 6);
+        assert_eq!(char_column(line, 6), 6);
+        assert_eq!(char_column("abc", 2), 3);
+    }
+
+    #[test]
+    fn diagnostics_compare_without_positions() {
+        let parsed = Diagnostic::from_value(&json!({
+            "range": { "start": { "line": 2, "character": 4 }, "end": { "line": 2, "character": 5 } },
+            "severity": 1,
+            "message": "mismatched types"
+        }))
+        .expect("diagnostic");
+        assert_eq!((parsed.line, parsed.column), (3, 5));
+        let moved = Diagnostic {
+            line: 10,
+            ..parsed.clone()
+        };
+        assert!(parsed.same_problem(&moved));
+    }
+
+    /// A tiny stdio language server that reports every line containing
+    /// "BROKEN" as an error, and answers definition requests with line 1.
+    const FAKE_SERVER: &str = r#"
+import json, sys
+def send(msg):
+    body = json.dumps(msg)
+    sys.stdout.write("Content-Length: %d\r\n\r\n%s" % (len(body.encode()), body))
+    sys.stdout.flush()
+def publish(uri, text):
+    diags = [{"range": {"start": {"line": i, "character": 0}, "end": {"line": i, "character": 1}},
+              "severity": 1, "message": "broken line"}
+             for i, line in enumerate(text.split("\n")) if "BROKEN" in line]
+    send({"jsonrpc": "2.0", "method": "textDocument/publishDiagnostics",
+          "params": {"uri": uri, "diagnostics": diags}})
+while True:
+    length = None
+    while True:
+        line = sys.stdin.buffer.readline()
+        if not line:
+            sys.exit(0)
+        line = line.decode().strip()
+        if not line:
+            break
+        if line.lower().startswith("content-length:"):
+            length = int(line.split(":")[1])
+    msg = json.loads(sys.stdin.buffer.read(length))
+    method = msg.get("method")
+    if method == "initialize":
+        send({"jsonrpc": "2.0", "id": msg["id"], "result": {"capabilities": {}}})
+    elif method == "textDocument/didOpen":
+        doc = msg["params"]["textDocument"]
+        publish(doc["uri"], doc["text"])
+    elif method == "textDocument/didChange":
+        publish(msg["params"]["textDocument"]["uri"], msg["params"]["contentChanges"][0]["text"])
+    elif method == "textDocument/definition":
+        uri = msg["params"]["textDocument"]["uri"]
+        send({"jsonrpc": "2.0", "id": msg["id"],
+              "result": [{"uri": uri, "range": {"start": {"line": 0, "character": 3}, "end": {"line": 0, "character": 4}}}]})
+    elif "id" in msg:
+        send({"jsonrpc": "2.0", "id": msg["id"], "result": None})
+"#;
+
+    fn fake_server_config(dir: &Path) -> Option<LanguageServerConfig> {
+        let python = ["python3", "python"].into_iter().find(|candidate| {
+            std::process::Command::new(candidate)
+                .arg("--version")
+                .output()
+                .is_ok_and(|output| output.status.success())
+        })?;
+        let script = dir.join("fake_lsp.py");
+        fs::write(&script, FAKE_SERVER).expect("write fake server");
+        Some(LanguageServerConfig {
+            command: python.to_string(),
+            args: vec![script.to_string_lossy().to_string()],
+            extensions: vec!["rs".to_string()],
+            ..LanguageServerConfig::default()
+        })
+    }
+
+    #[tokio::test]
+    async fn manager_reports_only_new_diagnostics_after_writes() {
+        let temp = TempDir::new("lsp-diagnostics");
+        let Some(config) = fake_server_config(temp.path()) else {
+            return;
+        };
+        let file = temp.path().join("lib.rs");
+        let mut manager = LspManager::new(
+            temp.path().to_path_buf(),
+            BTreeMap::from([("fake".to_string(), config)]),
+        );
+
+        fs::write(&file, "fn ok() {}\nBROKEN\n").expect("write");
+        let first = manager
+            .diagnostics_after_write(&file, &IgnorePolicy::default())
+            .await
+            .expect("report");
+        assert_eq!(
+            first,
+            "New diagnostics from fake:\n  lib.rs:2:1 error: broken line"
+        );
+
+        fs::write(&file, "// moved\nfn ok() {}\nBROKEN\n").expect("write");
+        assert_eq!(
+            manager
+                .diagnostics_after_write(&file, &IgnorePolicy::default())
+                .await,
+            None
+        );
+
+        fs::write(&file, "fn ok() {}\n").expect("write");
+        assert_eq!(
+            manager
+                .diagnostics_after_write(&file, &IgnorePolicy::default())
+                .await,
+            None
+        );
+
+        assert_eq!(
+            manager
+                .diagnostics_after_write(&temp.path().join("notes.md"), &IgnorePolicy::default())
+                .await,
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn removed_paths_are_closed_and_their_diagnostics_dropped() {
+        let temp = TempDir::new("lsp-removed");
+        let Some(config) = fake_server_config(temp.path()) else {
+            return;
+        };
+        let file = temp.path().join("src/lib.rs");
+        fs::create_dir_all(file.parent().expect("parent")).expect("create src");
+        let mut manager = LspManager::new(
+            temp.path().to_path_buf(),
+            BTreeMap::from([("fake".to_string(), config)]),
+        );
+        fs::write(&file, "BROKEN\n").expect("write");
+        assert!(
+            manager
+                .diagnostics_after_write(&file, &IgnorePolicy::default())
+                .await
+                .is_some()
+        );
+
+        fs::remove_dir_all(temp.path().join("src")).expect("remove");
+        manager.path_removed(&temp.path().join("src")).await;
+        let client = manager
+            .client_for(&file)
+            .await
+            .expect("client")
+            .expect("configured");
+        assert!(!client.is_open(&file).await);
+        assert!(client.diagnostics_snapshot().is_empty());
+
+        fs::create_dir_all(file.parent().expect("parent")).expect("create src");
+        fs::write(&file, "BROKEN\n").expect("write");
+        assert_eq!(
+            manager
+                .diagnostics_after_write(&file, &IgnorePolicy::default())
+                .await
+                .as_deref(),
+            Some("New diagnostics from fake:\n  src/lib.rs:1:1 error: broken line")
+        );
+    }
+
+    #[tokio::test]
+    async fn manager_remembers_servers_that_fail_to_start() {
+        let temp = TempDir::new("lsp-missing");
+        let file = temp.path().join("main.rs");
+        fs::write(&file, "fn main() {}\n").expect("write");
+        let mut manager = LspManager::new(
+            temp.path().to_path_buf(),
+            BTreeMap::from([(
+                "rust".to_string(),
+                LanguageServerConfig {
+                    command: "codetoad-no-such-language-server".to_string(),
+                    extensions: vec!["rs".to_string()],
+                    ..LanguageServerConfig::default()
+                },
+            )]),
+        );
+
+        let report = manager
+            .diagnostics_after_write(&file, &IgnorePolicy::default())
+            .await
+            .expect("report");
+        assert!(report.starts_with("Language server unavailable: Failed to start language server"));
+        assert!(manager.client_for(&file).await.is_err());
+    }
+
+    struct TempDir {
+        path: PathBuf,
+    }
+
+    impl TempDir {
+        fn new(prefix: &str) -> Self {
+            let nonce = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .expect("clock after epoch")
+                .as_nanos();
+            let pid = std::process::id();
+            let path = std::env::temp_dir().join(format!("codetoad-{prefix}-{pid}-{nonce}"));
+            fs::create_dir_all(&path).expect("create temp dir");
+            Self { path }
+        }
+
+        fn path(&self) -> &Path {
+            &self.path
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.path);
+        }
+    }
+}
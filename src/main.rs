@@ -2,19 +2,26 @@ use anyhow::{Context, Result, bail};
 use clap::Parser;
 use codetoad::agent::Agent;
 use codetoad::app_context::AppContext;
-use codetoad::cli::{ApiKeyStorageArg, Cli, Commands, GitCommands};
+use codetoad::audit::{AuditQuery, format_entry, parse_time, read_entries};
+use codetoad::cli::{
+    ApiKeyStorageArg, AuditArgs, Cli, Commands, GitCommands, PermissionModeArg, TrustArgs,
+};
 use codetoad::git_ops::{
     CommitAndPushEvent, CommitAndPushOptions, CommitAndPushOutcome, CommitAndPushStep,
     run_commit_and_push,
 };
 use codetoad::image_input::prepare_user_input;
-use codetoad::onboarding::run_first_time_setup;
+use codetoad::onboarding::{prompt_workspace_trust, run_first_time_setup};
+use codetoad::permissions::PermissionMode;
+use codetoad::sandbox::{SandboxMode, landlock_available};
 use codetoad::settings::{ApiKeySaveLocation, ApiKeyStorageMode, SettingsManager};
+use codetoad::tools::kill_all_background_processes;
 use codetoad::ui::inline;
 use crossterm::event::DisableMouseCapture;
 use crossterm::execute;
 use crossterm::terminal::disable_raw_mode;
 use std::io::{self, IsTerminal};
+use std::path::Path;
 
 #[tokio::main]
 async fn main() -> Result<()> {
@@ -28,6 +35,29 @@ async fn main() -> Result<()> {
     let cwd = std::env::current_dir().context("Failed to determine current directory")?;
 
     let mut settings = SettingsManager::load(&cwd)?;
+    if let Some(Commands::Audit(args)) = &cli.command {
+        return print_audit_entries(&settings.audit(&cwd).dir, args);
+    }
+    if let Some(Commands::Trust(args)) = &cli.command {
+        return update_workspace_trust(&mut settings, &cwd, args);
+    }
+    let interactive = io::stdin().is_terminal() && io::stdout().is_terminal();
+    let trusted = match settings.workspace_trust(&cwd) {
+        Some(trusted) => trusted,
+        None if interactive => {
+            let trusted = prompt_workspace_trust(&cwd)?;
+            settings.set_workspace_trust(&cwd, trusted)?;
+            trusted
+        }
+        None => false,
+    };
+    if !trusted {
+        eprintln!(
+            "warning: {} is not a trusted workspace; ignoring its .grok/GROK.md and .grok/settings.json",
+            cwd.display()
+        );
+        settings.distrust_project();
+    }
     if let Some(storage_arg) = cli.api_key_storage {
         let mode = match storage_arg {
             ApiKeyStorageArg::Keychain => ApiKeyStorageMode::Keychain,
@@ -57,11 +87,7 @@ async fn main() -> Result<()> {
         println!("Saved base URL to ~/.grok/user-settings.json");
     }
 
-    if cli.api_key.is_none()
-        && settings.get_api_key().is_none()
-        && io::stdin().is_terminal()
-        && io::stdout().is_terminal()
-    {
+    if cli.api_key.is_none() && settings.get_api_key().is_none() && interactive {
         run_first_time_setup(&mut settings)?;
     }
 
@@ -84,11 +110,31 @@ async fn main() -> Result<()> {
         .or_else(|| std::env::var("GROK_MODEL").ok())
         .unwrap_or_else(|| settings.get_current_model());
 
-    let app = AppContext::new(
-        cwd.clone(),
-        Agent::new(api_key, base_url, model, cli.max_tool_rounds, &cwd)?,
-        settings,
-    );
+    let mut agent = Agent::new(api_key, base_url, model, cli.max_tool_rounds, &cwd)?;
+    agent.set_workspace_trusted(trusted);
+    agent.configure_language_servers(settings.language_servers());
+    agent.set_persistent_shell(settings.persistent_shell());
+    let sandbox = settings.sandbox();
+    if sandbox.mode != SandboxMode::Off && !landlock_available() {
+        eprintln!(
+            "warning: sandbox mode {} needs Linux Landlock, which is unavailable here; bash commands will be refused until sandbox.mode is set to \"off\"",
+            sandbox.mode.as_str()
+        );
+    }
+    agent.configure_sandbox(&sandbox)?;
+    agent.configure_ignore_patterns(&settings.ignore_patterns())?;
+    agent.configure_redaction(&settings.redaction())?;
+    agent.configure_audit(&settings.audit(&cwd));
+    agent.configure_permissions(settings.permission_rules()?);
+    if let Some(mode) = cli.permission_mode {
+        agent.set_permission_mode(match mode {
+            PermissionModeArg::Default => PermissionMode::Default,
+            PermissionModeArg::AcceptEdits => PermissionMode::AcceptEdits,
+            PermissionModeArg::Plan => PermissionMode::Plan,
+            PermissionModeArg::Bypass => PermissionMode::Bypass,
+        });
+    }
+    let app = AppContext::new(cwd.clone(), agent, settings);
 
     if let Some(Commands::Git { command }) = cli.command {
         match command {
@@ -125,6 +171,7 @@ async fn main() -> Result<()> {
 
 fn install_ctrlc_handler() -> Result<()> {
     ctrlc::set_handler(|| {
+        kill_all_background_processes();
         let _ = disable_raw_mode();
         let mut stdout = io::stdout();
         let _ = execute!(stdout, DisableMouseCapture);
@@ -182,6 +229,50 @@ async fn headless_commit_and_push(app: AppContext) -> Result<()> {
     Ok(())
 }
 
+fn print_audit_entries(dir: &Path, args: &AuditArgs) -> Result<()> {
+    let query = AuditQuery {
+        since_ms: args.since.as_deref().map(parse_time).transpose()?,
+        until_ms: args.until.as_deref().map(parse_time).transpose()?,
+        tool: args.tool.clone(),
+        session: args.session.clone(),
+    };
+    let entries = read_entries(dir, &query)?;
+    if entries.is_empty() && !args.json {
+        println!("No audit entries found in {}", dir.display());
+        return Ok(());
+    }
+    for entry in &entries {
+        if args.json {
+            println!("{}", serde_json::to_string(entry)?);
+        } else {
+            println!("{}", format_entry(entry));
+        }
+    }
+    Ok(())
+}
+
+fn update_workspace_trust(
+    settings: &mut SettingsManager,
+    cwd: &Path,
+    args: &TrustArgs,
+) -> Result<()> {
+    if args.forget {
+        if settings.forget_workspace_trust(cwd)? {
+            println!(
+                "Forgot the trust decision for {}; the next launch asks again",
+                cwd.display()
+            );
+        } else {
+            println!("No trust decision saved for {}", cwd.display());
+        }
+        return Ok(());
+    }
+    settings.set_workspace_trust(cwd, !args.deny)?;
+    let state = if args.deny { "untrusted" } else { "trusted" };
+    println!("Marked {} as {state}", cwd.display());
+    Ok(())
+}
+
 fn first_line(text: String) -> String {
     text.lines().next().unwrap_or_default().to_string()
 }
@@ -2,6 +2,7 @@ use crate::provider::{XAI_DEFAULT_BASE_URL, default_model_for, detect_provider};
 use crate::settings::{ApiKeySaveLocation, SettingsManager};
 use anyhow::{Context, Result, bail};
 use std::io::{self, BufRead, Write};
+use std::path::Path;
 
 pub fn run_first_time_setup(settings: &mut SettingsManager) -> Result<()> {
     println!("No API key is configured for the active provider.");
@@ -95,6 +96,38 @@ fn prompt_provider_type() -> Result<ProviderType> {
     }
 }
 
+/// Asks whether to trust `dir` on first launch there. Anything but yes leaves
+/// it untrusted.
+pub fn prompt_workspace_trust(dir: &Path) -> Result<bool> {
+    let stdin = io::stdin();
+    let mut input = stdin.lock();
+    let mut output = io::stdout();
+    prompt_workspace_trust_io(dir, &mut input, &mut output)
+}
+
+fn prompt_workspace_trust_io<R: BufRead, W: Write>(
+    dir: &Path,
+    input: &mut R,
+    output: &mut W,
+) -> Result<bool> {
+    writeln!(output, "Do you trust the files in {}?", dir.display())
+        .context("Failed writing prompt")?;
+    writeln!(
+        output,
+        "Trusted workspaces load .grok/GROK.md instructions and .grok/settings.json, including permission rules and language servers."
+    )
+    .context("Failed writing prompt")?;
+    write!(output, "Trust this workspace? [y/N]: ").context("Failed writing prompt")?;
+    output.flush().context("Failed flushing stdout")?;
+
+    let mut raw = String::new();
+    input.read_line(&mut raw).context("Failed reading input")?;
+    Ok(matches!(
+        raw.trim().to_ascii_lowercase().as_str(),
+        "y" | "yes"
+    ))
+}
+
 fn prompt_with_default(prompt: &str, default: &str) -> Result<String> {
     let stdin = io::stdin();
     let mut input = stdin.lock();
@@ -140,8 +173,11 @@ enum ProviderType {
 
 #[cfg(test)]
 mod tests {
-    use super::{ProviderType, parse_provider_choice, prompt_with_default_io};
+    use super::{
+        ProviderType, parse_provider_choice, prompt_with_default_io, prompt_workspace_trust_io,
+    };
     use std::io::Cursor;
+    use std::path::Path;
 
     #[test]
     fn parse_provider_choice_accepts_known_options() {
@@ -156,6 +192,25 @@ mod tests {
         assert!(parse_provider_choice("x").is_none());
     }
 
+    #[test]
+    fn workspace_trust_prompt_defaults_to_untrusted() {
+        let dir = Path::new("/work/repo");
+        let mut output = Vec::<u8>::new();
+        for (answer, expected) in [
+            ("y\n", true),
+            (" YES \n", true),
+            ("\n", false),
+            ("n\n", false),
+        ] {
+            let trusted = prompt_workspace_trust_io(dir, &mut Cursor::new(answer), &mut output)
+                .expect("answer");
+            assert_eq!(trusted, expected, "{answer:?}");
+        }
+        let rendered = String::from_utf8(output).expect("utf8 output");
+        assert!(rendered.contains("Do you trust the files in /work/repo?"));
+        assert!(rendered.contains("[y/N]: "));
+    }
+
     #[test]
     fn prompt_with_default_uses_default_for_blank_input() {
         let mut input = Cursor::new("\n");
@@ -0,0 +1,414 @@
+use crate::ignore_policy::IgnorePolicy;
+use ignore::WalkBuilder;
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+const MAX_INDEXED_PATHS: usize = 100_000;
+
+const MATCH_SCORE: i32 = 16;
+const SEGMENT_START_BONUS: i32 = 24;
+const CONSECUTIVE_BONUS: i32 = 16;
+const BASENAME_BONUS: i32 = 8;
+const MAX_GAP_PENALTY: i32 = 8;
+const EXACT_BASENAME_BONUS: i32 = 120;
+const BASENAME_STEM_BONUS: i32 = 90;
+const BASENAME_PREFIX_BONUS: i32 = 40;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexedPath {
+    /// Path relative to the index root, `/`-separated.
+    pub path: String,
+    pub is_dir: bool,
+    /// True when the entry or any of its parent directories is a dotfile.
+    pub hidden: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathMatch {
+    pub path: String,
+    pub is_dir: bool,
+    pub score: i32,
+}
+
+#[derive(Debug, Clone)]
+struct DirState {
+    modified: Option<SystemTime>,
+    gitignore_modified: Option<SystemTime>,
+    entries: Vec<IndexedPath>,
+}
+
+/// Gitignore-aware index of every file and directory under a root, ranked with
+/// fuzzy subsequence matching. `refresh` only re-lists directories whose mtime
+/// (or `.gitignore`) changed since the previous refresh, so it is cheap to call
+/// before every lookup.
+#[derive(Debug)]
+pub struct PathIndex {
+    root: PathBuf,
+    dirs: HashMap<PathBuf, DirState>,
+    ignore: IgnorePolicy,
+}
+
+impl PathIndex {
+    pub fn new(root: PathBuf) -> Self {
+        Self {
+            root,
+            dirs: HashMap::new(),
+            ignore: IgnorePolicy::default(),
+        }
+    }
+
+    /// Leaves out paths the policy hides; the next refresh re-lists everything.
+    pub fn set_ignore_policy(&mut self, ignore: IgnorePolicy) {
+        self.ignore = ignore;
+        self.dirs.clear();
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Brings the index up to date and returns how many directories were re-listed.
+    pub fn refresh(&mut self) -> usize {
+        let mut rescanned = 0usize;
+        let mut indexed = 0usize;
+        let mut live = HashSet::new();
+        let mut pending = vec![(self.root.clone(), false, false)];
+
+        while let Some((dir, hidden, parent_rules_changed)) = pending.pop() {
+            let modified = modified_time(&dir);
+            let gitignore_modified = modified_time(&dir.join(".gitignore"));
+            let fresh = !parent_rules_changed
+                && self.dirs.get(&dir).is_some_and(|state| {
+                    state.modified.is_some()
+                        && state.modified == modified
+                        && state.gitignore_modified == gitignore_modified
+                });
+            let rules_changed = parent_rules_changed
+                || self
+                    .dirs
+                    .get(&dir)
+                    .is_some_and(|state| state.gitignore_modified != gitignore_modified);
+            if !fresh {
+                let entries = self.scan_dir(&dir, hidden);
+                self.dirs.insert(
+                    dir.clone(),
+                    DirState {
+                        modified,
+                        gitignore_modified,
+                        entries,
+                    },
+                );
+                rescanned += 1;
+            }
+
+            let state = &self.dirs[&dir];
+            indexed += state.entries.len();
+            for entry in state.entries.iter().filter(|entry| entry.is_dir) {
+                pending.push((self.root.join(&entry.path), entry.hidden, rules_changed));
+            }
+            live.insert(dir);
+            if indexed >= MAX_INDEXED_PATHS {
+                break;
+            }
+        }
+
+        self.dirs.retain(|dir, _| live.contains(dir));
+        rescanned
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = &IndexedPath> {
+        self.dirs.values().flat_map(|state| state.entries.iter())
+    }
+
+    pub fn find(&self, query: &str, limit: usize) -> Vec<PathMatch> {
+        self.find_where(query, limit, |_| true)
+    }
+
+    /// Ranks entries accepted by `keep` against `query`, best first. Ties go to
+    /// the shorter path, then alphabetical order.
+    pub fn find_where(
+        &self,
+        query: &str,
+        limit: usize,
+        mut keep: impl FnMut(&IndexedPath) -> bool,
+    ) -> Vec<PathMatch> {
+        let mut matches = self
+            .entries()
+            .filter(|entry| keep(entry))
+            .filter_map(|entry| {
+                score_path(query, &entry.path).map(|score| PathMatch {
+                    path: entry.path.clone(),
+                    is_dir: entry.is_dir,
+                    score,
+                })
+            })
+            .collect::<Vec<_>>();
+        matches.sort_by(|a, b| match b.score.cmp(&a.score) {
+            Ordering::Equal => a
+                .path
+                .len()
+                .cmp(&b.path.len())
+                .then_with(|| a.path.cmp(&b.path)),
+            ordering => ordering,
+        });
+        matches.truncate(limit);
+        matches
+    }
+
+    fn scan_dir(&self, dir: &Path, parent_hidden: bool) -> Vec<IndexedPath> {
+        let ignore = self.ignore.clone();
+        let walker = WalkBuilder::new(dir)
+            .max_depth(Some(1))
+            .hidden(false)
+            .require_git(false)
+            .follow_links(false)
+            .filter_entry(move |entry| {
+                entry.file_name() != ".git"
+                    && !ignore.is_ignored(
+                        entry.path(),
+                        entry.file_type().is_some_and(|kind| kind.is_dir()),
+                    )
+            })
+            .build();
+
+        let mut entries = Vec::new();
+        for entry in walker.flatten() {
+            if entry.depth() == 0 {
+                continue;
+            }
+            let Ok(relative) = entry.path().strip_prefix(&self.root) else {
+                continue;
+            };
+            let path = relative
+                .components()
+                .map(|part| part.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            let hidden = parent_hidden || entry.file_name().to_string_lossy().starts_with('.');
+            entries.push(IndexedPath {
+                path,
+                is_dir: entry.file_type().is_some_and(|kind| kind.is_dir()),
+                hidden,
+            });
+        }
+        entries
+    }
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|meta| meta.modified()).ok()
+}
+
+/// Scores `path` as a fuzzy match for `query`, or `None` when the query is not
+/// a subsequence of the path. Matches inside the basename, at the start of a
+/// path segment or word, and in consecutive runs score higher; an empty query
+/// matches everything equally.
+pub fn score_path(query: &str, path: &str) -> Option<i32> {
+    let query = query
+        .chars()
+        .filter(|ch| !ch.is_whitespace())
+        .map(lowercase)
+        .collect::<Vec<_>>();
+    if query.is_empty() {
+        return Some(0);
+    }
+
+    let chars = path.chars().collect::<Vec<_>>();
+    let lower = chars.iter().copied().map(lowercase).collect::<Vec<_>>();
+    let base_start = chars
+        .iter()
+        .rposition(|ch| *ch == '/')
+        .map_or(0, |index| index + 1);
+    let in_basename = if query.contains(&'/') {
+        None
+    } else {
+        match_positions(&query, &lower, base_start)
+    };
+    let positions = in_basename.or_else(|| match_positions(&query, &lower, 0))?;
+
+    let mut score = 0i32;
+    let mut previous: Option<usize> = None;
+    for &position in &positions {
+        score += MATCH_SCORE;
+        if is_segment_start(&chars, position) {
+            score += SEGMENT_START_BONUS;
+        }
+        if position >= base_start {
+            score += BASENAME_BONUS;
+        }
+        match previous {
+            Some(prev) if position == prev + 1 => score += CONSECUTIVE_BONUS,
+            Some(prev) => score -= ((position - prev - 1) as i32).min(MAX_GAP_PENALTY),
+            None => {}
+        }
+        previous = Some(position);
+    }
+
+    let basename = lower[base_start..].iter().collect::<String>();
+    let query = query.iter().collect::<String>();
+    if basename == query {
+        score += EXACT_BASENAME_BONUS;
+    } else if basename.split('.').next() == Some(query.as_str()) {
+        score += BASENAME_STEM_BONUS;
+    } else if basename.starts_with(&query) {
+        score += BASENAME_PREFIX_BONUS;
+    }
+    score -= (chars.len() / 8) as i32;
+    Some(score.max(1))
+}
+
+fn lowercase(ch: char) -> char {
+    ch.to_lowercase().next().unwrap_or(ch)
+}
+
+fn match_positions(query: &[char], lower: &[char], from: usize) -> Option<Vec<usize>> {
+    let mut positions = Vec::with_capacity(query.len());
+    for (index, ch) in lower.iter().enumerate().skip(from) {
+        if positions.len() == query.len() {
+            break;
+        }
+        if *ch == query[positions.len()] {
+            positions.push(index);
+        }
+    }
+    (positions.len() == query.len()).then_some(positions)
+}
+
+fn is_segment_start(chars: &[char], position: usize) -> bool {
+    if position == 0 {
+        return true;
+    }
+    let previous = chars[position - 1];
+    matches!(previous, '/' | '_' | '-' | '.' | ' ')
+        || (previous.is_lowercase() && chars[position].is_uppercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PathIndex, score_path};
+    use crate::ignore_policy::IgnorePolicy;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    #[test]
+    fn score_path_prefers_basename_and_segment_matches() {
+        let exact = score_path("main.rs", "src/main.rs").expect("match");
+        let stem = score_path("main", "src/main.rs").expect("match");
+        let buried = score_path("main", "src/domain/nested.rs").expect("match");
+        assert!(exact > stem);
+        assert!(stem > buried);
+
+        assert!(
+            score_path("search", "src/tools/search_tool.rs").expect("match")
+                > score_path("search", "src/research/tooling.rs").unwrap_or(0)
+        );
+        assert!(
+            score_path("st", "src/tools/search_tool.rs").expect("match")
+                > score_path("st", "src/tools/list.rs").expect("match")
+        );
+        assert_eq!(score_path("xyz", "src/main.rs"), None);
+        assert!(score_path("tools/search", "src/tools/search_tool.rs").is_some());
+    }
+
+    #[test]
+    fn index_respects_gitignore_and_ranks_results() {
+        let temp = TempDir::new("path-index-rank");
+        let root = temp.path();
+        fs::create_dir_all(root.join("src/ui")).expect("create dirs");
+        fs::create_dir_all(root.join("target/debug")).expect("create dirs");
+        fs::write(root.join(".gitignore"), "target/\n").expect("write");
+        fs::write(root.join("src/ui/prompt.rs"), "").expect("write");
+        fs::write(root.join("src/prompt_utils.rs"), "").expect("write");
+        fs::write(root.join("target/debug/prompt.rs"), "").expect("write");
+
+        let mut index = PathIndex::new(root.to_path_buf());
+        index.refresh();
+
+        let matches = index.find("prompt", 10);
+        let paths = matches
+            .iter()
+            .map(|found| found.path.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(paths, vec!["src/ui/prompt.rs", "src/prompt_utils.rs"]);
+        assert!(
+            index
+                .entries()
+                .any(|entry| entry.path == ".gitignore" && entry.hidden)
+        );
+        assert!(
+            index
+                .entries()
+                .any(|entry| entry.path == "src/ui" && entry.is_dir)
+        );
+
+        index
+            .set_ignore_policy(IgnorePolicy::load(root, &["src/ui/".to_string()]).expect("policy"));
+        index.refresh();
+        let matches = index.find("prompt", 10);
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].path, "src/prompt_utils.rs");
+    }
+
+    #[test]
+    fn refresh_only_rescans_changed_directories() {
+        let temp = TempDir::new("path-index-refresh");
+        let root = temp.path();
+        fs::create_dir_all(root.join("a")).expect("create dirs");
+        fs::create_dir_all(root.join("b")).expect("create dirs");
+        fs::write(root.join("a/one.rs"), "").expect("write");
+
+        let mut index = PathIndex::new(root.to_path_buf());
+        assert_eq!(index.refresh(), 3);
+        assert_eq!(index.refresh(), 0);
+
+        fs::write(root.join("b/two.rs"), "").expect("write");
+        force_new_mtime(&root.join("b"));
+        assert_eq!(index.refresh(), 1);
+        assert_eq!(index.find("two", 5)[0].path, "b/two.rs");
+
+        fs::remove_dir_all(root.join("a")).expect("remove");
+        force_new_mtime(root);
+        index.refresh();
+        assert!(index.find("one", 5).is_empty());
+    }
+
+    /// Some filesystems have coarse mtime resolution; bump it explicitly so the
+    /// change is visible regardless of how fast the test runs.
+    fn force_new_mtime(dir: &Path) {
+        let later = SystemTime::now() + std::time::Duration::from_secs(5);
+        fs::File::open(dir)
+            .and_then(|handle| handle.set_modified(later))
+            .expect("set mtime");
+    }
+
+    struct TempDir {
+        path: PathBuf,
+    }
+
+    impl TempDir {
+        fn new(prefix: &str) -> Self {
+            let nonce = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .expect("clock after epoch")
+                .as_nanos();
+            let pid = std::process::id();
+            let path = std::env::temp_dir().join(format!("codetoad-{prefix}-{pid}-{nonce}"));
+            fs::create_dir_all(&path).expect("create temp dir");
+            Self { path }
+        }
+
+        fn path(&self) -> &Path {
+            &self.path
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.path);
+        }
+    }
+}
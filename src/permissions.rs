@@ -0,0 +1,1018 @@
+use crate::confirmation::ConfirmationOperation;
+use crate::shell::{self, RedirectOp, Script, Word};
+use crate::tool_catalog::{
+    TOOL_BASH, TOOL_BASH_BACKGROUND, confirmation_operation_for_tool, is_read_only_tool,
+};
+use crate::tool_context::ToolContext;
+use anyhow::{Context, Result, bail};
+use globset::{GlobBuilder, GlobMatcher};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// Argument keys that name the paths a tool call touches.
+const PATH_ARGUMENTS: &[&str] = &["path", "source", "destination"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PermissionAction {
+    Allow,
+    Ask,
+    Deny,
+}
+
+impl PermissionAction {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Allow => "allow",
+            Self::Ask => "ask",
+            Self::Deny => "deny",
+        }
+    }
+}
+
+/// Where a rule was loaded from. Matching managed rules decide on their own;
+/// the others are pooled, with deny beating ask beating allow.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RuleSource {
+    Managed,
+    Project,
+    User,
+    Session,
+}
+
+impl RuleSource {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Managed => "managed settings",
+            Self::Project => "project settings",
+            Self::User => "user settings",
+            Self::Session => "this session",
+        }
+    }
+}
+
+/// How much the agent may do without asking, cycled with Shift+Tab.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PermissionMode {
+    /// Rules decide; file edits and commands that may write ask.
+    #[default]
+    Default,
+    /// File edits run without asking; commands still ask.
+    AcceptEdits,
+    /// Only tools that read may run, so the agent can explore and plan.
+    Plan,
+    /// Nothing asks; deny rules still apply.
+    Bypass,
+}
+
+impl PermissionMode {
+    pub const ALL: [Self; 4] = [Self::Default, Self::AcceptEdits, Self::Plan, Self::Bypass];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Default => "default",
+            Self::AcceptEdits => "accept_edits",
+            Self::Plan => "plan",
+            Self::Bypass => "bypass",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Default => "default",
+            Self::AcceptEdits => "accept edits",
+            Self::Plan => "plan",
+            Self::Bypass => "bypass permissions",
+        }
+    }
+
+    pub fn next(self) -> Self {
+        let index = Self::ALL.iter().position(|mode| *mode == self).unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+
+    /// Adjusts a rule or default decision for this mode. Deny always stands;
+    /// explicit ask rules only give way in bypass mode.
+    pub fn apply(
+        self,
+        request: &PermissionRequest,
+        decision: PermissionDecision,
+    ) -> PermissionDecision {
+        let overridden = |action| PermissionDecision {
+            action,
+            rule: decision.rule.clone(),
+            mode: Some(self),
+        };
+        match (self, decision.action) {
+            (_, PermissionAction::Deny) | (Self::Default, _) => decision,
+            (Self::AcceptEdits, PermissionAction::Ask)
+                if decision.rule.is_none()
+                    && confirmation_operation_for_tool(&request.tool)
+                        == Some(ConfirmationOperation::File) =>
+            {
+                overridden(PermissionAction::Allow)
+            }
+            (Self::AcceptEdits, _) => decision,
+            (Self::Plan, _) => {
+                if is_read_only_tool(&request.tool) || request.read_only {
+                    decision
+                } else {
+                    overridden(PermissionAction::Deny)
+                }
+            }
+            (Self::Bypass, PermissionAction::Ask) => overridden(PermissionAction::Allow),
+            (Self::Bypass, PermissionAction::Allow) => decision,
+        }
+    }
+}
+
+/// `tool(pattern)=action`, e.g. `bash(cargo test:*)` or `view_file(secrets/**)=deny`.
+/// The action defaults to allow and a missing pattern covers every call of the
+/// tool. Bash patterns match the command, where `*` is any text and a trailing
+/// `:*` means "followed by arguments or nothing"; other tools match their path
+/// arguments with globs relative to the project root. `bash` rules also cover
+/// `bash_background`.
+#[derive(Debug, Clone)]
+pub struct PermissionRule {
+    tool: String,
+    pattern: Option<String>,
+    action: PermissionAction,
+    glob: Option<GlobMatcher>,
+}
+
+impl PermissionRule {
+    pub fn new(tool: &str, pattern: Option<&str>, action: PermissionAction) -> Result<Self> {
+        let tool = tool.trim();
+        if tool.is_empty()
+            || !tool
+                .chars()
+                .all(|ch| ch.is_ascii_alphanumeric() || ch == '_')
+        {
+            bail!("invalid tool name `{tool}`");
+        }
+        let pattern = pattern.map(str::trim).filter(|pattern| !pattern.is_empty());
+        let glob = match pattern {
+            Some(pattern) if !is_command_tool(tool) => Some(
+                GlobBuilder::new(pattern)
+                    .literal_separator(true)
+                    .build()
+                    .with_context(|| format!("invalid path pattern `{pattern}`"))?
+                    .compile_matcher(),
+            ),
+            _ => None,
+        };
+        Ok(Self {
+            tool: tool.to_string(),
+            pattern: pattern.map(str::to_string),
+            action,
+            glob,
+        })
+    }
+
+    pub fn tool(&self) -> &str {
+        &self.tool
+    }
+
+    pub fn action(&self) -> PermissionAction {
+        self.action
+    }
+
+    /// The narrowest rule that would have allowed this call: the program and
+    /// subcommand of a simple command, the exact text of anything more
+    /// complex, or the exact paths of a file tool.
+    pub fn suggested_for(request: &PermissionRequest) -> Self {
+        let pattern = match &request.subject {
+            Subject::Command(command) => Some(suggested_command_pattern(command)),
+            Subject::Paths(paths) => {
+                let patterns = paths
+                    .iter()
+                    .map(|path| match path.strip_prefix(&request.root) {
+                        Ok(relative) if !relative.as_os_str().is_empty() => {
+                            globset::escape(&slash_path(relative))
+                        }
+                        Ok(_) => "**".to_string(),
+                        Err(_) => globset::escape(&slash_path(path)),
+                    })
+                    .collect::<Vec<_>>();
+                match patterns.as_slice() {
+                    [] => None,
+                    [single] => Some(single.clone()),
+                    _ => Some(format!("{{{}}}", patterns.join(","))),
+                }
+            }
+            Subject::None => None,
+        };
+        Self::new(&request.tool, pattern.as_deref(), PermissionAction::Allow).unwrap_or_else(|_| {
+            Self {
+                tool: request.tool.clone(),
+                pattern: None,
+                action: PermissionAction::Allow,
+                glob: None,
+            }
+        })
+    }
+
+    fn applies_to_tool(&self, tool: &str) -> bool {
+        self.tool == tool || (self.tool == TOOL_BASH && tool == TOOL_BASH_BACKGROUND)
+    }
+
+    fn matches_command(&self, command: &str) -> bool {
+        match &self.pattern {
+            Some(pattern) => command_matches(pattern, command),
+            None => true,
+        }
+    }
+
+    fn matches_path(&self, path: &Path, root: &Path) -> bool {
+        let Some(glob) = &self.glob else {
+            return self.pattern.is_none();
+        };
+        let pattern = self.pattern.as_deref().unwrap_or_default();
+        if Path::new(pattern).is_absolute() {
+            return glob.is_match(path);
+        }
+        match path.strip_prefix(root) {
+            Ok(relative) if relative.as_os_str().is_empty() => glob.is_match("."),
+            Ok(relative) => glob.is_match(relative),
+            Err(_) => false,
+        }
+    }
+
+    /// Deny and ask rules fire when any part of the call matches.
+    fn hits(&self, request: &PermissionRequest) -> bool {
+        if !self.applies_to_tool(&request.tool) {
+            return false;
+        }
+        match &request.subject {
+            Subject::Command(command) => {
+                self.matches_command(command)
+                    || command_segments(command)
+                        .iter()
+                        .any(|segment| self.matches_command(segment))
+            }
+            Subject::Paths(paths) => paths
+                .iter()
+                .any(|path| self.matches_path(path, &request.root)),
+            Subject::None => self.pattern.is_none(),
+        }
+    }
+}
+
+impl fmt::Display for PermissionRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.tool)?;
+        if let Some(pattern) = &self.pattern {
+            write!(f, "({pattern})")?;
+        }
+        if self.action != PermissionAction::Allow {
+            write!(f, "={}", self.action.as_str())?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for PermissionRule {
+    type Err = anyhow::Error;
+
+    fn from_str(raw: &str) -> Result<Self> {
+        let raw = raw.trim();
+        let (body, action) = match raw.rsplit_once('=') {
+            Some((body, action))
+                if !action.contains(')') && (body.ends_with(')') || !body.contains('(')) =>
+            {
+                let action = match action.trim().to_ascii_lowercase().as_str() {
+                    "allow" => PermissionAction::Allow,
+                    "ask" => PermissionAction::Ask,
+                    "deny" => PermissionAction::Deny,
+                    other => bail!("unknown permission action `{other}` in `{raw}`"),
+                };
+                (body.trim(), action)
+            }
+            _ => (raw, PermissionAction::Allow),
+        };
+        let (tool, pattern) = match body.split_once('(') {
+            Some((tool, rest)) => {
+                let Some(pattern) = rest.strip_suffix(')') else {
+                    bail!("missing `)` in permission rule `{raw}`");
+                };
+                (tool, Some(pattern))
+            }
+            None => (body, None),
+        };
+        Self::new(tool, pattern, action).with_context(|| format!("in permission rule `{raw}`"))
+    }
+}
+
+/// What a tool call would touch, resolved against the session's directories.
+#[derive(Debug, Clone)]
+pub struct PermissionRequest {
+    tool: String,
+    subject: Subject,
+    root: PathBuf,
+    /// Whether a command provably only reads; recursive searches do not count
+    /// when ignore patterns are set, since they reach ignored files unnamed.
+    read_only: bool,
+}
+
+#[derive(Debug, Clone)]
+enum Subject {
+    Command(String),
+    Paths(Vec<PathBuf>),
+    None,
+}
+
+impl PermissionRequest {
+    pub fn new(tool: &str, args: &Value, context: &ToolContext) -> Self {
+        let subject = if is_command_tool(tool) {
+            Subject::Command(
+                args.get("command")
+                    .and_then(Value::as_str)
+                    .unwrap_or_default()
+                    .trim()
+                    .to_string(),
+            )
+        } else {
+            let paths = PATH_ARGUMENTS
+                .iter()
+                .filter_map(|key| args.get(*key).and_then(Value::as_str))
+                .map(|raw| {
+                    // Paths that fail to resolve escape the root and are
+                    // refused by the tool itself; absolute rules may still
+                    // name them.
+                    context.resolve_path(raw).unwrap_or_else(|_| {
+                        let path = Path::new(raw);
+                        if path.is_absolute() {
+                            path.to_path_buf()
+                        } else {
+                            context.current_dir().join(path)
+                        }
+                    })
+                })
+                .collect::<Vec<_>>();
+            if paths.is_empty() {
+                Subject::None
+            } else {
+                Subject::Paths(paths)
+            }
+        };
+        let read_only = match &subject {
+            Subject::Command(command) => {
+                shell::is_read_only(command)
+                    && (context.ignore_policy().is_empty() || !shell::reads_recursively(command))
+            }
+            _ => false,
+        };
+        Self {
+            tool: tool.to_string(),
+            subject,
+            root: context.project_root().to_path_buf(),
+            read_only,
+        }
+    }
+
+    pub fn tool(&self) -> &str {
+        &self.tool
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PermissionDecision {
+    pub action: PermissionAction,
+    /// The deciding rule and where it came from; `None` for tool defaults.
+    pub rule: Option<(RuleSource, String)>,
+    /// The permission mode, when it overrode the rule or default.
+    pub mode: Option<PermissionMode>,
+}
+
+impl PermissionDecision {
+    /// Tools that change files or run commands ask by default; the rest run,
+    /// as do shell commands that provably only read.
+    pub fn default_for(request: &PermissionRequest) -> Self {
+        Self {
+            action: if confirmation_operation_for_tool(&request.tool).is_some()
+                && !request.read_only
+            {
+                PermissionAction::Ask
+            } else {
+                PermissionAction::Allow
+            },
+            rule: None,
+            mode: None,
+        }
+    }
+
+    pub fn denial_message(&self) -> String {
+        if self.mode == Some(PermissionMode::Plan) {
+            return "Permission denied: plan mode only allows tools that read. Present the plan instead of making changes".to_string();
+        }
+        match &self.rule {
+            Some((source, rule)) => {
+                format!(
+                    "Permission denied by rule `{rule}` from {}",
+                    source.as_str()
+                )
+            }
+            None => "Permission denied".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct PermissionRules {
+    rules: Vec<(RuleSource, PermissionRule)>,
+}
+
+impl PermissionRules {
+    pub fn parse<'a>(
+        source: RuleSource,
+        raw_rules: impl IntoIterator<Item = &'a String>,
+    ) -> Result<Self> {
+        let mut rules = Self::default();
+        for raw in raw_rules {
+            let rule = raw
+                .parse::<PermissionRule>()
+                .with_context(|| format!("Invalid permission rule in {}", source.as_str()))?;
+            rules.add(source, rule);
+        }
+        Ok(rules)
+    }
+
+    pub fn add(&mut self, source: RuleSource, rule: PermissionRule) {
+        let rendered = rule.to_string();
+        let exists = self.rules.iter().any(|(existing_source, existing)| {
+            *existing_source == source && existing.to_string() == rendered
+        });
+        if !exists {
+            self.rules.push((source, rule));
+        }
+    }
+
+    pub fn extend(&mut self, other: Self) {
+        for (source, rule) in other.rules {
+            self.add(source, rule);
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    pub fn from_source(&self, source: RuleSource) -> impl Iterator<Item = &PermissionRule> {
+        self.rules
+            .iter()
+            .filter(move |(rule_source, _)| *rule_source == source)
+            .map(|(_, rule)| rule)
+    }
+
+    /// Keeps the rules whose source passes `keep`, e.g. to reload settings
+    /// without dropping session rules.
+    pub fn retain(&mut self, keep: impl Fn(RuleSource) -> bool) {
+        self.rules.retain(|(source, _)| keep(*source));
+    }
+
+    /// `None` when no rule matched, so the caller falls back to the tool default.
+    pub fn evaluate(&self, request: &PermissionRequest) -> Option<PermissionDecision> {
+        let managed = self
+            .rules
+            .iter()
+            .filter(|(source, _)| *source == RuleSource::Managed)
+            .collect::<Vec<_>>();
+        let others = self
+            .rules
+            .iter()
+            .filter(|(source, _)| *source != RuleSource::Managed)
+            .collect::<Vec<_>>();
+        decide(&managed, request).or_else(|| decide(&others, request))
+    }
+}
+
+fn decide(
+    rules: &[&(RuleSource, PermissionRule)],
+    request: &PermissionRequest,
+) -> Option<PermissionDecision> {
+    let decision = |action, (source, rule): &(RuleSource, PermissionRule)| PermissionDecision {
+        action,
+        rule: Some((*source, rule.to_string())),
+        mode: None,
+    };
+    for action in [PermissionAction::Deny, PermissionAction::Ask] {
+        if let Some(entry) = rules
+            .iter()
+            .find(|(_, rule)| rule.action == action && rule.hits(request))
+        {
+            return Some(decision(action, entry));
+        }
+    }
+
+    let allows = rules
+        .iter()
+        .filter(|(_, rule)| {
+            rule.action == PermissionAction::Allow && rule.applies_to_tool(&request.tool)
+        })
+        .collect::<Vec<_>>();
+    let covering = |part: &dyn Fn(&PermissionRule) -> bool| {
+        allows.iter().find(|(_, rule)| part(rule)).copied()
+    };
+    // Allow rules must cover every part of the call, so approving `git status`
+    // never approves what is chained after it.
+    let allowed_by = match &request.subject {
+        Subject::Command(command) => covering(&|rule: &PermissionRule| {
+            rule.pattern
+                .as_deref()
+                .is_none_or(|pattern| !pattern.contains('*') && pattern == command)
+        })
+        .or_else(|| {
+            let segments = split_simple_commands(command)?;
+            let mut first = None;
+            for segment in &segments {
+                let entry = covering(&|rule: &PermissionRule| rule.matches_command(segment))?;
+                first.get_or_insert(entry);
+            }
+            first
+        }),
+        Subject::Paths(paths) => {
+            let mut first = None;
+            for path in paths {
+                match covering(&|rule: &PermissionRule| rule.matches_path(path, &request.root)) {
+                    Some(entry) => {
+                        first.get_or_insert(entry);
+                    }
+                    None => return None,
+                }
+            }
+            first
+        }
+        Subject::None => covering(&|rule: &PermissionRule| rule.pattern.is_none()),
+    };
+    allowed_by.map(|entry| decision(PermissionAction::Allow, entry))
+}
+
+fn is_command_tool(tool: &str) -> bool {
+    tool == TOOL_BASH || tool == TOOL_BASH_BACKGROUND
+}
+
+fn slash_path(path: &Path) -> String {
+    path.to_string_lossy().replace('\\', "/")
+}
+
+fn command_matches(pattern: &str, command: &str) -> bool {
+    let pattern = pattern.split_whitespace().collect::<Vec<_>>().join(" ");
+    let command = command.split_whitespace().collect::<Vec<_>>().join(" ");
+    if let Some(prefix) = pattern.strip_suffix(":*") {
+        let prefix = prefix.trim_end();
+        return command == prefix
+            || command
+                .strip_prefix(prefix)
+                .is_some_and(|rest| rest.starts_with(' '));
+    }
+    wildcard_match(&pattern, &command)
+}
+
+/// `*` matches any run of characters, including none.
+fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = text.strip_prefix(first) else {
+        return false;
+    };
+    let parts = parts.collect::<Vec<_>>();
+    let Some((last, middle)) = parts.split_last() else {
+        return rest.is_empty();
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+    rest.len() >= last.len() && rest.ends_with(last)
+}
+
+/// Programs that run whatever their arguments say, so a wildcard rule for them
+/// would allow any command.
+const COMMAND_RUNNERS: &[&str] = &[
+    "sh", "bash", "zsh", "dash", "ksh", "fish", "python", "python3", "node", "deno", "bun", "perl",
+    "ruby", "env", "xargs", "sudo", "exec", "eval", "command", "nohup", "nice", "timeout", "watch",
+];
+
+/// `program subcommand:*` for commands like `cargo test --all`; anything else,
+/// including flags right after the program (`rm -rf target`) and programs that
+/// run other commands, is suggested exactly.
+fn suggested_command_pattern(command: &str) -> String {
+    let simple = split_simple_commands(command).filter(|segments| segments.len() == 1);
+    let Some(segment) = simple.as_ref().and_then(|segments| segments.first()) else {
+        return command.to_string();
+    };
+    let words = segment.split_whitespace().collect::<Vec<_>>();
+    let plain = |word: &str| {
+        word.chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || matches!(ch, '-' | '_' | '.' | '/'))
+    };
+    match words.as_slice() {
+        [program, subcommand, ..]
+            if plain(program)
+                && !program.contains('=')
+                && !COMMAND_RUNNERS.contains(program)
+                && subcommand.starts_with(|ch: char| ch.is_ascii_alphabetic())
+                && subcommand
+                    .chars()
+                    .all(|ch| ch.is_ascii_alphanumeric() || matches!(ch, '-' | '_')) =>
+        {
+            format!("{program} {subcommand}:*")
+        }
+        _ => command.to_string(),
+    }
+}
+
+/// Keywords of compound commands, dropped from the front of a segment so
+/// `if make; then rm -rf x; fi` is judged as `make` and `rm -rf x`.
+const LEADING_KEYWORDS: &[&str] = &[
+    "if", "then", "else", "elif", "fi", "do", "done", "while", "until", "{", "}", "!", "time",
+];
+
+/// Every simple command in `command`, including those inside subshells and
+/// substitutions, for deny and ask rules to look at.
+fn command_segments(command: &str) -> Vec<String> {
+    shell::parse_command(command)
+        .map(|script| segments_of(&script))
+        .unwrap_or_default()
+}
+
+/// The simple commands of `command`, or `None` when it substitutes commands
+/// or writes files through redirections, which an allow rule for its plain
+/// words should not approve.
+fn split_simple_commands(command: &str) -> Option<Vec<String>> {
+    let script = shell::parse_command(command).ok()?;
+    let substitutes = !script.is_fully_supported()
+        || script.simple_commands().iter().any(|simple| {
+            simple
+                .assignments
+                .iter()
+                .chain(&simple.words)
+                .chain(
+                    simple
+                        .redirections
+                        .iter()
+                        .map(|redirection| &redirection.target),
+                )
+                .any(Word::has_substitution)
+        })
+        || script
+            .here_docs
+            .iter()
+            .any(|doc| doc.expands && (doc.body.contains("$(") || doc.body.contains('`')));
+    let writes = script.redirections().iter().any(|redirection| {
+        let target = redirection.target.static_value().unwrap_or_default();
+        match redirection.op {
+            RedirectOp::DupOutput => !(target == "-" || target.parse::<u32>().is_ok()),
+            op => op.writes_file() && target != "/dev/null",
+        }
+    });
+    if substitutes || writes {
+        return None;
+    }
+    Some(segments_of(&script))
+}
+
+fn segments_of(script: &Script) -> Vec<String> {
+    script
+        .simple_commands()
+        .into_iter()
+        .map(|simple| {
+            let words = simple
+                .words
+                .iter()
+                .map(|word| word.raw.as_str())
+                .skip_while(|word| LEADING_KEYWORDS.contains(word));
+            simple
+                .assignments
+                .iter()
+                .map(|word| word.raw.clone())
+                .chain(words.map(str::to_string))
+                .chain(simple.redirections.iter().map(|redirection| {
+                    format!(
+                        "{}{}{}",
+                        redirection.fd.map(|fd| fd.to_string()).unwrap_or_default(),
+                        redirection.op.as_str(),
+                        redirection.target.raw
+                    )
+                }))
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .filter(|segment| !segment.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        PermissionAction, PermissionDecision, PermissionRequest, PermissionRule, PermissionRules,
+        RuleSource, split_simple_commands,
+    };
+    use crate::tool_context::ToolContext;
+    use serde_json::json;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    #[test]
+    fn parses_and_renders_rules() {
+        let rule = "view_file(secrets/**)=deny"
+            .parse::<PermissionRule>()
+            .expect("rule");
+        assert_eq!(rule.action(), PermissionAction::Deny);
+        assert_eq!(rule.to_string(), "view_file(secrets/**)=deny");
+        assert_eq!(
+            "bash(FOO=1 make)"
+                .parse::<PermissionRule>()
+                .expect("rule")
+                .to_string(),
+            "bash(FOO=1 make)"
+        );
+        assert_eq!(
+            "search=ask"
+                .parse::<PermissionRule>()
+                .expect("rule")
+                .to_string(),
+            "search=ask"
+        );
+        assert!("bash(git status".parse::<PermissionRule>().is_err());
+        assert!("bash(ls)=maybe".parse::<PermissionRule>().is_err());
+    }
+
+    #[test]
+    fn allow_rules_cover_every_chained_command() {
+        let temp = TempDir::new("permissions-bash");
+        let context = ToolContext::new(temp.path().to_path_buf()).expect("context");
+        let rules = rules(
+            RuleSource::User,
+            &["bash(git status*)", "bash(cargo test:*)", "bash(rm:*)=deny"],
+        );
+        let decide = |command: &str| {
+            rules
+                .evaluate(&PermissionRequest::new(
+                    "bash",
+                    &json!({ "command": command }),
+                    &context,
+                ))
+                .map(|decision| decision.action)
+        };
+
+        assert_eq!(decide("git status --short"), Some(PermissionAction::Allow));
+        assert_eq!(decide("cargo test"), Some(PermissionAction::Allow));
+        assert_eq!(
+            decide("cargo test -- --nocapture"),
+            Some(PermissionAction::Allow)
+        );
+        assert_eq!(decide("cargo testing"), None);
+        assert_eq!(
+            decide("git status && cargo test"),
+            Some(PermissionAction::Allow)
+        );
+        assert_eq!(decide("git status && curl example.com"), None);
+        assert_eq!(decide("git status $(curl example.com)"), None);
+        assert_eq!(decide("git status > status.txt"), None);
+        assert_eq!(
+            decide("git status && rm -rf target"),
+            Some(PermissionAction::Deny)
+        );
+        assert_eq!(
+            decide("echo $(rm -rf target)"),
+            Some(PermissionAction::Deny)
+        );
+        assert_eq!(
+            decide("if cargo test; then rm -rf target; fi"),
+            Some(PermissionAction::Deny)
+        );
+    }
+
+    #[test]
+    fn read_only_commands_run_by_default() {
+        let temp = TempDir::new("permissions-read-only");
+        let context = ToolContext::new(temp.path().to_path_buf()).expect("context");
+        let default = |tool: &str, args: serde_json::Value| {
+            PermissionDecision::default_for(&PermissionRequest::new(tool, &args, &context)).action
+        };
+
+        assert_eq!(
+            default("view_file", json!({ "path": "src/main.rs" })),
+            PermissionAction::Allow
+        );
+        assert_eq!(
+            default("create_file", json!({ "path": "a.txt" })),
+            PermissionAction::Ask
+        );
+        assert_eq!(
+            default("bash", json!({ "command": "git push" })),
+            PermissionAction::Ask
+        );
+        assert_eq!(
+            default("bash", json!({ "command": "git log --oneline | head -5" })),
+            if cfg!(windows) {
+                PermissionAction::Ask
+            } else {
+                PermissionAction::Allow
+            }
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn recursive_searches_ask_when_ignore_patterns_are_set() {
+        let temp = TempDir::new("permissions-recursive");
+        let root = fs::canonicalize(temp.path()).expect("root");
+        let open = ToolContext::new(root.clone()).expect("context");
+        fs::write(root.join(".grokignore"), ".env\n").expect("write");
+        let ignoring = ToolContext::new(root).expect("context");
+        let default = |context: &ToolContext, command: &str| {
+            PermissionDecision::default_for(&PermissionRequest::new(
+                "bash",
+                &json!({ "command": command }),
+                context,
+            ))
+            .action
+        };
+
+        assert_eq!(default(&open, "grep -r KEY ."), PermissionAction::Allow);
+        assert_eq!(default(&ignoring, "grep -r KEY ."), PermissionAction::Ask);
+        assert_eq!(default(&ignoring, "rg KEY"), PermissionAction::Ask);
+        assert_eq!(
+            default(&ignoring, "grep KEY a.txt"),
+            PermissionAction::Allow
+        );
+    }
+
+    #[test]
+    fn path_rules_match_relative_to_the_project_root() {
+        let temp = TempDir::new("permissions-paths");
+        let root = fs::canonicalize(temp.path()).expect("root");
+        fs::create_dir_all(root.join("src/nested")).expect("mkdir");
+        let context = ToolContext::new(root).expect("context");
+        let mut rules = rules(RuleSource::Project, &["str_replace_editor(src/**)"]);
+        rules.extend(self::rules(
+            RuleSource::User,
+            &["view_file(secrets/**)=deny"],
+        ));
+        let decide = |tool: &str, args: serde_json::Value| {
+            rules
+                .evaluate(&PermissionRequest::new(tool, &args, &context))
+                .map(|decision| decision.action)
+        };
+
+        assert_eq!(
+            decide("str_replace_editor", json!({ "path": "src/nested/lib.rs" })),
+            Some(PermissionAction::Allow)
+        );
+        assert_eq!(
+            decide(
+                "str_replace_editor",
+                json!({ "path": "./src/../Cargo.toml" })
+            ),
+            None
+        );
+        assert_eq!(
+            decide("view_file", json!({ "path": "secrets/key.pem" })),
+            Some(PermissionAction::Deny)
+        );
+        assert_eq!(decide("view_file", json!({ "path": "src/main.rs" })), None);
+    }
+
+    #[test]
+    fn managed_rules_take_precedence_and_deny_beats_allow() {
+        let temp = TempDir::new("permissions-precedence");
+        let context = ToolContext::new(temp.path().to_path_buf()).expect("context");
+        let request =
+            PermissionRequest::new("bash", &json!({ "command": "curl example.com" }), &context);
+
+        let mut rules = rules(RuleSource::Session, &["bash(curl:*)"]);
+        rules.extend(self::rules(RuleSource::User, &["bash(curl:*)=ask"]));
+        let decision = rules.evaluate(&request).expect("decision");
+        assert_eq!(decision.action, PermissionAction::Ask);
+        assert_eq!(
+            decision.rule,
+            Some((RuleSource::User, "bash(curl:*)=ask".to_string()))
+        );
+
+        rules.extend(self::rules(RuleSource::Managed, &["bash(curl:*)=deny"]));
+        let decision = rules.evaluate(&request).expect("decision");
+        assert_eq!(
+            decision.denial_message(),
+            "Permission denied by rule `bash(curl:*)=deny` from managed settings"
+        );
+
+        let managed_allow = self::rules(RuleSource::Managed, &["bash"]);
+        let mut rules = self::rules(RuleSource::Project, &["bash(curl:*)=deny"]);
+        rules.extend(managed_allow);
+        assert_eq!(
+            rules.evaluate(&request).map(|decision| decision.action),
+            Some(PermissionAction::Allow)
+        );
+    }
+
+    #[test]
+    fn suggests_narrow_rules() {
+        let temp = TempDir::new("permissions-suggest");
+        let root = fs::canonicalize(temp.path()).expect("root");
+        let context = ToolContext::new(root).expect("context");
+        let suggest = |tool: &str, args: serde_json::Value| {
+            PermissionRule::suggested_for(&PermissionRequest::new(tool, &args, &context))
+                .to_string()
+        };
+
+        assert_eq!(
+            suggest("bash", json!({ "command": "cargo test --all" })),
+            "bash(cargo test:*)"
+        );
+        assert_eq!(
+            suggest("bash", json!({ "command": "ls -la" })),
+            "bash(ls -la)"
+        );
+        assert_eq!(
+            suggest("bash", json!({ "command": "rm -rf target" })),
+            "bash(rm -rf target)"
+        );
+        assert_eq!(
+            suggest("bash", json!({ "command": "sh -c 'make all'" })),
+            "bash(sh -c 'make all')"
+        );
+        assert_eq!(
+            suggest("bash", json!({ "command": "python script.py" })),
+            "bash(python script.py)"
+        );
+        assert_eq!(
+            suggest("bash", json!({ "command": "xargs rm" })),
+            "bash(xargs rm)"
+        );
+        assert_eq!(
+            suggest("bash", json!({ "command": "make && ./run.sh" })),
+            "bash(make && ./run.sh)"
+        );
+        assert_eq!(
+            suggest("str_replace_editor", json!({ "path": "src/main.rs" })),
+            "str_replace_editor(src/main.rs)"
+        );
+        assert_eq!(
+            suggest(
+                "move_file",
+                json!({ "source": "a.txt", "destination": "b/c.txt" })
+            ),
+            "move_file({a.txt,b/c.txt})"
+        );
+        assert_eq!(suggest("create_todo_list", json!({})), "create_todo_list");
+    }
+
+    #[test]
+    fn splits_chained_commands_outside_quotes() {
+        assert_eq!(
+            split_simple_commands("git add . && git commit -m 'a; b' | cat 2>&1"),
+            Some(vec![
+                "git add .".to_string(),
+                "git commit -m 'a; b'".to_string(),
+                "cat 2>&1".to_string(),
+            ])
+        );
+        assert_eq!(split_simple_commands("echo `id`"), None);
+        assert_eq!(
+            split_simple_commands("ls > /dev/null; pwd").map(|s| s.len()),
+            Some(2)
+        );
+    }
+
+    fn rules(source: RuleSource, raw: &[&str]) -> PermissionRules {
+        let raw = raw.iter().map(|rule| rule.to_string()).collect::<Vec<_>>();
+        PermissionRules::parse(source, &raw).expect("rules")
+    }
+
+    struct TempDir {
+        path: PathBuf,
+    }
+
+    impl TempDir {
+        fn new(prefix: &str) -> Self {
+            let nonce = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .expect("clock should be after epoch")
+                .as_nanos();
+            let pid = std::process::id();
+            let path = std::env::temp_dir().join(format!("codetoad-{prefix}-{pid}-{nonce}"));
+            fs::create_dir_all(&path).expect("create temp dir");
+            Self { path }
+        }
+
+        fn path(&self) -> &Path {
+            &self.path
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.path);
+        }
+    }
+}
@@ -0,0 +1,508 @@
+use crate::code_outline::{OutlineParser, SourceLanguage};
+use crate::ignore_policy::IgnorePolicy;
+use anyhow::{Context, Result};
+use ignore::WalkBuilder;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+const CACHE_FILE: &str = ".grok/repo-map.json";
+const CACHE_VERSION: u32 = 1;
+const MAX_MAP_FILES: usize = 2_000;
+/// Bounds the walk itself, so huge trees with few source files stay cheap.
+const MAX_WALK_ENTRIES: usize = 50_000;
+const MAX_SOURCE_BYTES: u64 = 1024 * 1024;
+const MAX_REFERENCES_PER_FILE: usize = 4_000;
+/// Identifiers shorter than this are too generic to link files together.
+const MIN_IDENTIFIER_CHARS: usize = 3;
+const PAGERANK_ITERATIONS: usize = 30;
+const PAGERANK_DAMPING: f64 = 0.85;
+pub const DEFAULT_REPO_MAP_TOKENS: usize = 1_024;
+/// Files whose presence marks a directory as a project worth caching a map for.
+const PROJECT_MARKERS: &[&str] = &[
+    ".git",
+    ".grok/settings.json",
+    "Cargo.toml",
+    "package.json",
+    "pyproject.toml",
+    "go.mod",
+];
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct MappedSymbol {
+    name: String,
+    kind: String,
+    line: usize,
+    depth: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct MappedFile {
+    modified_epoch_ns: u128,
+    size: u64,
+    symbols: Vec<MappedSymbol>,
+    /// Unique identifiers used in the file, matched against other files' symbols.
+    references: Vec<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct RepoMapCache {
+    version: u32,
+    files: BTreeMap<String, MappedFile>,
+}
+
+/// Symbol index behind the repository map in the system prompt. Files are
+/// re-parsed only when their size or mtime changes, and the index is persisted
+/// under `.grok/` so new sessions start from the previous state.
+#[derive(Default)]
+pub struct RepoMap {
+    root: PathBuf,
+    files: BTreeMap<String, MappedFile>,
+    ignore: IgnorePolicy,
+}
+
+impl RepoMap {
+    /// Loads the cached index for `root`, starting empty when there is none.
+    pub fn load(root: &Path) -> Self {
+        let files = fs::read_to_string(root.join(CACHE_FILE))
+            .ok()
+            .and_then(|payload| serde_json::from_str::<RepoMapCache>(&payload).ok())
+            .filter(|cache| cache.version == CACHE_VERSION)
+            .map(|cache| cache.files)
+            .unwrap_or_default();
+        Self {
+            root: root.to_path_buf(),
+            files,
+            ignore: IgnorePolicy::default(),
+        }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Files the policy hides are left out, and dropped on the next refresh
+    /// if already indexed.
+    pub fn set_ignore_policy(&mut self, ignore: IgnorePolicy) {
+        self.ignore = ignore;
+    }
+
+    /// Re-indexes changed files and drops deleted ones. Returns true when the
+    /// index changed.
+    pub fn refresh(&mut self) -> bool {
+        let mut parser = OutlineParser::new();
+        let mut changed = false;
+        let mut seen = HashSet::new();
+        let ignore = self.ignore.clone();
+        let walker = WalkBuilder::new(&self.root)
+            .require_git(false)
+            .sort_by_file_name(|a, b| a.cmp(b))
+            .filter_entry(move |entry| {
+                let is_dir = entry.file_type().is_some_and(|kind| kind.is_dir());
+                !ignore.is_ignored(entry.path(), is_dir)
+            })
+            .build();
+
+        for (visited, entry) in walker.flatten().enumerate() {
+            if visited >= MAX_WALK_ENTRIES || seen.len() >= MAX_MAP_FILES {
+                break;
+            }
+            if !entry.file_type().is_some_and(|kind| kind.is_file()) {
+                continue;
+            }
+            let Some(language) = SourceLanguage::from_path(entry.path()) else {
+                continue;
+            };
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+            if metadata.len() > MAX_SOURCE_BYTES {
+                continue;
+            }
+            let Ok(relative) = entry.path().strip_prefix(&self.root) else {
+                continue;
+            };
+            let key = relative
+                .components()
+                .map(|part| part.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            let modified_epoch_ns = metadata
+                .modified()
+                .ok()
+                .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                .map(|duration| duration.as_nanos())
+                .unwrap_or_default();
+            seen.insert(key.clone());
+
+            let unchanged = self.files.get(&key).is_some_and(|file| {
+                file.modified_epoch_ns == modified_epoch_ns && file.size == metadata.len()
+            });
+            if unchanged {
+                continue;
+            }
+            let Some(mut mapped) = index_file(&mut parser, entry.path(), language) else {
+                changed |= self.files.remove(&key).is_some();
+                continue;
+            };
+            mapped.modified_epoch_ns = modified_epoch_ns;
+            mapped.size = metadata.len();
+            self.files.insert(key, mapped);
+            changed = true;
+        }
+
+        let before = self.files.len();
+        self.files.retain(|path, _| seen.contains(path));
+        changed || self.files.len() != before
+    }
+
+    /// Whether `root` looks like a project, so the cache is not written into
+    /// arbitrary directories such as `$HOME`.
+    pub fn is_project_root(&self) -> bool {
+        PROJECT_MARKERS
+            .iter()
+            .any(|marker| self.root.join(marker).exists())
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let path = self.root.join(CACHE_FILE);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed creating {}", parent.display()))?;
+        }
+        let cache = RepoMapCache {
+            version: CACHE_VERSION,
+            files: self.files.clone(),
+        };
+        let payload = serde_json::to_string(&cache).context("Failed encoding repo map")?;
+        fs::write(&path, payload).with_context(|| format!("Failed writing {}", path.display()))
+    }
+
+    /// Renders the highest-ranked files and symbols within roughly
+    /// `token_budget` tokens, or `None` when no supported source files exist.
+    pub fn render(&self, token_budget: usize) -> Option<String> {
+        let paths = self.files.keys().collect::<Vec<_>>();
+        if paths.is_empty() {
+            return None;
+        }
+        let ranks = self.file_ranks(&paths);
+        let referrers = self.referrer_counts();
+
+        let mut candidates = Vec::new();
+        for (index, path) in paths.iter().enumerate() {
+            for (symbol_index, symbol) in self.files[*path].symbols.iter().enumerate() {
+                let used_by = referrers.get(symbol.name.as_str()).map_or(0, |files| {
+                    files
+                        .iter()
+                        .filter(|file| file.as_str() != path.as_str())
+                        .count()
+                });
+                let score = ranks[index] * (1.0 + used_by as f64);
+                candidates.push((score, index, symbol_index));
+            }
+        }
+        candidates.sort_by(|a, b| {
+            b.0.total_cmp(&a.0)
+                .then_with(|| (a.1, a.2).cmp(&(b.1, b.2)))
+        });
+
+        let char_budget = token_budget * 4;
+        let mut used = 0usize;
+        let mut selected: BTreeMap<usize, BTreeSet<usize>> = BTreeMap::new();
+        for (_, file_index, symbol_index) in candidates {
+            let symbol = &self.files[paths[file_index]].symbols[symbol_index];
+            let mut cost = symbol_line(symbol).len() + 1;
+            if !selected.contains_key(&file_index) {
+                cost += paths[file_index].len() + 2;
+            }
+            if used + cost > char_budget {
+                continue;
+            }
+            used += cost;
+            selected.entry(file_index).or_default().insert(symbol_index);
+        }
+        if selected.is_empty() {
+            return None;
+        }
+
+        let mut files = selected.into_iter().collect::<Vec<_>>();
+        files.sort_by(|a, b| ranks[b.0].total_cmp(&ranks[a.0]).then(a.0.cmp(&b.0)));
+        let mut output = String::new();
+        for (file_index, symbols) in files {
+            let file = &self.files[paths[file_index]];
+            output.push_str(paths[file_index]);
+            output.push_str(":\n");
+            for symbol_index in symbols {
+                output.push_str(&symbol_line(&file.symbols[symbol_index]));
+                output.push('\n');
+            }
+        }
+        Some(output.trim_end().to_string())
+    }
+
+    /// PageRank over the "file A uses a name defined in file B" graph. Names
+    /// defined in several files split their weight between them.
+    fn file_ranks(&self, paths: &[&String]) -> Vec<f64> {
+        let mut definitions: HashMap<&str, Vec<usize>> = HashMap::new();
+        for (index, path) in paths.iter().enumerate() {
+            let names = self.files[*path]
+                .symbols
+                .iter()
+                .map(|symbol| symbol.name.as_str())
+                .collect::<HashSet<_>>();
+            for name in names {
+                definitions.entry(name).or_default().push(index);
+            }
+        }
+
+        let count = paths.len();
+        let mut edges: Vec<HashMap<usize, f64>> = vec![HashMap::new(); count];
+        for (from, path) in paths.iter().enumerate() {
+            for reference in &self.files[*path].references {
+                let Some(targets) = definitions.get(reference.as_str()) else {
+                    continue;
+                };
+                let weight = 1.0 / targets.len() as f64;
+                for &to in targets.iter().filter(|&&to| to != from) {
+                    *edges[from].entry(to).or_default() += weight;
+                }
+            }
+        }
+
+        let base = 1.0 / count as f64;
+        let mut ranks = vec![base; count];
+        for _ in 0..PAGERANK_ITERATIONS {
+            let mut next = vec![(1.0 - PAGERANK_DAMPING) * base; count];
+            let mut dangling = 0.0;
+            for (from, targets) in edges.iter().enumerate() {
+                let total = targets.values().sum::<f64>();
+                if total == 0.0 {
+                    dangling += ranks[from];
+                    continue;
+                }
+                for (&to, weight) in targets {
+                    next[to] += PAGERANK_DAMPING * ranks[from] * weight / total;
+                }
+            }
+            for rank in &mut next {
+                *rank += PAGERANK_DAMPING * dangling * base;
+            }
+            ranks = next;
+        }
+        ranks
+    }
+
+    fn referrer_counts(&self) -> HashMap<&str, Vec<&String>> {
+        let mut referrers: HashMap<&str, Vec<&String>> = HashMap::new();
+        for (path, file) in &self.files {
+            for reference in &file.references {
+                referrers.entry(reference.as_str()).or_default().push(path);
+            }
+        }
+        referrers
+    }
+}
+
+fn symbol_line(symbol: &MappedSymbol) -> String {
+    format!(
+        "  {}{} {} ({})",
+        "  ".repeat(symbol.depth),
+        symbol.kind,
+        symbol.name,
+        symbol.line
+    )
+}
+
+fn index_file(
+    parser: &mut OutlineParser,
+    path: &Path,
+    language: SourceLanguage,
+) -> Option<MappedFile> {
+    let source = fs::read_to_string(path).ok()?;
+    let symbols = parser
+        .outline(language, &source)
+        .ok()?
+        .into_iter()
+        .filter(|symbol| symbol.depth <= 1)
+        .map(|symbol| MappedSymbol {
+            name: symbol.display_name(),
+            kind: symbol.kind.label().to_string(),
+            line: symbol.start_line,
+            depth: symbol.depth,
+        })
+        .collect();
+    Some(MappedFile {
+        modified_epoch_ns: 0,
+        size: 0,
+        symbols,
+        references: identifiers(&source),
+    })
+}
+
+fn identifiers(source: &str) -> Vec<String> {
+    let mut found = BTreeSet::new();
+    let is_start = |ch: char| ch.is_ascii_alphabetic() || ch == '_';
+    let mut rest = source;
+    while let Some(start) = rest.find(is_start) {
+        let tail = &rest[start..];
+        let end = tail
+            .find(|ch: char| !(ch.is_ascii_alphanumeric() || ch == '_'))
+            .unwrap_or(tail.len());
+        if end >= MIN_IDENTIFIER_CHARS {
+            found.insert(&tail[..end]);
+            if found.len() >= MAX_REFERENCES_PER_FILE {
+                break;
+            }
+        }
+        rest = &tail[end..];
+    }
+    found.into_iter().map(str::to_string).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CACHE_FILE, RepoMap, identifiers};
+    use crate::ignore_policy::IgnorePolicy;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    fn write_project(root: &Path) {
+        fs::create_dir_all(root.join("src")).expect("create src");
+        fs::write(
+            root.join("src/store.rs"),
+            "pub struct Store;\n\nimpl Store {\n    pub fn open() -> Self {\n        Store\n    }\n}\n\nfn unused_helper() {}\n",
+        )
+        .expect("write");
+        fs::write(
+            root.join("src/app.rs"),
+            "use crate::store::Store;\n\npub fn run_app() {\n    let _ = Store::open();\n}\n",
+        )
+        .expect("write");
+        fs::write(
+            root.join("src/cli.rs"),
+            "pub fn main_cli() {\n    crate::app::run_app();\n    crate::store::Store::open();\n}\n",
+        )
+        .expect("write");
+    }
+
+    #[test]
+    fn render_ranks_referenced_files_first() {
+        let temp = TempDir::new("repo-map-rank");
+        write_project(temp.path());
+        let mut map = RepoMap::load(temp.path());
+        assert!(map.refresh());
+
+        let rendered = map.render(1_024).expect("map");
+        let store = rendered.find("src/store.rs:").expect("store listed");
+        let app = rendered.find("src/app.rs:").expect("app listed");
+        let cli = rendered.find("src/cli.rs:").expect("cli listed");
+        assert!(store < app && app < cli, "{rendered}");
+        assert!(
+            rendered.contains(
+                "src/store.rs:\n  struct Store (1)\n  impl Store (3)\n    method open (4)"
+            )
+        );
+
+        let tight = map.render(12).expect("map");
+        assert!(tight.starts_with("src/store.rs:\n  struct Store (1)"));
+        assert!(!tight.contains("unused_helper"));
+    }
+
+    #[test]
+    fn cache_round_trips_and_refresh_tracks_changes() {
+        let temp = TempDir::new("repo-map-cache");
+        write_project(temp.path());
+        let mut map = RepoMap::load(temp.path());
+        map.refresh();
+        map.save().expect("save");
+        assert!(temp.path().join(CACHE_FILE).exists());
+
+        let mut reloaded = RepoMap::load(temp.path());
+        assert!(!reloaded.refresh());
+        assert_eq!(reloaded.render(1_024), map.render(1_024));
+
+        fs::write(
+            temp.path().join("src/extra.py"),
+            "def brand_new():\n    pass\n",
+        )
+        .expect("write");
+        fs::remove_file(temp.path().join("src/cli.rs")).expect("remove");
+        assert!(reloaded.refresh());
+        let rendered = reloaded.render(1_024).expect("map");
+        assert!(rendered.contains("src/extra.py:\n  fn brand_new (1)"));
+        assert!(!rendered.contains("src/cli.rs"));
+    }
+
+    #[test]
+    fn refresh_skips_ignored_files() {
+        let temp = TempDir::new("repo-map-ignore");
+        write_project(temp.path());
+        let mut map = RepoMap::load(temp.path());
+        map.refresh();
+        assert!(map.render(1_024).expect("map").contains("src/cli.rs"));
+
+        map.set_ignore_policy(
+            IgnorePolicy::load(temp.path(), &["src/cli.rs".to_string()]).expect("policy"),
+        );
+        assert!(map.refresh());
+        let rendered = map.render(1_024).expect("map");
+        assert!(!rendered.contains("src/cli.rs"), "{rendered}");
+        assert!(!rendered.contains("main_cli"));
+    }
+
+    #[test]
+    fn render_is_none_without_source_files() {
+        let temp = TempDir::new("repo-map-empty");
+        fs::write(temp.path().join("README.md"), "# hi\n").expect("write");
+        let mut map = RepoMap::load(temp.path());
+        assert!(!map.refresh());
+        assert_eq!(map.render(1_024), None);
+    }
+
+    #[test]
+    fn project_root_requires_a_marker() {
+        let temp = TempDir::new("repo-map-project-root");
+        let map = RepoMap::load(temp.path());
+        assert!(!map.is_project_root());
+        fs::write(temp.path().join("Cargo.toml"), "[package]\n").expect("write");
+        assert!(map.is_project_root());
+    }
+
+    #[test]
+    fn identifiers_skips_short_tokens_and_duplicates() {
+        assert_eq!(
+            identifiers("let ab = Store::open(x); Store.open_all"),
+            vec!["Store", "let", "open", "open_all"]
+        );
+    }
+
+    struct TempDir {
+        path: PathBuf,
+    }
+
+    impl TempDir {
+        fn new(prefix: &str) -> Self {
+            let nonce = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .expect("clock after epoch")
+                .as_nanos();
+            let pid = std::process::id();
+            let path = std::env::temp_dir().join(format!("codetoad-{prefix}-{pid}-{nonce}"));
+            fs::create_dir_all(&path).expect("create temp dir");
+            Self { path }
+        }
+
+        fn path(&self) -> &Path {
+            &self.path
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.path);
+        }
+    }
+}
@@ -0,0 +1,140 @@
+use anyhow::{Context, Result, bail};
+use std::ffi::CString;
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+use tokio::process::Command;
+
+const CREATE_RULESET_VERSION: u32 = 1;
+const RULE_PATH_BENEATH: libc::c_int = 1;
+
+const ACCESS_EXECUTE: u64 = 1 << 0;
+const ACCESS_WRITE_FILE: u64 = 1 << 1;
+const ACCESS_READ_FILE: u64 = 1 << 2;
+const ACCESS_READ_DIR: u64 = 1 << 3;
+/// REMOVE_DIR through MAKE_SYM, all part of ABI v1.
+const ACCESS_MODIFY_V1: u64 = 0b1_1111_1111 << 4;
+const ACCESS_REFER: u64 = 1 << 13;
+const ACCESS_TRUNCATE: u64 = 1 << 14;
+
+#[repr(C)]
+struct RulesetAttr {
+    handled_access_fs: u64,
+}
+
+#[repr(C, packed)]
+struct PathBeneathAttr {
+    allowed_access: u64,
+    parent_fd: i32,
+}
+
+pub(super) fn abi_version() -> Option<u32> {
+    static ABI: OnceLock<Option<u32>> = OnceLock::new();
+    *ABI.get_or_init(|| {
+        // SAFETY: the version query takes no attribute pointer.
+        let version = unsafe {
+            libc::syscall(
+                libc::SYS_landlock_create_ruleset,
+                std::ptr::null::<RulesetAttr>(),
+                0usize,
+                CREATE_RULESET_VERSION,
+            )
+        };
+        (version > 0).then_some(version as u32)
+    })
+}
+
+pub(super) fn restrict(command: &mut Command, writable_roots: &[PathBuf]) -> Result<()> {
+    let Some(abi) = abi_version() else {
+        bail!(
+            "the sandbox needs Landlock (Linux 5.13+ with Landlock enabled), which this kernel does not provide. Set sandbox.mode to \"off\" to run commands unsandboxed."
+        );
+    };
+    let mut handled =
+        ACCESS_EXECUTE | ACCESS_WRITE_FILE | ACCESS_READ_FILE | ACCESS_READ_DIR | ACCESS_MODIFY_V1;
+    if abi >= 2 {
+        handled |= ACCESS_REFER;
+    }
+    if abi >= 3 {
+        handled |= ACCESS_TRUNCATE;
+    }
+    let read = ACCESS_EXECUTE | ACCESS_READ_FILE | ACCESS_READ_DIR;
+
+    // The ruleset is built here, before fork, so the child only needs two
+    // async-signal-safe syscalls.
+    let attr = RulesetAttr {
+        handled_access_fs: handled,
+    };
+    // SAFETY: `attr` is a valid ruleset attribute of the size passed.
+    let fd = unsafe {
+        libc::syscall(
+            libc::SYS_landlock_create_ruleset,
+            &attr as *const RulesetAttr,
+            std::mem::size_of::<RulesetAttr>(),
+            0u32,
+        )
+    };
+    if fd < 0 {
+        return Err(std::io::Error::last_os_error()).context("Failed to create Landlock ruleset");
+    }
+    // SAFETY: the syscall returned a new file descriptor we now own.
+    let ruleset = unsafe { OwnedFd::from_raw_fd(fd as i32) };
+
+    add_rule(&ruleset, Path::new("/"), read)?;
+    add_rule(&ruleset, Path::new("/dev"), read | ACCESS_WRITE_FILE)?;
+    for root in writable_roots {
+        if root.is_dir() {
+            add_rule(&ruleset, root, handled)?;
+        }
+    }
+
+    let ruleset = Arc::new(ruleset);
+    // SAFETY: the closure only calls prctl and landlock_restrict_self,
+    // both async-signal-safe, and allocates nothing.
+    unsafe {
+        command.pre_exec(move || {
+            if libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0) != 0 {
+                return Err(std::io::Error::last_os_error());
+            }
+            if libc::syscall(libc::SYS_landlock_restrict_self, ruleset.as_raw_fd(), 0u32) != 0 {
+                return Err(std::io::Error::last_os_error());
+            }
+            Ok(())
+        });
+    }
+    Ok(())
+}
+
+fn add_rule(ruleset: &OwnedFd, path: &Path, access: u64) -> Result<()> {
+    let Ok(c_path) = CString::new(path.as_os_str().as_bytes()) else {
+        return Ok(());
+    };
+    // SAFETY: `c_path` is a valid NUL-terminated string.
+    let parent_fd = unsafe { libc::open(c_path.as_ptr(), libc::O_PATH | libc::O_CLOEXEC) };
+    if parent_fd < 0 {
+        // Missing paths simply get no rule.
+        return Ok(());
+    }
+    // SAFETY: open returned a new file descriptor we now own.
+    let parent = unsafe { OwnedFd::from_raw_fd(parent_fd) };
+    let attr = PathBeneathAttr {
+        allowed_access: access,
+        parent_fd: parent.as_raw_fd(),
+    };
+    // SAFETY: `attr` is a valid path-beneath rule for this ruleset.
+    let result = unsafe {
+        libc::syscall(
+            libc::SYS_landlock_add_rule,
+            ruleset.as_raw_fd(),
+            RULE_PATH_BENEATH,
+            &attr as *const PathBeneathAttr,
+            0u32,
+        )
+    };
+    if result != 0 {
+        return Err(std::io::Error::last_os_error())
+            .with_context(|| format!("Failed to add Landlock rule for {}", path.display()));
+    }
+    Ok(())
+}
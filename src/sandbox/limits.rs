@@ -0,0 +1,291 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Per-process rlimits for tool commands; unset fields are left alone.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ResourceLimits {
+    #[serde(rename = "cpuSeconds", alias = "cpu_seconds")]
+    pub cpu_seconds: Option<u64>,
+    /// Address space, which bounds how much memory a process can map.
+    #[serde(rename = "memoryMb", alias = "memory_mb")]
+    pub memory_mb: Option<u64>,
+    /// Counted across all processes of the user, not just this command.
+    #[serde(rename = "maxProcesses", alias = "max_processes")]
+    pub max_processes: Option<u64>,
+    #[serde(rename = "fileSizeMb", alias = "file_size_mb")]
+    pub file_size_mb: Option<u64>,
+    #[serde(rename = "openFiles", alias = "open_files")]
+    pub open_files: Option<u64>,
+}
+
+/// A limit a command ran into, worked out from how it exited.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LimitHit {
+    CpuTime(u64),
+    Memory(u64),
+    Processes(u64),
+    FileSize(u64),
+    OpenFiles(u64),
+}
+
+impl fmt::Display for LimitHit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::CpuTime(seconds) => {
+                write!(
+                    f,
+                    "Command exceeded the CPU time limit ({seconds}s) and was killed"
+                )
+            }
+            Self::Memory(mb) => write!(f, "Command ran out of memory (limit {mb} MB)"),
+            Self::Processes(count) => write!(
+                f,
+                "Command hit the process limit ({count} processes for this user)"
+            ),
+            Self::FileSize(mb) => write!(f, "Command exceeded the file size limit ({mb} MB)"),
+            Self::OpenFiles(count) => write!(f, "Command hit the open file limit ({count})"),
+        }
+    }
+}
+
+impl ResourceLimits {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// The lower of each limit; a limit set on only one side applies.
+    pub fn stricter(self, other: Self) -> Self {
+        let min = |a: Option<u64>, b: Option<u64>| match (a, b) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            _ => a.or(b),
+        };
+        Self {
+            cpu_seconds: min(self.cpu_seconds, other.cpu_seconds),
+            memory_mb: min(self.memory_mb, other.memory_mb),
+            max_processes: min(self.max_processes, other.max_processes),
+            file_size_mb: min(self.file_size_mb, other.file_size_mb),
+            open_files: min(self.open_files, other.open_files),
+        }
+    }
+
+    /// Which limit a failed command most likely hit. `status` is the exit code
+    /// as a shell reports it, so 128 + N means killed by signal N.
+    pub fn classify(
+        &self,
+        status: Option<i32>,
+        signal: Option<i32>,
+        output: &str,
+    ) -> Option<LimitHit> {
+        let killed_by = |sig: i32| signal == Some(sig) || status == Some(128 + sig);
+        #[cfg(unix)]
+        {
+            if let Some(seconds) = self.cpu_seconds
+                && killed_by(libc::SIGXCPU)
+            {
+                return Some(LimitHit::CpuTime(seconds));
+            }
+            if let Some(mb) = self.file_size_mb
+                && (killed_by(libc::SIGXFSZ) || output.contains("File too large"))
+            {
+                return Some(LimitHit::FileSize(mb));
+            }
+        }
+        #[cfg(not(unix))]
+        let _ = killed_by;
+        if let Some(count) = self.open_files
+            && output.contains("Too many open files")
+        {
+            return Some(LimitHit::OpenFiles(count));
+        }
+        // A process limit hit shows up only as a failed fork (EAGAIN); other
+        // EAGAIN errors are not counted.
+        if let Some(count) = self.max_processes
+            && [
+                "fork: retry",
+                "fork: Resource temporarily unavailable",
+                "Cannot fork",
+                "can't fork",
+                "fork failed",
+            ]
+            .iter()
+            .any(|marker| output.contains(marker))
+        {
+            return Some(LimitHit::Processes(count));
+        }
+        if let Some(mb) = self.memory_mb
+            && [
+                "Cannot allocate memory",
+                "MemoryError",
+                "memory allocation of",
+                "out of memory",
+                "Out of memory",
+                "std::bad_alloc",
+            ]
+            .iter()
+            .any(|marker| output.contains(marker))
+        {
+            return Some(LimitHit::Memory(mb));
+        }
+        None
+    }
+}
+
+#[cfg(unix)]
+impl ResourceLimits {
+    /// `(resource, value)` pairs ready for `setrlimit` in the child.
+    pub(super) fn rlimits(&self) -> Vec<(libc::c_int, libc::rlim_t)> {
+        const MB: u64 = 1024 * 1024;
+        [
+            (libc::RLIMIT_CPU as libc::c_int, self.cpu_seconds),
+            (
+                libc::RLIMIT_AS as libc::c_int,
+                self.memory_mb.map(|mb| mb.saturating_mul(MB)),
+            ),
+            (libc::RLIMIT_NPROC as libc::c_int, self.max_processes),
+            (
+                libc::RLIMIT_FSIZE as libc::c_int,
+                self.file_size_mb.map(|mb| mb.saturating_mul(MB)),
+            ),
+            (libc::RLIMIT_NOFILE as libc::c_int, self.open_files),
+        ]
+        .into_iter()
+        .filter_map(|(resource, value)| Some((resource, value? as libc::rlim_t)))
+        .collect()
+    }
+}
+
+/// Lowers both the soft and hard limit so the command cannot raise them back.
+/// Limits are never raised above what the process inherited, since that fails
+/// without privileges. Runs in the forked child, so it only calls
+/// `getrlimit` and `setrlimit`.
+#[cfg(unix)]
+pub(super) fn apply_rlimits(limits: &[(libc::c_int, libc::rlim_t)]) -> std::io::Result<()> {
+    for (resource, value) in limits {
+        let mut current = libc::rlimit {
+            rlim_cur: 0,
+            rlim_max: 0,
+        };
+        // SAFETY: getrlimit only writes `current`.
+        if unsafe { libc::getrlimit(*resource as _, &mut current) } != 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        // At the hard CPU limit the kernel sends SIGKILL, which would hide
+        // the SIGXCPU that tells a CPU limit hit apart from other kills.
+        let wanted_hard = if *resource == libc::RLIMIT_CPU as libc::c_int {
+            value.saturating_add(1)
+        } else {
+            *value
+        };
+        let hard = wanted_hard.min(current.rlim_max);
+        let soft = (*value).min(current.rlim_cur).min(hard);
+        let limit = libc::rlimit {
+            rlim_cur: soft,
+            rlim_max: hard,
+        };
+        // SAFETY: setrlimit only reads `limit`.
+        if unsafe { libc::setrlimit(*resource as _, &limit) } != 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LimitHit, ResourceLimits};
+
+    #[test]
+    fn classifies_limit_hits_only_for_configured_limits() {
+        let limits = ResourceLimits {
+            cpu_seconds: Some(5),
+            open_files: Some(64),
+            ..ResourceLimits::default()
+        };
+        assert_eq!(
+            limits.classify(Some(152), None, ""),
+            Some(LimitHit::CpuTime(5))
+        );
+        assert_eq!(
+            limits.classify(Some(1), None, "ls: Too many open files"),
+            Some(LimitHit::OpenFiles(64))
+        );
+        assert_eq!(
+            limits.classify(Some(1), None, "Cannot allocate memory"),
+            None
+        );
+        let processes = ResourceLimits {
+            max_processes: Some(32),
+            ..ResourceLimits::default()
+        };
+        assert_eq!(
+            processes.classify(
+                Some(128),
+                None,
+                "bash: fork: retry: Resource temporarily unavailable"
+            ),
+            Some(LimitHit::Processes(32))
+        );
+        assert_eq!(
+            processes.classify(Some(1), None, "read: Resource temporarily unavailable"),
+            None
+        );
+        assert_eq!(
+            LimitHit::CpuTime(5).to_string(),
+            "Command exceeded the CPU time limit (5s) and was killed"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn limits_above_the_inherited_hard_limit_are_clamped() {
+        use std::os::unix::process::CommandExt;
+
+        let mut current = libc::rlimit {
+            rlim_cur: 0,
+            rlim_max: 0,
+        };
+        // SAFETY: getrlimit only writes `current`.
+        assert_eq!(
+            unsafe { libc::getrlimit(libc::RLIMIT_NOFILE as _, &mut current) },
+            0
+        );
+        if current.rlim_max == libc::RLIM_INFINITY {
+            return;
+        }
+        let limits = vec![(libc::RLIMIT_NOFILE as libc::c_int, current.rlim_max + 1_000)];
+        let mut command = std::process::Command::new("sh");
+        command.args(["-c", "ulimit -Hn"]);
+        // SAFETY: the closure only calls getrlimit and setrlimit.
+        unsafe {
+            command.pre_exec(move || super::apply_rlimits(&limits));
+        }
+        let output = command.output().expect("command runs");
+        assert!(output.status.success());
+        assert_eq!(
+            String::from_utf8_lossy(&output.stdout).trim(),
+            current.rlim_max.to_string()
+        );
+    }
+
+    #[test]
+    fn stricter_limits_take_the_lower_value() {
+        let user = ResourceLimits {
+            cpu_seconds: Some(60),
+            memory_mb: Some(4096),
+            ..ResourceLimits::default()
+        };
+        let project = ResourceLimits {
+            cpu_seconds: Some(3600),
+            memory_mb: Some(1024),
+            open_files: Some(256),
+            ..ResourceLimits::default()
+        };
+        let merged = user.stricter(project);
+        assert_eq!(merged.cpu_seconds, Some(60));
+        assert_eq!(merged.memory_mb, Some(1024));
+        assert_eq!(merged.open_files, Some(256));
+        assert_eq!(merged.max_processes, None);
+        assert!(ResourceLimits::default().is_empty());
+    }
+}
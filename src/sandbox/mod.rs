@@ -0,0 +1,531 @@
+use anyhow::Result;
+#[cfg(not(target_os = "linux"))]
+use anyhow::bail;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::process::Command;
+
+#[cfg(target_os = "linux")]
+mod landlock;
+mod limits;
+#[cfg(target_os = "linux")]
+mod network;
+#[cfg(target_os = "linux")]
+mod proxy;
+
+pub use self::limits::{LimitHit, ResourceLimits};
+#[cfg(target_os = "linux")]
+use self::proxy::EgressProxy;
+
+/// Set on sandboxed commands under an allow list; the shell never needs to
+/// carry them over on its own.
+pub const PROXY_ENV_VARS: &[&str] = &[
+    "HTTP_PROXY",
+    "HTTPS_PROXY",
+    "ALL_PROXY",
+    "http_proxy",
+    "https_proxy",
+    "all_proxy",
+    "NO_PROXY",
+    "no_proxy",
+];
+
+/// How much of the filesystem tool commands may write to.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SandboxMode {
+    #[default]
+    Off,
+    WorkspaceWrite,
+    ReadOnly,
+}
+
+impl SandboxMode {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Off => "off",
+            Self::WorkspaceWrite => "workspace-write",
+            Self::ReadOnly => "read-only",
+        }
+    }
+
+    /// Whichever of the two restricts more.
+    pub fn stricter(self, other: Self) -> Self {
+        let rank = |mode| match mode {
+            Self::Off => 0,
+            Self::WorkspaceWrite => 1,
+            Self::ReadOnly => 2,
+        };
+        if rank(other) > rank(self) {
+            other
+        } else {
+            self
+        }
+    }
+}
+
+/// Which hosts tool commands may reach.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum NetworkPolicy {
+    #[default]
+    AllowAll,
+    DenyAll,
+    /// Only these hosts, through the filtering proxy.
+    AllowList(Vec<String>),
+}
+
+impl NetworkPolicy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::AllowAll => "allow-all",
+            Self::DenyAll => "deny-all",
+            Self::AllowList(_) => "allow-list",
+        }
+    }
+
+    /// Whichever of the two lets less through; two allow lists keep only the
+    /// hosts on both.
+    pub fn stricter(self, other: Self) -> Self {
+        match (self, other) {
+            (Self::DenyAll, _) | (_, Self::DenyAll) => Self::DenyAll,
+            (Self::AllowList(hosts), Self::AllowList(others)) => Self::AllowList(
+                hosts
+                    .into_iter()
+                    .filter(|host| others.contains(host))
+                    .collect(),
+            ),
+            (Self::AllowList(hosts), Self::AllowAll) | (Self::AllowAll, Self::AllowList(hosts)) => {
+                Self::AllowList(hosts)
+            }
+            (Self::AllowAll, Self::AllowAll) => Self::AllowAll,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SandboxConfig {
+    pub mode: SandboxMode,
+    /// Writable in both enforcing modes, on top of the project and temp dirs.
+    pub writable_roots: Vec<PathBuf>,
+    pub network: NetworkPolicy,
+    pub limits: ResourceLimits,
+}
+
+/// Filesystem and network rules applied to every command started by the bash
+/// tools. The whole filesystem stays readable; writes are limited to
+/// `writable_roots`.
+#[derive(Debug, Clone, Default)]
+pub struct SandboxPolicy {
+    mode: SandboxMode,
+    writable_roots: Vec<PathBuf>,
+    network: NetworkPolicy,
+    limits: ResourceLimits,
+    #[cfg(target_os = "linux")]
+    proxy: Option<Arc<EgressProxy>>,
+}
+
+impl PartialEq for SandboxPolicy {
+    fn eq(&self, other: &Self) -> bool {
+        self.mode == other.mode
+            && self.writable_roots == other.writable_roots
+            && self.network == other.network
+            && self.limits == other.limits
+    }
+}
+
+impl SandboxPolicy {
+    /// The project root is writable only in `workspace-write`; temp dirs and
+    /// the configured extra roots are writable in both enforcing modes. An
+    /// allow list starts the filtering proxy, so this needs a tokio runtime.
+    pub fn new(config: &SandboxConfig, project_root: &Path) -> Result<Self> {
+        let mode = config.mode;
+        let mut writable_roots = Vec::new();
+        if mode != SandboxMode::Off {
+            if mode == SandboxMode::WorkspaceWrite {
+                writable_roots.push(project_root.to_path_buf());
+            }
+            writable_roots.push(std::env::temp_dir());
+            writable_roots.push(PathBuf::from("/tmp"));
+            writable_roots.push(PathBuf::from("/var/tmp"));
+            writable_roots.extend(config.writable_roots.iter().cloned());
+
+            let mut seen = Vec::new();
+            writable_roots.retain(|root| {
+                let canonical = std::fs::canonicalize(root).unwrap_or_else(|_| root.clone());
+                if seen.contains(&canonical) {
+                    false
+                } else {
+                    seen.push(canonical);
+                    true
+                }
+            });
+        }
+
+        #[cfg(target_os = "linux")]
+        let proxy = match &config.network {
+            NetworkPolicy::AllowList(hosts) => Some(Arc::new(EgressProxy::start(hosts.clone())?)),
+            _ => None,
+        };
+        Ok(Self {
+            mode,
+            writable_roots,
+            network: config.network.clone(),
+            limits: config.limits,
+            #[cfg(target_os = "linux")]
+            proxy,
+        })
+    }
+
+    pub fn mode(&self) -> SandboxMode {
+        self.mode
+    }
+
+    pub fn writable_roots(&self) -> &[PathBuf] {
+        &self.writable_roots
+    }
+
+    pub fn network(&self) -> &NetworkPolicy {
+        &self.network
+    }
+
+    pub fn limits(&self) -> ResourceLimits {
+        self.limits
+    }
+
+    /// The resource limit a failed command ran into, if any. `status` is the
+    /// exit code as a shell reports it.
+    pub fn limit_hit(
+        &self,
+        status: Option<i32>,
+        signal: Option<i32>,
+        output: &str,
+    ) -> Option<LimitHit> {
+        self.limits.classify(status, signal, output)
+    }
+
+    /// Restricts the process `command` will spawn. Fails rather than running
+    /// unrestricted when the kernel cannot enforce the policy.
+    pub fn apply(&self, command: &mut Command) -> Result<()> {
+        #[cfg(target_os = "linux")]
+        {
+            if self.network != NetworkPolicy::AllowAll {
+                let proxy_socket = self.proxy.as_ref().map(|proxy| proxy.socket_path());
+                let setup = network::NamespaceSetup::new(proxy_socket)?;
+                if proxy_socket.is_some() {
+                    let url = format!("http://127.0.0.1:{}", network::BRIDGE_PORT);
+                    for name in PROXY_ENV_VARS {
+                        if name.eq_ignore_ascii_case("NO_PROXY") {
+                            command.env(name, "localhost,127.0.0.1,::1");
+                        } else {
+                            command.env(name, &url);
+                        }
+                    }
+                }
+                // SAFETY: `enter` only makes async-signal-safe syscalls on
+                // memory prepared before fork. It runs before Landlock, which
+                // would otherwise block the writes to /proc/self.
+                unsafe {
+                    command.pre_exec(move || setup.enter());
+                }
+            }
+            if self.mode != SandboxMode::Off {
+                landlock::restrict(command, &self.writable_roots)?;
+            }
+        }
+        #[cfg(not(target_os = "linux"))]
+        {
+            if self.network != NetworkPolicy::AllowAll {
+                bail!(
+                    "network policy {} needs Linux namespaces, which this platform lacks. Set sandbox.network to \"allow-all\" to run commands unrestricted.",
+                    self.network.as_str()
+                );
+            }
+            if self.mode != SandboxMode::Off {
+                bail!(
+                    "sandbox mode {} needs Linux Landlock, which this platform lacks. Set sandbox.mode to \"off\" to run commands unsandboxed.",
+                    self.mode.as_str()
+                );
+            }
+        }
+        // Registered last so the namespace bridge is not bound by the limits.
+        #[cfg(unix)]
+        if !self.limits.is_empty() {
+            let rlimits = self.limits.rlimits();
+            // SAFETY: `apply_rlimits` only calls setrlimit on prepared values.
+            unsafe {
+                command.pre_exec(move || limits::apply_rlimits(&rlimits));
+            }
+        }
+        #[cfg(not(unix))]
+        let _ = command;
+        Ok(())
+    }
+
+    /// Hosts the proxy refused since the last call, as a note for the tool
+    /// result.
+    pub fn take_network_report(&self) -> Option<String> {
+        #[cfg(target_os = "linux")]
+        if let (Some(proxy), NetworkPolicy::AllowList(hosts)) = (&self.proxy, &self.network) {
+            let blocked = proxy.take_blocked();
+            if !blocked.is_empty() {
+                return Some(format!(
+                    "Network policy blocked connections to: {}. Allowed hosts: {}.",
+                    blocked.join(", "),
+                    hosts.join(", ")
+                ));
+            }
+        }
+        None
+    }
+
+    /// A note for the model when a failed command looks like it hit the sandbox.
+    pub fn explain_failure(&self, output: &str) -> Option<String> {
+        let mut notes = Vec::new();
+        if self.mode != SandboxMode::Off
+            && (output.contains("Permission denied")
+                || output.contains("Operation not permitted")
+                || output.contains("Read-only file system"))
+        {
+            let roots = self
+                .writable_roots
+                .iter()
+                .map(|root| root.display().to_string())
+                .collect::<Vec<_>>()
+                .join(", ");
+            notes.push(format!(
+                "This may be a sandbox violation: commands run under the {} sandbox, which only allows writes under {roots}.",
+                self.mode.as_str()
+            ));
+        }
+        let network_failure = [
+            "Could not resolve host",
+            "Temporary failure in name resolution",
+            "Name or service not known",
+            "failed to lookup address",
+            "Network is unreachable",
+        ]
+        .iter()
+        .any(|marker| output.contains(marker));
+        match &self.network {
+            NetworkPolicy::DenyAll if network_failure => notes.push(
+                "Network access is disabled for commands by the deny-all network policy."
+                    .to_string(),
+            ),
+            NetworkPolicy::AllowList(hosts) if network_failure => notes.push(format!(
+                "Commands can only reach {} through the HTTP(S) proxy set in HTTP_PROXY and HTTPS_PROXY.",
+                hosts.join(", ")
+            )),
+            _ => {}
+        }
+        if notes.is_empty() {
+            return None;
+        }
+        notes.push(
+            "Do not try to work around it; ask the user if the access is needed.".to_string(),
+        );
+        Some(notes.join(" "))
+    }
+}
+
+/// Whether the running kernel supports Landlock.
+pub fn landlock_available() -> bool {
+    #[cfg(target_os = "linux")]
+    {
+        landlock::abi_version().is_some()
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        false
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::{NetworkPolicy, SandboxConfig, SandboxMode, SandboxPolicy, landlock_available};
+    use std::fs;
+    use std::path::{Path, PathBuf};
+    use std::process::Stdio;
+    use std::time::{SystemTime, UNIX_EPOCH};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::process::Command;
+
+    fn sandbox_policy(
+        mode: SandboxMode,
+        network: NetworkPolicy,
+        project_root: &Path,
+    ) -> SandboxPolicy {
+        let config = SandboxConfig {
+            mode,
+            network,
+            ..SandboxConfig::default()
+        };
+        SandboxPolicy::new(&config, project_root).expect("policy")
+    }
+
+    async fn run(policy: &SandboxPolicy, cwd: &Path, script: &str) -> (bool, String) {
+        let mut command = Command::new("sh");
+        command
+            .arg("-c")
+            .arg(script)
+            .current_dir(cwd)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        policy.apply(&mut command).expect("apply sandbox");
+        let output = command.output().await.expect("run");
+        (
+            output.status.success(),
+            String::from_utf8_lossy(&output.stderr).to_string(),
+        )
+    }
+
+    #[tokio::test]
+    async fn workspace_write_blocks_writes_outside_writable_roots() {
+        if !landlock_available() {
+            return;
+        }
+        // Neither directory may sit under the temp dir, which stays writable.
+        let project = TempDir::new_in_cwd("sandbox-project");
+        let outside = TempDir::new_in_cwd("sandbox-outside");
+        let policy = sandbox_policy(
+            SandboxMode::WorkspaceWrite,
+            NetworkPolicy::AllowAll,
+            project.path(),
+        );
+
+        let (ok, _) = run(
+            &policy,
+            project.path(),
+            "echo hi > inside.txt && cat inside.txt",
+        )
+        .await;
+        assert!(ok);
+        let script = format!("echo hi > {}/escaped.txt", outside.path().display());
+        let (ok, stderr) = run(&policy, project.path(), &script).await;
+        assert!(!ok);
+        assert!(!outside.path().join("escaped.txt").exists());
+        assert!(policy.explain_failure(&stderr).is_some());
+
+        let policy = sandbox_policy(
+            SandboxMode::ReadOnly,
+            NetworkPolicy::AllowAll,
+            project.path(),
+        );
+        let (ok, _) = run(&policy, project.path(), "echo hi > blocked.txt").await;
+        assert!(!ok);
+        let (ok, _) = run(&policy, project.path(), "cat inside.txt > /dev/null").await;
+        assert!(ok);
+    }
+
+    #[tokio::test]
+    async fn network_policies_isolate_commands_and_report_blocked_hosts() {
+        let project = TempDir::new_in_cwd("sandbox-network");
+        // The namespace still has a working loopback.
+        let policy = sandbox_policy(SandboxMode::Off, NetworkPolicy::DenyAll, project.path());
+        let (ok, stderr) = run(
+            &policy,
+            project.path(),
+            "python3 -c 'import socket; s = socket.socket(); s.bind((\"127.0.0.1\", 0)); s.listen(); socket.create_connection(s.getsockname())' && test -z \"$HTTPS_PROXY\"",
+        )
+        .await;
+        assert!(ok, "{stderr}");
+        let (ok, _) = run(
+            &policy,
+            project.path(),
+            "python3 -c 'import socket; socket.create_connection((\"1.1.1.1\", 80), timeout=2)'",
+        )
+        .await;
+        assert!(!ok);
+
+        let server = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("bind");
+        let port = server.local_addr().expect("addr").port();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = server.accept().await {
+                let mut request = [0u8; 1024];
+                let _ = stream.read(&mut request).await;
+                let _ = stream
+                    .write_all(b"HTTP/1.0 200 OK\r\nContent-Length: 5\r\n\r\nhello")
+                    .await;
+            }
+        });
+        let policy = sandbox_policy(
+            SandboxMode::Off,
+            NetworkPolicy::AllowList(vec!["127.0.0.1".to_string()]),
+            project.path(),
+        );
+        // 127.0.0.1 is in NO_PROXY, so the test bypasses that.
+        let script = format!(
+            "env -u NO_PROXY -u no_proxy python3 -c 'import urllib.request as r; print(r.urlopen(\"http://127.0.0.1:{port}/\").read().decode())'"
+        );
+        let (ok, stderr) = run(&policy, project.path(), &script).await;
+        assert!(ok, "{stderr}");
+        assert!(policy.take_network_report().is_none());
+
+        let policy = sandbox_policy(
+            SandboxMode::Off,
+            NetworkPolicy::AllowList(vec!["allowed.invalid".to_string()]),
+            project.path(),
+        );
+        let (ok, stderr) = run(
+            &policy,
+            project.path(),
+            "python3 -c 'import os, urllib.request; urllib.request.urlopen(\"http://blocked.invalid/\")'",
+        )
+        .await;
+        assert!(!ok);
+        assert!(stderr.contains("403"), "{stderr}");
+        let report = policy.take_network_report().expect("report");
+        assert!(report.contains("blocked.invalid:80"));
+        assert!(report.contains("Allowed hosts: allowed.invalid"));
+        assert!(policy.take_network_report().is_none());
+    }
+
+    #[test]
+    fn off_mode_has_no_writable_roots_or_notes() {
+        let policy = sandbox_policy(
+            SandboxMode::Off,
+            NetworkPolicy::AllowAll,
+            Path::new("/project"),
+        );
+        assert!(policy.writable_roots().is_empty());
+        assert!(policy.explain_failure("Permission denied").is_none());
+        let policy = sandbox_policy(
+            SandboxMode::ReadOnly,
+            NetworkPolicy::AllowAll,
+            Path::new("/project"),
+        );
+        assert!(!policy.writable_roots().contains(&PathBuf::from("/project")));
+    }
+
+    struct TempDir {
+        path: PathBuf,
+    }
+
+    impl TempDir {
+        fn new_in_cwd(prefix: &str) -> Self {
+            let nonce = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .expect("clock after epoch")
+                .as_nanos();
+            let pid = std::process::id();
+            let path = std::env::current_dir()
+                .expect("cwd")
+                .join("target")
+                .join(format!("codetoad-{prefix}-{pid}-{nonce}"));
+            fs::create_dir_all(&path).expect("create temp dir");
+            Self { path }
+        }
+
+        fn path(&self) -> &Path {
+            &self.path
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.path);
+        }
+    }
+}
@@ -0,0 +1,258 @@
+//! Runs in the forked child before `exec`, so everything here sticks to raw
+//! syscalls: no allocation, no locks.
+
+use std::io;
+use std::mem::{size_of, zeroed};
+use std::os::unix::ffi::OsStrExt;
+use std::path::Path;
+
+/// Loopback port of the proxy bridge inside the namespace. The namespace is
+/// fresh, so nothing else can be listening on it.
+pub(super) const BRIDGE_PORT: u16 = 3128;
+const RELAY_BUFFER: usize = 16 * 1024;
+
+/// Everything the child needs, prepared before fork.
+pub(super) struct NamespaceSetup {
+    uid_map: Vec<u8>,
+    gid_map: Vec<u8>,
+    proxy_addr: Option<(libc::sockaddr_un, libc::socklen_t)>,
+}
+
+impl NamespaceSetup {
+    pub(super) fn new(proxy_socket: Option<&Path>) -> io::Result<Self> {
+        // SAFETY: getuid and getgid cannot fail.
+        let (uid, gid) = unsafe { (libc::getuid(), libc::getgid()) };
+        let proxy_addr = proxy_socket.map(unix_addr).transpose()?;
+        Ok(Self {
+            uid_map: format!("{uid} {uid} 1").into_bytes(),
+            gid_map: format!("{gid} {gid} 1").into_bytes(),
+            proxy_addr,
+        })
+    }
+
+    /// Moves the calling process into new user and network namespaces with
+    /// only loopback up, and starts the proxy bridge if there is a proxy.
+    pub(super) fn enter(&self) -> io::Result<()> {
+        // SAFETY: plain syscalls on memory owned by `self` or the stack.
+        unsafe {
+            check(libc::unshare(libc::CLONE_NEWUSER | libc::CLONE_NEWNET))?;
+            // Older kernels have no setgroups file; the gid map then works without it.
+            let _ = write_file(c"/proc/self/setgroups".as_ptr(), b"deny");
+            write_file(c"/proc/self/uid_map".as_ptr(), &self.uid_map)?;
+            write_file(c"/proc/self/gid_map".as_ptr(), &self.gid_map)?;
+            bring_loopback_up()?;
+            if let Some((addr, len)) = &self.proxy_addr {
+                spawn_bridge(addr, *len)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn unix_addr(path: &Path) -> io::Result<(libc::sockaddr_un, libc::socklen_t)> {
+    // SAFETY: sockaddr_un is plain data; all zeroes is a valid value.
+    let mut addr: libc::sockaddr_un = unsafe { zeroed() };
+    let bytes = path.as_os_str().as_bytes();
+    if bytes.len() >= addr.sun_path.len() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "proxy socket path is too long",
+        ));
+    }
+    addr.sun_family = libc::AF_UNIX as libc::sa_family_t;
+    for (slot, byte) in addr.sun_path.iter_mut().zip(bytes) {
+        *slot = *byte as libc::c_char;
+    }
+    let len = size_of::<libc::sa_family_t>() + bytes.len() + 1;
+    Ok((addr, len as libc::socklen_t))
+}
+
+fn check(result: libc::c_int) -> io::Result<libc::c_int> {
+    if result < 0 {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(result)
+    }
+}
+
+unsafe fn write_file(path: *const libc::c_char, contents: &[u8]) -> io::Result<()> {
+    // SAFETY: `path` is NUL-terminated and `contents` is a live slice.
+    unsafe {
+        let fd = check(libc::open(path, libc::O_WRONLY | libc::O_CLOEXEC))?;
+        let written = libc::write(fd, contents.as_ptr().cast(), contents.len());
+        libc::close(fd);
+        if written != contents.len() as isize {
+            return Err(io::Error::last_os_error());
+        }
+    }
+    Ok(())
+}
+
+unsafe fn bring_loopback_up() -> io::Result<()> {
+    // SAFETY: ifreq is plain data and the ioctls only touch it.
+    unsafe {
+        let fd = check(libc::socket(
+            libc::AF_INET,
+            libc::SOCK_DGRAM | libc::SOCK_CLOEXEC,
+            0,
+        ))?;
+        let mut request: libc::ifreq = zeroed();
+        request.ifr_name[0] = b'l' as libc::c_char;
+        request.ifr_name[1] = b'o' as libc::c_char;
+        let result = if libc::ioctl(fd, libc::SIOCGIFFLAGS as _, &mut request) < 0 {
+            -1
+        } else {
+            request.ifr_ifru.ifru_flags |= libc::IFF_UP as libc::c_short;
+            libc::ioctl(fd, libc::SIOCSIFFLAGS as _, &mut request)
+        };
+        let error = io::Error::last_os_error();
+        libc::close(fd);
+        if result < 0 {
+            return Err(error);
+        }
+    }
+    Ok(())
+}
+
+/// Forks a helper that accepts on loopback inside the namespace and relays
+/// each connection to the proxy's unix socket, which is reachable because the
+/// filesystem is shared.
+unsafe fn spawn_bridge(proxy: &libc::sockaddr_un, proxy_len: libc::socklen_t) -> io::Result<()> {
+    // SAFETY: plain socket syscalls on stack-owned structs.
+    unsafe {
+        let listener = check(libc::socket(
+            libc::AF_INET,
+            libc::SOCK_STREAM | libc::SOCK_CLOEXEC,
+            0,
+        ))?;
+        let mut addr: libc::sockaddr_in = zeroed();
+        addr.sin_family = libc::AF_INET as libc::sa_family_t;
+        addr.sin_port = BRIDGE_PORT.to_be();
+        addr.sin_addr.s_addr = u32::from(std::net::Ipv4Addr::LOCALHOST).to_be();
+        check(libc::bind(
+            listener,
+            (&addr as *const libc::sockaddr_in).cast(),
+            size_of::<libc::sockaddr_in>() as libc::socklen_t,
+        ))?;
+        check(libc::listen(listener, 64))?;
+
+        let parent = libc::getpid();
+        match check(libc::fork())? {
+            0 => run_bridge(listener, parent, proxy, proxy_len),
+            _ => {
+                libc::close(listener);
+                Ok(())
+            }
+        }
+    }
+}
+
+unsafe fn run_bridge(
+    listener: libc::c_int,
+    parent: libc::pid_t,
+    proxy: &libc::sockaddr_un,
+    proxy_len: libc::socklen_t,
+) -> ! {
+    // SAFETY: the helper never returns into Rust code from the parent; it
+    // only makes syscalls and exits with `_exit`.
+    unsafe {
+        die_with_parent(parent);
+        // Inherited descriptors include the command's output pipes and the
+        // spawn error pipe; holding them would stall the parent.
+        for fd in 0..1024 {
+            if fd != listener {
+                libc::close(fd);
+            }
+        }
+        libc::signal(libc::SIGCHLD, libc::SIG_IGN);
+        let helper = libc::getpid();
+        loop {
+            let client = libc::accept(listener, std::ptr::null_mut(), std::ptr::null_mut());
+            if client < 0 {
+                if io::Error::last_os_error().kind() == io::ErrorKind::Interrupted {
+                    continue;
+                }
+                libc::_exit(1);
+            }
+            if libc::fork() == 0 {
+                libc::close(listener);
+                die_with_parent(helper);
+                relay(client, proxy, proxy_len);
+                libc::_exit(0);
+            }
+            libc::close(client);
+        }
+    }
+}
+
+unsafe fn die_with_parent(parent: libc::pid_t) {
+    // SAFETY: prctl and getppid have no memory effects.
+    unsafe {
+        libc::prctl(libc::PR_SET_PDEATHSIG, libc::SIGKILL);
+        if libc::getppid() != parent {
+            libc::_exit(0);
+        }
+    }
+}
+
+unsafe fn relay(client: libc::c_int, proxy: &libc::sockaddr_un, proxy_len: libc::socklen_t) {
+    // SAFETY: plain syscalls on descriptors owned by this process and a
+    // stack buffer.
+    unsafe {
+        let upstream = libc::socket(libc::AF_UNIX, libc::SOCK_STREAM, 0);
+        if upstream < 0
+            || libc::connect(
+                upstream,
+                (proxy as *const libc::sockaddr_un).cast(),
+                proxy_len,
+            ) < 0
+        {
+            return;
+        }
+        let mut fds = [
+            libc::pollfd {
+                fd: client,
+                events: libc::POLLIN,
+                revents: 0,
+            },
+            libc::pollfd {
+                fd: upstream,
+                events: libc::POLLIN,
+                revents: 0,
+            },
+        ];
+        let mut buffer = [0u8; RELAY_BUFFER];
+        let mut open = 2;
+        while open > 0 {
+            if libc::poll(fds.as_mut_ptr(), 2, -1) < 0 {
+                if io::Error::last_os_error().kind() == io::ErrorKind::Interrupted {
+                    continue;
+                }
+                return;
+            }
+            for (index, entry) in fds.iter_mut().enumerate() {
+                if entry.fd < 0 || entry.revents == 0 {
+                    continue;
+                }
+                let from = entry.fd;
+                let to = if index == 0 { upstream } else { client };
+                let read = libc::read(from, buffer.as_mut_ptr().cast(), buffer.len());
+                if read <= 0 {
+                    libc::shutdown(to, libc::SHUT_WR);
+                    entry.fd = -1;
+                    open -= 1;
+                    continue;
+                }
+                let mut sent = 0;
+                while sent < read as usize {
+                    let written =
+                        libc::write(to, buffer.as_ptr().add(sent).cast(), read as usize - sent);
+                    if written <= 0 {
+                        return;
+                    }
+                    sent += written as usize;
+                }
+            }
+        }
+    }
+}
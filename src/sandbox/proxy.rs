@@ -0,0 +1,256 @@
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpStream, UnixListener, UnixStream};
+use tokio::task::JoinHandle;
+
+const MAX_HEADER_BYTES: usize = 64 * 1024;
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// An HTTP proxy on a unix socket that only connects to allow-listed hosts.
+/// Sandboxed commands reach it through a loopback bridge inside their network
+/// namespace; everything it refuses is recorded for the tool result.
+#[derive(Debug)]
+pub(super) struct EgressProxy {
+    socket_path: PathBuf,
+    blocked: Arc<Mutex<Vec<String>>>,
+    task: JoinHandle<()>,
+}
+
+impl EgressProxy {
+    pub(super) fn start(allowed_hosts: Vec<String>) -> Result<Self> {
+        let nonce = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_nanos())
+            .unwrap_or_default();
+        let socket_path = std::env::temp_dir().join(format!(
+            "codetoad-proxy-{}-{nonce}.sock",
+            std::process::id()
+        ));
+        let listener = UnixListener::bind(&socket_path).with_context(|| {
+            format!("Failed to start network proxy at {}", socket_path.display())
+        })?;
+        let allowed_hosts = Arc::new(allowed_hosts);
+        let blocked = Arc::new(Mutex::new(Vec::new()));
+        let task_blocked = Arc::clone(&blocked);
+        let task = tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let allowed_hosts = Arc::clone(&allowed_hosts);
+                let blocked = Arc::clone(&task_blocked);
+                tokio::spawn(async move {
+                    let _ = handle_connection(stream, &allowed_hosts, &blocked).await;
+                });
+            }
+        });
+        Ok(Self {
+            socket_path,
+            blocked,
+            task,
+        })
+    }
+
+    pub(super) fn socket_path(&self) -> &Path {
+        &self.socket_path
+    }
+
+    /// Hosts refused since the last call, deduplicated in first-seen order.
+    pub(super) fn take_blocked(&self) -> Vec<String> {
+        std::mem::take(&mut *self.blocked.lock().unwrap_or_else(PoisonError::into_inner))
+    }
+}
+
+impl Drop for EgressProxy {
+    fn drop(&mut self) {
+        self.task.abort();
+        let _ = std::fs::remove_file(&self.socket_path);
+    }
+}
+
+async fn handle_connection(
+    stream: UnixStream,
+    allowed_hosts: &[String],
+    blocked: &Mutex<Vec<String>>,
+) -> std::io::Result<()> {
+    let mut reader = BufReader::new(stream);
+    let mut header = Vec::new();
+    loop {
+        let read = reader.read_until(b'\n', &mut header).await?;
+        if read == 0 || header.len() > MAX_HEADER_BYTES {
+            return Ok(());
+        }
+        if header.ends_with(b"\r\n\r\n") || header.ends_with(b"\n\n") {
+            break;
+        }
+    }
+
+    let request_line = String::from_utf8_lossy(&header);
+    let request_line = request_line.lines().next().unwrap_or_default();
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or_default();
+    let target = parts.next().unwrap_or_default();
+    let is_connect = method.eq_ignore_ascii_case("CONNECT");
+    let Some((host, port)) = (if is_connect {
+        split_host_port(target, 443)
+    } else {
+        target
+            .strip_prefix("http://")
+            .and_then(|rest| split_host_port(rest.split('/').next().unwrap_or_default(), 80))
+    }) else {
+        return respond(
+            reader.get_mut(),
+            "400 Bad Request",
+            "Unsupported proxy request\n",
+        )
+        .await;
+    };
+
+    if !host_allowed(&host, allowed_hosts) {
+        record_blocked(blocked, format!("{host}:{port}"));
+        return respond(
+            reader.get_mut(),
+            "403 Forbidden",
+            &format!("Blocked by codetoad network policy: {host} is not in the allow list\n"),
+        )
+        .await;
+    }
+
+    let mut upstream = match tokio::time::timeout(
+        CONNECT_TIMEOUT,
+        TcpStream::connect((host.as_str(), port)),
+    )
+    .await
+    {
+        Ok(Ok(upstream)) => upstream,
+        _ => {
+            return respond(
+                reader.get_mut(),
+                "502 Bad Gateway",
+                &format!("Could not connect to {host}:{port}\n"),
+            )
+            .await;
+        }
+    };
+    if is_connect {
+        reader
+            .get_mut()
+            .write_all(b"HTTP/1.1 200 Connection established\r\n\r\n")
+            .await?;
+    } else {
+        upstream.write_all(&header).await?;
+    }
+    let buffered = reader.buffer().to_vec();
+    upstream.write_all(&buffered).await?;
+    let mut client = reader.into_inner();
+    tokio::io::copy_bidirectional(&mut client, &mut upstream).await?;
+    Ok(())
+}
+
+fn record_blocked(blocked: &Mutex<Vec<String>>, entry: String) {
+    let mut blocked = blocked.lock().unwrap_or_else(PoisonError::into_inner);
+    if !blocked.contains(&entry) {
+        blocked.push(entry);
+    }
+}
+
+async fn respond(stream: &mut UnixStream, status: &str, body: &str) -> std::io::Result<()> {
+    let response = format!(
+        "HTTP/1.1 {status}\r\nContent-Type: text/plain\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await
+}
+
+fn split_host_port(authority: &str, default_port: u16) -> Option<(String, u16)> {
+    let authority = authority.rsplit('@').next()?;
+    let (host, port) = if let Some(rest) = authority.strip_prefix('[') {
+        let (host, after) = rest.split_once(']')?;
+        (host, after.strip_prefix(':'))
+    } else {
+        match authority.rsplit_once(':') {
+            Some((host, port)) => (host, Some(port)),
+            None => (authority, None),
+        }
+    };
+    let port = match port {
+        Some(port) => port.parse().ok()?,
+        None => default_port,
+    };
+    let host = host.trim_end_matches('.').to_ascii_lowercase();
+    (!host.is_empty()).then_some((host, port))
+}
+
+/// `example.com` matches only itself; `*.example.com` and `.example.com` also
+/// match every subdomain.
+pub(super) fn host_allowed(host: &str, allowed_hosts: &[String]) -> bool {
+    let host = host.trim_end_matches('.').to_ascii_lowercase();
+    allowed_hosts.iter().any(|pattern| {
+        let pattern = pattern.trim().trim_end_matches('.').to_ascii_lowercase();
+        match pattern
+            .strip_prefix("*.")
+            .or_else(|| pattern.strip_prefix('.'))
+        {
+            Some(domain) => {
+                host == domain
+                    || host
+                        .strip_suffix(domain)
+                        .is_some_and(|prefix| prefix.ends_with('.'))
+            }
+            None => host == pattern,
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{EgressProxy, host_allowed, split_host_port};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::UnixStream;
+
+    #[test]
+    fn matches_exact_hosts_and_wildcard_subdomains() {
+        let allowed = vec!["crates.io".to_string(), "*.example.com".to_string()];
+        assert!(host_allowed("crates.io", &allowed));
+        assert!(host_allowed("CRATES.IO.", &allowed));
+        assert!(!host_allowed("static.crates.io", &allowed));
+        assert!(host_allowed("api.example.com", &allowed));
+        assert!(host_allowed("example.com", &allowed));
+        assert!(!host_allowed("badexample.com", &allowed));
+    }
+
+    #[test]
+    fn splits_authorities() {
+        assert_eq!(
+            split_host_port("index.crates.io:443", 80),
+            Some(("index.crates.io".to_string(), 443))
+        );
+        assert_eq!(
+            split_host_port("example.com", 80),
+            Some(("example.com".to_string(), 80))
+        );
+        assert_eq!(
+            split_host_port("[::1]:8080", 80),
+            Some(("::1".to_string(), 8080))
+        );
+        assert_eq!(split_host_port("example.com:http", 80), None);
+    }
+
+    #[tokio::test]
+    async fn refuses_and_records_hosts_outside_the_allow_list() {
+        let proxy = EgressProxy::start(vec!["crates.io".to_string()]).expect("proxy");
+        let mut stream = UnixStream::connect(proxy.socket_path())
+            .await
+            .expect("connect");
+        stream
+            .write_all(b"CONNECT evil.example:443 HTTP/1.1\r\nHost: evil.example:443\r\n\r\n")
+            .await
+            .expect("write");
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.expect("read");
+        assert!(response.starts_with("HTTP/1.1 403 Forbidden"));
+        assert_eq!(proxy.take_blocked(), vec!["evil.example:443".to_string()]);
+        assert!(proxy.take_blocked().is_empty());
+    }
+}
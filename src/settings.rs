@@ -1,8 +1,12 @@
+use crate::audit::{AuditConfig, DEFAULT_AUDIT_DIR};
+use crate::permissions::{PermissionRule, PermissionRules, RuleSource};
 use crate::provider::{
     ProviderKind, XAI_DEFAULT_BASE_URL, XAI_DEFAULT_MODEL, api_key_env_candidates,
     default_model_for, default_models_for, detect_provider,
 };
-use anyhow::{Context, Result};
+use crate::redaction::RedactionConfig;
+use crate::sandbox::{NetworkPolicy, ResourceLimits, SandboxConfig, SandboxMode};
+use anyhow::{Context, Result, bail};
 use dirs::home_dir;
 use keyring::Entry;
 use keyring::Error as KeyringError;
@@ -36,6 +40,21 @@ pub struct UserSettings {
     pub providers: Option<BTreeMap<String, ProviderProfile>>,
     #[serde(rename = "activeProvider", alias = "active_provider")]
     pub active_provider: Option<String>,
+    #[serde(rename = "languageServers", alias = "language_servers")]
+    pub language_servers: Option<BTreeMap<String, LanguageServerConfig>>,
+    #[serde(rename = "persistentShell", alias = "persistent_shell")]
+    pub persistent_shell: Option<bool>,
+    pub sandbox: Option<SandboxSettings>,
+    /// Permission rules such as `bash(cargo test:*)` or `view_file(secrets/**)=deny`.
+    pub permissions: Option<Vec<String>>,
+    /// Gitignore-style patterns for paths no tool may touch, on top of `.grokignore`.
+    #[serde(rename = "ignorePatterns", alias = "ignore_patterns")]
+    pub ignore_patterns: Option<Vec<String>>,
+    pub redaction: Option<RedactionSettings>,
+    pub audit: Option<AuditSettings>,
+    /// Trust decisions keyed by canonical project path.
+    #[serde(rename = "trustedWorkspaces", alias = "trusted_workspaces")]
+    pub trusted_workspaces: Option<BTreeMap<String, bool>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -63,6 +82,81 @@ pub struct ProviderSummary {
 #[serde(default)]
 pub struct ProjectSettings {
     pub model: Option<String>,
+    #[serde(rename = "languageServers", alias = "language_servers")]
+    pub language_servers: Option<BTreeMap<String, LanguageServerConfig>>,
+    #[serde(rename = "persistentShell", alias = "persistent_shell")]
+    pub persistent_shell: Option<bool>,
+    pub sandbox: Option<SandboxSettings>,
+    pub permissions: Option<Vec<String>>,
+    #[serde(rename = "ignorePatterns", alias = "ignore_patterns")]
+    pub ignore_patterns: Option<Vec<String>>,
+    pub redaction: Option<RedactionSettings>,
+}
+
+/// Settings an administrator installs system-wide; read but never written.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct ManagedSettings {
+    pub permissions: Option<Vec<String>>,
+    #[serde(rename = "ignorePatterns", alias = "ignore_patterns")]
+    pub ignore_patterns: Option<Vec<String>>,
+    pub audit: Option<AuditSettings>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+#[serde(default)]
+pub struct SandboxSettings {
+    pub mode: Option<SandboxMode>,
+    /// Extra directories commands may write to, beyond the project and temp dirs.
+    #[serde(rename = "writableRoots", alias = "writable_roots")]
+    pub writable_roots: Vec<String>,
+    pub network: Option<NetworkMode>,
+    /// Hosts reachable under `network: "allow-list"`; `*.example.com` covers subdomains.
+    #[serde(rename = "allowedHosts", alias = "allowed_hosts")]
+    pub allowed_hosts: Vec<String>,
+    pub limits: ResourceLimits,
+}
+
+/// Masking of credentials in tool output sent to the model.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+#[serde(default)]
+pub struct RedactionSettings {
+    pub enabled: Option<bool>,
+    #[serde(rename = "highEntropy", alias = "high_entropy")]
+    pub high_entropy: Option<bool>,
+    /// Extra regular expressions to mask, on top of the built-in formats.
+    pub patterns: Vec<String>,
+}
+
+/// Where tool calls are logged; `path` is relative to the project root unless
+/// absolute or starting with `~/`.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+#[serde(default)]
+pub struct AuditSettings {
+    pub enabled: Option<bool>,
+    pub path: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum NetworkMode {
+    AllowAll,
+    DenyAll,
+    AllowList,
+}
+
+/// A language server launched over stdio, keyed by language name in settings.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+#[serde(default)]
+pub struct LanguageServerConfig {
+    pub command: String,
+    pub args: Vec<String>,
+    /// File extensions (without the dot) handled by this server.
+    pub extensions: Vec<String>,
+    #[serde(rename = "languageId", alias = "language_id")]
+    pub language_id: Option<String>,
+    #[serde(rename = "initializationOptions", alias = "initialization_options")]
+    pub initialization_options: Option<serde_json::Value>,
 }
 
 #[derive(Debug, Clone)]
@@ -71,7 +165,9 @@ pub struct SettingsManager {
     project_settings_path: PathBuf,
     user_settings: UserSettings,
     project_settings: ProjectSettings,
+    managed_settings: ManagedSettings,
     session_api_keys: BTreeMap<String, String>,
+    project_trusted: bool,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -104,11 +200,16 @@ impl SettingsManager {
     }
 
     pub fn load_with_home(cwd: &Path, home: &Path) -> Result<Self> {
+        Self::load_with_paths(cwd, home, &managed_settings_path())
+    }
+
+    pub fn load_with_paths(cwd: &Path, home: &Path, managed_settings_path: &Path) -> Result<Self> {
         let user_settings_path = home.join(".grok").join("user-settings.json");
         let project_settings_path = cwd.join(".grok").join("settings.json");
 
         let mut user_settings = load_json_or_default::<UserSettings>(&user_settings_path)?;
         let project_settings = load_json_or_default::<ProjectSettings>(&project_settings_path)?;
+        let managed_settings = load_json_or_default::<ManagedSettings>(managed_settings_path)?;
 
         if user_settings.settings_version.unwrap_or(0) < SETTINGS_VERSION {
             migrate_user_settings(&mut user_settings);
@@ -121,7 +222,9 @@ impl SettingsManager {
             project_settings_path,
             user_settings,
             project_settings,
+            managed_settings,
             session_api_keys: BTreeMap::new(),
+            project_trusted: true,
         };
 
         manager.ensure_default_files()?;
@@ -157,11 +260,57 @@ impl SettingsManager {
         write_json(&self.user_settings_path, &self.user_settings)
     }
 
+    /// Untrusted project settings are neither read nor written.
     pub fn save_project(&self) -> Result<()> {
+        if !self.project_trusted {
+            return Ok(());
+        }
         ensure_parent_dir(&self.project_settings_path)?;
         write_json(&self.project_settings_path, &self.project_settings)
     }
 
+    /// The saved decision for `dir`, or `None` if the user was never asked.
+    pub fn workspace_trust(&self, dir: &Path) -> Option<bool> {
+        self.user_settings
+            .trusted_workspaces
+            .as_ref()?
+            .get(&workspace_key(dir))
+            .copied()
+    }
+
+    pub fn set_workspace_trust(&mut self, dir: &Path, trusted: bool) -> Result<()> {
+        self.user_settings
+            .trusted_workspaces
+            .get_or_insert_default()
+            .insert(workspace_key(dir), trusted);
+        self.save_user()
+    }
+
+    /// Removes the saved decision for `dir`. Returns false when there was none.
+    pub fn forget_workspace_trust(&mut self, dir: &Path) -> Result<bool> {
+        let removed = self
+            .user_settings
+            .trusted_workspaces
+            .as_mut()
+            .and_then(|workspaces| workspaces.remove(&workspace_key(dir)))
+            .is_some();
+        if removed {
+            self.save_user()?;
+        }
+        Ok(removed)
+    }
+
+    pub fn is_project_trusted(&self) -> bool {
+        self.project_trusted
+    }
+
+    /// Drops everything loaded from the project's settings file for this run
+    /// and stops writing to it.
+    pub fn distrust_project(&mut self) {
+        self.project_trusted = false;
+        self.project_settings = ProjectSettings::default();
+    }
+
     pub fn get_api_key(&self) -> Option<String> {
         for key in api_key_env_candidates(self.current_provider()) {
             if let Ok(from_env) = std::env::var(key)
@@ -237,6 +386,165 @@ impl SettingsManager {
             .collect()
     }
 
+    /// User-level servers merged with project-level ones; the project wins per language.
+    pub fn language_servers(&self) -> BTreeMap<String, LanguageServerConfig> {
+        let mut servers = self
+            .user_settings
+            .language_servers
+            .clone()
+            .unwrap_or_default();
+        servers.extend(
+            self.project_settings
+                .language_servers
+                .clone()
+                .unwrap_or_default(),
+        );
+        servers.retain(|_, config| !config.command.trim().is_empty());
+        servers
+    }
+
+    /// Whether bash calls share one long-lived shell; off unless enabled.
+    pub fn persistent_shell(&self) -> bool {
+        self.project_settings
+            .persistent_shell
+            .or(self.user_settings.persistent_shell)
+            .unwrap_or(false)
+    }
+
+    /// A cloned repository must not loosen the user's sandbox: the stricter of
+    /// the two modes applies and only the user's writable roots count, with
+    /// `~/` expanded. The stricter network policy and the lower of each
+    /// resource limit apply too.
+    pub fn sandbox(&self) -> SandboxConfig {
+        let user = self.user_settings.sandbox.clone().unwrap_or_default();
+        let project = self.project_settings.sandbox.clone().unwrap_or_default();
+        let mode = [user.mode, project.mode]
+            .into_iter()
+            .flatten()
+            .fold(SandboxMode::default(), SandboxMode::stricter);
+        let network_policy = |layer: &SandboxSettings| match layer.network {
+            None | Some(NetworkMode::AllowAll) => NetworkPolicy::AllowAll,
+            Some(NetworkMode::DenyAll) => NetworkPolicy::DenyAll,
+            Some(NetworkMode::AllowList) => NetworkPolicy::AllowList(
+                layer
+                    .allowed_hosts
+                    .iter()
+                    .map(|host| host.trim().to_string())
+                    .filter(|host| !host.is_empty())
+                    .collect(),
+            ),
+        };
+        let network = network_policy(&user).stricter(network_policy(&project));
+        let home = home_dir();
+        let writable_roots = user
+            .writable_roots
+            .iter()
+            .map(|root| root.trim())
+            .filter(|root| !root.is_empty())
+            .map(|root| match (root.strip_prefix("~/"), &home) {
+                (Some(rest), Some(home)) => home.join(rest),
+                _ => PathBuf::from(root),
+            })
+            .collect();
+        SandboxConfig {
+            mode,
+            writable_roots,
+            network,
+            limits: user.limits.stricter(project.limits),
+        }
+    }
+
+    /// A project can switch redaction on and add patterns, but never switch
+    /// off what the user's settings leave on.
+    pub fn redaction(&self) -> RedactionConfig {
+        let user = self.user_settings.redaction.clone().unwrap_or_default();
+        let project = self.project_settings.redaction.clone().unwrap_or_default();
+        let defaults = RedactionConfig::default();
+        RedactionConfig {
+            enabled: user.enabled.unwrap_or(defaults.enabled) || project.enabled == Some(true),
+            high_entropy: user.high_entropy.unwrap_or(defaults.high_entropy)
+                || project.high_entropy == Some(true),
+            patterns: user
+                .patterns
+                .iter()
+                .chain(&project.patterns)
+                .map(|pattern| pattern.trim().to_string())
+                .filter(|pattern| !pattern.is_empty())
+                .collect(),
+        }
+    }
+
+    /// Managed settings win over the user's; a project cannot turn the log
+    /// off or move it. Auditing is on by default and writes to a per-project
+    /// directory under `~/.grok/audit/`, outside the workspace, so tools
+    /// cannot rewrite it.
+    pub fn audit(&self, project_root: &Path) -> AuditConfig {
+        let layers = [&self.managed_settings.audit, &self.user_settings.audit];
+        let enabled = layers
+            .iter()
+            .find_map(|layer| layer.as_ref().and_then(|audit| audit.enabled))
+            .unwrap_or(true);
+        let path = layers
+            .iter()
+            .find_map(|layer| layer.as_ref().and_then(|audit| audit.path.clone()))
+            .map(|path| path.trim().to_string())
+            .filter(|path| !path.is_empty());
+        // The user settings file lives at `<home>/.grok/user-settings.json`.
+        let home = self
+            .user_settings_path
+            .parent()
+            .and_then(Path::parent)
+            .unwrap_or(project_root);
+        let dir = match path {
+            None => home
+                .join(DEFAULT_AUDIT_DIR)
+                .join(audit_dir_name(project_root)),
+            Some(path) => home.join(path.strip_prefix("~/").unwrap_or(&path)),
+        };
+        AuditConfig { enabled, dir }
+    }
+
+    /// Rules from managed, project and user settings, tagged with their source.
+    pub fn permission_rules(&self) -> Result<PermissionRules> {
+        let mut rules = PermissionRules::default();
+        for (source, raw_rules) in [
+            (RuleSource::Managed, &self.managed_settings.permissions),
+            (RuleSource::Project, &self.project_settings.permissions),
+            (RuleSource::User, &self.user_settings.permissions),
+        ] {
+            rules.extend(PermissionRules::parse(source, raw_rules.iter().flatten())?);
+        }
+        Ok(rules)
+    }
+
+    /// Ignore patterns from managed, project and user settings; they all apply.
+    pub fn ignore_patterns(&self) -> Vec<String> {
+        [
+            &self.managed_settings.ignore_patterns,
+            &self.project_settings.ignore_patterns,
+            &self.user_settings.ignore_patterns,
+        ]
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|pattern| pattern.trim().to_string())
+        .filter(|pattern| !pattern.is_empty())
+        .collect()
+    }
+
+    pub fn add_project_permission_rule(&mut self, rule: &PermissionRule) -> Result<()> {
+        if !self.project_trusted {
+            bail!("This workspace is not trusted, so project settings are not saved");
+        }
+        let rule = rule.to_string();
+        let rules = self.project_settings.permissions.get_or_insert_default();
+        if !rules.contains(&rule) {
+            rules.push(rule);
+            self.save_project()?;
+        }
+        Ok(())
+    }
+
     pub fn update_project_model(&mut self, model: &str) -> Result<()> {
         self.project_settings.model = Some(model.to_string());
         self.save_project()
@@ -749,6 +1057,39 @@ impl SettingsManager {
     }
 }
 
+fn managed_settings_path() -> PathBuf {
+    if cfg!(target_os = "macos") {
+        PathBuf::from("/Library/Application Support/codetoad/managed-settings.json")
+    } else if cfg!(windows) {
+        PathBuf::from(r"C:\ProgramData\codetoad\managed-settings.json")
+    } else {
+        PathBuf::from("/etc/codetoad/managed-settings.json")
+    }
+}
+
+fn workspace_key(dir: &Path) -> String {
+    fs::canonicalize(dir)
+        .unwrap_or_else(|_| dir.to_path_buf())
+        .to_string_lossy()
+        .to_string()
+}
+
+/// The project's canonical path flattened into one directory name, e.g.
+/// `home-me-src-app`.
+fn audit_dir_name(project_root: &Path) -> String {
+    let key = workspace_key(project_root);
+    let name = key
+        .split(|ch: char| !(ch.is_alphanumeric() || matches!(ch, '-' | '_' | '.')))
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-");
+    if name.is_empty() {
+        "root".to_string()
+    } else {
+        name
+    }
+}
+
 fn ensure_parent_dir(path: &Path) -> Result<()> {
     if let Some(parent) = path.parent() {
         fs::create_dir_all(parent)
@@ -864,11 +1205,381 @@ fn load_legacy_api_key_from_keychain() -> Result<Option<String>> {
 #[cfg(test)]
 mod tests {
     use super::{
-        ProviderProfile, UserSettings, default_provider_id_for, migrate_user_settings,
-        models_match, normalize_provider_id, resolve_provider_id,
+        ProviderProfile, SettingsManager, UserSettings, audit_dir_name, default_provider_id_for,
+        migrate_user_settings, models_match, normalize_provider_id, resolve_provider_id,
     };
+    use crate::permissions::RuleSource;
     use crate::provider::ProviderKind;
+    use crate::sandbox::{NetworkPolicy, SandboxMode};
     use std::collections::BTreeMap;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    #[test]
+    fn language_servers_merge_user_and_project_settings() {
+        let home = TempDir::new("settings-lsp-home");
+        let project = TempDir::new("settings-lsp-project");
+        fs::create_dir_all(home.path().join(".grok")).expect("create home settings dir");
+        fs::create_dir_all(project.path().join(".grok")).expect("create project settings dir");
+        fs::write(
+            home.path().join(".grok/user-settings.json"),
+            r#"{"settingsVersion": 2, "apiKeyStorage": "plaintext", "languageServers": {
+                "rust": {"command": "rust-analyzer", "extensions": ["rs"]},
+                "python": {"command": "pylsp", "extensions": ["py"]}
+            }}"#,
+        )
+        .expect("write user settings");
+        fs::write(
+            project.path().join(".grok/settings.json"),
+            r#"{"languageServers": {
+                "rust": {"command": "ra-wrapper", "args": ["--log"], "extensions": ["rs"]},
+                "go": {"command": "", "extensions": ["go"]}
+            }}"#,
+        )
+        .expect("write project settings");
+
+        let settings =
+            SettingsManager::load_with_home(project.path(), home.path()).expect("settings");
+        let servers = settings.language_servers();
+        assert_eq!(
+            servers.keys().map(String::as_str).collect::<Vec<_>>(),
+            vec!["python", "rust"]
+        );
+        assert_eq!(servers["rust"].command, "ra-wrapper");
+        assert_eq!(servers["rust"].args, vec!["--log".to_string()]);
+    }
+
+    #[test]
+    fn persistent_shell_prefers_project_setting() {
+        let home = TempDir::new("settings-shell-home");
+        let project = TempDir::new("settings-shell-project");
+        fs::create_dir_all(home.path().join(".grok")).expect("create home settings dir");
+        fs::write(
+            home.path().join(".grok/user-settings.json"),
+            r#"{"settingsVersion": 2, "apiKeyStorage": "plaintext", "persistentShell": true}"#,
+        )
+        .expect("write user settings");
+
+        let settings =
+            SettingsManager::load_with_home(project.path(), home.path()).expect("settings");
+        assert!(settings.persistent_shell());
+
+        fs::create_dir_all(project.path().join(".grok")).expect("create project settings dir");
+        fs::write(
+            project.path().join(".grok/settings.json"),
+            r#"{"persistentShell": false}"#,
+        )
+        .expect("write project settings");
+        let settings =
+            SettingsManager::load_with_home(project.path(), home.path()).expect("settings");
+        assert!(!settings.persistent_shell());
+    }
+
+    #[test]
+    fn sandbox_takes_the_stricter_of_user_and_project_settings() {
+        let home = TempDir::new("settings-sandbox-home");
+        let project = TempDir::new("settings-sandbox-project");
+        fs::create_dir_all(home.path().join(".grok")).expect("create home settings dir");
+        fs::create_dir_all(project.path().join(".grok")).expect("create project settings dir");
+        fs::write(
+            home.path().join(".grok/user-settings.json"),
+            r#"{"settingsVersion": 2, "apiKeyStorage": "plaintext",
+                "sandbox": {"mode": "workspace-write", "writableRoots": ["/opt/cache"],
+                            "network": "deny-all", "allowedHosts": ["crates.io"],
+                            "limits": {"cpuSeconds": 600, "memoryMb": 8192}}}"#,
+        )
+        .expect("write user settings");
+        fs::write(
+            project.path().join(".grok/settings.json"),
+            r#"{"sandbox": {"mode": "read-only", "writableRoots": ["/srv/out"],
+                "network": "allow-list", "allowedHosts": ["*.example.com"],
+                "limits": {"cpuSeconds": 86400, "memoryMb": 2048}}}"#,
+        )
+        .expect("write project settings");
+
+        let settings =
+            SettingsManager::load_with_home(project.path(), home.path()).expect("settings");
+        let config = settings.sandbox();
+        assert_eq!(config.mode, SandboxMode::ReadOnly);
+        assert_eq!(config.writable_roots, vec![PathBuf::from("/opt/cache")]);
+        assert_eq!(config.network, NetworkPolicy::DenyAll);
+        assert_eq!(config.limits.cpu_seconds, Some(600));
+        assert_eq!(config.limits.memory_mb, Some(2048));
+
+        fs::write(
+            project.path().join(".grok/settings.json"),
+            r#"{"sandbox": {"mode": "off", "writableRoots": ["/"]}}"#,
+        )
+        .expect("write project settings");
+        let settings =
+            SettingsManager::load_with_home(project.path(), home.path()).expect("settings");
+        let config = settings.sandbox();
+        assert_eq!(config.mode, SandboxMode::WorkspaceWrite);
+        assert_eq!(config.writable_roots, vec![PathBuf::from("/opt/cache")]);
+        assert_eq!(config.network, NetworkPolicy::DenyAll);
+
+        fs::write(
+            home.path().join(".grok/user-settings.json"),
+            r#"{"settingsVersion": 2, "apiKeyStorage": "plaintext",
+                "sandbox": {"network": "allow-list", "allowedHosts": ["crates.io", "github.com"]}}"#,
+        )
+        .expect("write user settings");
+        for (project_sandbox, expected) in [
+            (
+                r#"{"network": "allow-all"}"#,
+                vec!["crates.io", "github.com"],
+            ),
+            (
+                r#"{"network": "allow-list", "allowedHosts": ["crates.io", "evil.example"]}"#,
+                vec!["crates.io"],
+            ),
+        ] {
+            fs::write(
+                project.path().join(".grok/settings.json"),
+                format!(r#"{{"sandbox": {project_sandbox}}}"#),
+            )
+            .expect("write project settings");
+            let settings =
+                SettingsManager::load_with_home(project.path(), home.path()).expect("settings");
+            assert_eq!(
+                settings.sandbox().network,
+                NetworkPolicy::AllowList(expected.into_iter().map(str::to_string).collect())
+            );
+        }
+    }
+
+    #[test]
+    fn permission_rules_load_from_every_source() {
+        let home = TempDir::new("settings-permissions-home");
+        let project = TempDir::new("settings-permissions-project");
+        let managed = TempDir::new("settings-permissions-managed");
+        fs::create_dir_all(home.path().join(".grok")).expect("create home settings dir");
+        fs::create_dir_all(project.path().join(".grok")).expect("create project settings dir");
+        fs::write(
+            home.path().join(".grok/user-settings.json"),
+            r#"{"settingsVersion": 2, "apiKeyStorage": "plaintext",
+                "permissions": ["bash(git status*)"]}"#,
+        )
+        .expect("write user settings");
+        fs::write(
+            project.path().join(".grok/settings.json"),
+            r#"{"permissions": ["str_replace_editor(src/**)"]}"#,
+        )
+        .expect("write project settings");
+        let managed_path = managed.path().join("managed-settings.json");
+        fs::write(&managed_path, r#"{"permissions": ["bash(curl:*)=deny"]}"#)
+            .expect("write managed settings");
+
+        let mut settings =
+            SettingsManager::load_with_paths(project.path(), home.path(), &managed_path)
+                .expect("settings");
+        let rules = settings.permission_rules().expect("rules");
+        let rendered = |source| {
+            rules
+                .from_source(source)
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(rendered(RuleSource::Managed), vec!["bash(curl:*)=deny"]);
+        assert_eq!(
+            rendered(RuleSource::Project),
+            vec!["str_replace_editor(src/**)"]
+        );
+        assert_eq!(rendered(RuleSource::User), vec!["bash(git status*)"]);
+
+        let rule = "bash(cargo test:*)".parse().expect("rule");
+        settings
+            .add_project_permission_rule(&rule)
+            .expect("save rule");
+        let saved = fs::read_to_string(project.path().join(".grok/settings.json"))
+            .expect("read project settings");
+        assert!(saved.contains("bash(cargo test:*)"));
+
+        fs::write(
+            project.path().join(".grok/settings.json"),
+            r#"{"permissions": ["bash(ls"]}"#,
+        )
+        .expect("write project settings");
+        let settings = SettingsManager::load_with_paths(project.path(), home.path(), &managed_path)
+            .expect("settings");
+        let error = settings.permission_rules().expect_err("invalid rule");
+        assert!(format!("{error:#}").contains("project settings"));
+    }
+
+    #[test]
+    fn ignore_patterns_merge_every_source() {
+        let home = TempDir::new("settings-ignore-home");
+        let project = TempDir::new("settings-ignore-project");
+        let managed = TempDir::new("settings-ignore-managed");
+        fs::create_dir_all(home.path().join(".grok")).expect("create home settings dir");
+        fs::create_dir_all(project.path().join(".grok")).expect("create project settings dir");
+        fs::write(
+            home.path().join(".grok/user-settings.json"),
+            r#"{"settingsVersion": 2, "apiKeyStorage": "plaintext",
+                "ignorePatterns": ["*.pem", " "]}"#,
+        )
+        .expect("write user settings");
+        fs::write(
+            project.path().join(".grok/settings.json"),
+            r#"{"ignorePatterns": ["dumps/"]}"#,
+        )
+        .expect("write project settings");
+        let managed_path = managed.path().join("managed-settings.json");
+        fs::write(&managed_path, r#"{"ignorePatterns": [".env"]}"#)
+            .expect("write managed settings");
+
+        let settings = SettingsManager::load_with_paths(project.path(), home.path(), &managed_path)
+            .expect("settings");
+        assert_eq!(settings.ignore_patterns(), vec![".env", "dumps/", "*.pem"]);
+    }
+
+    #[test]
+    fn redaction_lets_projects_tighten_but_not_disable() {
+        let home = TempDir::new("settings-redaction-home");
+        let project = TempDir::new("settings-redaction-project");
+        let managed = TempDir::new("settings-redaction-managed");
+        fs::create_dir_all(home.path().join(".grok")).expect("create home settings dir");
+        fs::create_dir_all(project.path().join(".grok")).expect("create project settings dir");
+        fs::write(
+            home.path().join(".grok/user-settings.json"),
+            r#"{"settingsVersion": 2, "apiKeyStorage": "plaintext",
+                "redaction": {"highEntropy": false, "patterns": ["acme-[0-9]+"]}}"#,
+        )
+        .expect("write user settings");
+        fs::write(
+            project.path().join(".grok/settings.json"),
+            r#"{"redaction": {"highEntropy": true, "patterns": ["corp_[a-z]+"]}}"#,
+        )
+        .expect("write project settings");
+
+        let settings = SettingsManager::load_with_paths(
+            project.path(),
+            home.path(),
+            &managed.path().join("missing.json"),
+        )
+        .expect("settings");
+        let config = settings.redaction();
+        assert!(config.enabled);
+        assert!(config.high_entropy);
+        assert_eq!(config.patterns, vec!["acme-[0-9]+", "corp_[a-z]+"]);
+
+        fs::write(
+            project.path().join(".grok/settings.json"),
+            r#"{"redaction": {"enabled": false, "highEntropy": false}}"#,
+        )
+        .expect("write project settings");
+        let settings = SettingsManager::load_with_paths(
+            project.path(),
+            home.path(),
+            &managed.path().join("missing.json"),
+        )
+        .expect("settings");
+        let config = settings.redaction();
+        assert!(config.enabled);
+        assert!(!config.high_entropy);
+    }
+
+    #[test]
+    fn workspace_trust_persists_per_canonical_path_and_distrust_drops_project_settings() {
+        let home = TempDir::new("settings-trust-home");
+        let project = TempDir::new("settings-trust-project");
+        let managed = TempDir::new("settings-trust-managed");
+        fs::create_dir_all(project.path().join(".grok")).expect("create project settings dir");
+        let project_settings = r#"{"model": "repo-model", "permissions": ["bash(rm:*)"]}"#;
+        fs::write(project.path().join(".grok/settings.json"), project_settings)
+            .expect("write project settings");
+        let managed_path = managed.path().join("managed-settings.json");
+
+        let mut settings =
+            SettingsManager::load_with_paths(project.path(), home.path(), &managed_path)
+                .expect("settings");
+        assert_eq!(settings.workspace_trust(project.path()), None);
+        settings
+            .set_workspace_trust(&project.path().join("."), false)
+            .expect("save trust");
+
+        let mut settings =
+            SettingsManager::load_with_paths(project.path(), home.path(), &managed_path)
+                .expect("settings");
+        assert_eq!(settings.workspace_trust(project.path()), Some(false));
+        assert!(
+            settings
+                .forget_workspace_trust(project.path())
+                .expect("forget")
+        );
+        assert!(
+            !settings
+                .forget_workspace_trust(project.path())
+                .expect("forget")
+        );
+        let mut settings =
+            SettingsManager::load_with_paths(project.path(), home.path(), &managed_path)
+                .expect("settings");
+        assert_eq!(settings.workspace_trust(project.path()), None);
+        assert_eq!(settings.get_current_model(), "repo-model");
+        settings.distrust_project();
+        assert!(!settings.is_project_trusted());
+        assert_ne!(settings.get_current_model(), "repo-model");
+        assert!(settings.permission_rules().expect("rules").is_empty());
+        settings
+            .update_project_model("other")
+            .expect("update model");
+        assert!(
+            settings
+                .add_project_permission_rule(&"bash(ls:*)".parse().expect("rule"))
+                .is_err()
+        );
+        assert_eq!(
+            fs::read_to_string(project.path().join(".grok/settings.json")).expect("read"),
+            project_settings
+        );
+    }
+
+    #[test]
+    fn audit_defaults_to_a_user_dir_and_ignores_project_settings() {
+        let home = TempDir::new("settings-audit-home");
+        let project = TempDir::new("settings-audit-project");
+        let managed = TempDir::new("settings-audit-managed");
+        fs::create_dir_all(project.path().join(".grok")).expect("create project settings dir");
+        let managed_path = managed.path().join("managed-settings.json");
+
+        let settings = SettingsManager::load_with_paths(project.path(), home.path(), &managed_path)
+            .expect("settings");
+        let config = settings.audit(project.path());
+        assert!(config.enabled);
+        assert!(config.dir.starts_with(home.path().join(".grok/audit")));
+        assert!(!config.dir.starts_with(project.path()));
+        assert_eq!(
+            config.dir.file_name().and_then(|name| name.to_str()),
+            Some(audit_dir_name(project.path()).as_str())
+        );
+        assert!(!audit_dir_name(project.path()).contains('/'));
+
+        fs::write(
+            project.path().join(".grok/settings.json"),
+            r#"{"audit": {"enabled": false, "path": "/tmp/elsewhere"}}"#,
+        )
+        .expect("write project settings");
+        fs::create_dir_all(home.path().join(".grok")).expect("create home settings dir");
+        fs::write(
+            home.path().join(".grok/user-settings.json"),
+            r#"{"settingsVersion": 2, "apiKeyStorage": "plaintext",
+                "audit": {"enabled": false, "path": "logs/audit"}}"#,
+        )
+        .expect("write user settings");
+        let settings = SettingsManager::load_with_paths(project.path(), home.path(), &managed_path)
+            .expect("settings");
+        let config = settings.audit(project.path());
+        assert!(!config.enabled);
+        assert_eq!(config.dir, home.path().join("logs/audit"));
+
+        fs::write(&managed_path, r#"{"audit": {"enabled": true}}"#)
+            .expect("write managed settings");
+        let settings = SettingsManager::load_with_paths(project.path(), home.path(), &managed_path)
+            .expect("settings");
+        assert!(settings.audit(project.path()).enabled);
+    }
 
     #[test]
     fn models_match_ignores_case_and_whitespace() {
@@ -933,4 +1644,31 @@ mod tests {
         let providers = BTreeMap::<String, ProviderProfile>::new();
         assert_eq!(resolve_provider_id("missing", &providers), None);
     }
+
+    struct TempDir {
+        path: PathBuf,
+    }
+
+    impl TempDir {
+        fn new(prefix: &str) -> Self {
+            let nonce = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .expect("clock after epoch")
+                .as_nanos();
+            let pid = std::process::id();
+            let path = std::env::temp_dir().join(format!("codetoad-{prefix}-{pid}-{nonce}"));
+            fs::create_dir_all(&path).expect("create temp dir");
+            Self { path }
+        }
+
+        fn path(&self) -> &Path {
+            &self.path
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.path);
+        }
+    }
 }
@@ -0,0 +1,8 @@
+mod parser;
+mod read_only;
+
+pub use self::parser::{
+    AndOr, Command, Connector, HereDoc, ListItem, ParseError, Pipeline, RedirectOp, Redirection,
+    Script, SimpleCommand, Word, WordPart, parse, parse_command, parse_without_escapes,
+};
+pub use self::read_only::{is_read_only, reads_recursively};
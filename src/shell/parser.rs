@@ -0,0 +1,1063 @@
+//! A recursive-descent parser for the POSIX shell language commands are
+//! written in: lists, pipelines, subshells, redirections, here-documents,
+//! quoting and substitutions. Compound commands such as `if` or `for` are not
+//! modelled; their keywords come through as ordinary words.
+
+use std::fmt;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Script {
+    pub items: Vec<ListItem>,
+    /// Bodies of the here-documents in this script, in source order.
+    pub here_docs: Vec<HereDoc>,
+    /// Set when the parser skipped syntax it does not model, such as function
+    /// definitions or `case` patterns.
+    pub unsupported: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ListItem {
+    pub and_or: AndOr,
+    /// Terminated by `&`.
+    pub background: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AndOr {
+    pub first: Pipeline,
+    pub rest: Vec<(Connector, Pipeline)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Connector {
+    And,
+    Or,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pipeline {
+    pub negated: bool,
+    pub commands: Vec<Command>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    Simple(SimpleCommand),
+    Subshell {
+        script: Script,
+        redirections: Vec<Redirection>,
+    },
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SimpleCommand {
+    /// Leading `NAME=value` words.
+    pub assignments: Vec<Word>,
+    pub words: Vec<Word>,
+    pub redirections: Vec<Redirection>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Redirection {
+    pub fd: Option<u32>,
+    pub op: RedirectOp,
+    /// The file, descriptor or here-document delimiter.
+    pub target: Word,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RedirectOp {
+    /// `<`
+    Input,
+    /// `>`
+    Output,
+    /// `>>`
+    Append,
+    /// `>|`
+    Clobber,
+    /// `<>`
+    ReadWrite,
+    /// `<&`
+    DupInput,
+    /// `>&`
+    DupOutput,
+    /// `<<` or `<<-`
+    HereDoc,
+    /// `<<<`
+    HereString,
+    /// `&>`
+    OutputAll,
+    /// `&>>`
+    AppendAll,
+}
+
+impl RedirectOp {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Input => "<",
+            Self::Output => ">",
+            Self::Append => ">>",
+            Self::Clobber => ">|",
+            Self::ReadWrite => "<>",
+            Self::DupInput => "<&",
+            Self::DupOutput => ">&",
+            Self::HereDoc => "<<",
+            Self::HereString => "<<<",
+            Self::OutputAll => "&>",
+            Self::AppendAll => "&>>",
+        }
+    }
+
+    pub fn writes_file(self) -> bool {
+        matches!(
+            self,
+            Self::Output
+                | Self::Append
+                | Self::Clobber
+                | Self::ReadWrite
+                | Self::OutputAll
+                | Self::AppendAll
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HereDoc {
+    pub body: String,
+    /// Unquoted delimiters make the body subject to expansion.
+    pub expands: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Word {
+    /// The word as written, quotes included.
+    pub raw: String,
+    pub parts: Vec<WordPart>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WordPart {
+    /// Unquoted text, where glob and brace characters are live.
+    Literal(String),
+    /// Quoted or escaped text.
+    Quoted(String),
+    /// A leading `~` or `~user`.
+    Tilde(String),
+    /// `$name`, `${...}` or `$'...'`, as written without the `$`.
+    Parameter(String),
+    CommandSubstitution(Script),
+    Arithmetic(String),
+    ProcessSubstitution(Script),
+}
+
+impl Word {
+    /// The word's value when it has no expansions.
+    pub fn static_value(&self) -> Option<String> {
+        let mut value = String::new();
+        for part in &self.parts {
+            match part {
+                WordPart::Literal(text) | WordPart::Quoted(text) => value.push_str(text),
+                _ => return None,
+            }
+        }
+        Some(value)
+    }
+
+    /// Unquoted `*`, `?`, `[` or `{`, which the shell may expand to other words.
+    pub fn has_pattern(&self) -> bool {
+        self.parts.iter().any(
+            |part| matches!(part, WordPart::Literal(text) if text.contains(['*', '?', '[', '{'])),
+        )
+    }
+
+    pub fn is_quoted(&self) -> bool {
+        self.parts
+            .iter()
+            .any(|part| matches!(part, WordPart::Quoted(_)))
+    }
+
+    fn is_assignment(&self) -> bool {
+        let Some(WordPart::Literal(first)) = self.parts.first() else {
+            return false;
+        };
+        first.split_once('=').is_some_and(|(name, _)| {
+            !name.is_empty()
+                && !name.starts_with(|ch: char| ch.is_ascii_digit())
+                && name
+                    .chars()
+                    .all(|ch| ch.is_ascii_alphanumeric() || ch == '_')
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub message: String,
+    pub offset: usize,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at offset {}", self.message, self.offset)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+type ParseResult<T> = Result<T, ParseError>;
+
+/// Parses `source` with POSIX quoting, where a backslash escapes the next
+/// character.
+pub fn parse(source: &str) -> ParseResult<Script> {
+    Parser::new(source, true).parse_all()
+}
+
+/// Parses with backslashes kept literally, as in PowerShell and cmd paths.
+pub fn parse_without_escapes(source: &str) -> ParseResult<Script> {
+    Parser::new(source, false).parse_all()
+}
+
+/// Parses a command the way the bash tool's shell reads it: POSIX `sh` on
+/// unix, PowerShell on Windows where backslashes separate paths.
+pub fn parse_command(source: &str) -> ParseResult<Script> {
+    if cfg!(windows) {
+        parse_without_escapes(source)
+    } else {
+        parse(source)
+    }
+}
+
+impl Script {
+    /// Every simple command, including those in subshells and substitutions.
+    pub fn simple_commands(&self) -> Vec<&SimpleCommand> {
+        let mut commands = Vec::new();
+        self.collect_simple_commands(&mut commands);
+        commands
+    }
+
+    fn collect_simple_commands<'a>(&'a self, commands: &mut Vec<&'a SimpleCommand>) {
+        for command in self.commands() {
+            match command {
+                Command::Simple(simple) => {
+                    commands.push(simple);
+                    let words = simple.assignments.iter().chain(&simple.words).chain(
+                        simple
+                            .redirections
+                            .iter()
+                            .map(|redirection| &redirection.target),
+                    );
+                    for script in words.flat_map(Word::nested_scripts) {
+                        script.collect_simple_commands(commands);
+                    }
+                }
+                Command::Subshell {
+                    script,
+                    redirections,
+                } => {
+                    script.collect_simple_commands(commands);
+                    for nested in redirections
+                        .iter()
+                        .flat_map(|redirection| redirection.target.nested_scripts())
+                    {
+                        nested.collect_simple_commands(commands);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Every redirection, including those on subshells and in nested scripts.
+    pub fn redirections(&self) -> Vec<&Redirection> {
+        let mut redirections = Vec::new();
+        for command in self.commands() {
+            let (own, words, nested) = match command {
+                Command::Simple(simple) => (
+                    &simple.redirections,
+                    simple.assignments.iter().chain(&simple.words).collect(),
+                    None,
+                ),
+                Command::Subshell {
+                    script,
+                    redirections,
+                } => (redirections, Vec::new(), Some(script)),
+            };
+            redirections.extend(own);
+            let targets = own.iter().map(|redirection| &redirection.target);
+            for script in words
+                .into_iter()
+                .chain(targets)
+                .flat_map(Word::nested_scripts)
+                .chain(nested)
+            {
+                redirections.extend(script.redirections());
+            }
+        }
+        redirections
+    }
+
+    /// The commands of every pipeline at this level, in order.
+    pub fn commands(&self) -> impl Iterator<Item = &Command> {
+        self.items.iter().flat_map(|item| {
+            std::iter::once(&item.and_or.first)
+                .chain(item.and_or.rest.iter().map(|(_, pipeline)| pipeline))
+                .flat_map(|pipeline| &pipeline.commands)
+        })
+    }
+
+    /// Whether this script or any nested one skipped unmodelled syntax.
+    pub fn is_fully_supported(&self) -> bool {
+        !self.unsupported
+            && self.commands().all(|command| match command {
+                Command::Simple(simple) => simple
+                    .assignments
+                    .iter()
+                    .chain(&simple.words)
+                    .chain(
+                        simple
+                            .redirections
+                            .iter()
+                            .map(|redirection| &redirection.target),
+                    )
+                    .flat_map(Word::nested_scripts)
+                    .all(Script::is_fully_supported),
+                Command::Subshell { script, .. } => script.is_fully_supported(),
+            })
+    }
+}
+
+impl Word {
+    pub fn has_substitution(&self) -> bool {
+        self.nested_scripts().next().is_some()
+    }
+
+    fn nested_scripts(&self) -> impl Iterator<Item = &Script> {
+        self.parts.iter().filter_map(|part| match part {
+            WordPart::CommandSubstitution(script) | WordPart::ProcessSubstitution(script) => {
+                Some(script)
+            }
+            _ => None,
+        })
+    }
+}
+
+struct PendingHereDoc {
+    delimiter: String,
+    strip_tabs: bool,
+    expands: bool,
+}
+
+struct Parser<'a> {
+    src: &'a str,
+    pos: usize,
+    escapes: bool,
+    pending_here_docs: Vec<PendingHereDoc>,
+    here_docs: Vec<HereDoc>,
+    unsupported: bool,
+}
+
+impl<'a> Parser<'a> {
+    fn new(src: &'a str, escapes: bool) -> Self {
+        Self {
+            src,
+            pos: 0,
+            escapes,
+            pending_here_docs: Vec::new(),
+            here_docs: Vec::new(),
+            unsupported: false,
+        }
+    }
+
+    fn parse_all(mut self) -> ParseResult<Script> {
+        let mut script = self.parse_script(false)?;
+        self.read_here_docs();
+        script.here_docs = self.here_docs;
+        script.unsupported |= self.unsupported;
+        Ok(script)
+    }
+
+    fn error<T>(&self, message: &str) -> ParseResult<T> {
+        Err(ParseError {
+            message: message.to_string(),
+            offset: self.pos,
+        })
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.src[self.pos..].chars().next()
+    }
+
+    fn peek_second(&self) -> Option<char> {
+        let mut chars = self.src[self.pos..].chars();
+        chars.next();
+        chars.next()
+    }
+
+    fn starts_with(&self, text: &str) -> bool {
+        self.src[self.pos..].starts_with(text)
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let ch = self.peek()?;
+        self.pos += ch.len_utf8();
+        Some(ch)
+    }
+
+    /// Skips spaces, tabs, line continuations and comments, stopping at a
+    /// newline.
+    fn skip_blanks(&mut self) {
+        loop {
+            match self.peek() {
+                Some(' ' | '\t' | '\r') => {
+                    self.bump();
+                }
+                Some('\\') if self.escapes && self.peek_second() == Some('\n') => {
+                    self.pos += 2;
+                }
+                Some('#') => {
+                    while !matches!(self.peek(), None | Some('\n')) {
+                        self.bump();
+                    }
+                }
+                _ => return,
+            }
+        }
+    }
+
+    fn skip_blanks_and_newlines(&mut self) {
+        loop {
+            self.skip_blanks();
+            if self.peek() != Some('\n') {
+                return;
+            }
+            self.bump();
+            self.read_here_docs();
+        }
+    }
+
+    /// Parses a list up to the end of input, or up to and including `)` when
+    /// `in_parens` is set.
+    fn parse_script(&mut self, in_parens: bool) -> ParseResult<Script> {
+        let mut script = Script::default();
+        loop {
+            self.skip_blanks();
+            match self.peek() {
+                None if in_parens => return self.error("missing `)`"),
+                None => break,
+                Some(')') if in_parens => {
+                    self.bump();
+                    break;
+                }
+                Some('\n') => {
+                    self.bump();
+                    self.read_here_docs();
+                    continue;
+                }
+                Some(';') => {
+                    self.bump();
+                    // `;;` and `;&` end `case` branches.
+                    if matches!(self.peek(), Some(';' | '&')) {
+                        self.bump();
+                        script.unsupported = true;
+                    }
+                    continue;
+                }
+                Some(')' | '|') => {
+                    self.bump();
+                    script.unsupported = true;
+                    continue;
+                }
+                Some('&') if self.peek_second() != Some('>') => {
+                    self.bump();
+                    script.unsupported = true;
+                    continue;
+                }
+                _ => {}
+            }
+
+            let and_or = self.parse_and_or()?;
+            self.skip_blanks();
+            let background =
+                self.peek() == Some('&') && !matches!(self.peek_second(), Some('&' | '>')) && {
+                    self.bump();
+                    true
+                };
+            script.items.push(ListItem { and_or, background });
+            self.skip_blanks();
+            if !matches!(self.peek(), None | Some(';' | '\n' | '&' | ')')) {
+                // e.g. the `(` of a function definition
+                script.unsupported = true;
+            }
+        }
+        Ok(script)
+    }
+
+    fn parse_and_or(&mut self) -> ParseResult<AndOr> {
+        let first = self.parse_pipeline()?;
+        let mut rest = Vec::new();
+        loop {
+            self.skip_blanks();
+            let connector = if self.starts_with("&&") {
+                Connector::And
+            } else if self.starts_with("||") {
+                Connector::Or
+            } else {
+                break;
+            };
+            self.pos += 2;
+            self.skip_blanks_and_newlines();
+            rest.push((connector, self.parse_pipeline()?));
+        }
+        Ok(AndOr { first, rest })
+    }
+
+    fn parse_pipeline(&mut self) -> ParseResult<Pipeline> {
+        self.skip_blanks();
+        let negated =
+            self.peek() == Some('!') && matches!(self.peek_second(), Some(' ' | '\t')) && {
+                self.bump();
+                true
+            };
+        let mut commands = vec![self.parse_command()?];
+        loop {
+            self.skip_blanks();
+            if self.peek() != Some('|') || self.peek_second() == Some('|') {
+                break;
+            }
+            self.bump();
+            if self.peek() == Some('&') {
+                self.bump();
+            }
+            self.skip_blanks_and_newlines();
+            commands.push(self.parse_command()?);
+        }
+        Ok(Pipeline { negated, commands })
+    }
+
+    fn parse_command(&mut self) -> ParseResult<Command> {
+        self.skip_blanks();
+        if self.peek() != Some('(') {
+            return self.parse_simple_command().map(Command::Simple);
+        }
+        self.bump();
+        let script = self.parse_script(true)?;
+        let mut redirections = Vec::new();
+        loop {
+            self.skip_blanks();
+            match self.parse_redirection()? {
+                Some(redirection) => redirections.push(redirection),
+                None => break,
+            }
+        }
+        Ok(Command::Subshell {
+            script,
+            redirections,
+        })
+    }
+
+    fn parse_simple_command(&mut self) -> ParseResult<SimpleCommand> {
+        let mut command = SimpleCommand::default();
+        loop {
+            self.skip_blanks();
+            match self.peek() {
+                None | Some('\n' | ';' | '|' | '(' | ')') => break,
+                Some('&') if self.peek_second() != Some('>') => break,
+                _ => {}
+            }
+            if let Some(redirection) = self.parse_redirection()? {
+                command.redirections.push(redirection);
+                continue;
+            }
+            let word = self.parse_word()?;
+            if command.words.is_empty() && word.is_assignment() {
+                command.assignments.push(word);
+            } else {
+                command.words.push(word);
+            }
+        }
+        if command.words.is_empty()
+            && command.assignments.is_empty()
+            && command.redirections.is_empty()
+        {
+            return self.error("expected a command");
+        }
+        Ok(command)
+    }
+
+    fn parse_redirection(&mut self) -> ParseResult<Option<Redirection>> {
+        let start = self.pos;
+        let digits = self.src[self.pos..]
+            .chars()
+            .take_while(char::is_ascii_digit)
+            .count();
+        self.pos += digits;
+        let fd = if digits > 0 {
+            match self.src[start..self.pos].parse() {
+                Ok(fd) if matches!(self.peek(), Some('<' | '>')) => Some(fd),
+                _ => {
+                    self.pos = start;
+                    return Ok(None);
+                }
+            }
+        } else {
+            None
+        };
+
+        const OPERATORS: &[(&str, RedirectOp)] = &[
+            ("<<<", RedirectOp::HereString),
+            ("<<-", RedirectOp::HereDoc),
+            ("<<", RedirectOp::HereDoc),
+            ("<>", RedirectOp::ReadWrite),
+            ("<&", RedirectOp::DupInput),
+            ("<", RedirectOp::Input),
+            (">>", RedirectOp::Append),
+            (">|", RedirectOp::Clobber),
+            (">&", RedirectOp::DupOutput),
+            (">", RedirectOp::Output),
+            ("&>>", RedirectOp::AppendAll),
+            ("&>", RedirectOp::OutputAll),
+        ];
+        let Some((text, op)) = OPERATORS
+            .iter()
+            .filter(|(text, _)| fd.is_none() || !text.starts_with('&'))
+            .find(|(text, _)| self.starts_with(text))
+        else {
+            self.pos = start;
+            return Ok(None);
+        };
+        // `<(` and `>(` start process substitutions, not redirections.
+        if matches!(op, RedirectOp::Input | RedirectOp::Output)
+            && fd.is_none()
+            && self.src[self.pos + text.len()..].starts_with('(')
+        {
+            self.pos = start;
+            return Ok(None);
+        }
+        self.pos += text.len();
+        self.skip_blanks();
+        if matches!(
+            self.peek(),
+            None | Some('\n' | ';' | '&' | '|' | '<' | '>' | '(' | ')')
+        ) {
+            return self.error("expected a redirection target");
+        }
+        let target = self.parse_word()?;
+        if *op == RedirectOp::HereDoc {
+            self.pending_here_docs.push(PendingHereDoc {
+                delimiter: target
+                    .parts
+                    .iter()
+                    .map(|part| match part {
+                        WordPart::Literal(text) | WordPart::Quoted(text) => text.as_str(),
+                        _ => "",
+                    })
+                    .collect(),
+                strip_tabs: *text == "<<-",
+                expands: !target.is_quoted(),
+            });
+        }
+        Ok(Some(Redirection {
+            fd,
+            op: *op,
+            target,
+        }))
+    }
+
+    /// Reads the bodies of here-documents opened on the line just ended.
+    fn read_here_docs(&mut self) {
+        for pending in std::mem::take(&mut self.pending_here_docs) {
+            let mut body = String::new();
+            while self.pos < self.src.len() {
+                let rest = &self.src[self.pos..];
+                let line_len = rest.find('\n').map_or(rest.len(), |index| index + 1);
+                let line = &rest[..line_len];
+                self.pos += line_len;
+                let content = line.trim_end_matches('\n');
+                let content = if pending.strip_tabs {
+                    content.trim_start_matches('\t')
+                } else {
+                    content
+                };
+                if content == pending.delimiter {
+                    break;
+                }
+                body.push_str(content);
+                body.push('\n');
+            }
+            self.here_docs.push(HereDoc {
+                body,
+                expands: pending.expands,
+            });
+        }
+    }
+
+    fn parse_word(&mut self) -> ParseResult<Word> {
+        let start = self.pos;
+        let mut parts = Vec::new();
+        let mut literal = String::new();
+        while let Some(ch) = self.peek() {
+            match ch {
+                '<' | '>' if self.peek_second() == Some('(') && self.pos == start => {
+                    self.pos += 2;
+                    let script = self.parse_script(true)?;
+                    parts.push(WordPart::ProcessSubstitution(script));
+                }
+                ' ' | '\t' | '\r' | '\n' | ';' | '&' | '|' | '<' | '>' | '(' | ')' => break,
+                '\\' if self.escapes => {
+                    self.bump();
+                    match self.bump() {
+                        Some('\n') => {}
+                        Some(escaped) => {
+                            flush_literal(&mut parts, &mut literal);
+                            push_quoted(&mut parts, escaped);
+                        }
+                        None => literal.push('\\'),
+                    }
+                }
+                '\'' => {
+                    self.bump();
+                    let Some(end) = self.src[self.pos..].find('\'') else {
+                        return self.error("missing closing `'`");
+                    };
+                    flush_literal(&mut parts, &mut literal);
+                    parts.push(WordPart::Quoted(
+                        self.src[self.pos..self.pos + end].to_string(),
+                    ));
+                    self.pos += end + 1;
+                }
+                '"' => {
+                    self.bump();
+                    flush_literal(&mut parts, &mut literal);
+                    self.parse_double_quoted(&mut parts)?;
+                }
+                '`' => {
+                    self.bump();
+                    flush_literal(&mut parts, &mut literal);
+                    parts.push(self.parse_backticks()?);
+                }
+                '$' => {
+                    flush_literal(&mut parts, &mut literal);
+                    self.parse_dollar(&mut parts)?;
+                }
+                '~' if self.pos == start => {
+                    self.bump();
+                    let name_len = self.src[self.pos..]
+                        .find(|ch: char| ch == '/' || ch.is_whitespace() || ";&|<>()".contains(ch))
+                        .unwrap_or(self.src.len() - self.pos);
+                    parts.push(WordPart::Tilde(
+                        self.src[self.pos..self.pos + name_len].to_string(),
+                    ));
+                    self.pos += name_len;
+                }
+                _ => {
+                    literal.push(ch);
+                    self.bump();
+                }
+            }
+        }
+        flush_literal(&mut parts, &mut literal);
+        Ok(Word {
+            raw: self.src[start..self.pos].to_string(),
+            parts,
+        })
+    }
+
+    fn parse_double_quoted(&mut self, parts: &mut Vec<WordPart>) -> ParseResult<()> {
+        let mut text = String::new();
+        loop {
+            let Some(ch) = self.peek() else {
+                return self.error("missing closing `\"`");
+            };
+            match ch {
+                '"' => {
+                    self.bump();
+                    break;
+                }
+                '\\' if self.escapes => {
+                    self.bump();
+                    match self.bump() {
+                        Some('\n') => {}
+                        Some(escaped @ ('$' | '`' | '"' | '\\')) => text.push(escaped),
+                        Some(other) => {
+                            text.push('\\');
+                            text.push(other);
+                        }
+                        None => return self.error("missing closing `\"`"),
+                    }
+                }
+                '$' | '`' => {
+                    if !text.is_empty() {
+                        parts.push(WordPart::Quoted(std::mem::take(&mut text)));
+                    }
+                    if ch == '$' {
+                        self.parse_dollar(parts)?;
+                    } else {
+                        self.bump();
+                        parts.push(self.parse_backticks()?);
+                    }
+                }
+                _ => {
+                    text.push(ch);
+                    self.bump();
+                }
+            }
+        }
+        parts.push(WordPart::Quoted(text));
+        Ok(())
+    }
+
+    fn parse_backticks(&mut self) -> ParseResult<WordPart> {
+        let mut inner = String::new();
+        loop {
+            match self.bump() {
+                None => return self.error("missing closing backtick"),
+                Some('`') => break,
+                Some('\\') => match self.bump() {
+                    Some(escaped @ ('`' | '\\' | '$')) => inner.push(escaped),
+                    Some(other) => {
+                        inner.push('\\');
+                        inner.push(other);
+                    }
+                    None => return self.error("missing closing backtick"),
+                },
+                Some(ch) => inner.push(ch),
+            }
+        }
+        let script = Parser::new(&inner, self.escapes).parse_all()?;
+        Ok(WordPart::CommandSubstitution(script))
+    }
+
+    /// Parses what follows a `$`, which is the next character.
+    fn parse_dollar(&mut self, parts: &mut Vec<WordPart>) -> ParseResult<()> {
+        self.bump();
+        match self.peek() {
+            Some('(') if self.peek_second() == Some('(') => {
+                self.pos += 2;
+                let start = self.pos;
+                let mut depth = 0usize;
+                loop {
+                    if self.starts_with("))") && depth == 0 {
+                        parts.push(WordPart::Arithmetic(self.src[start..self.pos].to_string()));
+                        self.pos += 2;
+                        return Ok(());
+                    }
+                    match self.bump() {
+                        None => return self.error("missing `))`"),
+                        Some('(') => depth += 1,
+                        Some(')') => depth = depth.saturating_sub(1),
+                        Some(_) => {}
+                    }
+                }
+            }
+            Some('(') => {
+                self.bump();
+                let script = self.parse_script(true)?;
+                parts.push(WordPart::CommandSubstitution(script));
+            }
+            Some('{') => {
+                let start = self.pos;
+                let mut depth = 0usize;
+                loop {
+                    match self.bump() {
+                        None => return self.error("missing `}`"),
+                        Some('{') => depth += 1,
+                        Some('}') => {
+                            depth -= 1;
+                            if depth == 0 {
+                                break;
+                            }
+                        }
+                        Some('\\') if self.escapes => {
+                            self.bump();
+                        }
+                        Some(_) => {}
+                    }
+                }
+                parts.push(WordPart::Parameter(self.src[start..self.pos].to_string()));
+            }
+            Some('\'') if self.escapes => {
+                let start = self.pos;
+                self.bump();
+                loop {
+                    match self.bump() {
+                        None => return self.error("missing closing `'`"),
+                        Some('\\') => {
+                            self.bump();
+                        }
+                        Some('\'') => break,
+                        Some(_) => {}
+                    }
+                }
+                parts.push(WordPart::Parameter(self.src[start..self.pos].to_string()));
+            }
+            Some(ch) if ch.is_ascii_alphabetic() || ch == '_' => {
+                let len = self.src[self.pos..]
+                    .find(|ch: char| !(ch.is_ascii_alphanumeric() || ch == '_'))
+                    .unwrap_or(self.src.len() - self.pos);
+                parts.push(WordPart::Parameter(
+                    self.src[self.pos..self.pos + len].to_string(),
+                ));
+                self.pos += len;
+            }
+            Some(ch) if ch.is_ascii_digit() || "@*#?$!-".contains(ch) => {
+                self.bump();
+                parts.push(WordPart::Parameter(ch.to_string()));
+            }
+            // `$"..."` is a translated string; the quote is parsed next.
+            Some('"') => {}
+            _ => parts.push(WordPart::Literal("$".to_string())),
+        }
+        Ok(())
+    }
+}
+
+fn flush_literal(parts: &mut Vec<WordPart>, literal: &mut String) {
+    if !literal.is_empty() {
+        parts.push(WordPart::Literal(std::mem::take(literal)));
+    }
+}
+
+fn push_quoted(parts: &mut Vec<WordPart>, ch: char) {
+    if let Some(WordPart::Quoted(text)) = parts.last_mut() {
+        text.push(ch);
+    } else {
+        parts.push(WordPart::Quoted(ch.to_string()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Command, Connector, RedirectOp, WordPart, parse, parse_without_escapes};
+
+    fn words(source: &str) -> Vec<Vec<String>> {
+        parse(source)
+            .expect("parse")
+            .simple_commands()
+            .iter()
+            .map(|command| {
+                command
+                    .words
+                    .iter()
+                    .map(|word| word.static_value().unwrap_or_else(|| word.raw.clone()))
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn parses_lists_pipelines_and_quotes() {
+        let script =
+            parse("git add . && git commit -m 'a; b' | cat || echo \"x && y\" &").expect("parse");
+        assert_eq!(script.items.len(), 1);
+        let and_or = &script.items[0].and_or;
+        assert!(script.items[0].background);
+        assert_eq!(
+            and_or
+                .rest
+                .iter()
+                .map(|(connector, _)| *connector)
+                .collect::<Vec<_>>(),
+            vec![Connector::And, Connector::Or]
+        );
+        assert_eq!(and_or.rest[0].1.commands.len(), 2);
+        assert_eq!(
+            words("git add . && git commit -m 'a; b' | cat || echo \"x && y\""),
+            vec![
+                vec!["git", "add", "."],
+                vec!["git", "commit", "-m", "a; b"],
+                vec!["cat"],
+                vec!["echo", "x && y"],
+            ]
+        );
+        assert!(script.is_fully_supported());
+    }
+
+    #[test]
+    fn parses_redirections_and_here_documents() {
+        let script =
+            parse("cat <<'EOF' > out.txt 2>&1\nhello $name\nEOF\nwc -l<in.txt").expect("parse");
+        let commands = script.simple_commands();
+        assert_eq!(commands.len(), 2);
+        let ops = commands[0]
+            .redirections
+            .iter()
+            .map(|redirection| (redirection.fd, redirection.op))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            ops,
+            vec![
+                (None, RedirectOp::HereDoc),
+                (None, RedirectOp::Output),
+                (Some(2), RedirectOp::DupOutput),
+            ]
+        );
+        assert_eq!(script.here_docs.len(), 1);
+        assert_eq!(script.here_docs[0].body, "hello $name\n");
+        assert!(!script.here_docs[0].expands);
+        assert_eq!(commands[1].redirections[0].op, RedirectOp::Input);
+        assert_eq!(
+            commands[1].redirections[0].target.static_value().as_deref(),
+            Some("in.txt")
+        );
+    }
+
+    #[test]
+    fn parses_subshells_and_substitutions() {
+        let script =
+            parse("(cd src && ls) > /dev/null; echo $(rm -rf x) `id` <(cat a)").expect("parse");
+        assert!(matches!(
+            script.commands().next(),
+            Some(Command::Subshell { redirections, .. }) if redirections.len() == 1
+        ));
+        assert_eq!(
+            words("(cd src && ls); echo $(rm -rf x) `id` <(cat a)"),
+            vec![
+                vec!["cd", "src"],
+                vec!["ls"],
+                vec!["echo", "$(rm -rf x)", "`id`", "<(cat a)"],
+                vec!["rm", "-rf", "x"],
+                vec!["id"],
+                vec!["cat", "a"],
+            ]
+        );
+        let echo = script.simple_commands()[2];
+        assert!(matches!(
+            echo.words[1].parts[0],
+            WordPart::CommandSubstitution(_)
+        ));
+    }
+
+    #[test]
+    fn classifies_word_parts() {
+        let script = parse("FOO=1 ls ~/x $HOME \"${A}b\" src/*.rs '*.rs' a\\ b").expect("parse");
+        let command = script.simple_commands()[0];
+        assert_eq!(command.assignments[0].raw, "FOO=1");
+        let words = &command.words;
+        assert_eq!(words[0].static_value().as_deref(), Some("ls"));
+        assert!(matches!(words[1].parts[0], WordPart::Tilde(_)));
+        assert_eq!(words[2].static_value(), None);
+        assert_eq!(words[3].static_value(), None);
+        assert!(words[4].has_pattern());
+        assert!(!words[5].has_pattern());
+        assert_eq!(words[6].static_value().as_deref(), Some("a b"));
+    }
+
+    #[test]
+    fn tolerates_unmodelled_syntax_and_reports_errors() {
+        let script = parse("for f in *.rs; do wc -l \"$f\"; done").expect("parse");
+        assert_eq!(script.simple_commands().len(), 3);
+        let script = parse("case $x in a) ls;; esac").expect("parse");
+        assert!(!script.is_fully_supported());
+        assert!(parse("echo 'unterminated").is_err());
+        assert!(parse("(ls").is_err());
+        assert!(parse("ls |").is_err());
+        assert_eq!(
+            parse_without_escapes("type C:\\Windows\\x")
+                .expect("parse")
+                .simple_commands()[0]
+                .words[1]
+                .static_value()
+                .as_deref(),
+            Some("C:\\Windows\\x")
+        );
+    }
+}
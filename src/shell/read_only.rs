@@ -0,0 +1,447 @@
+//! Recognises commands that only read: every simple command must be on a
+//! curated list of programs, used without the flags that make them write
+//! files or run other programs, and nothing may redirect output to a file.
+
+use super::parser::{Command, RedirectOp, Redirection, Script, SimpleCommand, parse};
+
+/// Whether `command` provably reads without side effects. Anything the parser
+/// cannot fully see through is treated as not read-only.
+pub fn is_read_only(command: &str) -> bool {
+    // Commands run under PowerShell on Windows, where these names are aliases
+    // with different flags.
+    if cfg!(windows) {
+        return false;
+    }
+    match parse(command) {
+        Ok(script) => !script.items.is_empty() && script_is_read_only(&script),
+        Err(_) => false,
+    }
+}
+
+/// Whether `command` reads file contents below a directory, as `grep -r` and
+/// `rg` do, so it can reach files no argument names. Unparseable commands
+/// count as recursive.
+pub fn reads_recursively(command: &str) -> bool {
+    let Ok(script) = parse(command) else {
+        return true;
+    };
+    script.simple_commands().into_iter().any(|simple| {
+        let args = simple
+            .words
+            .iter()
+            .map(|word| word.static_value().unwrap_or_default())
+            .collect::<Vec<_>>();
+        let Some((program, args)) = args.split_first() else {
+            return false;
+        };
+        let recursive_flag = |short: &[char], long: &[&str]| {
+            args.iter().any(|arg| {
+                (arg.starts_with('-') && !arg.starts_with("--") && arg[1..].contains(short))
+                    || long.iter().any(|name| arg.starts_with(name))
+            })
+        };
+        match program.as_str() {
+            "grep" | "egrep" | "fgrep" => recursive_flag(
+                &['r', 'R'],
+                &[
+                    "--recursive",
+                    "--dereference-recursive",
+                    "--directories=recurse",
+                ],
+            ),
+            "rg" | "ag" | "ack" => true,
+            "git" => args.iter().any(|arg| arg == "grep"),
+            _ => false,
+        }
+    })
+}
+
+fn script_is_read_only(script: &Script) -> bool {
+    if !script.is_fully_supported() {
+        return false;
+    }
+    let expanding_here_doc = script
+        .here_docs
+        .iter()
+        .any(|doc| doc.expands && (doc.body.contains("$(") || doc.body.contains('`')));
+    if expanding_here_doc {
+        return false;
+    }
+    script.commands().all(|command| match command {
+        Command::Simple(simple) => simple_is_read_only(simple),
+        Command::Subshell {
+            script,
+            redirections,
+        } => script_is_read_only(script) && redirections.iter().all(redirection_is_read_only),
+    })
+}
+
+fn redirection_is_read_only(redirection: &Redirection) -> bool {
+    let Some(target) = redirection.target.static_value() else {
+        return false;
+    };
+    match redirection.op {
+        RedirectOp::Input | RedirectOp::HereDoc | RedirectOp::HereString => true,
+        RedirectOp::DupInput | RedirectOp::DupOutput => {
+            target == "-" || target.chars().all(|ch| ch.is_ascii_digit())
+        }
+        RedirectOp::Output
+        | RedirectOp::Append
+        | RedirectOp::Clobber
+        | RedirectOp::OutputAll
+        | RedirectOp::AppendAll => target == "/dev/null",
+        RedirectOp::ReadWrite => false,
+    }
+}
+
+/// A static argument; `pattern` marks unquoted glob or brace characters that
+/// the shell may expand into other words, options included.
+struct Arg {
+    value: String,
+    pattern: bool,
+}
+
+impl Arg {
+    fn is_option(&self) -> bool {
+        self.value.starts_with('-') && self.value != "-"
+    }
+
+    /// A pattern such as `*` can expand to a file named like an option, and
+    /// `-{delete,print}` or `-[d]elete` to the option itself.
+    fn may_expand_to_option(&self) -> bool {
+        self.pattern
+    }
+}
+
+fn simple_is_read_only(command: &SimpleCommand) -> bool {
+    if !command.assignments.is_empty() || !command.redirections.iter().all(redirection_is_read_only)
+    {
+        return false;
+    }
+    let mut args = Vec::with_capacity(command.words.len());
+    for word in &command.words {
+        let Some(value) = word.static_value() else {
+            return false;
+        };
+        args.push(Arg {
+            value,
+            pattern: word.has_pattern(),
+        });
+    }
+    let Some((program, args)) = args.split_first() else {
+        // Only redirections, e.g. `< file`.
+        return true;
+    };
+    if program.pattern || program.value.contains('/') {
+        return false;
+    }
+    program_is_read_only(&program.value, args)
+}
+
+fn program_is_read_only(program: &str, args: &[Arg]) -> bool {
+    match program {
+        "ls" | "cat" | "head" | "tail" | "wc" | "pwd" | "echo" | "printf" | "true" | "false"
+        | "which" | "whoami" | "id" | "uname" | "stat" | "du" | "df" | "grep" | "egrep"
+        | "fgrep" | "diff" | "cmp" | "comm" | "cut" | "tr" | "nl" | "basename" | "dirname"
+        | "realpath" | "readlink" | "printenv" | "seq" | "test" | "[" | "type" | "column"
+        | "hexdump" | "od" | "strings" | "md5sum" | "sha1sum" | "sha256sum" | "sha512sum"
+        | "cksum" | "tac" | "rev" | "fold" | "paste" | "join" | "nproc" | "free" | "uptime"
+        | "cd" => true,
+        "date" => without(args, &[], &['s'], &["--set"]),
+        "file" => without(args, &[], &['C'], &["--compile"]),
+        "tree" => without(args, &["-o"], &[], &[]),
+        "rg" => without(args, &[], &[], &["--pre"]),
+        "sort" => without(args, &[], &['o'], &["--output", "--compress-program"]),
+        "uniq" | "xxd" => positional_count(args) <= 1 && without(args, &[], &[], &[]),
+        "hostname" | "env" => args.is_empty(),
+        "find" => without(
+            args,
+            &[
+                "-delete", "-exec", "-execdir", "-ok", "-okdir", "-fprint", "-fprint0", "-fprintf",
+                "-fls",
+            ],
+            &[],
+            &[],
+        ),
+        "fd" => without(args, &[], &['x', 'X'], &["--exec", "--exec-batch"]),
+        "git" => git_is_read_only(args),
+        // cargo, rustc, rustup and go are left out: the repository's
+        // `.cargo/config.toml`, `rust-toolchain.toml` or `go.mod` can make
+        // them run arbitrary programs.
+        "node" | "npm" | "pnpm" | "yarn" | "python" | "python3" | "java" | "deno" | "bun"
+        | "make" | "cmake" | "gcc" | "clang" => {
+            matches!(args, [only] if only.value == "--version")
+        }
+        _ => false,
+    }
+}
+
+/// No argument is one of `exact`, a short-option cluster containing one of
+/// `short`, or one of `long` with or without `=value`. Patterns that could
+/// expand to an option fail the check too.
+fn without(args: &[Arg], exact: &[&str], short: &[char], long: &[&str]) -> bool {
+    !args.iter().any(|arg| {
+        let value = arg.value.as_str();
+        arg.may_expand_to_option()
+            || exact.contains(&value)
+            || (value.starts_with('-') && !value.starts_with("--") && value[1..].contains(short))
+            || long.iter().any(|name| {
+                value == *name
+                    || value
+                        .strip_prefix(name)
+                        .is_some_and(|rest| rest.starts_with('='))
+            })
+    })
+}
+
+fn positional_count(args: &[Arg]) -> usize {
+    args.iter().filter(|arg| !arg.is_option()).count()
+}
+
+fn has_any(args: &[Arg], names: &[&str]) -> bool {
+    args.iter().any(|arg| {
+        names.iter().any(|name| {
+            arg.value == *name
+                || arg
+                    .value
+                    .strip_prefix(name)
+                    .is_some_and(|rest| rest.starts_with('='))
+        })
+    })
+}
+
+/// Git option matching: a short-option cluster containing one of `short`
+/// (values may be attached, as in `-Ovim`), or a long option that starts with
+/// one of `long` or is an abbreviation of it.
+fn has_git_option(args: &[Arg], short: &[char], long: &[&str]) -> bool {
+    args.iter().any(|arg| {
+        let value = arg.value.as_str();
+        if let Some(cluster) = value.strip_prefix('-')
+            && !cluster.starts_with('-')
+        {
+            return cluster.contains(short);
+        }
+        let name = value.split('=').next().unwrap_or(value);
+        long.iter().any(|long| {
+            value.starts_with(long) || (name.len() > "--".len() + 1 && long.starts_with(name))
+        })
+    })
+}
+
+fn git_is_read_only(args: &[Arg]) -> bool {
+    // Global options come before the subcommand. `-c` can point git at
+    // arbitrary programs, and `-C`, `--git-dir` and `--work-tree` at a
+    // repository whose config does (`core.fsmonitor`), so only these pass.
+    let mut index = 0;
+    while let Some(arg) = args.get(index) {
+        match arg.value.as_str() {
+            "--no-pager" | "-P" | "--no-optional-locks" | "--literal-pathspecs" => index += 1,
+            _ if arg.is_option() || arg.may_expand_to_option() => return false,
+            _ => break,
+        }
+    }
+    let Some((subcommand, rest)) = args.get(index..).and_then(<[Arg]>::split_first) else {
+        return false;
+    };
+    // Every command that walks revisions or prints a diff accepts
+    // `--output=<file>`, which truncates and writes the file.
+    if rest.iter().any(Arg::may_expand_to_option) || has_git_option(rest, &[], &["--output"]) {
+        return false;
+    }
+    let first = rest.first().map(|arg| arg.value.as_str());
+    match subcommand.value.as_str() {
+        "status" | "log" | "show" | "rev-parse" | "ls-files" | "ls-tree" | "cat-file" | "blame"
+        | "shortlog" | "describe" | "merge-base" | "rev-list" | "for-each-ref" | "show-ref"
+        | "version" | "whatchanged" | "name-rev" | "count-objects" | "check-ignore"
+        | "check-attr" | "cherry" | "range-diff" => true,
+        "diff" => true,
+        "grep" => !has_git_option(rest, &['O'], &["--open-files-in-pager"]),
+        "branch" => {
+            !has_any(
+                rest,
+                &[
+                    "-d",
+                    "-D",
+                    "-m",
+                    "-M",
+                    "-c",
+                    "-C",
+                    "-f",
+                    "-u",
+                    "--delete",
+                    "--move",
+                    "--copy",
+                    "--force",
+                    "--set-upstream-to",
+                    "--unset-upstream",
+                    "--edit-description",
+                    "--track",
+                    "--no-track",
+                ],
+            ) && (positional_count(rest) == 0 || has_any(rest, &["-l", "--list"]))
+        }
+        "tag" => {
+            !has_any(
+                rest,
+                &[
+                    "-a",
+                    "-s",
+                    "-u",
+                    "-f",
+                    "-d",
+                    "-m",
+                    "-F",
+                    "-e",
+                    "--annotate",
+                    "--sign",
+                    "--local-user",
+                    "--force",
+                    "--delete",
+                    "--message",
+                    "--file",
+                    "--edit",
+                ],
+            ) && (positional_count(rest) == 0 || has_any(rest, &["-l", "--list"]))
+        }
+        "remote" => match first {
+            None => true,
+            Some("-v" | "--verbose") => rest.len() == 1,
+            Some("get-url" | "show") => true,
+            _ => false,
+        },
+        "config" => match first {
+            Some("get" | "list") => true,
+            _ => {
+                has_any(
+                    rest,
+                    &[
+                        "--get",
+                        "--get-all",
+                        "--get-regexp",
+                        "--get-urlmatch",
+                        "--list",
+                        "-l",
+                    ],
+                ) && !has_any(
+                    rest,
+                    &[
+                        "--add",
+                        "--unset",
+                        "--unset-all",
+                        "--replace-all",
+                        "--rename-section",
+                        "--remove-section",
+                        "--edit",
+                        "-e",
+                    ],
+                )
+            }
+        },
+        "stash" => matches!(first, Some("list" | "show")),
+        "worktree" => first == Some("list"),
+        "reflog" => matches!(first, None | Some("show")),
+        _ => false,
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::{is_read_only, reads_recursively};
+
+    #[test]
+    fn accepts_read_only_pipelines_and_lists() {
+        for command in [
+            "ls -la",
+            "git log --oneline -5",
+            "git status && git diff --stat",
+            "cat Cargo.toml | grep -n serde | head -5",
+            "find src -name '*.rs' -type f | wc -l",
+            "rg -n 'fn main' src 2>/dev/null || echo none",
+            "(cd src && ls) > /dev/null",
+            "grep -c TODO src/*.rs",
+            "wc -l <<'EOF'\n$(rm -rf x)\nEOF",
+            "git --no-pager branch --list 'feat/*'",
+            "git show --stat HEAD",
+            "git grep -n -e Open foo",
+            "git config --get user.name",
+            "sort -u names.txt 2>&1",
+        ] {
+            assert!(is_read_only(command), "{command}");
+        }
+    }
+
+    #[test]
+    fn rejects_writes_and_unknown_programs() {
+        for command in [
+            "",
+            "git push",
+            "git status; rm -rf target",
+            "git branch -D old",
+            "git -c core.pager=sh log",
+            "git log --output=src/main.rs",
+            "git show --output src/main.rs HEAD",
+            "git whatchanged --outp=notes.txt",
+            "git range-diff --output=x a...b",
+            "git diff --output=out.patch",
+            "git grep -O foo",
+            "git grep -Ovim foo",
+            "git grep -nOvim foo",
+            "git grep --open-files-in-pager=vim foo",
+            "git grep --open-files-in-pager vim foo",
+            "git grep --open-files vim foo",
+            "git stash",
+            "find . -name '*.tmp' -delete",
+            "find . -exec rm {} ;",
+            "find *",
+            "fd -Hx rm",
+            "ls > listing.txt",
+            "cat a >> b",
+            "echo $(rm -rf x)",
+            "echo `touch x`",
+            "cat <(touch x)",
+            "diff a b >| out",
+            "FOO=1 ls",
+            "ls $DIR",
+            "./ls",
+            "sort -o out.txt in.txt",
+            "sort --output=out.txt in.txt",
+            "uniq in.txt out.txt",
+            "rg --pre ./script pattern",
+            "date -s tomorrow",
+            "env rm -rf x",
+            "cargo build",
+            "cargo tree --locked",
+            "cargo metadata --locked",
+            "rustc --version",
+            "git -C sub status",
+            "git --git-dir=notgit --work-tree=. status",
+            "git --bare log",
+            "git log --{output,stat}=x",
+            "find . -name '*.tmp' -{delete,print}",
+            "find . -[d]elete",
+            "sed -i s/a/b/ file",
+            "wc -l <<EOF\n$(rm -rf x)\nEOF",
+            "if true; then ls; fi",
+            "f() { ls; }",
+            "echo 'unterminated",
+        ] {
+            assert!(!is_read_only(command), "{command}");
+        }
+    }
+
+    #[test]
+    fn detects_recursive_content_searches() {
+        for command in [
+            "grep -r KEY .",
+            "grep -nR KEY src",
+            "ls && grep --recursive KEY",
+            "rg KEY",
+            "git grep KEY",
+        ] {
+            assert!(reads_recursively(command), "{command}");
+        }
+        for command in ["grep KEY file.txt", "ls -R", "cat src/main.rs", "git log"] {
+            assert!(!reads_recursively(command), "{command}");
+        }
+    }
+}
@@ -56,6 +56,18 @@ pub const SLASH_COMMANDS: &[SlashCommand] = &[
         CommandGroup::BuiltIn,
         true,
     ),
+    SlashCommand::new(
+        "/ps",
+        "List and kill background processes",
+        CommandGroup::BuiltIn,
+        true,
+    ),
+    SlashCommand::new(
+        "/ps kill <id>",
+        "Kill a background process",
+        CommandGroup::BuiltIn,
+        false,
+    ),
     SlashCommand::new(
         "/providers",
         "Switch active provider",
@@ -82,6 +94,8 @@ pub enum ParsedSlashCommand {
     Models,
     SetModel(String),
     Resume,
+    Processes,
+    KillProcess(u32),
     Providers,
     AddProvider,
     CommitAndPush,
@@ -95,6 +109,7 @@ pub fn parse_slash_command(input: &str) -> Option<ParsedSlashCommand> {
         "/clear" => Some(ParsedSlashCommand::Clear),
         "/models" => Some(ParsedSlashCommand::Models),
         "/resume" => Some(ParsedSlashCommand::Resume),
+        "/ps" => Some(ParsedSlashCommand::Processes),
         "/providers" => Some(ParsedSlashCommand::Providers),
         "/providers add" => Some(ParsedSlashCommand::AddProvider),
         "/commit-and-push" => Some(ParsedSlashCommand::CommitAndPush),
@@ -105,6 +120,11 @@ pub fn parse_slash_command(input: &str) -> Option<ParsedSlashCommand> {
             {
                 return Some(ParsedSlashCommand::SetModel(model.to_string()));
             }
+            if let Some(id) = trimmed.strip_prefix("/ps kill ").map(str::trim)
+                && let Ok(id) = id.parse()
+            {
+                return Some(ParsedSlashCommand::KillProcess(id));
+            }
             None
         }
     }
@@ -161,6 +181,19 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn parses_process_commands() {
+        assert!(matches!(
+            parse_slash_command("/ps"),
+            Some(ParsedSlashCommand::Processes)
+        ));
+        assert!(matches!(
+            parse_slash_command("/ps kill 3"),
+            Some(ParsedSlashCommand::KillProcess(3))
+        ));
+        assert!(parse_slash_command("/ps kill abc").is_none());
+    }
+
     #[test]
     fn parses_provider_commands() {
         assert!(matches!(
@@ -5,8 +5,20 @@ use serde_json::json;
 pub const TOOL_VIEW_FILE: &str = "view_file";
 pub const TOOL_CREATE_FILE: &str = "create_file";
 pub const TOOL_STR_REPLACE_EDITOR: &str = "str_replace_editor";
+pub const TOOL_DELETE_FILE: &str = "delete_file";
+pub const TOOL_MOVE_FILE: &str = "move_file";
+pub const TOOL_COPY_FILE: &str = "copy_file";
 pub const TOOL_BASH: &str = "bash";
+pub const TOOL_BASH_BACKGROUND: &str = "bash_background";
+pub const TOOL_READ_PROCESS_OUTPUT: &str = "read_process_output";
+pub const TOOL_LIST_PROCESSES: &str = "list_processes";
+pub const TOOL_KILL_PROCESS: &str = "kill_process";
 pub const TOOL_SEARCH: &str = "search";
+pub const TOOL_CODE_OUTLINE: &str = "code_outline";
+pub const TOOL_FIND_DEFINITION: &str = "find_definition";
+pub const TOOL_GOTO_DEFINITION: &str = "goto_definition";
+pub const TOOL_FIND_REFERENCES: &str = "find_references";
+pub const TOOL_HOVER: &str = "hover";
 pub const TOOL_CREATE_TODO_LIST: &str = "create_todo_list";
 pub const TOOL_UPDATE_TODO_LIST: &str = "update_todo_list";
 
@@ -16,13 +28,15 @@ pub fn default_tools() -> Vec<ChatTool> {
             r#type: "function".to_string(),
             function: ChatToolFunction {
                 name: TOOL_VIEW_FILE.to_string(),
-                description: "View contents of a file or list directory contents".to_string(),
+                description: "View contents of a file or list directory contents. Long files are returned one page at a time with a hint for the next start_line; binary files are reported by type and size.".to_string(),
                 parameters: json!({
                     "type": "object",
                     "properties": {
                         "path": { "type": "string", "description": "Path to file or directory" },
-                        "start_line": { "type": "number", "description": "Optional start line" },
-                        "end_line": { "type": "number", "description": "Optional end line" }
+                        "start_line": { "type": "number", "description": "Optional start line (pages forward from here when end_line is omitted)" },
+                        "end_line": { "type": "number", "description": "Optional end line" },
+                        "recursive": { "type": "boolean", "description": "For directories, show a tree of nested entries (respects .gitignore)" },
+                        "max_depth": { "type": "number", "description": "Tree depth when recursive is true (default 3, max 10)" }
                     },
                     "required": ["path"]
                 }),
@@ -32,12 +46,13 @@ pub fn default_tools() -> Vec<ChatTool> {
             r#type: "function".to_string(),
             function: ChatToolFunction {
                 name: TOOL_CREATE_FILE.to_string(),
-                description: "Create a new file with specified content".to_string(),
+                description: "Create a new file with specified content. Fails if the file exists unless overwrite is true".to_string(),
                 parameters: json!({
                     "type": "object",
                     "properties": {
                         "path": { "type": "string" },
-                        "content": { "type": "string" }
+                        "content": { "type": "string" },
+                        "overwrite": { "type": "boolean", "description": "Replace an existing file's entire contents" }
                     },
                     "required": ["path", "content"]
                 }),
@@ -60,11 +75,73 @@ pub fn default_tools() -> Vec<ChatTool> {
                 }),
             },
         },
+        ChatTool {
+            r#type: "function".to_string(),
+            function: ChatToolFunction {
+                name: TOOL_DELETE_FILE.to_string(),
+                description: "Delete a file, symlink or directory (directories with contents need recursive: true)".to_string(),
+                parameters: json!({
+                    "type": "object",
+                    "properties": {
+                        "path": { "type": "string" },
+                        "recursive": { "type": "boolean", "description": "Required to delete a non-empty directory" }
+                    },
+                    "required": ["path"]
+                }),
+            },
+        },
+        ChatTool {
+            r#type: "function".to_string(),
+            function: ChatToolFunction {
+                name: TOOL_MOVE_FILE.to_string(),
+                description: "Move or rename a file or directory".to_string(),
+                parameters: json!({
+                    "type": "object",
+                    "properties": {
+                        "source": { "type": "string" },
+                        "destination": { "type": "string", "description": "Full destination path, including the file name" },
+                        "overwrite": { "type": "boolean", "description": "Replace an existing destination file" }
+                    },
+                    "required": ["source", "destination"]
+                }),
+            },
+        },
+        ChatTool {
+            r#type: "function".to_string(),
+            function: ChatToolFunction {
+                name: TOOL_COPY_FILE.to_string(),
+                description: "Copy a single file".to_string(),
+                parameters: json!({
+                    "type": "object",
+                    "properties": {
+                        "source": { "type": "string" },
+                        "destination": { "type": "string", "description": "Full destination path, including the file name" },
+                        "overwrite": { "type": "boolean", "description": "Replace an existing destination file" }
+                    },
+                    "required": ["source", "destination"]
+                }),
+            },
+        },
         ChatTool {
             r#type: "function".to_string(),
             function: ChatToolFunction {
                 name: TOOL_BASH.to_string(),
-                description: "Execute a shell command".to_string(),
+                description: "Execute a shell command. Stdin is closed, and the command with everything it started is killed after the timeout".to_string(),
+                parameters: json!({
+                    "type": "object",
+                    "properties": {
+                        "command": { "type": "string" },
+                        "timeout_ms": { "type": "number", "description": "Timeout in milliseconds (default 120000, max 600000)" }
+                    },
+                    "required": ["command"]
+                }),
+            },
+        },
+        ChatTool {
+            r#type: "function".to_string(),
+            function: ChatToolFunction {
+                name: TOOL_BASH_BACKGROUND.to_string(),
+                description: "Start a long-running shell command (dev server, watcher, test server) in the background and return its process id right away. It keeps running across turns until killed".to_string(),
                 parameters: json!({
                     "type": "object",
                     "properties": {
@@ -74,11 +151,51 @@ pub fn default_tools() -> Vec<ChatTool> {
                 }),
             },
         },
+        ChatTool {
+            r#type: "function".to_string(),
+            function: ChatToolFunction {
+                name: TOOL_READ_PROCESS_OUTPUT.to_string(),
+                description: "Read output a background process produced since the last read, plus its status".to_string(),
+                parameters: json!({
+                    "type": "object",
+                    "properties": {
+                        "id": { "type": "number", "description": "Process id returned by bash_background" },
+                        "wait_ms": { "type": "number", "description": "Wait up to this long for new output or exit (max 30000)" }
+                    },
+                    "required": ["id"]
+                }),
+            },
+        },
+        ChatTool {
+            r#type: "function".to_string(),
+            function: ChatToolFunction {
+                name: TOOL_LIST_PROCESSES.to_string(),
+                description: "List background processes started with bash_background and their status".to_string(),
+                parameters: json!({
+                    "type": "object",
+                    "properties": {}
+                }),
+            },
+        },
+        ChatTool {
+            r#type: "function".to_string(),
+            function: ChatToolFunction {
+                name: TOOL_KILL_PROCESS.to_string(),
+                description: "Kill a background process and everything it started".to_string(),
+                parameters: json!({
+                    "type": "object",
+                    "properties": {
+                        "id": { "type": "number", "description": "Process id returned by bash_background" }
+                    },
+                    "required": ["id"]
+                }),
+            },
+        },
         ChatTool {
             r#type: "function".to_string(),
             function: ChatToolFunction {
                 name: TOOL_SEARCH.to_string(),
-                description: "Unified search for text content and files".to_string(),
+                description: "Unified search for text content and files. Text matches are returned with line numbers, columns and the matching line, grouped by file".to_string(),
                 parameters: json!({
                     "type": "object",
                     "properties": {
@@ -99,12 +216,93 @@ pub fn default_tools() -> Vec<ChatTool> {
                             "items": { "type": "string" },
                             "description": "Optional file type filters (e.g. ['rs', 'ts'])"
                         },
-                        "include_hidden": { "type": "boolean", "description": "Include hidden files" }
+                        "include_hidden": { "type": "boolean", "description": "Include hidden files" },
+                        "context_lines": { "type": "number", "description": "Lines of context to show before and after each text match (default 0, max 10)" }
                     },
                     "required": ["query"]
                 }),
             },
         },
+        ChatTool {
+            r#type: "function".to_string(),
+            function: ChatToolFunction {
+                name: TOOL_CODE_OUTLINE.to_string(),
+                description: "List the definitions (functions, methods, structs, classes, impls, traits, interfaces) in a source file or directory with their line ranges. Supports Rust, TypeScript, JavaScript, Python and Go".to_string(),
+                parameters: json!({
+                    "type": "object",
+                    "properties": {
+                        "path": { "type": "string", "description": "File or directory to outline (default: current directory)" }
+                    }
+                }),
+            },
+        },
+        ChatTool {
+            r#type: "function".to_string(),
+            function: ChatToolFunction {
+                name: TOOL_FIND_DEFINITION.to_string(),
+                description: "Find where a symbol is defined and return file paths with exact line ranges for view_file".to_string(),
+                parameters: json!({
+                    "type": "object",
+                    "properties": {
+                        "symbol": { "type": "string", "description": "Symbol name, optionally qualified by its type (e.g. Store::open or App.run)" },
+                        "path": { "type": "string", "description": "File or directory to search (default: current directory)" }
+                    },
+                    "required": ["symbol"]
+                }),
+            },
+        },
+        ChatTool {
+            r#type: "function".to_string(),
+            function: ChatToolFunction {
+                name: TOOL_GOTO_DEFINITION.to_string(),
+                description: "Ask the configured language server where the symbol at a position is defined. Resolves through imports, traits and macros".to_string(),
+                parameters: json!({
+                    "type": "object",
+                    "properties": {
+                        "path": { "type": "string", "description": "File containing the symbol" },
+                        "line": { "type": "number", "description": "1-based line number" },
+                        "column": { "type": "number", "description": "Optional 1-based column; defaults to where symbol appears on the line" },
+                        "symbol": { "type": "string", "description": "Optional symbol name on that line, used to locate the column" }
+                    },
+                    "required": ["path", "line"]
+                }),
+            },
+        },
+        ChatTool {
+            r#type: "function".to_string(),
+            function: ChatToolFunction {
+                name: TOOL_FIND_REFERENCES.to_string(),
+                description: "Ask the configured language server for every reference to the symbol at a position".to_string(),
+                parameters: json!({
+                    "type": "object",
+                    "properties": {
+                        "path": { "type": "string", "description": "File containing the symbol" },
+                        "line": { "type": "number", "description": "1-based line number" },
+                        "column": { "type": "number", "description": "Optional 1-based column; defaults to where symbol appears on the line" },
+                        "symbol": { "type": "string", "description": "Optional symbol name on that line, used to locate the column" },
+                        "include_declaration": { "type": "boolean", "description": "Include the declaration itself (default true)" }
+                    },
+                    "required": ["path", "line"]
+                }),
+            },
+        },
+        ChatTool {
+            r#type: "function".to_string(),
+            function: ChatToolFunction {
+                name: TOOL_HOVER.to_string(),
+                description: "Ask the configured language server for the type signature and documentation of the symbol at a position".to_string(),
+                parameters: json!({
+                    "type": "object",
+                    "properties": {
+                        "path": { "type": "string", "description": "File containing the symbol" },
+                        "line": { "type": "number", "description": "1-based line number" },
+                        "column": { "type": "number", "description": "Optional 1-based column; defaults to where symbol appears on the line" },
+                        "symbol": { "type": "string", "description": "Optional symbol name on that line, used to locate the column" }
+                    },
+                    "required": ["path", "line"]
+                }),
+            },
+        },
         ChatTool {
             r#type: "function".to_string(),
             function: ChatToolFunction {
@@ -172,10 +370,32 @@ pub fn default_tools() -> Vec<ChatTool> {
     ]
 }
 
+/// Tools that never change files, processes, or anything outside the agent.
+pub fn is_read_only_tool(tool_name: &str) -> bool {
+    matches!(
+        tool_name,
+        TOOL_VIEW_FILE
+            | TOOL_READ_PROCESS_OUTPUT
+            | TOOL_LIST_PROCESSES
+            | TOOL_SEARCH
+            | TOOL_CODE_OUTLINE
+            | TOOL_FIND_DEFINITION
+            | TOOL_GOTO_DEFINITION
+            | TOOL_FIND_REFERENCES
+            | TOOL_HOVER
+            | TOOL_CREATE_TODO_LIST
+            | TOOL_UPDATE_TODO_LIST
+    )
+}
+
 pub fn confirmation_operation_for_tool(tool_name: &str) -> Option<ConfirmationOperation> {
     match tool_name {
-        TOOL_CREATE_FILE | TOOL_STR_REPLACE_EDITOR => Some(ConfirmationOperation::File),
-        TOOL_BASH => Some(ConfirmationOperation::Bash),
+        TOOL_CREATE_FILE
+        | TOOL_STR_REPLACE_EDITOR
+        | TOOL_DELETE_FILE
+        | TOOL_MOVE_FILE
+        | TOOL_COPY_FILE => Some(ConfirmationOperation::File),
+        TOOL_BASH | TOOL_BASH_BACKGROUND => Some(ConfirmationOperation::Bash),
         _ => None,
     }
 }
@@ -185,8 +405,20 @@ pub fn tool_display_name(name: &str) -> &'static str {
         TOOL_VIEW_FILE => "Read",
         TOOL_STR_REPLACE_EDITOR => "Update",
         TOOL_CREATE_FILE => "Create",
+        TOOL_DELETE_FILE => "Delete",
+        TOOL_MOVE_FILE => "Move",
+        TOOL_COPY_FILE => "Copy",
         TOOL_BASH => "Bash",
+        TOOL_BASH_BACKGROUND => "BashBackground",
+        TOOL_READ_PROCESS_OUTPUT => "ProcessOutput",
+        TOOL_LIST_PROCESSES => "Processes",
+        TOOL_KILL_PROCESS => "KillProcess",
         TOOL_SEARCH => "Search",
+        TOOL_CODE_OUTLINE => "Outline",
+        TOOL_FIND_DEFINITION => "Definition",
+        TOOL_GOTO_DEFINITION => "GotoDefinition",
+        TOOL_FIND_REFERENCES => "References",
+        TOOL_HOVER => "Hover",
         TOOL_CREATE_TODO_LIST => "TodoCreate",
         TOOL_UPDATE_TODO_LIST => "TodoUpdate",
         _ => "Tool",
@@ -225,7 +457,33 @@ mod tests {
             confirmation_operation_for_tool(TOOL_BASH),
             Some(ConfirmationOperation::Bash)
         );
+        assert_eq!(
+            confirmation_operation_for_tool(TOOL_BASH_BACKGROUND),
+            Some(ConfirmationOperation::Bash)
+        );
+        for tool in [
+            TOOL_READ_PROCESS_OUTPUT,
+            TOOL_LIST_PROCESSES,
+            TOOL_KILL_PROCESS,
+        ] {
+            assert_eq!(confirmation_operation_for_tool(tool), None);
+        }
+        for tool in [TOOL_DELETE_FILE, TOOL_MOVE_FILE, TOOL_COPY_FILE] {
+            assert_eq!(
+                confirmation_operation_for_tool(tool),
+                Some(ConfirmationOperation::File)
+            );
+        }
         assert_eq!(confirmation_operation_for_tool(TOOL_VIEW_FILE), None);
+        assert_eq!(confirmation_operation_for_tool(TOOL_CODE_OUTLINE), None);
+        for tool in [
+            TOOL_FIND_DEFINITION,
+            TOOL_GOTO_DEFINITION,
+            TOOL_FIND_REFERENCES,
+            TOOL_HOVER,
+        ] {
+            assert_eq!(confirmation_operation_for_tool(tool), None);
+        }
     }
 
     #[test]
@@ -233,8 +491,18 @@ mod tests {
         assert_eq!(tool_display_name(TOOL_VIEW_FILE), "Read");
         assert_eq!(tool_display_name(TOOL_CREATE_FILE), "Create");
         assert_eq!(tool_display_name(TOOL_STR_REPLACE_EDITOR), "Update");
+        assert_eq!(tool_display_name(TOOL_DELETE_FILE), "Delete");
+        assert_eq!(tool_display_name(TOOL_MOVE_FILE), "Move");
+        assert_eq!(tool_display_name(TOOL_COPY_FILE), "Copy");
         assert_eq!(tool_display_name(TOOL_BASH), "Bash");
+        assert_eq!(tool_display_name(TOOL_BASH_BACKGROUND), "BashBackground");
+        assert_eq!(tool_display_name(TOOL_KILL_PROCESS), "KillProcess");
         assert_eq!(tool_display_name(TOOL_SEARCH), "Search");
+        assert_eq!(tool_display_name(TOOL_CODE_OUTLINE), "Outline");
+        assert_eq!(tool_display_name(TOOL_FIND_DEFINITION), "Definition");
+        assert_eq!(tool_display_name(TOOL_GOTO_DEFINITION), "GotoDefinition");
+        assert_eq!(tool_display_name(TOOL_FIND_REFERENCES), "References");
+        assert_eq!(tool_display_name(TOOL_HOVER), "Hover");
         assert_eq!(tool_display_name(TOOL_CREATE_TODO_LIST), "TodoCreate");
         assert_eq!(tool_display_name(TOOL_UPDATE_TODO_LIST), "TodoUpdate");
     }
@@ -1,3 +1,5 @@
+use crate::ignore_policy::IgnorePolicy;
+use crate::sandbox::SandboxPolicy;
 use anyhow::{Context, Result, bail};
 use std::ffi::OsString;
 use std::path::{Component, Path, PathBuf};
@@ -6,6 +8,8 @@ use std::path::{Component, Path, PathBuf};
 pub struct ToolContext {
     project_root: PathBuf,
     current_dir: PathBuf,
+    sandbox: SandboxPolicy,
+    ignore: IgnorePolicy,
 }
 
 impl ToolContext {
@@ -16,17 +20,48 @@ impl ToolContext {
                 project_root.display()
             )
         })?;
+        let ignore = IgnorePolicy::load(&normalized_root, &[])?;
 
         Ok(Self {
             project_root: normalized_root.clone(),
             current_dir: normalized_root,
+            sandbox: SandboxPolicy::default(),
+            ignore,
         })
     }
 
+    pub fn project_root(&self) -> &Path {
+        &self.project_root
+    }
+
     pub fn current_dir(&self) -> &Path {
         &self.current_dir
     }
 
+    pub fn sandbox(&self) -> &SandboxPolicy {
+        &self.sandbox
+    }
+
+    pub fn set_sandbox(&mut self, sandbox: SandboxPolicy) {
+        self.sandbox = sandbox;
+    }
+
+    pub fn ignore_policy(&self) -> &IgnorePolicy {
+        &self.ignore
+    }
+
+    pub fn set_ignore_policy(&mut self, ignore: IgnorePolicy) {
+        self.ignore = ignore;
+    }
+
+    /// Whether a path, taken lexically relative to the current directory, is
+    /// hidden by the ignore policy. Never fails, so it suits words that may not
+    /// be paths at all.
+    pub fn is_ignored(&self, path: &str) -> bool {
+        let candidate = lexical_normalize(&candidate_path(self, path));
+        self.ignore.is_ignored(&candidate, candidate.is_dir())
+    }
+
     pub fn set_current_dir(&mut self, path: &str) -> Result<PathBuf> {
         let normalized = resolve_and_validate(self, path)
             .with_context(|| format!("Failed to change directory to '{path}'"))?;
@@ -41,6 +76,20 @@ impl ToolContext {
         resolve_and_validate(self, path)
     }
 
+    /// Like `resolve_path`, but a symlink in the final component is left
+    /// unresolved so delete/move operate on the link rather than its target.
+    pub fn resolve_entry_path(&self, path: &str) -> Result<PathBuf> {
+        let normalized = lexical_normalize(&candidate_path(self, path));
+        let (Some(parent), Some(name)) = (normalized.parent(), normalized.file_name()) else {
+            return resolve_and_validate(self, path);
+        };
+        let entry = resolve_with_existing_ancestor(parent)?.join(name);
+        ensure_inside_project(&entry, &self.project_root)?;
+        self.ignore.check(&normalized)?;
+        self.ignore.check(&entry)?;
+        Ok(entry)
+    }
+
     pub(crate) fn relative_current_dir(&self) -> String {
         match self.current_dir.strip_prefix(&self.project_root) {
             Ok(relative) if !relative.as_os_str().is_empty() => {
@@ -70,16 +119,24 @@ fn normalize_existing_dir(path: &Path) -> Result<PathBuf> {
     Ok(canonical)
 }
 
-fn resolve_and_validate(context: &ToolContext, raw_path: &str) -> Result<PathBuf> {
-    let candidate = if Path::new(raw_path).is_absolute() {
+fn candidate_path(context: &ToolContext, raw_path: &str) -> PathBuf {
+    if Path::new(raw_path).is_absolute() {
         PathBuf::from(raw_path)
     } else {
         context.current_dir.join(raw_path)
-    };
-    let normalized = lexical_normalize(&candidate);
+    }
+}
+
+fn resolve_and_validate(context: &ToolContext, raw_path: &str) -> Result<PathBuf> {
+    let normalized = lexical_normalize(&candidate_path(context, raw_path));
     let resolved = resolve_with_existing_ancestor(&normalized)?;
 
     ensure_inside_project(&resolved, &context.project_root)?;
+    // Both names count: a symlink called `.env` is as hidden as the file it
+    // points at, and a link with an innocent name cannot reach into an
+    // ignored directory.
+    context.ignore.check(&normalized)?;
+    context.ignore.check(&resolved)?;
     Ok(resolved)
 }
 
@@ -149,6 +206,7 @@ fn ensure_inside_project(path: &Path, project_root: &Path) -> Result<()> {
 #[cfg(test)]
 mod tests {
     use super::{ToolContext, lexical_normalize};
+    use crate::ignore_policy::IgnorePolicy;
     use std::fs;
     use std::path::{Path, PathBuf};
     use std::time::{SystemTime, UNIX_EPOCH};
@@ -183,6 +241,78 @@ mod tests {
         assert!(err.to_string().contains("Path escapes project root"));
     }
 
+    #[test]
+    fn resolve_path_rejects_ignored_paths() {
+        let temp = TempDir::new("tool-context-ignore");
+        let root = fs::canonicalize(temp.path()).expect("canonical root");
+        fs::write(
+            root.join(".grokignore"),
+            ".env
+secrets/
+",
+        )
+        .expect("write ignore file");
+        fs::create_dir_all(root.join("secrets")).expect("mkdir");
+        let mut context = ToolContext::new(root.clone()).expect("tool context");
+
+        for path in [".env", "secrets/key.pem", "secrets", "./src/../.env"] {
+            let err = context.resolve_path(path).expect_err(path);
+            assert!(
+                err.to_string().contains("path is ignored by policy"),
+                "{path}"
+            );
+        }
+        assert!(context.resolve_entry_path("secrets/new.txt").is_err());
+        assert!(context.set_current_dir("secrets").is_err());
+        assert!(context.resolve_path("src/main.rs").is_ok());
+        #[cfg(unix)]
+        {
+            fs::write(root.join("public.txt"), "x").expect("write");
+            std::os::unix::fs::symlink(root.join("public.txt"), root.join(".env"))
+                .expect("symlink");
+            std::os::unix::fs::symlink(root.join("public.txt"), root.join("secrets/link"))
+                .expect("symlink");
+            assert!(context.resolve_path(".env").is_err());
+            assert!(context.resolve_path("secrets/link").is_err());
+            assert!(context.resolve_entry_path(".env").is_err());
+            assert!(context.resolve_path("public.txt").is_ok());
+        }
+        assert!(context.is_ignored(".env"));
+        assert!(!context.is_ignored("README.md"));
+
+        context
+            .set_ignore_policy(IgnorePolicy::load(&root, &["*.csv".to_string()]).expect("policy"));
+        assert!(context.resolve_path("data/customers.csv").is_err());
+        assert!(context.resolve_path(".env").is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn resolve_entry_path_keeps_final_symlink() {
+        let temp = TempDir::new("tool-context-entry");
+        let root = fs::canonicalize(temp.path()).expect("canonical root");
+        fs::write(root.join("target.txt"), "x").expect("write target");
+        std::os::unix::fs::symlink(root.join("target.txt"), root.join("link.txt"))
+            .expect("symlink");
+        let context = ToolContext::new(root.clone()).expect("tool context");
+
+        assert_eq!(
+            context.resolve_entry_path("link.txt").expect("entry"),
+            root.join("link.txt")
+        );
+        assert_eq!(
+            context.resolve_path("link.txt").expect("resolved"),
+            root.join("target.txt")
+        );
+        assert!(
+            context
+                .resolve_entry_path("../outside.txt")
+                .expect_err("must reject")
+                .to_string()
+                .contains("Path escapes project root")
+        );
+    }
+
     struct TempDir {
         path: PathBuf,
     }
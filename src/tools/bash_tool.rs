@@ -1,27 +1,90 @@
-use super::ToolResult;
+use super::shell_session::{ShellExit, ShellSession};
+use super::{ToolOutputStream, ToolProgress, ToolResult};
+use crate::shell::{self, RedirectOp};
 use crate::tool_context::ToolContext;
 use anyhow::{Context, Result};
+use globset::GlobBuilder;
 use serde_json::Value;
-use std::path::Path;
-use tokio::process::Command;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt};
+use tokio::process::{Child, Command};
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+
+const DEFAULT_BASH_TIMEOUT_MS: u64 = 120_000;
+const MAX_BASH_TIMEOUT_MS: u64 = 600_000;
+/// How long to keep reading pipes after the shell exits or is killed. Stray
+/// background children can hold them open indefinitely.
+const OUTPUT_DRAIN_GRACE: Duration = Duration::from_millis(500);
+/// Per-stream cap on what the model sees. The user already watched the full
+/// output scroll by, so the middle is dropped and the end kept.
+const MAX_STREAM_OUTPUT_CHARS: usize = 30_000;
+/// Directory entries looked at when expanding a glob against the ignore policy.
+const MAX_GLOB_ENTRIES: usize = 10_000;
+
+enum Termination {
+    TimedOut(Duration),
+    Cancelled,
+}
 
 pub(super) async fn execute_bash_tool(
     args: &Value,
     tool_context: &mut ToolContext,
+    shell: &mut ShellSession,
+    cancel_token: &CancellationToken,
+    progress: Option<ToolProgress>,
 ) -> Result<ToolResult> {
     let command = args
         .get("command")
         .and_then(Value::as_str)
         .context("Missing 'command' argument")?;
-    execute_bash_command(command, tool_context).await
+    let timeout_ms = args
+        .get("timeout_ms")
+        .and_then(Value::as_u64)
+        .unwrap_or(DEFAULT_BASH_TIMEOUT_MS)
+        .clamp(1, MAX_BASH_TIMEOUT_MS);
+    run_bash_command(
+        command,
+        tool_context,
+        shell,
+        Duration::from_millis(timeout_ms),
+        cancel_token,
+        progress,
+    )
+    .await
 }
 
 pub async fn execute_bash_command(
     command: &str,
     tool_context: &mut ToolContext,
+    shell: &mut ShellSession,
+) -> Result<ToolResult> {
+    run_bash_command(
+        command,
+        tool_context,
+        shell,
+        Duration::from_millis(DEFAULT_BASH_TIMEOUT_MS),
+        &CancellationToken::new(),
+        None,
+    )
+    .await
+}
+
+async fn run_bash_command(
+    command: &str,
+    tool_context: &mut ToolContext,
+    shell: &mut ShellSession,
+    timeout: Duration,
+    cancel_token: &CancellationToken,
+    progress: Option<ToolProgress>,
 ) -> Result<ToolResult> {
     let trimmed = command.trim();
-    if let Some(path) = trimmed.strip_prefix("cd ").map(str::trim) {
+    let persistent = cfg!(unix) && shell.enabled();
+    if !persistent && let Some(path) = trimmed.strip_prefix("cd ").map(str::trim) {
         return match tool_context.set_current_dir(path) {
             Ok(new_dir) => Ok(ToolResult::ok(format!(
                 "Changed directory to: {}",
@@ -36,65 +99,400 @@ pub async fn execute_bash_command(
             "Blocked by shell sandbox policy: {reason}"
         )));
     }
+    if persistent {
+        return run_in_persistent_shell(
+            trimmed,
+            tool_context,
+            shell,
+            timeout,
+            cancel_token,
+            progress,
+        )
+        .await;
+    }
+
+    let mut process = shell_command(trimmed);
+    process
+        .current_dir(tool_context.current_dir())
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    // Its own process group, so a timeout or cancel also takes down whatever
+    // the command started.
+    #[cfg(unix)]
+    process.process_group(0);
+    if let Err(err) = tool_context.sandbox().apply(&mut process) {
+        return Ok(ToolResult::err(format!("Sandbox unavailable: {err:#}")));
+    }
+    let mut child = match process.spawn() {
+        Ok(child) => child,
+        Err(err) => {
+            return Ok(ToolResult::err(format!(
+                "Failed running command: {trimmed}: {err}"
+            )));
+        }
+    };
+    let stdout = OutputCapture::spawn(
+        child.stdout.take(),
+        ToolOutputStream::Stdout,
+        progress.clone(),
+    );
+    let stderr = OutputCapture::spawn(child.stderr.take(), ToolOutputStream::Stderr, progress);
+
+    let outcome = tokio::select! {
+        status = child.wait() => Ok(status),
+        _ = tokio::time::sleep(timeout) => Err(Termination::TimedOut(timeout)),
+        _ = cancel_token.cancelled() => Err(Termination::Cancelled),
+    };
+    if outcome.is_err() {
+        kill_process_group(&mut child).await;
+    }
+    let stdout = stdout.finish().await;
+    let stderr = stderr.finish().await;
+
+    match outcome {
+        Ok(Ok(status)) => {
+            #[cfg(unix)]
+            let signal = std::os::unix::process::ExitStatusExt::signal(&status);
+            #[cfg(not(unix))]
+            let signal = None;
+            Ok(finished_result(
+                trimmed,
+                &stdout,
+                &stderr,
+                status.code(),
+                signal,
+                tool_context,
+            ))
+        }
+        Ok(Err(err)) => Ok(ToolResult::err(format!(
+            "Failed running command: {trimmed}: {err}"
+        ))),
+        Err(termination) => Ok(terminated_result(
+            trimmed,
+            &stdout,
+            &stderr,
+            termination,
+            "",
+        )),
+    }
+}
 
+async fn run_in_persistent_shell(
+    command: &str,
+    tool_context: &mut ToolContext,
+    shell: &mut ShellSession,
+    timeout: Duration,
+    cancel_token: &CancellationToken,
+    progress: Option<ToolProgress>,
+) -> Result<ToolResult> {
     let cwd = tool_context.current_dir().to_path_buf();
-    let output = if cfg!(windows) {
-        match Command::new("powershell")
-            .arg("-NoProfile")
-            .arg("-Command")
-            .arg(trimmed)
-            .current_dir(&cwd)
-            .output()
-            .await
-        {
-            Ok(output) => output,
-            Err(err) => {
-                return Ok(ToolResult::err(format!(
-                    "Failed running command: {trimmed}: {err}"
-                )));
+    let outcome = match shell
+        .run(
+            command,
+            &cwd,
+            tool_context.sandbox(),
+            timeout,
+            cancel_token,
+            progress,
+        )
+        .await
+    {
+        Ok(outcome) => outcome,
+        Err(err) => {
+            return Ok(ToolResult::err(format!(
+                "Failed running command: {command}: {err:#}"
+            )));
+        }
+    };
+    let stdout = truncate_middle(outcome.stdout.trim_end(), MAX_STREAM_OUTPUT_CHARS);
+    let stderr = truncate_middle(outcome.stderr.trim_end(), MAX_STREAM_OUTPUT_CHARS);
+    const RESTARTED: &str = "\nThe persistent shell was restarted; exported variables were kept.";
+
+    match outcome.exit {
+        ShellExit::Finished {
+            status,
+            cwd: new_dir,
+        } => {
+            let mut result =
+                finished_result(command, &stdout, &stderr, Some(status), None, tool_context);
+            // The next command starts with a `cd` back to the tracked
+            // directory, so refusing the change here is enough.
+            if let Some(new_dir) = new_dir.filter(|dir| dir != &cwd)
+                && let Err(err) = tool_context.set_current_dir(&new_dir.to_string_lossy())
+            {
+                let note = format!(
+                    "\n\nDirectory change ignored: {err}. Staying in {}",
+                    cwd.display()
+                );
+                match (&mut result.output, &mut result.error) {
+                    (_, Some(error)) => error.push_str(&note),
+                    (Some(output), None) => output.push_str(&note),
+                    (None, None) => {}
+                }
             }
+            Ok(result)
         }
-    } else {
-        match Command::new("sh")
-            .arg("-lc")
-            .arg(trimmed)
-            .current_dir(&cwd)
-            .output()
-            .await
-        {
-            Ok(output) => output,
-            Err(err) => {
-                return Ok(ToolResult::err(format!(
-                    "Failed running command: {trimmed}: {err}"
-                )));
+        ShellExit::TimedOut(limit) => Ok(terminated_result(
+            command,
+            &stdout,
+            &stderr,
+            Termination::TimedOut(limit),
+            RESTARTED,
+        )),
+        ShellExit::Cancelled => Ok(terminated_result(
+            command,
+            &stdout,
+            &stderr,
+            Termination::Cancelled,
+            RESTARTED,
+        )),
+        ShellExit::ShellExited => {
+            let output = format_output(&stdout, &stderr);
+            let mut message = format!(
+                "The persistent shell exited while running: {command}\nA new shell starts with the next command; exported variables were kept."
+            );
+            if !output.is_empty() {
+                message.push_str(&format!("\n\nOutput:\n{output}"));
             }
+            Ok(ToolResult::err(message))
         }
-    };
+    }
+}
 
-    let stdout = String::from_utf8_lossy(&output.stdout).trim().to_string();
-    let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+/// The result of a command that ran to completion, with resource limit hits
+/// reported as their own errors.
+fn finished_result(
+    command: &str,
+    stdout: &str,
+    stderr: &str,
+    code: Option<i32>,
+    signal: Option<i32>,
+    tool_context: &ToolContext,
+) -> ToolResult {
+    if code != Some(0) {
+        let output = format_output(stdout, stderr);
+        if let Some(hit) = tool_context.sandbox().limit_hit(code, signal, &output) {
+            return ToolResult::err(if output.is_empty() {
+                format!("{hit}: {command}")
+            } else {
+                format!("{hit}: {command}\n\nPartial output:\n{output}")
+            });
+        }
+    }
+    with_sandbox_note(
+        command_result(command, stdout, stderr, code == Some(0)),
+        tool_context,
+    )
+}
 
-    if output.status.success() {
-        if stdout.is_empty() && stderr.is_empty() {
-            Ok(ToolResult::ok("Command executed successfully (no output)"))
-        } else if stderr.is_empty() {
-            Ok(ToolResult::ok(stdout))
-        } else if stdout.is_empty() {
-            Ok(ToolResult::ok(format!("STDERR:\n{stderr}")))
+fn command_result(command: &str, stdout: &str, stderr: &str, success: bool) -> ToolResult {
+    if success {
+        let output = format_output(stdout, stderr);
+        if output.is_empty() {
+            ToolResult::ok("Command executed successfully (no output)")
         } else {
-            Ok(ToolResult::ok(format!("{stdout}\n\nSTDERR:\n{stderr}")))
+            ToolResult::ok(output)
         }
+    } else if stderr.is_empty() {
+        ToolResult::err(format!("Command failed: {command}"))
     } else {
-        let message = if stderr.is_empty() {
-            format!("Command failed: {trimmed}")
-        } else {
-            format!("Command failed: {stderr}")
+        ToolResult::err(format!("Command failed: {stderr}"))
+    }
+}
+
+/// Adds connections the network policy refused, and points the model at the
+/// sandbox when a failure looks like it was denied.
+fn with_sandbox_note(mut result: ToolResult, tool_context: &ToolContext) -> ToolResult {
+    let sandbox = tool_context.sandbox();
+    let mut notes = Vec::new();
+    if let Some(report) = sandbox.take_network_report() {
+        notes.push(report);
+    }
+    if let Some(note) = result
+        .error
+        .as_deref()
+        .and_then(|error| sandbox.explain_failure(error))
+    {
+        notes.push(note);
+    }
+    for note in notes {
+        let text = match (&mut result.error, &mut result.output) {
+            (Some(error), _) => error,
+            (None, Some(output)) => output,
+            (None, None) => continue,
         };
-        Ok(ToolResult::err(message))
+        text.push_str("\n\n");
+        text.push_str(&note);
+    }
+    result
+}
+
+fn terminated_result(
+    command: &str,
+    stdout: &str,
+    stderr: &str,
+    termination: Termination,
+    note: &str,
+) -> ToolResult {
+    let reason = match termination {
+        Termination::TimedOut(limit) => format!(
+            "Command timed out after {} and was killed",
+            format_timeout(limit)
+        ),
+        Termination::Cancelled => "Command was cancelled and killed".to_string(),
+    };
+    let partial = format_output(stdout, stderr);
+    ToolResult::err(if partial.is_empty() {
+        format!("{reason}: {command}{note}")
+    } else {
+        format!("{reason}: {command}{note}\n\nPartial output:\n{partial}")
+    })
+}
+
+pub(super) fn shell_command(command: &str) -> Command {
+    if cfg!(windows) {
+        let mut process = Command::new("powershell");
+        process.arg("-NoProfile").arg("-Command").arg(command);
+        process
+    } else {
+        let mut process = Command::new("sh");
+        process.arg("-lc").arg(command);
+        process
+    }
+}
+
+pub(super) async fn kill_process_group(child: &mut Child) {
+    #[cfg(unix)]
+    if let Some(pid) = child.id() {
+        signal_process_group(pid);
+    }
+    let _ = child.kill().await;
+}
+
+/// Kills the group led by `pid`, which must have been spawned with
+/// `process_group(0)`.
+#[cfg(unix)]
+pub(super) fn signal_process_group(pid: u32) {
+    // SAFETY: killpg only sends a signal; the group id is the child's pid.
+    unsafe {
+        libc::killpg(pid as libc::pid_t, libc::SIGKILL);
+    }
+}
+
+fn format_output(stdout: &str, stderr: &str) -> String {
+    match (stdout.is_empty(), stderr.is_empty()) {
+        (true, true) => String::new(),
+        (false, true) => stdout.to_string(),
+        (true, false) => format!("STDERR:\n{stderr}"),
+        (false, false) => format!("{stdout}\n\nSTDERR:\n{stderr}"),
     }
 }
 
-fn validate_command_paths(command: &str, tool_context: &ToolContext) -> Result<(), String> {
+fn format_timeout(limit: Duration) -> String {
+    if limit.as_millis().is_multiple_of(1000) {
+        format!("{}s", limit.as_secs())
+    } else {
+        format!("{}ms", limit.as_millis())
+    }
+}
+
+/// Keeps the start and end of long output, dropping whole lines in between.
+fn truncate_middle(text: &str, max_chars: usize) -> String {
+    if text.len() <= max_chars {
+        return text.to_string();
+    }
+    let head_end = text.floor_char_boundary(max_chars / 3);
+    let head_end = text[..head_end].rfind('\n').unwrap_or(head_end);
+    let tail_start = text.ceil_char_boundary(text.len() - (max_chars - max_chars / 3));
+    let tail_start = text[tail_start..]
+        .find('\n')
+        .map_or(tail_start, |offset| tail_start + offset + 1);
+    let omitted = text[head_end..tail_start].lines().count();
+    format!(
+        "{}\n... [{omitted} lines omitted] ...\n{}",
+        &text[..head_end],
+        &text[tail_start..]
+    )
+}
+
+/// Collects a pipe in the background so partial output survives a kill, and
+/// forwards complete lines to `progress` as they arrive.
+struct OutputCapture {
+    buffer: Arc<Mutex<Vec<u8>>>,
+    task: Option<JoinHandle<()>>,
+}
+
+impl OutputCapture {
+    fn spawn<R: AsyncRead + Unpin + Send + 'static>(
+        pipe: Option<R>,
+        stream: ToolOutputStream,
+        progress: Option<ToolProgress>,
+    ) -> Self {
+        let buffer = Arc::new(Mutex::new(Vec::new()));
+        let task = pipe.map(|mut pipe| {
+            let buffer = Arc::clone(&buffer);
+            tokio::spawn(async move {
+                let mut chunk = [0u8; 8192];
+                let mut pending_line = Vec::new();
+                while let Ok(read) = pipe.read(&mut chunk).await {
+                    if read == 0 {
+                        break;
+                    }
+                    buffer
+                        .lock()
+                        .unwrap_or_else(PoisonError::into_inner)
+                        .extend_from_slice(&chunk[..read]);
+                    let Some(progress) = &progress else {
+                        continue;
+                    };
+                    for &byte in &chunk[..read] {
+                        if byte == b'\n' {
+                            emit_line(progress, stream, &mut pending_line);
+                        } else {
+                            pending_line.push(byte);
+                        }
+                    }
+                }
+                if let Some(progress) = &progress
+                    && !pending_line.is_empty()
+                {
+                    emit_line(progress, stream, &mut pending_line);
+                }
+            })
+        });
+        Self { buffer, task }
+    }
+
+    async fn finish(mut self) -> String {
+        if let Some(mut task) = self.task.take()
+            && tokio::time::timeout(OUTPUT_DRAIN_GRACE, &mut task)
+                .await
+                .is_err()
+        {
+            task.abort();
+        }
+        let bytes = self.buffer.lock().unwrap_or_else(PoisonError::into_inner);
+        truncate_middle(
+            String::from_utf8_lossy(&bytes).trim(),
+            MAX_STREAM_OUTPUT_CHARS,
+        )
+    }
+}
+
+fn emit_line(progress: &ToolProgress, stream: ToolOutputStream, pending_line: &mut Vec<u8>) {
+    let line = String::from_utf8_lossy(pending_line)
+        .trim_end_matches('\r')
+        .to_string();
+    pending_line.clear();
+    progress(stream, line);
+}
+
+pub(super) fn validate_command_paths(
+    command: &str,
+    tool_context: &ToolContext,
+) -> Result<(), String> {
     if has_dynamic_path_expansion(command) {
         return Err(
             "dynamic path expansion is not allowed (e.g. ~, $VAR/path, %VAR%\\path, $(...))"
@@ -102,31 +500,62 @@ fn validate_command_paths(command: &str, tool_context: &ToolContext) -> Result<(
         );
     }
 
-    let mut expect_redirection_target = false;
-    for token in split_shell_like(command) {
-        if let Some(candidate) = extract_path_candidate(&token, &mut expect_redirection_target) {
-            if candidate.contains("://") {
-                continue;
-            }
-            let sanitized = sanitize_path_token(&candidate);
-            if sanitized.is_empty() {
-                continue;
+    let script =
+        shell::parse_command(command).map_err(|err| format!("could not parse command: {err}"))?;
+
+    let words = script
+        .simple_commands()
+        .into_iter()
+        .flat_map(|simple| &simple.words)
+        .map(|word| (word, false));
+    let redirection_targets = script
+        .redirections()
+        .into_iter()
+        .filter(|redirection| redirection.op != RedirectOp::HereDoc)
+        .map(|redirection| (&redirection.target, true));
+    for (word, is_redirect) in words.chain(redirection_targets) {
+        let Some(candidate) = word.static_value() else {
+            continue;
+        };
+        if candidate.contains("://") {
+            continue;
+        }
+        let sanitized = sanitize_path_token(&candidate);
+        if sanitized.is_empty() {
+            continue;
+        }
+        // Subcommands and plain arguments such as `build` in `cargo build`
+        // are not checked; dotfiles like `.env` and globs are.
+        let path_like = is_redirect
+            || looks_like_path(&sanitized)
+            || sanitized.starts_with('.')
+            || word.has_pattern();
+        if path_like && !sanitized.starts_with('-') {
+            if tool_context.is_ignored(&sanitized) {
+                return Err(format!("path is ignored by policy: {sanitized}"));
             }
-            if contains_glob_pattern(&sanitized) {
-                if let Some(prefix) = prefix_before_glob(&sanitized)
-                    && looks_like_path(&prefix)
-                {
-                    tool_context
-                        .resolve_path(&prefix)
-                        .map_err(|err| err.to_string())?;
-                }
-                continue;
+            if word.has_pattern()
+                && let Some(matched) = ignored_glob_match(&sanitized, tool_context)
+            {
+                return Err(format!(
+                    "path is ignored by policy: {matched} (matched by {sanitized})"
+                ));
             }
-            if looks_like_path(&sanitized) {
+        }
+        if contains_glob_pattern(&sanitized) {
+            if let Some(prefix) = prefix_before_glob(&sanitized)
+                && looks_like_path(&prefix)
+            {
                 tool_context
-                    .resolve_path(&sanitized)
+                    .resolve_path(&prefix)
                     .map_err(|err| err.to_string())?;
             }
+            continue;
+        }
+        if looks_like_path(&sanitized) {
+            tool_context
+                .resolve_path(&sanitized)
+                .map_err(|err| err.to_string())?;
         }
     }
 
@@ -170,68 +599,6 @@ fn contains_variable_path_like(command: &str) -> bool {
     false
 }
 
-fn split_shell_like(command: &str) -> Vec<String> {
-    let mut tokens = Vec::new();
-    let mut current = String::new();
-    let mut quote: Option<char> = None;
-
-    for ch in command.chars() {
-        if let Some(active_quote) = quote {
-            if ch == active_quote {
-                quote = None;
-            } else {
-                current.push(ch);
-            }
-            continue;
-        }
-
-        match ch {
-            '"' | '\'' => quote = Some(ch),
-            ch if ch.is_whitespace() => {
-                if !current.is_empty() {
-                    tokens.push(current.clone());
-                    current.clear();
-                }
-            }
-            _ => current.push(ch),
-        }
-    }
-    if !current.is_empty() {
-        tokens.push(current);
-    }
-    tokens
-}
-
-fn extract_path_candidate(token: &str, expect_redirection_target: &mut bool) -> Option<String> {
-    if token.is_empty() {
-        return None;
-    }
-
-    if *expect_redirection_target {
-        *expect_redirection_target = false;
-        return Some(token.to_string());
-    }
-
-    if matches!(token, ">" | ">>" | "<" | "1>" | "1>>" | "2>" | "2>>") {
-        *expect_redirection_target = true;
-        return None;
-    }
-
-    for prefix in ["2>>", "1>>", ">>", "2>", "1>", ">", "<"] {
-        if let Some(rest) = token.strip_prefix(prefix)
-            && !rest.is_empty()
-        {
-            return Some(rest.to_string());
-        }
-    }
-
-    if looks_like_path(token) {
-        Some(token.to_string())
-    } else {
-        None
-    }
-}
-
 fn looks_like_path(token: &str) -> bool {
     if token.is_empty() || token == "-" {
         return false;
@@ -271,6 +638,64 @@ fn sanitize_path_token(token: &str) -> String {
         .to_string()
 }
 
+/// Expands `pattern` the way the shell would, one directory level per
+/// component with dotfiles only matched by components starting with a dot,
+/// and returns the first match the ignore policy hides.
+fn ignored_glob_match(pattern: &str, tool_context: &ToolContext) -> Option<String> {
+    if tool_context.ignore_policy().is_empty() {
+        return None;
+    }
+    let mut matches = vec![if Path::new(pattern).is_absolute() {
+        PathBuf::from("/")
+    } else {
+        tool_context.current_dir().to_path_buf()
+    }];
+    let mut visited = 0usize;
+    for component in pattern.split('/').filter(|part| !part.is_empty()) {
+        let is_glob = component.contains(['*', '?', '[', '{']);
+        let matcher = match GlobBuilder::new(component).literal_separator(true).build() {
+            Ok(glob) if is_glob => glob.compile_matcher(),
+            _ => {
+                matches = matches
+                    .into_iter()
+                    .map(|dir| dir.join(component))
+                    .filter(|path| path.exists())
+                    .collect();
+                continue;
+            }
+        };
+        let mut next = Vec::new();
+        for dir in &matches {
+            let Ok(entries) = fs::read_dir(dir) else {
+                continue;
+            };
+            for entry in entries.flatten() {
+                visited += 1;
+                if visited > MAX_GLOB_ENTRIES {
+                    return None;
+                }
+                let name = entry.file_name().to_string_lossy().to_string();
+                if name.starts_with('.') && !component.starts_with('.') {
+                    continue;
+                }
+                if matcher.is_match(&name) {
+                    next.push(entry.path());
+                }
+            }
+        }
+        matches = next;
+    }
+    matches.into_iter().find_map(|path| {
+        let path = path.to_string_lossy();
+        tool_context.is_ignored(&path).then(|| {
+            Path::new(path.as_ref())
+                .strip_prefix(tool_context.current_dir())
+                .map(|relative| relative.display().to_string())
+                .unwrap_or_else(|_| path.to_string())
+        })
+    })
+}
+
 fn contains_glob_pattern(token: &str) -> bool {
     token.contains('*') || token.contains('?')
 }
@@ -291,12 +716,19 @@ fn prefix_before_glob(token: &str) -> Option<String> {
 #[cfg(test)]
 mod tests {
     use super::{
-        has_dynamic_path_expansion, looks_like_path, sanitize_path_token, validate_command_paths,
+        execute_bash_tool, has_dynamic_path_expansion, looks_like_path, sanitize_path_token,
+        truncate_middle, validate_command_paths,
     };
+    use crate::sandbox::{ResourceLimits, SandboxConfig, SandboxPolicy};
     use crate::tool_context::ToolContext;
+    use crate::tools::shell_session::ShellSession;
+    use crate::tools::{ToolOutputStream, ToolProgress};
+    use serde_json::json;
     use std::fs;
     use std::path::{Path, PathBuf};
-    use std::time::{SystemTime, UNIX_EPOCH};
+    use std::sync::{Arc, Mutex};
+    use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+    use tokio_util::sync::CancellationToken;
 
     #[test]
     fn rejects_absolute_out_of_root_path() {
@@ -320,6 +752,74 @@ mod tests {
         validate_command_paths("cat src/main.rs", &context).expect("path should be allowed");
     }
 
+    #[test]
+    fn checks_paths_in_every_parsed_command_and_redirection() {
+        let temp = TempDir::new("bash-sandbox-parsed");
+        let root = fs::canonicalize(temp.path()).expect("canonical root");
+        let context = ToolContext::new(root).expect("tool context");
+
+        for command in [
+            "ls && cat ../outside.txt",
+            "echo hi>../out.txt",
+            "(cd src; ls) 2> '../err log'",
+            "diff <(cat ../a) src/b",
+        ] {
+            let err = validate_command_paths(command, &context).expect_err(command);
+            assert!(
+                err.contains("Path escapes project root"),
+                "{command}: {err}"
+            );
+        }
+        validate_command_paths("cat <<'EOF' > notes.txt\n/etc/passwd\nEOF", &context)
+            .expect("here-document bodies are not paths");
+        let err = validate_command_paths("echo 'unterminated", &context).expect_err("parse error");
+        assert!(err.contains("could not parse command"));
+    }
+
+    #[test]
+    fn rejects_commands_touching_ignored_paths() {
+        let temp = TempDir::new("bash-sandbox-ignored");
+        let root = fs::canonicalize(temp.path()).expect("canonical root");
+        fs::write(
+            root.join(".grokignore"),
+            ".env\n*.pem\nsecrets/\nbuild/\ntarget\n",
+        )
+        .expect("write");
+        fs::create_dir_all(root.join("config")).expect("mkdir");
+        fs::create_dir_all(root.join("target")).expect("mkdir");
+        fs::write(root.join(".env"), "KEY=1").expect("write");
+        fs::write(root.join("config/server.pem"), "key").expect("write");
+        fs::write(root.join("README.md"), "readme").expect("write");
+        let context = ToolContext::new(root).expect("tool context");
+
+        for command in [
+            "cat .env",
+            "cp ./secrets/key.txt out.txt",
+            "ls secrets/*",
+            "grep -r token *.pem",
+            "echo hi > src/../.env",
+            "cat .e*",
+            "cat .e{nv,x}",
+            "head config/*",
+        ] {
+            let err = validate_command_paths(command, &context).expect_err(command);
+            assert!(
+                err.contains("path is ignored by policy"),
+                "{command}: {err}"
+            );
+        }
+        validate_command_paths("cat .env.example src/main.rs", &context)
+            .expect("unignored paths are allowed");
+        for command in [
+            "cargo build",
+            "make target secrets",
+            "cat *.md",
+            "ls src/*.rs",
+        ] {
+            validate_command_paths(command, &context).expect(command);
+        }
+    }
+
     #[test]
     fn rejects_dynamic_path_expansion_patterns() {
         assert!(has_dynamic_path_expansion("cat $HOME/.ssh/id_rsa"));
@@ -345,6 +845,201 @@ mod tests {
         );
     }
 
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn timeout_kills_process_group_and_keeps_partial_output() {
+        let temp = TempDir::new("bash-timeout");
+        let mut context = ToolContext::new(temp.path().to_path_buf()).expect("tool context");
+        let started = Instant::now();
+
+        let result = execute_bash_tool(
+            &json!({
+                "command": "echo started; (sleep 5; echo late > marker.txt) & sleep 5",
+                "timeout_ms": 300
+            }),
+            &mut context,
+            &mut ShellSession::default(),
+            &CancellationToken::new(),
+            None,
+        )
+        .await
+        .expect("bash");
+
+        assert!(started.elapsed() < Duration::from_secs(3));
+        assert!(!result.success);
+        let error = result.error.expect("error");
+        assert!(error.starts_with("Command timed out after 300ms and was killed"));
+        assert!(error.ends_with("Partial output:\nstarted"));
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert!(!temp.path().join("marker.txt").exists());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn cancellation_kills_running_command() {
+        let temp = TempDir::new("bash-cancel");
+        let mut context = ToolContext::new(temp.path().to_path_buf()).expect("tool context");
+        let cancel_token = CancellationToken::new();
+        let canceller = cancel_token.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            canceller.cancel();
+        });
+
+        let result = execute_bash_tool(
+            &json!({ "command": "sleep 10" }),
+            &mut context,
+            &mut ShellSession::default(),
+            &cancel_token,
+            None,
+        )
+        .await
+        .expect("bash");
+
+        assert_eq!(
+            result.error.as_deref(),
+            Some("Command was cancelled and killed: sleep 10")
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn streams_output_lines_while_running() {
+        let temp = TempDir::new("bash-progress");
+        let mut context = ToolContext::new(temp.path().to_path_buf()).expect("tool context");
+        let lines = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&lines);
+        let progress: ToolProgress = Arc::new(move |stream, line| {
+            sink.lock().expect("lines").push((stream, line));
+        });
+
+        let result = execute_bash_tool(
+            &json!({ "command": "printf 'one\\ntwo\\r\\n'; echo oops >&2; printf tail" }),
+            &mut context,
+            &mut ShellSession::default(),
+            &CancellationToken::new(),
+            Some(progress),
+        )
+        .await
+        .expect("bash");
+
+        assert_eq!(
+            result.output.as_deref(),
+            Some("one\ntwo\r\ntail\n\nSTDERR:\noops")
+        );
+        let mut lines = lines.lock().expect("lines").clone();
+        lines.sort_by_key(|(stream, _)| *stream == ToolOutputStream::Stderr);
+        assert_eq!(
+            lines,
+            vec![
+                (ToolOutputStream::Stdout, "one".to_string()),
+                (ToolOutputStream::Stdout, "two".to_string()),
+                (ToolOutputStream::Stdout, "tail".to_string()),
+                (ToolOutputStream::Stderr, "oops".to_string()),
+            ]
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn persistent_shell_tracks_cwd_within_project_root() {
+        let temp = TempDir::new("bash-persistent");
+        let root = fs::canonicalize(temp.path()).expect("canonical root");
+        fs::create_dir_all(root.join("src")).expect("create src");
+        let mut context = ToolContext::new(root.clone()).expect("tool context");
+        let mut shell = ShellSession::default();
+        shell.set_enabled(true);
+        let mut run = async |command: &str| {
+            execute_bash_tool(
+                &json!({ "command": command }),
+                &mut context,
+                &mut shell,
+                &CancellationToken::new(),
+                None,
+            )
+            .await
+            .expect("bash")
+        };
+
+        run("export GREETING=hello && cd src").await;
+        let result = run("echo \"$GREETING\" && pwd").await;
+        assert_eq!(
+            result.output,
+            Some(format!("hello\n{}", root.join("src").display()))
+        );
+
+        // A bare `cd` goes to $HOME, which is outside the project root.
+        let result = run("cd").await;
+        assert!(
+            result
+                .output
+                .as_deref()
+                .is_some_and(|output| output.contains("Directory change ignored"))
+        );
+        let result = run("pwd").await;
+        assert_eq!(
+            result.output.as_deref(),
+            Some(root.join("src").to_string_lossy().as_ref())
+        );
+        assert_eq!(context.current_dir(), root.join("src"));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn reports_resource_limit_hits_as_distinct_errors() {
+        let temp = TempDir::new("bash-limits");
+        let mut context = ToolContext::new(temp.path().to_path_buf()).expect("tool context");
+        let config = SandboxConfig {
+            limits: ResourceLimits {
+                cpu_seconds: Some(1),
+                file_size_mb: Some(1),
+                ..ResourceLimits::default()
+            },
+            ..SandboxConfig::default()
+        };
+        context.set_sandbox(SandboxPolicy::new(&config, temp.path()).expect("policy"));
+        let mut run = async |command: &str| {
+            execute_bash_tool(
+                &json!({ "command": command }),
+                &mut context,
+                &mut ShellSession::default(),
+                &CancellationToken::new(),
+                None,
+            )
+            .await
+            .expect("bash")
+        };
+
+        let result = run("while :; do :; done").await;
+        assert!(
+            result
+                .error
+                .as_deref()
+                .is_some_and(|error| error.starts_with("Command exceeded the CPU time limit (1s)"))
+        );
+        let result = run("yes x | head -c 2000000 > big.bin").await;
+        assert!(
+            result.error.as_deref().is_some_and(
+                |error| error.starts_with("Command exceeded the file size limit (1 MB)")
+            ),
+            "{result:?}"
+        );
+    }
+
+    #[test]
+    fn truncate_middle_keeps_head_and_tail_lines() {
+        let text = (1..=100)
+            .map(|n| format!("line {n}"))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let truncated = truncate_middle(&text, 120);
+        assert!(truncated.starts_with("line 1\n"));
+        assert!(truncated.ends_with("line 100"));
+        assert!(truncated.contains("lines omitted] ..."));
+        assert!(truncated.len() < 160);
+        assert_eq!(truncate_middle("short", 120), "short");
+    }
+
     struct TempDir {
         path: PathBuf,
     }
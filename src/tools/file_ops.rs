@@ -1,91 +1,79 @@
 use super::ToolResult;
+use super::file_view::format_size;
+use super::text_file::{TextFormat, decode_text};
+use crate::redaction::placeholders;
 use crate::tool_context::ToolContext;
 use anyhow::{Context, Result};
 use serde_json::Value;
 use similar::TextDiff;
+use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
 use std::fs;
+use std::hash::Hasher;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
 
-pub(super) fn execute_view_file(args: &Value, tool_context: &ToolContext) -> Result<ToolResult> {
-    let path = args
-        .get("path")
-        .and_then(Value::as_str)
-        .context("Missing 'path' argument")?;
+/// Deleted text files up to this many lines are echoed back as a removal diff.
+const MAX_DELETE_DIFF_LINES: usize = 200;
 
-    let resolved = tool_context.resolve_path(path)?;
-    if !resolved.exists() {
-        return Ok(ToolResult::err(format!(
-            "File or directory not found: {path}"
-        )));
-    }
+/// Remembers what each file looked like when the model last viewed it, so edits
+/// based on an outdated read can be refused instead of silently applied.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct FileReadTracker {
+    stamps: HashMap<PathBuf, FileStamp>,
+}
 
-    if resolved.is_dir() {
-        let mut names = Vec::new();
-        for entry in fs::read_dir(resolved)? {
-            let entry = entry?;
-            names.push(entry.file_name().to_string_lossy().to_string());
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct FileStamp {
+    modified: Option<SystemTime>,
+    hash: u64,
+}
+
+impl FileStamp {
+    fn capture(path: &Path, hash: u64) -> Self {
+        Self {
+            modified: fs::metadata(path).and_then(|meta| meta.modified()).ok(),
+            hash,
         }
-        names.sort();
-        return Ok(ToolResult::ok(format!(
-            "Directory contents of {path}:\n{}",
-            names.join("\n")
-        )));
     }
+}
 
-    let content = fs::read_to_string(&resolved)
-        .with_context(|| format!("Failed reading file {}", resolved.display()))?;
-    let lines: Vec<&str> = content.lines().collect();
-
-    let start = args
-        .get("start_line")
-        .and_then(Value::as_u64)
-        .map(|value| value as usize);
-    let end = args
-        .get("end_line")
-        .and_then(Value::as_u64)
-        .map(|value| value as usize);
-
-    if let (Some(start), Some(end)) = (start, end) {
-        if start == 0 || end < start {
-            return Ok(ToolResult::err("Invalid line range"));
-        }
-        let selected: Vec<String> = lines
-            .iter()
-            .enumerate()
-            .filter_map(|(index, line)| {
-                let line_no = index + 1;
-                if line_no >= start && line_no <= end {
-                    Some(format!("{line_no}: {line}"))
-                } else {
-                    None
-                }
-            })
-            .collect();
-        return Ok(ToolResult::ok(format!(
-            "Lines {start}-{end} of {path}:\n{}",
-            selected.join("\n")
-        )));
+impl FileReadTracker {
+    pub(super) fn record(&mut self, path: &Path, content: &[u8]) {
+        self.record_hash(path, content_hash(content));
     }
 
-    let limit = 10usize;
-    let display = lines
-        .iter()
-        .take(limit)
-        .enumerate()
-        .map(|(index, line)| format!("{}: {}", index + 1, line))
-        .collect::<Vec<_>>()
-        .join("\n");
-    let suffix = if lines.len() > limit {
-        format!("\n... +{} lines", lines.len() - limit)
-    } else {
-        String::new()
-    };
+    /// Records a read whose hash was computed while streaming the file.
+    pub(super) fn record_hash(&mut self, path: &Path, hash: u64) {
+        self.stamps
+            .insert(path.to_path_buf(), FileStamp::capture(path, hash));
+    }
 
-    Ok(ToolResult::ok(format!(
-        "Contents of {path}:\n{display}{suffix}"
-    )))
+    /// Returns true when the file was viewed earlier and its content has changed
+    /// on disk since then. Files that were never viewed are not considered stale.
+    fn is_stale(&self, path: &Path, current: &[u8]) -> bool {
+        let Some(stamp) = self.stamps.get(path) else {
+            return false;
+        };
+        let modified = fs::metadata(path).and_then(|meta| meta.modified()).ok();
+        if modified.is_some() && modified == stamp.modified {
+            return false;
+        }
+        stamp.hash != content_hash(current)
+    }
+
+    /// Drops stamps for `path` and, when it was a directory, everything under it.
+    fn forget(&mut self, path: &Path) {
+        self.stamps.retain(|tracked, _| !tracked.starts_with(path));
+    }
 }
 
-pub(super) fn execute_create_file(args: &Value, tool_context: &ToolContext) -> Result<ToolResult> {
+pub(super) fn execute_create_file(
+    args: &Value,
+    tool_context: &ToolContext,
+    tracker: &mut FileReadTracker,
+) -> Result<ToolResult> {
     let path = args
         .get("path")
         .and_then(Value::as_str)
@@ -95,25 +83,90 @@ pub(super) fn execute_create_file(args: &Value, tool_context: &ToolContext) -> R
         .and_then(Value::as_str)
         .context("Missing 'content' argument")?;
 
+    let overwrite = args
+        .get("overwrite")
+        .and_then(Value::as_bool)
+        .unwrap_or(false);
+
     let resolved = tool_context.resolve_path(path)?;
+    if inside_git_dir(tool_context, &resolved) {
+        return Ok(ToolResult::err(git_dir_message(path)));
+    }
+    if resolved.is_dir() {
+        return Ok(ToolResult::err(format!("{path} is an existing directory")));
+    }
+    let mut format = TextFormat::default();
+    let mut previous = None;
+    if let Ok(existing) = fs::read(&resolved) {
+        if !overwrite {
+            return Ok(ToolResult::err(format!(
+                "File already exists: {path}. Use str_replace_editor to change it, or pass overwrite: true to replace the whole file."
+            )));
+        }
+        if tracker.is_stale(&resolved, &existing) {
+            return Ok(ToolResult::err(stale_file_message(path)));
+        }
+        previous = Some(match decode_text(&existing) {
+            Ok(decoded) => {
+                format = decoded.format;
+                Ok(decoded.text.replace("\r\n", "\n"))
+            }
+            Err(_) => Err(existing.len() as u64),
+        });
+    }
+    let existing_text = match &previous {
+        Some(Ok(old)) => old.as_str(),
+        _ => "",
+    };
+    if let Some(message) = placeholder_message(path, content, existing_text) {
+        return Ok(ToolResult::err(message));
+    }
     if let Some(parent) = resolved.parent() {
         fs::create_dir_all(parent)
             .with_context(|| format!("Failed to create parent directory {}", parent.display()))?;
     }
-    fs::write(&resolved, content)
-        .with_context(|| format!("Failed writing file {}", resolved.display()))?;
+    let encoded = format.encode(&format.apply_line_endings(content));
+    write_file_atomic(&resolved, &encoded)?;
+    tracker.record(&resolved, &encoded);
 
-    let created = TextDiff::from_lines("", content)
-        .unified_diff()
-        .header("/dev/null", &format!("b/{path}"))
-        .to_string();
-
-    Ok(ToolResult::ok(format!("Created {path}\n{created}")))
+    let content = content.replace("\r\n", "\n");
+    match previous {
+        None => {
+            let created = TextDiff::from_lines("", content.as_str())
+                .unified_diff()
+                .header("/dev/null", &format!("b/{path}"))
+                .to_string();
+            Ok(ToolResult::ok(format!("Created {path}\n{created}")))
+        }
+        Some(Ok(old)) => {
+            let diff = TextDiff::from_lines(old.as_str(), content.as_str())
+                .unified_diff()
+                .header(&format!("a/{path}"), &format!("b/{path}"))
+                .to_string();
+            let diff = if diff.is_empty() {
+                "(contents unchanged)".to_string()
+            } else {
+                diff
+            };
+            Ok(ToolResult::ok(format!("Overwrote {path}\n{diff}")))
+        }
+        Some(Err(size)) => {
+            let created = TextDiff::from_lines("", content.as_str())
+                .unified_diff()
+                .header("/dev/null", &format!("b/{path}"))
+                .to_string();
+            Ok(ToolResult::ok(format!(
+                "Overwrote {path} (previous contents were not text, {})\n{created}",
+                format_size(size)
+            )))
+        }
+    }
 }
 
 pub(super) fn execute_str_replace_editor(
     args: &Value,
     tool_context: &ToolContext,
+    tracker: &mut FileReadTracker,
 ) -> Result<ToolResult> {
     let path = args
         .get("path")
@@ -133,80 +186,464 @@ pub(super) fn execute_str_replace_editor(
         .unwrap_or(false);
 
     let resolved = tool_context.resolve_path(path)?;
+    if inside_git_dir(tool_context, &resolved) {
+        return Ok(ToolResult::err(git_dir_message(path)));
+    }
     if !resolved.exists() {
         return Ok(ToolResult::err(format!("File not found: {path}")));
     }
 
-    let original = fs::read_to_string(&resolved)
+    let bytes = fs::read(&resolved)
         .with_context(|| format!("Failed reading file {}", resolved.display()))?;
+    if tracker.is_stale(&resolved, &bytes) {
+        return Ok(ToolResult::err(stale_file_message(path)));
+    }
+    let decoded = match decode_text(&bytes) {
+        Ok(decoded) => decoded,
+        Err(reason) => return Ok(ToolResult::err(format!("Cannot edit {path}: {reason}"))),
+    };
 
-    if !original.contains(old_str) {
+    // Match and replace in the file's own line ending style so LF arguments work
+    // against CRLF files and the edit never introduces mixed endings.
+    let original = decoded.text;
+    let old_str = decoded.format.apply_line_endings(old_str);
+    let new_str = decoded.format.apply_line_endings(new_str);
+    if !original.contains(&old_str) {
         return Ok(ToolResult::err(format!(
-            "String not found in file: \"{old_str}\""
+            "String not found in file: \"{}\"",
+            old_str.replace("\r\n", "\n")
         )));
     }
+    if let Some(message) = placeholder_message(path, &new_str, &original) {
+        return Ok(ToolResult::err(message));
+    }
 
     let updated = if replace_all {
-        original.replace(old_str, new_str)
+        original.replace(&old_str, &new_str)
     } else {
-        original.replacen(old_str, new_str, 1)
+        original.replacen(&old_str, &new_str, 1)
+    };
+
+    let encoded = decoded.format.encode(&updated);
+    write_file_atomic(&resolved, &encoded)?;
+    tracker.record(&resolved, &encoded);
+
+    let diff = TextDiff::from_lines(
+        &original.replace("\r\n", "\n"),
+        &updated.replace("\r\n", "\n"),
+    )
+    .unified_diff()
+    .header(&format!("a/{path}"), &format!("b/{path}"))
+    .to_string();
+
+    Ok(ToolResult::ok(format!("Updated {path}\n{diff}")))
+}
+
+pub(super) fn execute_delete_file(
+    args: &Value,
+    tool_context: &ToolContext,
+    tracker: &mut FileReadTracker,
+) -> Result<ToolResult> {
+    let path = args
+        .get("path")
+        .and_then(Value::as_str)
+        .context("Missing 'path' argument")?;
+    let recursive = args
+        .get("recursive")
+        .and_then(Value::as_bool)
+        .unwrap_or(false);
+
+    let target = tool_context.resolve_entry_path(path)?;
+    let Ok(meta) = fs::symlink_metadata(&target) else {
+        return Ok(ToolResult::err(format!(
+            "File or directory not found: {path}"
+        )));
     };
+    if inside_git_dir(tool_context, &target) {
+        return Ok(ToolResult::err(git_dir_message(path)));
+    }
+    if let Some(reason) = protected_entry(tool_context, &target) {
+        return Ok(ToolResult::err(format!(
+            "Refusing to delete {path}: {reason}"
+        )));
+    }
 
-    fs::write(&resolved, &updated)
-        .with_context(|| format!("Failed writing file {}", resolved.display()))?;
+    if meta.is_symlink() {
+        let link_target = fs::read_link(&target)
+            .map(|link| link.display().to_string())
+            .unwrap_or_else(|_| "unknown".to_string());
+        fs::remove_file(&target)
+            .with_context(|| format!("Failed deleting symlink {}", target.display()))?;
+        tracker.forget(&target);
+        return Ok(ToolResult::ok(format!(
+            "Deleted symlink {path} (its target {link_target} was not touched)"
+        )));
+    }
 
-    let diff = TextDiff::from_lines(&original, &updated)
+    if meta.is_dir() {
+        let summary = summarize_dir(&target)
+            .with_context(|| format!("Failed reading directory {}", target.display()))?;
+        if summary.is_empty() {
+            fs::remove_dir(&target)
+                .with_context(|| format!("Failed deleting directory {}", target.display()))?;
+            return Ok(ToolResult::ok(format!("Deleted empty directory {path}")));
+        }
+        if !recursive {
+            return Ok(ToolResult::err(format!(
+                "{path} is a directory containing {}. Pass recursive: true to delete it and everything inside.",
+                summary.describe()
+            )));
+        }
+        fs::remove_dir_all(&target)
+            .with_context(|| format!("Failed deleting directory {}", target.display()))?;
+        tracker.forget(&target);
+        return Ok(ToolResult::ok(format!(
+            "Deleted directory {path} and everything inside ({})",
+            summary.describe()
+        )));
+    }
+
+    let bytes =
+        fs::read(&target).with_context(|| format!("Failed reading file {}", target.display()))?;
+    if tracker.is_stale(&target, &bytes) {
+        return Ok(ToolResult::err(stale_file_message(path)));
+    }
+    fs::remove_file(&target)
+        .with_context(|| format!("Failed deleting file {}", target.display()))?;
+    tracker.forget(&target);
+
+    let size = format_size(bytes.len() as u64);
+    let Ok(decoded) = decode_text(&bytes) else {
+        return Ok(ToolResult::ok(format!("Deleted {path} ({size})")));
+    };
+    let text = decoded.text.replace("\r\n", "\n");
+    let line_count = text.lines().count();
+    if line_count > MAX_DELETE_DIFF_LINES {
+        return Ok(ToolResult::ok(format!(
+            "Deleted {path} ({size}, {line_count} lines)"
+        )));
+    }
+    let removed = TextDiff::from_lines(text.as_str(), "")
         .unified_diff()
-        .header(&format!("a/{path}"), &format!("b/{path}"))
+        .header(&format!("a/{path}"), "/dev/null")
         .to_string();
+    Ok(ToolResult::ok(format!(
+        "Deleted {path} ({size}, {line_count} lines)\n{removed}"
+    )))
+}
 
-    Ok(ToolResult::ok(format!("Updated {path}\n{diff}")))
+pub(super) fn execute_move_file(
+    args: &Value,
+    tool_context: &ToolContext,
+    tracker: &mut FileReadTracker,
+) -> Result<ToolResult> {
+    let (source, destination) = source_and_destination(args)?;
+    let overwrite = args
+        .get("overwrite")
+        .and_then(Value::as_bool)
+        .unwrap_or(false);
+
+    let from = tool_context.resolve_entry_path(source)?;
+    let to = tool_context.resolve_entry_path(destination)?;
+    for (path, resolved) in [(source, &from), (destination, &to)] {
+        if inside_git_dir(tool_context, resolved) {
+            return Ok(ToolResult::err(git_dir_message(path)));
+        }
+    }
+    let Ok(meta) = fs::symlink_metadata(&from) else {
+        return Ok(ToolResult::err(format!("Source not found: {source}")));
+    };
+    if let Some(reason) = protected_entry(tool_context, &from) {
+        return Ok(ToolResult::err(format!(
+            "Refusing to move {source}: {reason}"
+        )));
+    }
+    if from == to {
+        return Ok(ToolResult::err(format!(
+            "Source and destination are the same path: {source}"
+        )));
+    }
+    if meta.is_dir() && to.starts_with(&from) {
+        return Ok(ToolResult::err(format!(
+            "Cannot move directory {source} into itself ({destination})"
+        )));
+    }
+    let replaced = match existing_destination(&to, destination, overwrite) {
+        Ok(replaced) => replaced,
+        Err(message) => return Ok(ToolResult::err(message)),
+    };
+
+    if let Some(parent) = to.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create parent directory {}", parent.display()))?;
+    }
+    match fs::rename(&from, &to) {
+        Ok(()) => {}
+        Err(err) if err.kind() == io::ErrorKind::CrossesDevices && meta.is_file() => {
+            copy_then_remove(&from, &to)?;
+        }
+        Err(err) if err.kind() == io::ErrorKind::CrossesDevices => {
+            return Ok(ToolResult::err(format!(
+                "Cannot move {source} to {destination}: they are on different filesystems and only files can be moved across. Copy the contents and delete the source instead"
+            )));
+        }
+        Err(err) => {
+            return Err(err)
+                .with_context(|| format!("Failed moving {} to {}", from.display(), to.display()));
+        }
+    }
+    tracker.forget(&from);
+    tracker.forget(&to);
+
+    let kind = if meta.is_symlink() {
+        "symlink"
+    } else if meta.is_dir() {
+        "directory"
+    } else {
+        "file"
+    };
+    let mut message = format!("Moved {kind} {source} -> {destination}");
+    if let Some(previous) = replaced {
+        message.push_str(&format!(" (replaced existing {destination}, {previous})"));
+    }
+    Ok(ToolResult::ok(message))
 }
 
-#[cfg(test)]
-mod tests {
-    use super::{execute_create_file, execute_str_replace_editor, execute_view_file};
-    use crate::tool_context::ToolContext;
-    use serde_json::json;
-    use std::fs;
-    use std::path::{Path, PathBuf};
-    use std::time::{SystemTime, UNIX_EPOCH};
+/// `rename` cannot cross filesystems, so a file is copied over and the source
+/// removed instead.
+fn copy_then_remove(from: &Path, to: &Path) -> Result<()> {
+    fs::copy(from, to)
+        .with_context(|| format!("Failed copying {} to {}", from.display(), to.display()))?;
+    fs::remove_file(from).with_context(|| format!("Failed removing {}", from.display()))
+}
 
-    #[test]
-    fn view_file_returns_limited_preview_with_line_numbers() {
-        let temp = TempDir::new("file-ops-view-preview");
-        let file_path = temp.path().join("notes.txt");
-        let content = (1..=12)
-            .map(|n| format!("line-{n}"))
-            .collect::<Vec<_>>()
-            .join("\n");
-        fs::write(&file_path, content).expect("write fixture");
+pub(super) fn execute_copy_file(
+    args: &Value,
+    tool_context: &ToolContext,
+    tracker: &mut FileReadTracker,
+) -> Result<ToolResult> {
+    let (source, destination) = source_and_destination(args)?;
+    let overwrite = args
+        .get("overwrite")
+        .and_then(Value::as_bool)
+        .unwrap_or(false);
 
-        let context = ToolContext::new(temp.path().to_path_buf()).expect("tool context");
-        let result = execute_view_file(&json!({ "path": "notes.txt" }), &context).expect("view");
+    let from = tool_context.resolve_path(source)?;
+    let to = tool_context.resolve_path(destination)?;
+    if inside_git_dir(tool_context, &to) {
+        return Ok(ToolResult::err(git_dir_message(destination)));
+    }
+    let Ok(meta) = fs::metadata(&from) else {
+        return Ok(ToolResult::err(format!("Source not found: {source}")));
+    };
+    if meta.is_dir() {
+        return Ok(ToolResult::err(format!(
+            "copy_file copies single files; {source} is a directory"
+        )));
+    }
+    if from == to {
+        return Ok(ToolResult::err(format!(
+            "Source and destination are the same path: {source}"
+        )));
+    }
+    let replaced = match existing_destination(&to, destination, overwrite) {
+        Ok(replaced) => replaced,
+        Err(message) => return Ok(ToolResult::err(message)),
+    };
 
-        assert!(result.success);
-        let output = result.output.expect("output");
-        assert!(output.contains("Contents of notes.txt:"));
-        assert!(output.contains("1: line-1"));
-        assert!(output.contains("10: line-10"));
-        assert!(output.contains("... +2 lines"));
+    if let Some(parent) = to.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create parent directory {}", parent.display()))?;
     }
+    let copied = fs::copy(&from, &to)
+        .with_context(|| format!("Failed copying {} to {}", from.display(), to.display()))?;
+    tracker.forget(&to);
 
-    #[test]
-    fn view_file_rejects_invalid_line_ranges() {
-        let temp = TempDir::new("file-ops-view-range");
-        fs::write(temp.path().join("notes.txt"), "a\nb\nc").expect("write fixture");
-        let context = ToolContext::new(temp.path().to_path_buf()).expect("tool context");
+    let mut message = format!("Copied {source} -> {destination} ({})", format_size(copied));
+    if let Some(previous) = replaced {
+        message.push_str(&format!(" (replaced existing {destination}, {previous})"));
+    }
+    Ok(ToolResult::ok(message))
+}
 
-        let result = execute_view_file(
-            &json!({ "path": "notes.txt", "start_line": 0, "end_line": 1 }),
-            &context,
+fn source_and_destination(args: &Value) -> Result<(&str, &str)> {
+    let source = args
+        .get("source")
+        .and_then(Value::as_str)
+        .context("Missing 'source' argument")?;
+    let destination = args
+        .get("destination")
+        .and_then(Value::as_str)
+        .context("Missing 'destination' argument")?;
+    Ok((source, destination))
+}
+
+/// Why `path` must not be deleted or moved away, if anything.
+fn protected_entry(tool_context: &ToolContext, path: &Path) -> Option<&'static str> {
+    if path == tool_context.project_root() {
+        Some("it is the project root")
+    } else if tool_context.current_dir().starts_with(path) {
+        Some("it contains the current working directory")
+    } else {
+        None
+    }
+}
+
+/// Git runs programs named in `.git/config` and `.git/hooks` during commands
+/// that are otherwise auto-approved as read-only, such as `git status`, so the
+/// file tools never write there.
+fn inside_git_dir(tool_context: &ToolContext, path: &Path) -> bool {
+    path.strip_prefix(tool_context.project_root())
+        .is_ok_and(|relative| relative.components().any(|part| part.as_os_str() == ".git"))
+}
+
+/// Secrets reach the model as `[REDACTED:<kind>]`, so new content carrying a
+/// placeholder the file does not already contain would overwrite the real
+/// value on disk.
+fn placeholder_message(path: &str, content: &str, existing: &str) -> Option<String> {
+    let placeholder = placeholders(content)
+        .into_iter()
+        .find(|placeholder| !existing.contains(placeholder))?;
+    Some(format!(
+        "Refusing to write {path}: the content contains {placeholder}, which stands for a secret hidden from you. Edit around the secret instead of rewriting it"
+    ))
+}
+
+fn git_dir_message(path: &str) -> String {
+    format!("Refusing to modify {path}: files inside .git are managed by git")
+}
+
+/// Checks the destination slot. Returns the size of the file that will be
+/// replaced, or a model-facing error when the destination may not be replaced.
+fn existing_destination(
+    to: &Path,
+    destination: &str,
+    overwrite: bool,
+) -> std::result::Result<Option<String>, String> {
+    let Ok(existing) = fs::symlink_metadata(to) else {
+        return Ok(None);
+    };
+    if existing.is_dir() {
+        return Err(format!(
+            "Destination {destination} is an existing directory; give the full destination path including the file name"
+        ));
+    }
+    if !overwrite {
+        return Err(format!(
+            "Destination already exists: {destination}. Pass overwrite: true to replace it."
+        ));
+    }
+    Ok(Some(format_size(existing.len())))
+}
+
+#[derive(Debug, Default)]
+struct DirSummary {
+    files: usize,
+    dirs: usize,
+    bytes: u64,
+}
+
+impl DirSummary {
+    fn is_empty(&self) -> bool {
+        self.files == 0 && self.dirs == 0
+    }
+
+    fn describe(&self) -> String {
+        format!(
+            "{} files, {} subdirectories, {}",
+            self.files,
+            self.dirs,
+            format_size(self.bytes)
         )
-        .expect("view");
-        assert!(!result.success);
-        assert_eq!(result.error.as_deref(), Some("Invalid line range"));
     }
+}
+
+fn summarize_dir(path: &Path) -> std::io::Result<DirSummary> {
+    let mut summary = DirSummary::default();
+    let mut pending = vec![path.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        for entry in fs::read_dir(&dir)? {
+            let entry = entry?;
+            let meta = fs::symlink_metadata(entry.path())?;
+            if meta.is_dir() {
+                summary.dirs += 1;
+                pending.push(entry.path());
+            } else {
+                summary.files += 1;
+                summary.bytes += meta.len();
+            }
+        }
+    }
+    Ok(summary)
+}
+
+fn stale_file_message(path: &str) -> String {
+    format!(
+        "File {path} was modified outside this session since it was last viewed. Re-read it with view_file before editing."
+    )
+}
+
+/// Hashes raw bytes with `Hasher::write` so chunked streaming hashes (see
+/// `file_view`) produce the same value as hashing the whole buffer at once.
+pub(super) fn content_hash(content: &[u8]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    hasher.write(content);
+    hasher.finish()
+}
+
+/// Writes through a sibling temp file and renames it over the target so readers
+/// never observe a partially written file. Existing permissions are preserved.
+fn write_file_atomic(path: &Path, content: &[u8]) -> Result<()> {
+    let parent = path
+        .parent()
+        .with_context(|| format!("Path has no parent directory: {}", path.display()))?;
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .with_context(|| format!("Path has no file name: {}", path.display()))?;
+    let nonce = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    let temp_path = parent.join(format!(
+        ".{file_name}.codetoad-{}-{nonce}.tmp",
+        std::process::id()
+    ));
+    let permissions = fs::metadata(path).ok().map(|meta| meta.permissions());
+
+    let written = (|| -> Result<()> {
+        let mut file = fs::File::create(&temp_path)
+            .with_context(|| format!("Failed creating temp file {}", temp_path.display()))?;
+        file.write_all(content)
+            .with_context(|| format!("Failed writing temp file {}", temp_path.display()))?;
+        file.sync_all()
+            .with_context(|| format!("Failed syncing temp file {}", temp_path.display()))?;
+        if let Some(permissions) = permissions {
+            fs::set_permissions(&temp_path, permissions).with_context(|| {
+                format!("Failed copying permissions to {}", temp_path.display())
+            })?;
+        }
+        fs::rename(&temp_path, path)
+            .with_context(|| format!("Failed writing file {}", path.display()))
+    })();
+    if written.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+    written
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        FileReadTracker, copy_then_remove, execute_copy_file, execute_create_file,
+        execute_delete_file, execute_move_file, execute_str_replace_editor,
+    };
+    use crate::tool_context::ToolContext;
+    use crate::tools::file_view::execute_view_file;
+    use serde_json::json;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+    use std::time::{SystemTime, UNIX_EPOCH};
 
     #[test]
     fn create_file_writes_content_and_returns_diff() {
@@ -219,6 +656,7 @@ mod tests {
                 "content": "hello\nworld\n"
             }),
             &context,
+            &mut FileReadTracker::default(),
         )
         .expect("create");
         assert!(result.success);
@@ -230,12 +668,50 @@ mod tests {
         assert_eq!(created, "hello\nworld\n");
     }
 
+    #[test]
+    fn create_file_refuses_existing_files_unless_overwrite() {
+        let temp = TempDir::new("file-ops-create-overwrite");
+        fs::write(temp.path().join("notes.txt"), "keep\nold line\n").expect("write fixture");
+        let context = ToolContext::new(temp.path().to_path_buf()).expect("tool context");
+        let mut tracker = FileReadTracker::default();
+
+        let refused = execute_create_file(
+            &json!({ "path": "notes.txt", "content": "new\n" }),
+            &context,
+            &mut tracker,
+        )
+        .expect("create");
+        assert!(!refused.success);
+        assert!(
+            refused
+                .error
+                .as_deref()
+                .is_some_and(|msg| msg.starts_with("File already exists: notes.txt"))
+        );
+        assert_eq!(
+            fs::read_to_string(temp.path().join("notes.txt")).expect("read"),
+            "keep\nold line\n"
+        );
+
+        let replaced = execute_create_file(
+            &json!({ "path": "notes.txt", "content": "keep\nnew line\n", "overwrite": true }),
+            &context,
+            &mut tracker,
+        )
+        .expect("overwrite");
+        let output = replaced.output.expect("output");
+        assert!(output.starts_with("Overwrote notes.txt\n--- a/notes.txt\n+++ b/notes.txt"));
+        assert!(output.contains("-old line\n+new line"));
+        assert!(!output.contains("+keep"));
+    }
+
     #[test]
     fn str_replace_editor_replaces_first_or_all_matches() {
         let temp = TempDir::new("file-ops-replace");
         let file_path = temp.path().join("doc.txt");
         fs::write(&file_path, "foo foo foo").expect("write fixture");
         let context = ToolContext::new(temp.path().to_path_buf()).expect("tool context");
+        let mut tracker = FileReadTracker::default();
 
         let first = execute_str_replace_editor(
             &json!({
@@ -244,6 +720,7 @@ mod tests {
                 "new_str": "bar"
             }),
             &context,
+            &mut tracker,
         )
         .expect("replace first");
         assert!(first.success);
@@ -258,6 +735,7 @@ mod tests {
                 "replace_all": true
             }),
             &context,
+            &mut tracker,
         )
         .expect("replace all");
         assert!(all.success);
@@ -278,6 +756,7 @@ mod tests {
                 "new_str": "x"
             }),
             &context,
+            &mut FileReadTracker::default(),
         )
         .expect("replace");
 
@@ -290,6 +769,397 @@ mod tests {
         );
     }
 
+    #[test]
+    fn str_replace_editor_refuses_edits_after_external_change() {
+        let temp = TempDir::new("file-ops-stale");
+        let file_path = temp.path().join("doc.txt");
+        fs::write(&file_path, "alpha\n").expect("write fixture");
+        let context = ToolContext::new(temp.path().to_path_buf()).expect("tool context");
+        let mut tracker = FileReadTracker::default();
+
+        execute_view_file(&json!({ "path": "doc.txt" }), &context, &mut tracker).expect("view");
+        fs::write(&file_path, "alpha beta\n").expect("external edit");
+
+        let args = json!({ "path": "doc.txt", "old_str": "alpha", "new_str": "gamma" });
+        let stale = execute_str_replace_editor(&args, &context, &mut tracker).expect("replace");
+        assert!(!stale.success);
+        assert!(
+            stale
+                .error
+                .as_deref()
+                .is_some_and(|msg| msg.contains("Re-read it with view_file"))
+        );
+        assert_eq!(
+            fs::read_to_string(&file_path).expect("read"),
+            "alpha beta\n"
+        );
+
+        execute_view_file(&json!({ "path": "doc.txt" }), &context, &mut tracker).expect("view");
+        let fresh = execute_str_replace_editor(&args, &context, &mut tracker).expect("replace");
+        assert!(fresh.success);
+        let again = json!({ "path": "doc.txt", "old_str": "beta", "new_str": "delta" });
+        let follow_up = execute_str_replace_editor(&again, &context, &mut tracker).expect("edit");
+        assert!(follow_up.success, "own writes must not count as external");
+        assert_eq!(
+            fs::read_to_string(&file_path).expect("read"),
+            "gamma delta\n"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn atomic_writes_preserve_file_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp = TempDir::new("file-ops-permissions");
+        let file_path = temp.path().join("run.sh");
+        fs::write(&file_path, "echo one\n").expect("write fixture");
+        fs::set_permissions(&file_path, fs::Permissions::from_mode(0o755)).expect("chmod");
+        let context = ToolContext::new(temp.path().to_path_buf()).expect("tool context");
+
+        let result = execute_str_replace_editor(
+            &json!({ "path": "run.sh", "old_str": "one", "new_str": "two" }),
+            &context,
+            &mut FileReadTracker::default(),
+        )
+        .expect("replace");
+        assert!(result.success);
+
+        let mode = fs::metadata(&file_path)
+            .expect("metadata")
+            .permissions()
+            .mode();
+        assert_eq!(mode & 0o777, 0o755);
+        let leftovers = fs::read_dir(temp.path()).expect("read dir").count();
+        assert_eq!(leftovers, 1, "temp files must not be left behind");
+    }
+
+    #[test]
+    fn str_replace_editor_preserves_crlf_and_bom() {
+        let temp = TempDir::new("file-ops-crlf");
+        let file_path = temp.path().join("win.txt");
+        fs::write(&file_path, b"\xEF\xBB\xBFfirst\r\nsecond\r\nthird\r\n").expect("write fixture");
+        let context = ToolContext::new(temp.path().to_path_buf()).expect("tool context");
+
+        let result = execute_str_replace_editor(
+            &json!({
+                "path": "win.txt",
+                "old_str": "first\nsecond",
+                "new_str": "first\nsecond and a half\nmore"
+            }),
+            &context,
+            &mut FileReadTracker::default(),
+        )
+        .expect("replace");
+        assert!(result.success, "{:?}", result.error);
+        assert!(!result.output.expect("output").contains('\r'));
+
+        let bytes = fs::read(&file_path).expect("read");
+        assert_eq!(
+            bytes,
+            b"\xEF\xBB\xBFfirst\r\nsecond and a half\r\nmore\r\nthird\r\n".to_vec()
+        );
+    }
+
+    #[test]
+    fn view_file_reports_format_and_refuses_legacy_encodings() {
+        let temp = TempDir::new("file-ops-encodings");
+        fs::write(temp.path().join("win.txt"), b"\xEF\xBB\xBFa\r\nb\r\n").expect("write");
+        fs::write(temp.path().join("latin1.txt"), b"caf\xE9\n").expect("write");
+        let context = ToolContext::new(temp.path().to_path_buf()).expect("tool context");
+        let mut tracker = FileReadTracker::default();
+
+        let view =
+            execute_view_file(&json!({ "path": "win.txt" }), &context, &mut tracker).expect("view");
+        let output = view.output.expect("output");
+        assert!(output.starts_with("Contents of win.txt (CRLF, UTF-8 BOM):"));
+        assert!(output.contains("1: a\n2: b"));
+
+        let legacy = execute_str_replace_editor(
+            &json!({ "path": "latin1.txt", "old_str": "caf", "new_str": "tea" }),
+            &context,
+            &mut tracker,
+        )
+        .expect("replace");
+        assert!(!legacy.success);
+        assert!(
+            legacy
+                .error
+                .as_deref()
+                .is_some_and(|msg| msg.contains("not valid UTF-8"))
+        );
+    }
+
+    #[test]
+    fn delete_file_reports_removed_content_and_guards_directories() {
+        let temp = TempDir::new("file-ops-delete");
+        fs::create_dir_all(temp.path().join("build/out")).expect("create dirs");
+        fs::write(temp.path().join("build/out/a.o"), "obj").expect("write");
+        fs::write(temp.path().join("old.txt"), "first\nsecond\n").expect("write");
+        let context = ToolContext::new(temp.path().to_path_buf()).expect("tool context");
+        let mut tracker = FileReadTracker::default();
+
+        let deleted = execute_delete_file(&json!({ "path": "old.txt" }), &context, &mut tracker)
+            .expect("delete")
+            .output
+            .expect("output");
+        assert!(deleted.starts_with("Deleted old.txt (13 B, 2 lines)"));
+        assert!(deleted.contains("--- a/old.txt\n+++ /dev/null"));
+        assert!(deleted.contains("-second"));
+        assert!(!temp.path().join("old.txt").exists());
+
+        let refused = execute_delete_file(&json!({ "path": "build" }), &context, &mut tracker)
+            .expect("delete dir");
+        assert!(!refused.success);
+        assert!(refused.error.as_deref().is_some_and(
+            |msg| msg.contains("1 files, 1 subdirectories") && msg.contains("recursive: true")
+        ));
+
+        let removed = execute_delete_file(
+            &json!({ "path": "build", "recursive": true }),
+            &context,
+            &mut tracker,
+        )
+        .expect("delete dir");
+        assert_eq!(
+            removed.output.as_deref(),
+            Some("Deleted directory build and everything inside (1 files, 1 subdirectories, 3 B)")
+        );
+
+        let root = execute_delete_file(
+            &json!({ "path": ".", "recursive": true }),
+            &context,
+            &mut tracker,
+        )
+        .expect("delete root");
+        assert!(!root.success);
+        assert!(temp.path().exists());
+    }
+
+    #[test]
+    fn move_and_copy_refuse_to_clobber_without_overwrite() {
+        let temp = TempDir::new("file-ops-move-copy");
+        fs::write(temp.path().join("a.txt"), "alpha").expect("write");
+        fs::write(temp.path().join("b.txt"), "beta").expect("write");
+        let context = ToolContext::new(temp.path().to_path_buf()).expect("tool context");
+        let mut tracker = FileReadTracker::default();
+
+        let copied = execute_copy_file(
+            &json!({ "source": "a.txt", "destination": "nested/copy.txt" }),
+            &context,
+            &mut tracker,
+        )
+        .expect("copy");
+        assert_eq!(
+            copied.output.as_deref(),
+            Some("Copied a.txt -> nested/copy.txt (5 B)")
+        );
+        assert_eq!(
+            fs::read_to_string(temp.path().join("nested/copy.txt")).expect("read"),
+            "alpha"
+        );
+
+        let blocked = execute_move_file(
+            &json!({ "source": "a.txt", "destination": "b.txt" }),
+            &context,
+            &mut tracker,
+        )
+        .expect("move");
+        assert!(!blocked.success);
+        assert!(
+            blocked
+                .error
+                .as_deref()
+                .is_some_and(|msg| msg.contains("overwrite: true"))
+        );
+
+        let moved = execute_move_file(
+            &json!({ "source": "a.txt", "destination": "b.txt", "overwrite": true }),
+            &context,
+            &mut tracker,
+        )
+        .expect("move");
+        assert_eq!(
+            moved.output.as_deref(),
+            Some("Moved file a.txt -> b.txt (replaced existing b.txt, 4 B)")
+        );
+        assert!(!temp.path().join("a.txt").exists());
+        assert_eq!(
+            fs::read_to_string(temp.path().join("b.txt")).expect("read"),
+            "alpha"
+        );
+    }
+
+    #[test]
+    fn copy_then_remove_moves_file_contents() {
+        let temp = TempDir::new("file-ops-copy-then-remove");
+        let from = temp.path().join("a.txt");
+        let to = temp.path().join("b.txt");
+        fs::write(&from, "hello\n").expect("write");
+
+        copy_then_remove(&from, &to).expect("move");
+        assert!(!from.exists());
+        assert_eq!(fs::read_to_string(&to).expect("read"), "hello\n");
+    }
+
+    #[test]
+    fn move_and_copy_check_both_paths_against_project_root() {
+        let temp = TempDir::new("file-ops-move-escape");
+        fs::write(temp.path().join("a.txt"), "alpha").expect("write");
+        let context = ToolContext::new(temp.path().to_path_buf()).expect("tool context");
+        let mut tracker = FileReadTracker::default();
+
+        let err = execute_move_file(
+            &json!({ "source": "a.txt", "destination": "../escaped.txt" }),
+            &context,
+            &mut tracker,
+        )
+        .expect_err("destination outside root");
+        assert!(err.to_string().contains("Path escapes project root"));
+
+        let err = execute_copy_file(
+            &json!({ "source": "../../etc/hostname", "destination": "copy.txt" }),
+            &context,
+            &mut tracker,
+        )
+        .expect_err("source outside root");
+        assert!(err.to_string().contains("Path escapes project root"));
+        assert!(temp.path().join("a.txt").exists());
+    }
+
+    #[test]
+    fn edits_may_not_write_redaction_placeholders() {
+        let temp = TempDir::new("file-ops-placeholders");
+        fs::write(
+            temp.path().join(".env.example"),
+            "GROK_API_KEY=xai-abcdefghijklmnopqrstuvwx\nDEBUG=1\n",
+        )
+        .expect("write");
+        fs::write(
+            temp.path().join("notes.md"),
+            "Shown as [REDACTED:api_key].\n",
+        )
+        .expect("write");
+        let context = ToolContext::new(temp.path().to_path_buf()).expect("tool context");
+        let mut tracker = FileReadTracker::default();
+
+        let result = execute_str_replace_editor(
+            &json!({
+                "path": ".env.example",
+                "old_str": "DEBUG=1",
+                "new_str": "GROK_API_KEY=[REDACTED:api_key]\nDEBUG=0"
+            }),
+            &context,
+            &mut tracker,
+        )
+        .expect("edit");
+        assert!(
+            result
+                .error
+                .as_deref()
+                .is_some_and(|error| error.contains("contains [REDACTED:api_key]"))
+        );
+        let result = execute_create_file(
+            &json!({ "path": "copy.env", "content": "KEY=[REDACTED:secret]\n" }),
+            &context,
+            &mut tracker,
+        )
+        .expect("create");
+        assert!(!result.success);
+        assert!(!temp.path().join("copy.env").exists());
+        assert!(
+            fs::read_to_string(temp.path().join(".env.example"))
+                .expect("read")
+                .contains("xai-abcdefghijklmnopqrstuvwx")
+        );
+
+        let result = execute_str_replace_editor(
+            &json!({
+                "path": "notes.md",
+                "old_str": "Shown as",
+                "new_str": "Rendered as [REDACTED:api_key] and shown as"
+            }),
+            &context,
+            &mut tracker,
+        )
+        .expect("edit");
+        assert!(result.success, "{result:?}");
+    }
+
+    #[test]
+    fn file_tools_refuse_to_write_inside_git_dir() {
+        let temp = TempDir::new("file-ops-git-dir");
+        fs::create_dir_all(temp.path().join(".git/hooks")).expect("mkdir");
+        fs::write(temp.path().join(".git/config"), "[core]\n").expect("write");
+        fs::write(temp.path().join("a.txt"), "alpha").expect("write");
+        let context = ToolContext::new(temp.path().to_path_buf()).expect("tool context");
+        let mut tracker = FileReadTracker::default();
+
+        let results = [
+            execute_create_file(
+                &json!({ "path": ".git/hooks/pre-commit", "content": "#!/bin/sh\n" }),
+                &context,
+                &mut tracker,
+            ),
+            execute_str_replace_editor(
+                &json!({
+                    "path": ".git/config",
+                    "old_str": "[core]",
+                    "new_str": "[core]\n\tfsmonitor = ./x"
+                }),
+                &context,
+                &mut tracker,
+            ),
+            execute_delete_file(&json!({ "path": ".git/config" }), &context, &mut tracker),
+            execute_move_file(
+                &json!({ "source": "a.txt", "destination": "sub/.git/config" }),
+                &context,
+                &mut tracker,
+            ),
+            execute_copy_file(
+                &json!({ "source": "a.txt", "destination": ".git/config", "overwrite": true }),
+                &context,
+                &mut tracker,
+            ),
+        ];
+        for result in results {
+            let result = result.expect("tool result");
+            assert!(!result.success);
+            assert!(
+                result
+                    .error
+                    .as_deref()
+                    .is_some_and(|error| error.contains("inside .git"))
+            );
+        }
+        assert_eq!(
+            fs::read_to_string(temp.path().join(".git/config")).expect("read"),
+            "[core]\n"
+        );
+        assert!(!temp.path().join(".git/hooks/pre-commit").exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn delete_file_removes_symlink_not_target() {
+        let temp = TempDir::new("file-ops-delete-link");
+        fs::write(temp.path().join("real.txt"), "keep").expect("write");
+        std::os::unix::fs::symlink("real.txt", temp.path().join("link.txt")).expect("symlink");
+        let context = ToolContext::new(temp.path().to_path_buf()).expect("tool context");
+
+        let result = execute_delete_file(
+            &json!({ "path": "link.txt" }),
+            &context,
+            &mut FileReadTracker::default(),
+        )
+        .expect("delete");
+        assert_eq!(
+            result.output.as_deref(),
+            Some("Deleted symlink link.txt (its target real.txt was not touched)")
+        );
+        assert!(temp.path().join("real.txt").exists());
+    }
+
     struct TempDir {
         path: PathBuf,
     }
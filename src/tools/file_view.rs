@@ -0,0 +1,820 @@
+use super::ToolResult;
+use super::file_ops::FileReadTracker;
+use super::text_file::decode_text;
+use crate::ignore_policy::IgnorePolicy;
+use crate::tool_context::ToolContext;
+use anyhow::{Context, Result};
+use ignore::WalkBuilder;
+use serde_json::Value;
+use std::collections::hash_map::DefaultHasher;
+use std::fs;
+use std::hash::Hasher;
+use std::io::{BufRead, BufReader, Read};
+use std::path::Path;
+
+const DEFAULT_PAGE_LINES: usize = 200;
+const MAX_PAGE_BYTES: usize = 20_000;
+const MAX_LINE_CHARS: usize = 2_000;
+/// Enough bytes to hold `MAX_LINE_CHARS` characters of any UTF-8 text.
+const MAX_LINE_BYTES: usize = MAX_LINE_CHARS * 4;
+const SNIFF_BYTES: u64 = 8 * 1024;
+/// Files above this size are streamed line by line instead of decoded in memory.
+const LARGE_FILE_BYTES: u64 = 8 * 1024 * 1024;
+/// Streaming stops counting lines past this point once the requested page is read.
+const MAX_SCAN_BYTES: u64 = 256 * 1024 * 1024;
+const DEFAULT_TREE_DEPTH: usize = 3;
+const MAX_TREE_DEPTH: usize = 10;
+const MAX_TREE_ENTRIES: usize = 500;
+
+pub(super) fn execute_view_file(
+    args: &Value,
+    tool_context: &ToolContext,
+    tracker: &mut FileReadTracker,
+) -> Result<ToolResult> {
+    let path = args
+        .get("path")
+        .and_then(Value::as_str)
+        .context("Missing 'path' argument")?;
+
+    let resolved = tool_context.resolve_path(path)?;
+    if !resolved.exists() {
+        return Ok(ToolResult::err(format!(
+            "File or directory not found: {path}"
+        )));
+    }
+
+    if resolved.is_dir() {
+        let recursive = args
+            .get("recursive")
+            .and_then(Value::as_bool)
+            .unwrap_or(false);
+        if !recursive {
+            return list_directory(path, &resolved, tool_context.ignore_policy());
+        }
+        let max_depth = args
+            .get("max_depth")
+            .and_then(Value::as_u64)
+            .map(|value| value as usize)
+            .unwrap_or(DEFAULT_TREE_DEPTH)
+            .clamp(1, MAX_TREE_DEPTH);
+        return Ok(ToolResult::ok(render_tree(
+            path,
+            &resolved,
+            max_depth,
+            tool_context.ignore_policy(),
+        )));
+    }
+
+    let range = match LineRange::from_args(args) {
+        Ok(range) => range,
+        Err(message) => return Ok(ToolResult::err(message)),
+    };
+
+    let size = fs::metadata(&resolved)
+        .with_context(|| format!("Failed reading metadata for {}", resolved.display()))?
+        .len();
+    if let Some(kind) = sniff_binary(&resolved)? {
+        return Ok(ToolResult::ok(format!(
+            "{path} is a binary file ({kind}, {}). Its contents are not shown.",
+            format_size(size)
+        )));
+    }
+    if size > LARGE_FILE_BYTES {
+        return view_large_file(path, &resolved, size, &range, tracker);
+    }
+
+    let bytes = fs::read(&resolved)
+        .with_context(|| format!("Failed reading file {}", resolved.display()))?;
+    tracker.record(&resolved, &bytes);
+    let decoded = match decode_text(&bytes) {
+        Ok(decoded) => decoded,
+        Err(reason) => return Ok(ToolResult::err(format!("Cannot read {path}: {reason}"))),
+    };
+    let format_note = decoded
+        .format
+        .describe()
+        .map(|note| format!(" ({note})"))
+        .unwrap_or_default();
+    let lines: Vec<&str> = decoded.text.lines().collect();
+    if range.start > lines.len().max(1) {
+        return Ok(ToolResult::err(format!(
+            "start_line {} is past the end of {path} ({} lines)",
+            range.start,
+            lines.len()
+        )));
+    }
+    let page = lines[range.start - 1..range.last_line().min(lines.len())]
+        .iter()
+        .map(|line| truncate_line(line))
+        .collect::<Vec<_>>();
+
+    Ok(ToolResult::ok(render_page(
+        path,
+        &format_note,
+        &range,
+        &page,
+        Some(lines.len()),
+    )))
+}
+
+/// Requested window of 1-based line numbers. Without an explicit end the window
+/// is one default-sized page starting at `start`.
+struct LineRange {
+    start: usize,
+    end: Option<usize>,
+    explicit: bool,
+}
+
+impl LineRange {
+    fn from_args(args: &Value) -> Result<Self, String> {
+        let start = args
+            .get("start_line")
+            .and_then(Value::as_u64)
+            .map(|value| value as usize);
+        let end = args
+            .get("end_line")
+            .and_then(Value::as_u64)
+            .map(|value| value as usize);
+
+        if start == Some(0) || end == Some(0) {
+            return Err("Invalid line range".to_string());
+        }
+        if let (Some(start), Some(end)) = (start, end)
+            && end < start
+        {
+            return Err("Invalid line range".to_string());
+        }
+        Ok(Self {
+            start: start.unwrap_or(1),
+            end,
+            explicit: start.is_some() || end.is_some(),
+        })
+    }
+
+    fn last_line(&self) -> usize {
+        self.end
+            .unwrap_or(self.start.saturating_add(DEFAULT_PAGE_LINES - 1))
+    }
+}
+
+/// Numbers `page` (already truncated lines beginning at `range.start`) until the
+/// byte budget is spent, then explains how to fetch whatever was left out.
+fn render_page<S: AsRef<str>>(
+    path: &str,
+    format_note: &str,
+    range: &LineRange,
+    page: &[S],
+    total: Option<usize>,
+) -> String {
+    let mut body = String::new();
+    let mut shown = 0usize;
+    for (offset, line) in page.iter().enumerate() {
+        let rendered = format!("{}: {}", range.start + offset, line.as_ref());
+        if shown > 0 && body.len() + rendered.len() + 1 > MAX_PAGE_BYTES {
+            break;
+        }
+        if shown > 0 {
+            body.push('\n');
+        }
+        body.push_str(&rendered);
+        shown += 1;
+    }
+
+    let last = (range.start + shown).saturating_sub(1).max(range.start);
+    let reached_requested_end = range.end.is_some_and(|end| last >= end);
+    let whole_file = !range.explicit && total.is_some_and(|total| last >= total);
+    let header = if whole_file || shown == 0 {
+        format!("Contents of {path}{format_note}:")
+    } else {
+        format!("Lines {}-{last} of {path}{format_note}:", range.start)
+    };
+
+    let hint = match total {
+        Some(total) if last < total && !reached_requested_end => format!(
+            "\n... +{} lines (showing {}-{last} of {total}). Continue with start_line={}.",
+            total - last,
+            range.start,
+            last + 1
+        ),
+        None if !reached_requested_end => format!(
+            "\n... more lines follow (showing {}-{last}). Continue with start_line={}.",
+            range.start,
+            last + 1
+        ),
+        _ => String::new(),
+    };
+
+    format!("{header}\n{body}{hint}")
+}
+
+fn truncate_line(line: &str) -> String {
+    match line.char_indices().nth(MAX_LINE_CHARS) {
+        Some((cut, _)) => format!(
+            "{} ... [line truncated, {} chars total]",
+            &line[..cut],
+            line.chars().count()
+        ),
+        None => line.to_string(),
+    }
+}
+
+/// Pages through a file too large to decode in memory. Lines are decoded lossily
+/// and the file is hashed while streaming so later edits can still be checked.
+fn view_large_file(
+    path: &str,
+    resolved: &Path,
+    size: u64,
+    range: &LineRange,
+    tracker: &mut FileReadTracker,
+) -> Result<ToolResult> {
+    let file = fs::File::open(resolved)
+        .with_context(|| format!("Failed reading file {}", resolved.display()))?;
+    let mut reader = BufReader::new(HashingReader::new(file));
+    let head = reader.fill_buf()?;
+    if head.starts_with(&[0xFF, 0xFE]) || head.starts_with(&[0xFE, 0xFF]) {
+        return Ok(ToolResult::err(format!(
+            "Cannot read {path}: UTF-16 files larger than {} are not supported",
+            format_size(LARGE_FILE_BYTES)
+        )));
+    }
+
+    let last_wanted = range.last_line();
+    let mut page = Vec::new();
+    let mut page_bytes = 0usize;
+    let mut total = 0usize;
+    let mut complete = true;
+    let mut buffer = Vec::new();
+    while let Some(length) = read_capped_line(&mut reader, &mut buffer, MAX_LINE_BYTES)? {
+        total += 1;
+        if total >= range.start && total <= last_wanted && page_bytes <= MAX_PAGE_BYTES {
+            let mut line = String::from_utf8_lossy(&buffer).into_owned();
+            if total == 1 && line.starts_with('\u{feff}') {
+                line.remove(0);
+            }
+            let trimmed_len = line.trim_end_matches(['\n', '\r']).len();
+            line.truncate(trimmed_len);
+            let line = if length > buffer.len() {
+                let cut = line
+                    .char_indices()
+                    .nth(MAX_LINE_CHARS)
+                    .map_or(line.len(), |(cut, _)| cut);
+                format!(
+                    "{} ... [line truncated, {} total]",
+                    &line[..cut],
+                    format_size(length as u64)
+                )
+            } else {
+                truncate_line(&line)
+            };
+            page_bytes += line.len();
+            page.push(line);
+        }
+        if total > last_wanted && reader.get_ref().bytes_read > MAX_SCAN_BYTES {
+            complete = false;
+            break;
+        }
+    }
+
+    if range.start > total.max(1) {
+        return Ok(ToolResult::err(format!(
+            "start_line {} is past the end of {path} ({total} lines)",
+            range.start
+        )));
+    }
+    if complete {
+        tracker.record_hash(resolved, reader.into_inner().finish());
+    }
+
+    let format_note = format!(" (large file, {})", format_size(size));
+    Ok(ToolResult::ok(render_page(
+        path,
+        &format_note,
+        range,
+        &page,
+        complete.then_some(total),
+    )))
+}
+
+/// Reads one line, keeping at most `cap` bytes of it in `buffer` so a file
+/// without newlines is never held in memory. Returns the line's full length
+/// in bytes, or `None` at the end of the input.
+fn read_capped_line<R: BufRead>(
+    reader: &mut R,
+    buffer: &mut Vec<u8>,
+    cap: usize,
+) -> std::io::Result<Option<usize>> {
+    buffer.clear();
+    let mut length = 0usize;
+    loop {
+        let available = reader.fill_buf()?;
+        if available.is_empty() {
+            return Ok((length > 0).then_some(length));
+        }
+        let (chunk, ends_line) = match available.iter().position(|byte| *byte == b'\n') {
+            Some(index) => (&available[..=index], true),
+            None => (available, false),
+        };
+        let keep = chunk.len().min(cap.saturating_sub(buffer.len()));
+        buffer.extend_from_slice(&chunk[..keep]);
+        let used = chunk.len();
+        length += used;
+        reader.consume(used);
+        if ends_line {
+            return Ok(Some(length));
+        }
+    }
+}
+
+struct HashingReader<R> {
+    inner: R,
+    hasher: DefaultHasher,
+    bytes_read: u64,
+}
+
+impl<R> HashingReader<R> {
+    fn new(inner: R) -> Self {
+        Self {
+            inner,
+            hasher: DefaultHasher::new(),
+            bytes_read: 0,
+        }
+    }
+
+    fn finish(self) -> u64 {
+        self.hasher.finish()
+    }
+}
+
+impl<R: Read> Read for HashingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.hasher.write(&buf[..read]);
+        self.bytes_read += read as u64;
+        Ok(read)
+    }
+}
+
+fn sniff_binary(path: &Path) -> Result<Option<&'static str>> {
+    let mut head = Vec::new();
+    fs::File::open(path)
+        .with_context(|| format!("Failed reading file {}", path.display()))?
+        .take(SNIFF_BYTES)
+        .read_to_end(&mut head)
+        .with_context(|| format!("Failed reading file {}", path.display()))?;
+    Ok(binary_kind(&head))
+}
+
+/// Classifies a file from its first bytes: well-known magic numbers first, then
+/// any NUL byte. BOM-marked UTF-16 text is never reported as binary.
+fn binary_kind(head: &[u8]) -> Option<&'static str> {
+    const SIGNATURES: &[(&[u8], &str)] = &[
+        (b"\x89PNG\r\n\x1a\n", "PNG image"),
+        (b"\xFF\xD8\xFF", "JPEG image"),
+        (b"GIF87a", "GIF image"),
+        (b"GIF89a", "GIF image"),
+        (b"%PDF-", "PDF document"),
+        (b"PK\x03\x04", "ZIP archive"),
+        (b"\x1F\x8B", "gzip archive"),
+        (b"\xFD7zXZ\x00", "xz archive"),
+        (b"7z\xBC\xAF\x27\x1C", "7z archive"),
+        (b"\x7FELF", "ELF executable"),
+        (b"\xCF\xFA\xED\xFE", "Mach-O binary"),
+        (b"\xCE\xFA\xED\xFE", "Mach-O binary"),
+        (b"\x00asm", "WebAssembly module"),
+        (b"SQLite format 3\x00", "SQLite database"),
+    ];
+
+    if head.starts_with(&[0xFF, 0xFE]) || head.starts_with(&[0xFE, 0xFF]) {
+        return None;
+    }
+    if let Some((_, kind)) = SIGNATURES.iter().find(|(magic, _)| head.starts_with(magic)) {
+        return Some(kind);
+    }
+    if head.len() >= 12 && head.starts_with(b"RIFF") && &head[8..12] == b"WEBP" {
+        return Some("WebP image");
+    }
+    if !head.contains(&0) {
+        return None;
+    }
+    if head.starts_with(b"MZ") {
+        Some("Windows executable")
+    } else {
+        Some("binary data")
+    }
+}
+
+fn list_directory(path: &str, resolved: &Path, ignore: &IgnorePolicy) -> Result<ToolResult> {
+    let mut entries = Vec::new();
+    for entry in fs::read_dir(resolved)? {
+        let entry = entry?;
+        let is_dir = entry.file_type().is_ok_and(|kind| kind.is_dir());
+        if ignore.is_ignored(&entry.path(), is_dir) {
+            continue;
+        }
+        let name = entry.file_name().to_string_lossy().to_string();
+        let rendered = match entry.metadata() {
+            Ok(meta) if meta.is_dir() => format!("{name}/"),
+            Ok(meta) => format!("{name} ({})", format_size(meta.len())),
+            Err(_) => name.clone(),
+        };
+        entries.push((name, rendered));
+    }
+    entries.sort();
+    let listing = entries
+        .into_iter()
+        .map(|(_, rendered)| rendered)
+        .collect::<Vec<_>>()
+        .join("\n");
+    Ok(ToolResult::ok(format!(
+        "Directory contents of {path}:\n{listing}"
+    )))
+}
+
+/// Indented tree of `root`, honoring `.gitignore`/`.ignore` files the same way
+/// git would, skipping paths hidden by the ignore policy and never descending
+/// into `.git` itself.
+fn render_tree(path: &str, root: &Path, max_depth: usize, ignore: &IgnorePolicy) -> String {
+    let ignore = ignore.clone();
+    let walker = WalkBuilder::new(root)
+        .max_depth(Some(max_depth))
+        .hidden(false)
+        .require_git(false)
+        .filter_entry(move |entry| {
+            let is_dir = entry.file_type().is_some_and(|kind| kind.is_dir());
+            entry.file_name() != ".git" && !ignore.is_ignored(entry.path(), is_dir)
+        })
+        .sort_by_file_name(|a, b| a.cmp(b))
+        .build();
+
+    let mut lines = Vec::new();
+    let mut truncated = false;
+    for entry in walker.flatten() {
+        if entry.depth() == 0 {
+            continue;
+        }
+        if lines.len() >= MAX_TREE_ENTRIES {
+            truncated = true;
+            break;
+        }
+        let indent = "  ".repeat(entry.depth() - 1);
+        let name = entry.file_name().to_string_lossy();
+        if entry.file_type().is_some_and(|kind| kind.is_dir()) {
+            lines.push(format!("{indent}{name}/"));
+        } else {
+            let size = entry.metadata().map(|meta| meta.len()).unwrap_or(0);
+            lines.push(format!("{indent}{name} ({})", format_size(size)));
+        }
+    }
+
+    let mut output = format!(
+        "Directory tree of {path} (depth {max_depth}, .gitignore respected):\n{}",
+        lines.join("\n")
+    );
+    if truncated {
+        output.push_str(&format!(
+            "\n... stopped after {MAX_TREE_ENTRIES} entries. View a subdirectory or lower max_depth to see more."
+        ));
+    }
+    output
+}
+
+pub(super) fn format_size(bytes: u64) -> String {
+    if bytes >= 1024 * 1024 {
+        format!("{:.1} MB", bytes as f64 / (1024.0 * 1024.0))
+    } else if bytes >= 1024 {
+        format!("{:.1} KB", bytes as f64 / 1024.0)
+    } else {
+        format!("{bytes} B")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{HashingReader, binary_kind, execute_view_file, read_capped_line};
+    use crate::tool_context::ToolContext;
+    use crate::tools::file_ops::{FileReadTracker, content_hash};
+    use serde_json::json;
+    use std::fs;
+    use std::io::{BufReader, Read};
+    use std::path::{Path, PathBuf};
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    fn numbered_lines(count: usize) -> String {
+        (1..=count)
+            .map(|n| format!("line-{n}"))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn view_file_shows_small_files_in_full() {
+        let temp = TempDir::new("file-view-small");
+        fs::write(temp.path().join("notes.txt"), numbered_lines(12)).expect("write fixture");
+        let context = ToolContext::new(temp.path().to_path_buf()).expect("tool context");
+
+        let result = execute_view_file(
+            &json!({ "path": "notes.txt" }),
+            &context,
+            &mut FileReadTracker::default(),
+        )
+        .expect("view");
+
+        assert!(result.success);
+        let output = result.output.expect("output");
+        assert!(output.starts_with("Contents of notes.txt:\n1: line-1\n"));
+        assert!(output.ends_with("12: line-12"));
+    }
+
+    #[test]
+    fn view_file_pages_long_files_with_continuation_hint() {
+        let temp = TempDir::new("file-view-paging");
+        fs::write(temp.path().join("notes.txt"), numbered_lines(450)).expect("write fixture");
+        let context = ToolContext::new(temp.path().to_path_buf()).expect("tool context");
+        let mut tracker = FileReadTracker::default();
+
+        let first = execute_view_file(&json!({ "path": "notes.txt" }), &context, &mut tracker)
+            .expect("view")
+            .output
+            .expect("output");
+        assert!(first.starts_with("Lines 1-200 of notes.txt:"));
+        assert!(first.contains("200: line-200"));
+        assert!(!first.contains("201: line-201"));
+        assert!(
+            first.ends_with("... +250 lines (showing 1-200 of 450). Continue with start_line=201.")
+        );
+
+        let last = execute_view_file(
+            &json!({ "path": "notes.txt", "start_line": 401 }),
+            &context,
+            &mut tracker,
+        )
+        .expect("view")
+        .output
+        .expect("output");
+        assert!(last.starts_with("Lines 401-450 of notes.txt:"));
+        assert!(last.ends_with("450: line-450"));
+    }
+
+    #[test]
+    fn view_file_enforces_byte_budget_and_truncates_long_lines() {
+        let temp = TempDir::new("file-view-budget");
+        let wide = (0..100)
+            .map(|_| "x".repeat(3_000))
+            .collect::<Vec<_>>()
+            .join("\n");
+        fs::write(temp.path().join("wide.txt"), wide).expect("write fixture");
+        let context = ToolContext::new(temp.path().to_path_buf()).expect("tool context");
+
+        let output = execute_view_file(
+            &json!({ "path": "wide.txt", "start_line": 1, "end_line": 100 }),
+            &context,
+            &mut FileReadTracker::default(),
+        )
+        .expect("view")
+        .output
+        .expect("output");
+        assert!(output.len() < 22_000);
+        assert!(output.contains("[line truncated, 3000 chars total]"));
+        assert!(output.contains("Continue with start_line="));
+    }
+
+    #[test]
+    fn view_file_rejects_invalid_line_ranges() {
+        let temp = TempDir::new("file-view-range");
+        fs::write(temp.path().join("notes.txt"), "a\nb\nc").expect("write fixture");
+        let context = ToolContext::new(temp.path().to_path_buf()).expect("tool context");
+
+        let result = execute_view_file(
+            &json!({ "path": "notes.txt", "start_line": 0, "end_line": 1 }),
+            &context,
+            &mut FileReadTracker::default(),
+        )
+        .expect("view");
+        assert!(!result.success);
+        assert_eq!(result.error.as_deref(), Some("Invalid line range"));
+
+        let past_end = execute_view_file(
+            &json!({ "path": "notes.txt", "start_line": 9 }),
+            &context,
+            &mut FileReadTracker::default(),
+        )
+        .expect("view");
+        assert!(!past_end.success);
+        assert!(
+            past_end
+                .error
+                .as_deref()
+                .is_some_and(|msg| msg.contains("past the end"))
+        );
+    }
+
+    #[test]
+    fn view_file_reports_binary_files_by_type_and_size() {
+        let temp = TempDir::new("file-view-binary");
+        let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+        png.extend_from_slice(&[0u8; 2048]);
+        fs::write(temp.path().join("logo.png"), &png).expect("write fixture");
+        fs::write(temp.path().join("blob.bin"), b"abc\0def").expect("write fixture");
+        let context = ToolContext::new(temp.path().to_path_buf()).expect("tool context");
+        let mut tracker = FileReadTracker::default();
+
+        let image = execute_view_file(&json!({ "path": "logo.png" }), &context, &mut tracker)
+            .expect("view")
+            .output
+            .expect("output");
+        assert_eq!(
+            image,
+            "logo.png is a binary file (PNG image, 2.0 KB). Its contents are not shown."
+        );
+
+        let blob = execute_view_file(&json!({ "path": "blob.bin" }), &context, &mut tracker)
+            .expect("view")
+            .output
+            .expect("output");
+        assert!(blob.contains("(binary data, 7 B)"));
+    }
+
+    #[test]
+    fn binary_sniffing_ignores_utf16_text() {
+        assert_eq!(binary_kind(b"\xFF\xFEh\x00i\x00"), None);
+        assert_eq!(binary_kind(b"plain text"), None);
+        assert_eq!(binary_kind(b"MZ\x90\x00"), Some("Windows executable"));
+    }
+
+    #[test]
+    fn capped_lines_keep_a_prefix_and_report_the_full_length() {
+        let content = format!("{}\nshort\n{}", "x".repeat(10_000), "y".repeat(5));
+        let mut reader = BufReader::with_capacity(64, content.as_bytes());
+        let mut buffer = Vec::new();
+        assert_eq!(
+            read_capped_line(&mut reader, &mut buffer, 100).expect("read"),
+            Some(10_001)
+        );
+        assert_eq!(buffer, vec![b'x'; 100]);
+        assert_eq!(
+            read_capped_line(&mut reader, &mut buffer, 100).expect("read"),
+            Some(6)
+        );
+        assert_eq!(buffer, b"short\n");
+        assert_eq!(
+            read_capped_line(&mut reader, &mut buffer, 100).expect("read"),
+            Some(5)
+        );
+        assert_eq!(
+            read_capped_line(&mut reader, &mut buffer, 100).expect("read"),
+            None
+        );
+    }
+
+    #[test]
+    fn large_files_without_newlines_stay_within_the_page_budget() {
+        let temp = TempDir::new("file-view-minified");
+        fs::write(
+            temp.path().join("bundle.min.js"),
+            "a".repeat(9 * 1024 * 1024),
+        )
+        .expect("write fixture");
+        let context = ToolContext::new(temp.path().to_path_buf()).expect("tool context");
+
+        let output = execute_view_file(
+            &json!({ "path": "bundle.min.js" }),
+            &context,
+            &mut FileReadTracker::default(),
+        )
+        .expect("view")
+        .output
+        .expect("output");
+        assert!(output.len() < 3_000, "{}", output.len());
+        assert!(
+            output.contains("[line truncated, 9.0 MB total]"),
+            "{output}"
+        );
+    }
+
+    #[test]
+    fn streaming_hash_matches_whole_buffer_hash() {
+        let content = numbered_lines(5_000).into_bytes();
+        let mut reader = HashingReader::new(content.as_slice());
+        let mut chunk = [0u8; 333];
+        while reader.read(&mut chunk).expect("read") > 0 {}
+        assert_eq!(reader.finish(), content_hash(&content));
+    }
+
+    #[test]
+    fn directory_listing_includes_sizes() {
+        let temp = TempDir::new("file-view-listing");
+        fs::create_dir_all(temp.path().join("src")).expect("create dir");
+        fs::write(temp.path().join("Cargo.toml"), "x".repeat(2048)).expect("write");
+        let context = ToolContext::new(temp.path().to_path_buf()).expect("tool context");
+
+        let output = execute_view_file(
+            &json!({ "path": "." }),
+            &context,
+            &mut FileReadTracker::default(),
+        )
+        .expect("view")
+        .output
+        .expect("output");
+        assert_eq!(
+            output,
+            "Directory contents of .:\nCargo.toml (2.0 KB)\nsrc/"
+        );
+    }
+
+    #[test]
+    fn recursive_tree_respects_gitignore_and_depth() {
+        let temp = TempDir::new("file-view-tree");
+        let root = temp.path();
+        fs::create_dir_all(root.join("src/nested/deeper")).expect("create dirs");
+        fs::create_dir_all(root.join("target/debug")).expect("create dirs");
+        fs::write(root.join(".gitignore"), "target/\n*.log\n").expect("write");
+        fs::write(root.join("src/main.rs"), "fn main() {}\n").expect("write");
+        fs::write(root.join("src/nested/deeper/leaf.rs"), "").expect("write");
+        fs::write(root.join("debug.log"), "noise").expect("write");
+        fs::write(root.join("target/debug/app"), "bin").expect("write");
+        let context = ToolContext::new(root.to_path_buf()).expect("tool context");
+
+        let output = execute_view_file(
+            &json!({ "path": ".", "recursive": true, "max_depth": 2 }),
+            &context,
+            &mut FileReadTracker::default(),
+        )
+        .expect("view")
+        .output
+        .expect("output");
+
+        assert!(output.starts_with("Directory tree of . (depth 2, .gitignore respected):"));
+        assert!(output.contains("\nsrc/\n  main.rs (13 B)\n  nested/"));
+        assert!(output.contains(".gitignore (14 B)"));
+        assert!(!output.contains("target"));
+        assert!(!output.contains("debug.log"));
+        assert!(!output.contains("deeper"));
+    }
+
+    #[test]
+    fn listings_hide_ignored_entries() {
+        let temp = TempDir::new("file-view-ignored");
+        let root = temp.path();
+        fs::create_dir_all(root.join("secrets")).expect("create dirs");
+        fs::create_dir_all(root.join("src")).expect("create dirs");
+        fs::write(root.join(".grokignore"), "secrets/\n.env\n").expect("write");
+        fs::write(root.join(".env"), "KEY=1").expect("write");
+        fs::write(root.join("secrets/key.pem"), "pem").expect("write");
+        fs::write(root.join("src/.env"), "KEY=2").expect("write");
+        fs::write(root.join("src/main.rs"), "").expect("write");
+        let context = ToolContext::new(root.to_path_buf()).expect("tool context");
+        let mut tracker = FileReadTracker::default();
+
+        let listing = execute_view_file(&json!({ "path": "." }), &context, &mut tracker)
+            .expect("view")
+            .output
+            .expect("output");
+        assert_eq!(
+            listing,
+            "Directory contents of .:\n.grokignore (14 B)\nsrc/"
+        );
+
+        let tree = execute_view_file(
+            &json!({ "path": ".", "recursive": true }),
+            &context,
+            &mut tracker,
+        )
+        .expect("view")
+        .output
+        .expect("output");
+        assert!(tree.contains("src/\n  main.rs (0 B)"));
+        assert!(!tree.contains(".env"));
+        assert!(!tree.contains("secrets"));
+
+        let err = execute_view_file(&json!({ "path": ".env" }), &context, &mut tracker)
+            .expect_err("ignored");
+        assert!(err.to_string().contains("path is ignored by policy"));
+    }
+
+    struct TempDir {
+        path: PathBuf,
+    }
+
+    impl TempDir {
+        fn new(prefix: &str) -> Self {
+            let nonce = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .expect("clock after epoch")
+                .as_nanos();
+            let pid = std::process::id();
+            let path = std::env::temp_dir().join(format!("codetoad-{prefix}-{pid}-{nonce}"));
+            fs::create_dir_all(&path).expect("create temp dir");
+            Self { path }
+        }
+
+        fn path(&self) -> &Path {
+            &self.path
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.path);
+        }
+    }
+}
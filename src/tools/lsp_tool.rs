@@ -0,0 +1,370 @@
+use super::ToolResult;
+use super::text_file::decode_text;
+use crate::lsp::{LspManager, char_column, hover_text, parse_locations, path_to_uri, utf16_offset};
+use crate::tool_context::ToolContext;
+use anyhow::{Context, Result};
+use serde_json::{Value, json};
+use std::fs;
+use std::path::Path;
+
+const MAX_REFERENCES: usize = 100;
+const MAX_HOVER_CHARS: usize = 4_000;
+
+struct Position {
+    line: usize,
+    column: usize,
+}
+
+pub(super) async fn execute_goto_definition(
+    args: &Value,
+    tool_context: &ToolContext,
+    lsp: &mut LspManager,
+) -> Result<ToolResult> {
+    let params = match prepare(args, tool_context, lsp).await? {
+        Prepared::Ready(params) => params,
+        Prepared::Failed(result) => return Ok(result),
+    };
+    let result = params
+        .client
+        .request("textDocument/definition", params.position_params())
+        .await?;
+    let locations = visible_locations(tool_context, parse_locations(&result));
+    if locations.is_empty() {
+        return Ok(ToolResult::ok(format!(
+            "No definition found at {}:{}:{}",
+            params.display, params.position.line, params.position.column
+        )));
+    }
+    let mut output = format!("Definition of {}:\n", params.describe());
+    for location in &locations {
+        output.push_str(&format_location(tool_context, location));
+        output.push('\n');
+    }
+    Ok(ToolResult::ok(output.trim_end().to_string()))
+}
+
+pub(super) async fn execute_find_references(
+    args: &Value,
+    tool_context: &ToolContext,
+    lsp: &mut LspManager,
+) -> Result<ToolResult> {
+    let params = match prepare(args, tool_context, lsp).await? {
+        Prepared::Ready(params) => params,
+        Prepared::Failed(result) => return Ok(result),
+    };
+    let include_declaration = args
+        .get("include_declaration")
+        .and_then(Value::as_bool)
+        .unwrap_or(true);
+    let mut request = params.position_params();
+    request["context"] = json!({ "includeDeclaration": include_declaration });
+    let result = params
+        .client
+        .request("textDocument/references", request)
+        .await?;
+    let locations = visible_locations(tool_context, parse_locations(&result));
+    if locations.is_empty() {
+        return Ok(ToolResult::ok(format!(
+            "No references found for {}",
+            params.describe()
+        )));
+    }
+    let mut output = format!(
+        "References to {} ({} found):\n",
+        params.describe(),
+        locations.len()
+    );
+    for location in locations.iter().take(MAX_REFERENCES) {
+        output.push_str(&format_location(tool_context, location));
+        output.push('\n');
+    }
+    if locations.len() > MAX_REFERENCES {
+        output.push_str(&format!(
+            "  ... {} more\n",
+            locations.len() - MAX_REFERENCES
+        ));
+    }
+    Ok(ToolResult::ok(output.trim_end().to_string()))
+}
+
+pub(super) async fn execute_hover(
+    args: &Value,
+    tool_context: &ToolContext,
+    lsp: &mut LspManager,
+) -> Result<ToolResult> {
+    let params = match prepare(args, tool_context, lsp).await? {
+        Prepared::Ready(params) => params,
+        Prepared::Failed(result) => return Ok(result),
+    };
+    let result = params
+        .client
+        .request("textDocument/hover", params.position_params())
+        .await?;
+    let mut text = hover_text(&result);
+    if text.is_empty() {
+        return Ok(ToolResult::ok(format!(
+            "No hover information for {}",
+            params.describe()
+        )));
+    }
+    if text.len() > MAX_HOVER_CHARS {
+        let cut = text.floor_char_boundary(MAX_HOVER_CHARS);
+        text.truncate(cut);
+        text.push_str("\n... (truncated)");
+    }
+    Ok(ToolResult::ok(format!(
+        "Hover for {}:\n{text}",
+        params.describe()
+    )))
+}
+
+struct PositionParams {
+    client: std::sync::Arc<crate::lsp::LspClient>,
+    uri: String,
+    display: String,
+    symbol: Option<String>,
+    position: Position,
+    /// 0-based UTF-16 character offset sent to the server.
+    character: usize,
+}
+
+impl PositionParams {
+    fn position_params(&self) -> Value {
+        json!({
+            "textDocument": { "uri": self.uri },
+            "position": { "line": self.position.line - 1, "character": self.character }
+        })
+    }
+
+    fn describe(&self) -> String {
+        match &self.symbol {
+            Some(symbol) => format!(
+                "`{symbol}` at {}:{}:{}",
+                self.display, self.position.line, self.position.column
+            ),
+            None => format!(
+                "{}:{}:{}",
+                self.display, self.position.line, self.position.column
+            ),
+        }
+    }
+}
+
+enum Prepared {
+    Ready(PositionParams),
+    Failed(ToolResult),
+}
+
+/// Resolves the file and position, starts the server and syncs the file's
+/// current contents so positions match what is on disk.
+async fn prepare(
+    args: &Value,
+    tool_context: &ToolContext,
+    lsp: &mut LspManager,
+) -> Result<Prepared> {
+    let path = args
+        .get("path")
+        .and_then(Value::as_str)
+        .context("Missing 'path' argument")?;
+    let line = args
+        .get("line")
+        .and_then(Value::as_u64)
+        .context("Missing 'line' argument")? as usize;
+    let symbol = args
+        .get("symbol")
+        .and_then(Value::as_str)
+        .filter(|symbol| !symbol.trim().is_empty())
+        .map(|symbol| symbol.trim().to_string());
+    let column = args
+        .get("column")
+        .and_then(Value::as_u64)
+        .map(|c| c as usize);
+
+    let resolved = tool_context.resolve_path(path)?;
+    if !resolved.is_file() {
+        return Ok(Prepared::Failed(ToolResult::err(format!(
+            "File not found: {path}"
+        ))));
+    }
+    let Some(client) = lsp.client_for(&resolved).await? else {
+        return Ok(Prepared::Failed(ToolResult::err(not_configured_message(
+            &resolved,
+        ))));
+    };
+    let bytes = fs::read(&resolved).with_context(|| format!("Failed to read {path}"))?;
+    let text = match decode_text(&bytes) {
+        Ok(decoded) => decoded.text,
+        Err(message) => return Ok(Prepared::Failed(ToolResult::err(message))),
+    };
+    let Some(line_text) = text
+        .lines()
+        .nth(line.saturating_sub(1))
+        .filter(|_| line > 0)
+    else {
+        return Ok(Prepared::Failed(ToolResult::err(format!(
+            "Line {line} is out of range for {path}"
+        ))));
+    };
+    let column = match (column, &symbol) {
+        (Some(column), _) => column.max(1),
+        (None, Some(symbol)) => match symbol_column(line_text, symbol) {
+            Some(column) => column,
+            None => {
+                return Ok(Prepared::Failed(ToolResult::err(format!(
+                    "`{symbol}` does not appear on line {line} of {path}"
+                ))));
+            }
+        },
+        (None, None) => {
+            line_text
+                .chars()
+                .take_while(|ch| ch.is_whitespace())
+                .count()
+                + 1
+        }
+    };
+    let character = utf16_offset(line_text, column);
+    client.sync_document(&resolved, &text).await?;
+
+    Ok(Prepared::Ready(PositionParams {
+        client,
+        uri: path_to_uri(&resolved),
+        display: path.to_string(),
+        symbol,
+        position: Position { line, column },
+        character,
+    }))
+}
+
+/// 1-based column of the first whole-word occurrence of `symbol`, falling back
+/// to any occurrence.
+fn symbol_column(line_text: &str, symbol: &str) -> Option<usize> {
+    let is_word = |ch: char| ch.is_alphanumeric() || ch == '_';
+    let whole_word = line_text.match_indices(symbol).find(|(index, _)| {
+        let before = line_text[..*index].chars().next_back();
+        let after = line_text[index + symbol.len()..].chars().next();
+        !before.is_some_and(is_word) && !after.is_some_and(is_word)
+    });
+    let (index, _) = whole_word.or_else(|| line_text.match_indices(symbol).next())?;
+    Some(line_text[..index].chars().count() + 1)
+}
+
+/// Drops locations in files the ignore policy hides.
+fn visible_locations(
+    tool_context: &ToolContext,
+    locations: Vec<crate::lsp::Location>,
+) -> Vec<crate::lsp::Location> {
+    locations
+        .into_iter()
+        .filter(|location| !tool_context.is_ignored(&location.path.to_string_lossy()))
+        .collect()
+}
+
+fn format_location(tool_context: &ToolContext, location: &crate::lsp::Location) -> String {
+    let display = location
+        .path
+        .strip_prefix(tool_context.current_dir())
+        .or_else(|_| location.path.strip_prefix(tool_context.project_root()))
+        .unwrap_or(&location.path)
+        .display()
+        .to_string();
+    let line_text = fs::read(&location.path)
+        .ok()
+        .and_then(|bytes| decode_text(&bytes).ok())
+        .and_then(|decoded| {
+            decoded
+                .text
+                .lines()
+                .nth(location.line.saturating_sub(1))
+                .map(str::to_string)
+        });
+    match line_text {
+        Some(line_text) => format!(
+            "  {display}:{}:{}  {}",
+            location.line,
+            char_column(&line_text, location.column.saturating_sub(1)),
+            line_text.trim()
+        ),
+        None => format!("  {display}:{}:{}", location.line, location.column),
+    }
+}
+
+fn not_configured_message(path: &Path) -> String {
+    let kind = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(|extension| format!(".{extension} files"))
+        .unwrap_or_else(|| "this file".to_string());
+    format!(
+        "No language server configured for {kind}. Add one under languageServers in .grok/settings.json or ~/.grok/user-settings.json."
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{execute_hover, symbol_column};
+    use crate::lsp::LspManager;
+    use crate::tool_context::ToolContext;
+    use serde_json::json;
+    use std::collections::BTreeMap;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    #[test]
+    fn symbol_column_prefers_whole_words() {
+        assert_eq!(symbol_column("let store = open_store();", "store"), Some(5));
+        assert_eq!(symbol_column("    open_store()", "store"), Some(10));
+        assert_eq!(symbol_column("fn main() {}", "missing"), None);
+    }
+
+    #[tokio::test]
+    async fn reports_missing_language_server_configuration() {
+        let temp = TempDir::new("lsp-tool");
+        fs::write(temp.path().join("main.rs"), "fn main() {}\n").expect("write");
+        let context = ToolContext::new(temp.path().to_path_buf()).expect("context");
+        let mut lsp = LspManager::new(temp.path().to_path_buf(), BTreeMap::new());
+
+        let result = execute_hover(
+            &json!({ "path": "main.rs", "line": 1, "symbol": "main" }),
+            &context,
+            &mut lsp,
+        )
+        .await
+        .expect("hover");
+        assert!(!result.success);
+        assert_eq!(
+            result.error.as_deref(),
+            Some(
+                "No language server configured for .rs files. Add one under languageServers in .grok/settings.json or ~/.grok/user-settings.json."
+            )
+        );
+    }
+
+    struct TempDir {
+        path: PathBuf,
+    }
+
+    impl TempDir {
+        fn new(prefix: &str) -> Self {
+            let nonce = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .expect("clock after epoch")
+                .as_nanos();
+            let pid = std::process::id();
+            let path = std::env::temp_dir().join(format!("codetoad-{prefix}-{pid}-{nonce}"));
+            fs::create_dir_all(&path).expect("create temp dir");
+            Self { path }
+        }
+
+        fn path(&self) -> &Path {
+            &self.path
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.path);
+        }
+    }
+}
@@ -1,46 +1,141 @@
+use crate::ignore_policy::IgnorePolicy;
+use crate::lsp::LspManager;
+use crate::path_index::PathIndex;
+use crate::permissions::PermissionRequest;
+use crate::sandbox::{SandboxConfig, SandboxPolicy};
+use crate::settings::LanguageServerConfig;
 use crate::tool_catalog::{
-    TOOL_BASH, TOOL_CREATE_FILE, TOOL_CREATE_TODO_LIST, TOOL_SEARCH, TOOL_STR_REPLACE_EDITOR,
-    TOOL_UPDATE_TODO_LIST, TOOL_VIEW_FILE,
+    TOOL_BASH, TOOL_BASH_BACKGROUND, TOOL_CODE_OUTLINE, TOOL_COPY_FILE, TOOL_CREATE_FILE,
+    TOOL_CREATE_TODO_LIST, TOOL_DELETE_FILE, TOOL_FIND_DEFINITION, TOOL_FIND_REFERENCES,
+    TOOL_GOTO_DEFINITION, TOOL_HOVER, TOOL_KILL_PROCESS, TOOL_LIST_PROCESSES, TOOL_MOVE_FILE,
+    TOOL_READ_PROCESS_OUTPUT, TOOL_SEARCH, TOOL_STR_REPLACE_EDITOR, TOOL_UPDATE_TODO_LIST,
+    TOOL_VIEW_FILE,
 };
 use crate::tool_context::ToolContext;
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use std::collections::BTreeMap;
 use std::path::PathBuf;
+use std::sync::{Arc, Mutex, PoisonError};
+use tokio_util::sync::CancellationToken;
 
 mod bash_tool;
 mod file_ops;
+mod file_view;
+mod lsp_tool;
+mod outline_tool;
+mod process_tool;
 mod search_tool;
+mod shell_session;
+mod text_file;
 mod todos;
 
 use self::bash_tool::execute_bash_tool;
-use self::file_ops::{execute_create_file, execute_str_replace_editor, execute_view_file};
+use self::file_ops::{
+    FileReadTracker, execute_copy_file, execute_create_file, execute_delete_file,
+    execute_move_file, execute_str_replace_editor,
+};
+use self::file_view::execute_view_file;
+use self::lsp_tool::{execute_find_references, execute_goto_definition, execute_hover};
+use self::outline_tool::{execute_code_outline, execute_find_definition};
+use self::process_tool::{
+    ProcessRegistry, execute_bash_background, execute_kill_process, execute_list_processes,
+    execute_read_process_output,
+};
+pub use self::process_tool::{
+    ProcessStatus, ProcessSummary, format_process_table, kill_all_background_processes,
+};
 use self::search_tool::execute_search;
+use self::shell_session::ShellSession;
 use self::todos::{TodoStore, execute_create_todo_list, execute_update_todo_list};
 
 pub(crate) struct ToolSessionState {
     tool_context: ToolContext,
     todo_store: TodoStore,
+    file_tracker: FileReadTracker,
+    path_index: Arc<Mutex<PathIndex>>,
+    lsp: LspManager,
+    processes: ProcessRegistry,
+    shell: ShellSession,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub(crate) struct ToolSessionSnapshot {
     current_dir: String,
     todos: Value,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    shell_env: BTreeMap<String, String>,
 }
 
 impl ToolSessionState {
     pub(crate) fn new(project_root: PathBuf) -> Result<Self> {
+        let tool_context = ToolContext::new(project_root)?;
+        let mut path_index = PathIndex::new(tool_context.project_root().to_path_buf());
+        path_index.set_ignore_policy(tool_context.ignore_policy().clone());
+        let lsp = LspManager::new(tool_context.project_root().to_path_buf(), BTreeMap::new());
         Ok(Self {
-            tool_context: ToolContext::new(project_root)?,
+            tool_context,
             todo_store: TodoStore::default(),
+            file_tracker: FileReadTracker::default(),
+            path_index: Arc::new(Mutex::new(path_index)),
+            lsp,
+            processes: ProcessRegistry::default(),
+            shell: ShellSession::default(),
         })
     }
 
+    pub(crate) fn configure_sandbox(&mut self, config: &SandboxConfig) -> Result<()> {
+        let policy = SandboxPolicy::new(config, self.tool_context.project_root())?;
+        self.tool_context.set_sandbox(policy);
+        Ok(())
+    }
+
+    pub(crate) fn configure_ignore_patterns(&mut self, patterns: &[String]) -> Result<()> {
+        let policy = IgnorePolicy::load(self.tool_context.project_root(), patterns)?;
+        self.path_index
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .set_ignore_policy(policy.clone());
+        self.tool_context.set_ignore_policy(policy);
+        Ok(())
+    }
+
+    pub(crate) fn ignore_policy(&self) -> &IgnorePolicy {
+        self.tool_context.ignore_policy()
+    }
+
+    pub(crate) fn set_persistent_shell(&mut self, enabled: bool) {
+        self.shell.set_enabled(enabled);
+    }
+
+    pub(crate) fn background_processes(&self) -> Vec<ProcessSummary> {
+        self.processes.summaries()
+    }
+
+    pub(crate) async fn kill_background_process(
+        &mut self,
+        id: u32,
+    ) -> Result<ProcessSummary, String> {
+        self.processes.kill(id).await
+    }
+
+    pub(crate) fn permission_request(&self, tool_name: &str, args: &Value) -> PermissionRequest {
+        PermissionRequest::new(tool_name, args, &self.tool_context)
+    }
+
+    pub(crate) fn configure_language_servers(
+        &mut self,
+        configs: BTreeMap<String, LanguageServerConfig>,
+    ) {
+        self.lsp.configure(configs);
+    }
+
     pub(crate) fn snapshot(&self) -> Result<ToolSessionSnapshot> {
         Ok(ToolSessionSnapshot {
             current_dir: self.tool_context.relative_current_dir(),
             todos: self.todo_store.snapshot_value()?,
+            shell_env: self.shell.env().clone(),
         })
     }
 
@@ -51,6 +146,7 @@ impl ToolSessionState {
         self.todo_store
             .restore_value(snapshot.todos)
             .context("Failed restoring todo state")?;
+        self.shell.restore_env(snapshot.shell_env);
         Ok(())
     }
 }
@@ -88,6 +184,15 @@ impl ToolResult {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToolOutputStream {
+    Stdout,
+    Stderr,
+}
+
+/// Receives output lines from a running tool as they are produced.
+pub(crate) type ToolProgress = Arc<dyn Fn(ToolOutputStream, String) + Send + Sync>;
+
 pub fn tool_result_from_error(err: anyhow::Error) -> ToolResult {
     ToolResult::err(format!("{err:#}"))
 }
@@ -96,29 +201,124 @@ pub(crate) async fn execute_tool(
     name: &str,
     args: &Value,
     session: &mut ToolSessionState,
+    cancel_token: &CancellationToken,
+    progress: Option<ToolProgress>,
 ) -> ToolResult {
+    let removed_path = removed_path(name, args, session);
     let result: Result<ToolResult> = match name {
-        TOOL_VIEW_FILE => execute_view_file(args, &session.tool_context),
-        TOOL_CREATE_FILE => execute_create_file(args, &session.tool_context),
-        TOOL_STR_REPLACE_EDITOR => execute_str_replace_editor(args, &session.tool_context),
-        TOOL_BASH => execute_bash_tool(args, &mut session.tool_context).await,
-        TOOL_SEARCH => execute_search(args, &session.tool_context).await,
+        TOOL_VIEW_FILE => execute_view_file(args, &session.tool_context, &mut session.file_tracker),
+        TOOL_CREATE_FILE => {
+            execute_create_file(args, &session.tool_context, &mut session.file_tracker)
+        }
+        TOOL_STR_REPLACE_EDITOR => {
+            execute_str_replace_editor(args, &session.tool_context, &mut session.file_tracker)
+        }
+        TOOL_DELETE_FILE => {
+            execute_delete_file(args, &session.tool_context, &mut session.file_tracker)
+        }
+        TOOL_MOVE_FILE => execute_move_file(args, &session.tool_context, &mut session.file_tracker),
+        TOOL_COPY_FILE => execute_copy_file(args, &session.tool_context, &mut session.file_tracker),
+        TOOL_BASH => {
+            execute_bash_tool(
+                args,
+                &mut session.tool_context,
+                &mut session.shell,
+                cancel_token,
+                progress,
+            )
+            .await
+        }
+        TOOL_SEARCH => {
+            execute_search(
+                args,
+                &session.tool_context,
+                &session.path_index,
+                cancel_token,
+            )
+            .await
+        }
+        TOOL_CODE_OUTLINE => execute_code_outline(args, &session.tool_context, cancel_token).await,
+        TOOL_FIND_DEFINITION => {
+            execute_find_definition(args, &session.tool_context, cancel_token).await
+        }
+        TOOL_GOTO_DEFINITION => {
+            execute_goto_definition(args, &session.tool_context, &mut session.lsp).await
+        }
+        TOOL_FIND_REFERENCES => {
+            execute_find_references(args, &session.tool_context, &mut session.lsp).await
+        }
+        TOOL_HOVER => execute_hover(args, &session.tool_context, &mut session.lsp).await,
+        TOOL_BASH_BACKGROUND => {
+            execute_bash_background(args, &session.tool_context, &mut session.processes).await
+        }
+        TOOL_READ_PROCESS_OUTPUT => execute_read_process_output(args, &session.processes).await,
+        TOOL_LIST_PROCESSES => execute_list_processes(&session.processes),
+        TOOL_KILL_PROCESS => execute_kill_process(args, &mut session.processes).await,
         TOOL_CREATE_TODO_LIST => execute_create_todo_list(args, &mut session.todo_store),
         TOOL_UPDATE_TODO_LIST => execute_update_todo_list(args, &mut session.todo_store),
         _ => Ok(ToolResult::err(format!("Unknown tool: {name}"))),
     };
 
-    match result {
+    let mut tool_result = match result {
         Ok(tool_result) => tool_result,
         Err(error) => tool_result_from_error(error),
+    };
+    if tool_result.success
+        && let Some(path) = removed_path
+    {
+        session.lsp.path_removed(&path).await;
+    }
+    if tool_result.success
+        && let Some(report) = diagnostics_after_write(name, args, session).await
+    {
+        let output = tool_result.output.get_or_insert_with(String::new);
+        if !output.is_empty() {
+            output.push_str("\n\n");
+        }
+        output.push_str(&report);
+    }
+    tool_result
+}
+
+/// The path a delete or move takes away, resolved before the tool runs.
+fn removed_path(name: &str, args: &Value, session: &ToolSessionState) -> Option<PathBuf> {
+    let field = match name {
+        TOOL_DELETE_FILE => "path",
+        TOOL_MOVE_FILE => "source",
+        _ => return None,
+    };
+    let path = args.get(field).and_then(Value::as_str)?;
+    session.tool_context.resolve_entry_path(path).ok()
+}
+
+/// Lets the language server see a file a tool just wrote, and returns any
+/// diagnostics the write introduced.
+async fn diagnostics_after_write(
+    name: &str,
+    args: &Value,
+    session: &mut ToolSessionState,
+) -> Option<String> {
+    let field = match name {
+        TOOL_CREATE_FILE | TOOL_STR_REPLACE_EDITOR => "path",
+        TOOL_MOVE_FILE | TOOL_COPY_FILE => "destination",
+        _ => return None,
+    };
+    let path = args.get(field).and_then(Value::as_str)?;
+    let resolved = session.tool_context.resolve_path(path).ok()?;
+    if !resolved.is_file() {
+        return None;
     }
+    session
+        .lsp
+        .diagnostics_after_write(&resolved, session.tool_context.ignore_policy())
+        .await
 }
 
 pub(crate) async fn execute_bash_command(
     command: &str,
     session: &mut ToolSessionState,
 ) -> Result<ToolResult> {
-    bash_tool::execute_bash_command(command, &mut session.tool_context).await
+    bash_tool::execute_bash_command(command, &mut session.tool_context, &mut session.shell).await
 }
 
 #[cfg(test)]
@@ -128,6 +328,7 @@ mod tests {
     use std::fs;
     use std::path::{Path, PathBuf};
     use std::time::{SystemTime, UNIX_EPOCH};
+    use tokio_util::sync::CancellationToken;
 
     #[test]
     fn content_for_model_prefers_output_for_success() {
@@ -155,7 +356,14 @@ mod tests {
         let temp = TempDir::new("tools-unknown");
         let mut session = ToolSessionState::new(temp.path().to_path_buf()).expect("session");
 
-        let result = execute_tool("not_a_real_tool", &json!({}), &mut session).await;
+        let result = execute_tool(
+            "not_a_real_tool",
+            &json!({}),
+            &mut session,
+            &CancellationToken::new(),
+            None,
+        )
+        .await;
         assert!(!result.success);
         assert_eq!(
             result.error.as_deref(),
@@ -0,0 +1,475 @@
+use super::ToolResult;
+use super::text_file::decode_text;
+use crate::code_outline::{OutlineParser, SourceLanguage, Symbol};
+use crate::ignore_policy::IgnorePolicy;
+use crate::tool_context::ToolContext;
+use anyhow::{Context, Result};
+use ignore::WalkBuilder;
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tokio_util::sync::CancellationToken;
+
+/// Minified bundles and generated code rarely have a useful outline.
+const MAX_SOURCE_BYTES: u64 = 1024 * 1024;
+const MAX_SCANNED_FILES: usize = 5_000;
+const MAX_OUTPUT_CHARS: usize = 12_000;
+const MAX_DEFINITIONS: usize = 50;
+const SUPPORTED_LANGUAGES: &str = "Rust, TypeScript, JavaScript, Python, Go";
+
+struct FileOutline {
+    path: String,
+    language: SourceLanguage,
+    symbols: Vec<Symbol>,
+}
+
+pub(super) async fn execute_code_outline(
+    args: &Value,
+    tool_context: &ToolContext,
+    cancel_token: &CancellationToken,
+) -> Result<ToolResult> {
+    let path = args.get("path").and_then(Value::as_str).unwrap_or(".");
+    let resolved = tool_context.resolve_path(path)?;
+    if !resolved.exists() {
+        return Ok(ToolResult::err(format!(
+            "File or directory not found: {path}"
+        )));
+    }
+
+    if resolved.is_file() {
+        let Some(language) = SourceLanguage::from_path(&resolved) else {
+            return Ok(ToolResult::err(format!(
+                "No outline support for {path}. Supported languages: {SUPPORTED_LANGUAGES}"
+            )));
+        };
+        let outline = outline_file(&mut OutlineParser::new(), &resolved, language)?;
+        let Some(symbols) = outline else {
+            return Ok(ToolResult::err(format!(
+                "{path} is too large or not valid text, so it was not outlined"
+            )));
+        };
+        if symbols.is_empty() {
+            return Ok(ToolResult::ok(format!("No definitions found in {path}")));
+        }
+        let mut output = format!(
+            "Outline of {path} ({}, {}):\n",
+            language.name(),
+            count_label(symbols.len(), "definition")
+        );
+        push_symbols(&mut output, &symbols);
+        return Ok(ToolResult::ok(output.trim_end().to_string()));
+    }
+
+    let root = tool_context.current_dir().to_path_buf();
+    let ignore = tool_context.ignore_policy().clone();
+    let cancel = cancel_token.clone();
+    let (outlines, scan_limited) =
+        tokio::task::spawn_blocking(move || scan_outlines(&resolved, &root, &ignore, &cancel))
+            .await
+            .context("Outline task failed")?;
+    if cancel_token.is_cancelled() {
+        return Ok(ToolResult::err("Outline cancelled"));
+    }
+    if outlines.is_empty() {
+        return Ok(ToolResult::ok(format!(
+            "No definitions found under {path} (supported languages: {SUPPORTED_LANGUAGES})"
+        )));
+    }
+
+    let mut output = format!(
+        "Outline of {path} ({} with definitions, .gitignore respected):\n",
+        count_label(outlines.len(), "file")
+    );
+    let mut shown = 0usize;
+    for outline in &outlines {
+        let mut section = format!("{} ({}):\n", outline.path, outline.language.name());
+        push_symbols(&mut section, &outline.symbols);
+        if output.len() + section.len() > MAX_OUTPUT_CHARS {
+            break;
+        }
+        output.push_str(&section);
+        shown += 1;
+    }
+    if shown < outlines.len() {
+        output.push_str(&format!(
+            "... output limit reached: {} more not shown. Outline a subdirectory or a single file instead.\n",
+            count_label(outlines.len() - shown, "file")
+        ));
+    }
+    if scan_limited {
+        output.push_str(&format!(
+            "... stopped after scanning {MAX_SCANNED_FILES} source files.\n"
+        ));
+    }
+    Ok(ToolResult::ok(output.trim_end().to_string()))
+}
+
+pub(super) async fn execute_find_definition(
+    args: &Value,
+    tool_context: &ToolContext,
+    cancel_token: &CancellationToken,
+) -> Result<ToolResult> {
+    let symbol = args
+        .get("symbol")
+        .and_then(Value::as_str)
+        .map(str::trim)
+        .unwrap_or_default()
+        .to_string();
+    if symbol.is_empty() {
+        return Ok(ToolResult::err("Missing or empty 'symbol' argument"));
+    }
+    let path = args.get("path").and_then(Value::as_str).unwrap_or(".");
+    let resolved = tool_context.resolve_path(path)?;
+    if !resolved.exists() {
+        return Ok(ToolResult::err(format!(
+            "File or directory not found: {path}"
+        )));
+    }
+
+    let root = tool_context.current_dir().to_path_buf();
+    let ignore = tool_context.ignore_policy().clone();
+    let cancel = cancel_token.clone();
+    let (outlines, scan_limited) =
+        tokio::task::spawn_blocking(move || scan_outlines(&resolved, &root, &ignore, &cancel))
+            .await
+            .context("Definition lookup failed")?;
+    if cancel_token.is_cancelled() {
+        return Ok(ToolResult::err("Definition lookup cancelled"));
+    }
+
+    let (container, name) = split_qualified_name(&symbol);
+    let mut found = matching_definitions(&outlines, container, name, false);
+    if found.is_empty() {
+        found = matching_definitions(&outlines, container, name, true);
+    }
+    if found.is_empty() {
+        let limit_note = if scan_limited {
+            format!(" (stopped after scanning {MAX_SCANNED_FILES} source files)")
+        } else {
+            String::new()
+        };
+        return Ok(ToolResult::ok(format!(
+            "No definition of `{symbol}` found under {path}{limit_note}. Try search for text matches."
+        )));
+    }
+
+    let total = found.len();
+    let mut output = format!(
+        "Definitions of `{symbol}` ({}):\n",
+        count_label(total, "match")
+    );
+    for (file, definition) in found.into_iter().take(MAX_DEFINITIONS) {
+        let container = definition
+            .container
+            .as_deref()
+            .map(|container| format!(" (in {container})"))
+            .unwrap_or_default();
+        output.push_str(&format!(
+            "  {file}:{}-{}  {} {}{container}\n",
+            definition.start_line,
+            definition.end_line,
+            definition.kind.label(),
+            definition.name
+        ));
+    }
+    if total > MAX_DEFINITIONS {
+        output.push_str(&format!(
+            "  ... {} more not shown\n",
+            total - MAX_DEFINITIONS
+        ));
+    }
+    output.push_str("Use view_file with start_line and end_line to read a definition.");
+    Ok(ToolResult::ok(output))
+}
+
+/// Splits `Type::method` or `Type.method` into its container and name.
+fn split_qualified_name(symbol: &str) -> (Option<&str>, &str) {
+    if let Some((container, name)) = symbol.rsplit_once("::") {
+        return (
+            Some(container.rsplit("::").next().unwrap_or(container)),
+            name,
+        );
+    }
+    match symbol.rsplit_once('.') {
+        Some((container, name)) if !container.is_empty() && !name.is_empty() => (
+            Some(container.rsplit('.').next().unwrap_or(container)),
+            name,
+        ),
+        _ => (None, symbol),
+    }
+}
+
+fn matching_definitions<'a>(
+    outlines: &'a [FileOutline],
+    container: Option<&str>,
+    name: &str,
+    ignore_case: bool,
+) -> Vec<(&'a str, &'a Symbol)> {
+    let same = |left: &str, right: &str| {
+        if ignore_case {
+            left.eq_ignore_ascii_case(right)
+        } else {
+            left == right
+        }
+    };
+    outlines
+        .iter()
+        .flat_map(|outline| {
+            outline
+                .symbols
+                .iter()
+                .map(move |symbol| (outline.path.as_str(), symbol))
+        })
+        .filter(|(_, symbol)| same(&symbol.name, name))
+        .filter(|(_, symbol)| {
+            container.is_none_or(|container| {
+                symbol
+                    .container
+                    .as_deref()
+                    .is_some_and(|actual| same(actual, container))
+            })
+        })
+        .collect()
+}
+
+fn scan_outlines(
+    target: &Path,
+    root: &Path,
+    ignore: &IgnorePolicy,
+    cancel: &CancellationToken,
+) -> (Vec<FileOutline>, bool) {
+    let mut parser = OutlineParser::new();
+    let files = source_files(target, ignore);
+    let scan_limited = files.len() > MAX_SCANNED_FILES;
+    let mut outlines = Vec::new();
+    for (file, language) in files.into_iter().take(MAX_SCANNED_FILES) {
+        if cancel.is_cancelled() {
+            break;
+        }
+        let Ok(Some(symbols)) = outline_file(&mut parser, &file, language) else {
+            continue;
+        };
+        if symbols.is_empty() {
+            continue;
+        }
+        outlines.push(FileOutline {
+            path: file
+                .strip_prefix(root)
+                .unwrap_or(&file)
+                .to_string_lossy()
+                .to_string(),
+            language,
+            symbols,
+        });
+    }
+    (outlines, scan_limited)
+}
+
+fn source_files(target: &Path, ignore: &IgnorePolicy) -> Vec<(PathBuf, SourceLanguage)> {
+    if target.is_file() {
+        return SourceLanguage::from_path(target)
+            .map(|language| vec![(target.to_path_buf(), language)])
+            .unwrap_or_default();
+    }
+    let ignore = ignore.clone();
+    WalkBuilder::new(target)
+        .require_git(false)
+        .sort_by_file_name(|a, b| a.cmp(b))
+        .filter_entry(move |entry| {
+            let is_dir = entry.file_type().is_some_and(|kind| kind.is_dir());
+            !ignore.is_ignored(entry.path(), is_dir)
+        })
+        .build()
+        .flatten()
+        .filter(|entry| entry.file_type().is_some_and(|kind| kind.is_file()))
+        .filter_map(|entry| {
+            SourceLanguage::from_path(entry.path())
+                .map(|language| (entry.path().to_path_buf(), language))
+        })
+        .take(MAX_SCANNED_FILES + 1)
+        .collect()
+}
+
+/// Returns `None` for files that are too large or cannot be decoded as text.
+fn outline_file(
+    parser: &mut OutlineParser,
+    path: &Path,
+    language: SourceLanguage,
+) -> Result<Option<Vec<Symbol>>> {
+    let size = fs::metadata(path)
+        .with_context(|| format!("Failed reading metadata for {}", path.display()))?
+        .len();
+    if size > MAX_SOURCE_BYTES {
+        return Ok(None);
+    }
+    let bytes = fs::read(path).with_context(|| format!("Failed reading {}", path.display()))?;
+    let Ok(decoded) = decode_text(&bytes) else {
+        return Ok(None);
+    };
+    parser.outline(language, &decoded.text).map(Some)
+}
+
+fn push_symbols(output: &mut String, symbols: &[Symbol]) {
+    for symbol in symbols {
+        output.push_str(&format!(
+            "  {}{} {} (lines {}-{})\n",
+            "  ".repeat(symbol.depth),
+            symbol.kind.label(),
+            symbol.display_name(),
+            symbol.start_line,
+            symbol.end_line
+        ));
+    }
+}
+
+fn count_label(count: usize, noun: &str) -> String {
+    match (count, noun) {
+        (1, _) => format!("1 {noun}"),
+        (_, "match") => format!("{count} matches"),
+        _ => format!("{count} {noun}s"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{execute_code_outline, execute_find_definition, split_qualified_name};
+    use crate::tool_context::ToolContext;
+    use serde_json::json;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+    use std::time::{SystemTime, UNIX_EPOCH};
+    use tokio_util::sync::CancellationToken;
+
+    const LIB_RS: &str = "pub struct Store;\n\nimpl Store {\n    pub fn open() -> Self {\n        Store\n    }\n}\n\npub fn open() {}\n";
+
+    #[tokio::test]
+    async fn code_outline_lists_file_and_directory_definitions() {
+        let temp = TempDir::new("outline-tool");
+        fs::create_dir_all(temp.path().join("src")).expect("create src");
+        fs::write(temp.path().join("src/lib.rs"), LIB_RS).expect("write");
+        fs::write(
+            temp.path().join("src/app.py"),
+            "class App:\n    def run(self):\n        pass\n",
+        )
+        .expect("write");
+        fs::write(temp.path().join("notes.md"), "# notes\n").expect("write");
+        let context = ToolContext::new(temp.path().to_path_buf()).expect("tool context");
+        let cancel = CancellationToken::new();
+
+        let file = execute_code_outline(&json!({ "path": "src/lib.rs" }), &context, &cancel)
+            .await
+            .expect("outline")
+            .output
+            .expect("output");
+        assert_eq!(
+            file,
+            "Outline of src/lib.rs (rust, 4 definitions):\n  struct Store (lines 1-1)\n  impl Store (lines 3-7)\n    method open (lines 4-6)\n  fn open (lines 9-9)"
+        );
+
+        let dir = execute_code_outline(&json!({}), &context, &cancel)
+            .await
+            .expect("outline")
+            .output
+            .expect("output");
+        assert!(dir.starts_with("Outline of . (2 files with definitions"));
+        assert!(
+            dir.contains(
+                "src/app.py (python):\n  class App (lines 1-3)\n    method run (lines 2-3)"
+            )
+        );
+        assert!(!dir.contains("notes.md"));
+
+        let unsupported = execute_code_outline(&json!({ "path": "notes.md" }), &context, &cancel)
+            .await
+            .expect("outline");
+        assert!(
+            unsupported
+                .error
+                .as_deref()
+                .is_some_and(|msg| msg.starts_with("No outline support for notes.md"))
+        );
+    }
+
+    #[tokio::test]
+    async fn find_definition_returns_exact_line_ranges() {
+        let temp = TempDir::new("find-definition");
+        fs::create_dir_all(temp.path().join("src")).expect("create src");
+        fs::write(temp.path().join("src/lib.rs"), LIB_RS).expect("write");
+        let context = ToolContext::new(temp.path().to_path_buf()).expect("tool context");
+        let cancel = CancellationToken::new();
+
+        let all = execute_find_definition(&json!({ "symbol": "open" }), &context, &cancel)
+            .await
+            .expect("lookup")
+            .output
+            .expect("output");
+        assert!(all.starts_with("Definitions of `open` (2 matches):"));
+        assert!(all.contains("  src/lib.rs:4-6  method open (in Store)\n"));
+        assert!(all.contains("  src/lib.rs:9-9  fn open\n"));
+
+        let qualified = execute_find_definition(
+            &json!({ "symbol": "crate::Store::open" }),
+            &context,
+            &cancel,
+        )
+        .await
+        .expect("lookup")
+        .output
+        .expect("output");
+        assert!(qualified.contains("(1 match)"));
+        assert!(qualified.contains("src/lib.rs:4-6"));
+
+        let case_insensitive =
+            execute_find_definition(&json!({ "symbol": "store" }), &context, &cancel)
+                .await
+                .expect("lookup")
+                .output
+                .expect("output");
+        assert!(case_insensitive.contains("src/lib.rs:1-1  struct Store"));
+
+        let missing = execute_find_definition(&json!({ "symbol": "nope" }), &context, &cancel)
+            .await
+            .expect("lookup")
+            .output
+            .expect("output");
+        assert!(missing.starts_with("No definition of `nope` found under ."));
+    }
+
+    #[test]
+    fn split_qualified_name_handles_paths_and_dots() {
+        assert_eq!(split_qualified_name("open"), (None, "open"));
+        assert_eq!(
+            split_qualified_name("a::Store::open"),
+            (Some("Store"), "open")
+        );
+        assert_eq!(split_qualified_name("App.run"), (Some("App"), "run"));
+        assert_eq!(split_qualified_name(".hidden"), (None, ".hidden"));
+    }
+
+    struct TempDir {
+        path: PathBuf,
+    }
+
+    impl TempDir {
+        fn new(prefix: &str) -> Self {
+            let nonce = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .expect("clock after epoch")
+                .as_nanos();
+            let pid = std::process::id();
+            let path = std::env::temp_dir().join(format!("codetoad-{prefix}-{pid}-{nonce}"));
+            fs::create_dir_all(&path).expect("create temp dir");
+            Self { path }
+        }
+
+        fn path(&self) -> &Path {
+            &self.path
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.path);
+        }
+    }
+}
@@ -0,0 +1,601 @@
+use super::ToolResult;
+#[cfg(unix)]
+use super::bash_tool::signal_process_group;
+use super::bash_tool::{kill_process_group, shell_command, validate_command_paths};
+use crate::tool_context::ToolContext;
+use anyhow::{Context, Result};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::process::Stdio;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncRead, AsyncReadExt};
+use tokio::sync::{Notify, oneshot};
+
+const MAX_RUNNING_PROCESSES: usize = 16;
+/// Output kept per process; the oldest output is dropped past this.
+const MAX_BUFFERED_BYTES: usize = 1024 * 1024;
+const MAX_READ_CHARS: usize = 16_000;
+const MAX_WAIT_MS: u64 = 30_000;
+/// How long `bash_background` waits so commands that fail right away are
+/// reported as failures instead of "started".
+const STARTUP_GRACE: Duration = Duration::from_millis(300);
+const KILL_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Process groups of running background processes, so the Ctrl+C handler can
+/// kill them before `process::exit` skips every destructor.
+static LIVE_PROCESS_GROUPS: Mutex<Vec<u32>> = Mutex::new(Vec::new());
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProcessStatus {
+    Running,
+    Exited(Option<i32>),
+    Killed,
+}
+
+impl ProcessStatus {
+    pub fn label(&self) -> String {
+        match self {
+            Self::Running => "running".to_string(),
+            Self::Exited(Some(code)) => format!("exited ({code})"),
+            Self::Exited(None) => "exited (signal)".to_string(),
+            Self::Killed => "killed".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ProcessSummary {
+    pub id: u32,
+    pub pid: Option<u32>,
+    pub command: String,
+    pub status: ProcessStatus,
+    pub runtime: Duration,
+}
+
+struct ProcessOutput {
+    text: String,
+    /// Bytes dropped from the front of `text` to stay under the cap.
+    dropped: usize,
+    /// Absolute offset up to which `read_process_output` has returned output.
+    read_to: usize,
+    /// Bytes of an incomplete UTF-8 sequence per stream, carried to the next chunk.
+    partial: [Vec<u8>; 2],
+    status: ProcessStatus,
+    finished_at: Option<Instant>,
+}
+
+impl ProcessOutput {
+    fn end(&self) -> usize {
+        self.dropped + self.text.len()
+    }
+
+    fn append(&mut self, stream: usize, bytes: &[u8]) {
+        let partial = &mut self.partial[stream];
+        partial.extend_from_slice(bytes);
+        let valid = match std::str::from_utf8(partial) {
+            Ok(_) => partial.len(),
+            Err(err) if err.error_len().is_none() => err.valid_up_to(),
+            Err(_) => partial.len(),
+        };
+        let chunk = partial.drain(..valid).collect::<Vec<_>>();
+        self.text.push_str(&String::from_utf8_lossy(&chunk));
+        if self.text.len() > MAX_BUFFERED_BYTES {
+            let excess = self.text.len() - MAX_BUFFERED_BYTES;
+            let cut = self.text.ceil_char_boundary(excess);
+            let cut = self.text[cut..]
+                .find('\n')
+                .map_or(cut, |offset| cut + offset + 1);
+            self.text.drain(..cut);
+            self.dropped += cut;
+        }
+    }
+}
+
+struct BackgroundProcess {
+    command: String,
+    pid: Option<u32>,
+    started_at: Instant,
+    output: Arc<Mutex<ProcessOutput>>,
+    changed: Arc<Notify>,
+    /// Dropping or firing this makes the waiter task kill the process group.
+    kill: Option<oneshot::Sender<()>>,
+}
+
+impl BackgroundProcess {
+    fn summary(&self, id: u32) -> ProcessSummary {
+        let output = lock(&self.output);
+        ProcessSummary {
+            id,
+            pid: self.pid,
+            command: self.command.clone(),
+            status: output.status,
+            runtime: output
+                .finished_at
+                .unwrap_or_else(Instant::now)
+                .duration_since(self.started_at),
+        }
+    }
+
+    fn status(&self) -> ProcessStatus {
+        lock(&self.output).status
+    }
+}
+
+/// Processes started by `bash_background`. They keep running across turns and
+/// are killed when the registry is dropped.
+#[derive(Default)]
+pub(crate) struct ProcessRegistry {
+    next_id: u32,
+    processes: BTreeMap<u32, BackgroundProcess>,
+}
+
+impl ProcessRegistry {
+    fn spawn(&mut self, command: &str, tool_context: &ToolContext) -> Result<u32, String> {
+        let running = self
+            .processes
+            .values()
+            .filter(|process| process.status() == ProcessStatus::Running)
+            .count();
+        if running >= MAX_RUNNING_PROCESSES {
+            return Err(format!(
+                "Too many background processes ({running} running). Kill one with kill_process first."
+            ));
+        }
+
+        let mut process = shell_command(command);
+        process
+            .current_dir(tool_context.current_dir())
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+        #[cfg(unix)]
+        process.process_group(0);
+        tool_context
+            .sandbox()
+            .apply(&mut process)
+            .map_err(|err| format!("Sandbox unavailable: {err:#}"))?;
+        let mut child = process
+            .spawn()
+            .map_err(|err| format!("Failed running command: {command}: {err}"))?;
+
+        let pid = child.id();
+        let output = Arc::new(Mutex::new(ProcessOutput {
+            text: String::new(),
+            dropped: 0,
+            read_to: 0,
+            partial: [Vec::new(), Vec::new()],
+            status: ProcessStatus::Running,
+            finished_at: None,
+        }));
+        let changed = Arc::new(Notify::new());
+        let readers = [
+            spawn_reader(child.stdout.take(), 0, &output, &changed),
+            spawn_reader(child.stderr.take(), 1, &output, &changed),
+        ];
+        if let Some(pid) = pid {
+            lock(&LIVE_PROCESS_GROUPS).push(pid);
+        }
+
+        let (kill_tx, kill_rx) = oneshot::channel();
+        let waiter_output = Arc::clone(&output);
+        let waiter_changed = Arc::clone(&changed);
+        tokio::spawn(async move {
+            let status = tokio::select! {
+                status = child.wait() => ProcessStatus::Exited(status.ok().and_then(|status| status.code())),
+                _ = kill_rx => {
+                    kill_process_group(&mut child).await;
+                    ProcessStatus::Killed
+                }
+            };
+            if let Some(pid) = pid {
+                lock(&LIVE_PROCESS_GROUPS).retain(|live| *live != pid);
+            }
+            for reader in readers.into_iter().flatten() {
+                if tokio::time::timeout(Duration::from_millis(200), reader)
+                    .await
+                    .is_err()
+                {
+                    break;
+                }
+            }
+            let mut output = lock(&waiter_output);
+            output.status = status;
+            output.finished_at = Some(Instant::now());
+            drop(output);
+            waiter_changed.notify_waiters();
+        });
+
+        self.next_id += 1;
+        let id = self.next_id;
+        self.processes.insert(
+            id,
+            BackgroundProcess {
+                command: command.to_string(),
+                pid,
+                started_at: Instant::now(),
+                output,
+                changed,
+                kill: Some(kill_tx),
+            },
+        );
+        Ok(id)
+    }
+
+    pub(crate) fn summaries(&self) -> Vec<ProcessSummary> {
+        self.processes
+            .iter()
+            .map(|(id, process)| process.summary(*id))
+            .collect()
+    }
+
+    /// Returns output produced since the last read, waiting up to `wait` for
+    /// new output or for the process to exit.
+    async fn read(&self, id: u32, wait: Duration) -> Option<String> {
+        let process = self.processes.get(&id)?;
+        if !wait.is_zero() {
+            let _ = tokio::time::timeout(wait, async {
+                loop {
+                    let notified = process.changed.notified();
+                    {
+                        let output = lock(&process.output);
+                        if output.end() > output.read_to || output.status != ProcessStatus::Running
+                        {
+                            return;
+                        }
+                    }
+                    notified.await;
+                }
+            })
+            .await;
+        }
+
+        let summary = process.summary(id);
+        let mut output = lock(&process.output);
+        let skipped = output.dropped.saturating_sub(output.read_to);
+        let start = output.read_to.max(output.dropped) - output.dropped;
+        let mut fresh = output.text[start..].to_string();
+        output.read_to = output.end();
+        drop(output);
+
+        let mut report = format!(
+            "Process {id} ({}, {}): {}\n",
+            summary.status.label(),
+            format_runtime(summary.runtime),
+            summary.command
+        );
+        if skipped > 0 {
+            report.push_str(&format!(
+                "[{skipped} bytes of older output were discarded]\n"
+            ));
+        }
+        if fresh.len() > MAX_READ_CHARS {
+            let cut = fresh.ceil_char_boundary(fresh.len() - MAX_READ_CHARS);
+            fresh.drain(..cut);
+            report.push_str("[earlier new output omitted]\n");
+        }
+        let fresh = fresh.trim_end();
+        if fresh.is_empty() {
+            report.push_str("(no new output)");
+        } else {
+            report.push_str(fresh);
+        }
+        Some(report)
+    }
+
+    /// Kills a running process group and waits briefly for it to be reaped.
+    pub(crate) async fn kill(&mut self, id: u32) -> Result<ProcessSummary, String> {
+        let process = self
+            .processes
+            .get_mut(&id)
+            .ok_or_else(|| format!("No background process with id {id}"))?;
+        if process.status() != ProcessStatus::Running {
+            return Ok(process.summary(id));
+        }
+        if let Some(kill) = process.kill.take() {
+            let _ = kill.send(());
+        }
+        let _ = tokio::time::timeout(KILL_TIMEOUT, async {
+            loop {
+                let notified = process.changed.notified();
+                if process.status() != ProcessStatus::Running {
+                    return;
+                }
+                notified.await;
+            }
+        })
+        .await;
+        Ok(process.summary(id))
+    }
+}
+
+impl Drop for ProcessRegistry {
+    fn drop(&mut self) {
+        for process in self.processes.values_mut() {
+            if process.status() != ProcessStatus::Running {
+                continue;
+            }
+            // The waiter task may never run again if the runtime is shutting
+            // down, so signal the group directly as well.
+            #[cfg(unix)]
+            if let Some(pid) = process.pid {
+                signal_process_group(pid);
+            }
+            process.kill.take();
+        }
+    }
+}
+
+/// Kills every background process group still alive. Used on Ctrl+C exit.
+pub fn kill_all_background_processes() {
+    let groups = std::mem::take(&mut *lock(&LIVE_PROCESS_GROUPS));
+    #[cfg(unix)]
+    for pid in groups {
+        signal_process_group(pid);
+    }
+    #[cfg(not(unix))]
+    drop(groups);
+}
+
+fn spawn_reader<R: AsyncRead + Unpin + Send + 'static>(
+    pipe: Option<R>,
+    stream: usize,
+    output: &Arc<Mutex<ProcessOutput>>,
+    changed: &Arc<Notify>,
+) -> Option<tokio::task::JoinHandle<()>> {
+    let mut pipe = pipe?;
+    let output = Arc::clone(output);
+    let changed = Arc::clone(changed);
+    Some(tokio::spawn(async move {
+        let mut chunk = [0u8; 8192];
+        while let Ok(read) = pipe.read(&mut chunk).await {
+            if read == 0 {
+                break;
+            }
+            lock(&output).append(stream, &chunk[..read]);
+            changed.notify_waiters();
+        }
+    }))
+}
+
+pub(super) async fn execute_bash_background(
+    args: &Value,
+    tool_context: &ToolContext,
+    registry: &mut ProcessRegistry,
+) -> Result<ToolResult> {
+    let command = args
+        .get("command")
+        .and_then(Value::as_str)
+        .context("Missing 'command' argument")?
+        .trim();
+    if command.is_empty() {
+        return Ok(ToolResult::err("Command must not be empty"));
+    }
+    if let Err(reason) = validate_command_paths(command, tool_context) {
+        return Ok(ToolResult::err(format!(
+            "Blocked by shell sandbox policy: {reason}"
+        )));
+    }
+
+    let id = match registry.spawn(command, tool_context) {
+        Ok(id) => id,
+        Err(message) => return Ok(ToolResult::err(message)),
+    };
+    tokio::time::sleep(STARTUP_GRACE).await;
+    let report = registry.read(id, Duration::ZERO).await.unwrap_or_default();
+    let summary = registry.processes[&id].summary(id);
+    match summary.status {
+        ProcessStatus::Running => Ok(ToolResult::ok(format!(
+            "Started background process {id}{}. Use read_process_output with id {id} to check on it.\n{report}",
+            summary
+                .pid
+                .map(|pid| format!(" (pid {pid})"))
+                .unwrap_or_default()
+        ))),
+        _ => Ok(ToolResult::err(format!(
+            "Background process {id} stopped right away.\n{report}"
+        ))),
+    }
+}
+
+pub(super) async fn execute_read_process_output(
+    args: &Value,
+    registry: &ProcessRegistry,
+) -> Result<ToolResult> {
+    let id = process_id(args)?;
+    let wait_ms = args
+        .get("wait_ms")
+        .and_then(Value::as_u64)
+        .unwrap_or(0)
+        .min(MAX_WAIT_MS);
+    match registry.read(id, Duration::from_millis(wait_ms)).await {
+        Some(report) => Ok(ToolResult::ok(report)),
+        None => Ok(ToolResult::err(format!(
+            "No background process with id {id}"
+        ))),
+    }
+}
+
+pub(super) fn execute_list_processes(registry: &ProcessRegistry) -> Result<ToolResult> {
+    let summaries = registry.summaries();
+    if summaries.is_empty() {
+        return Ok(ToolResult::ok("No background processes"));
+    }
+    Ok(ToolResult::ok(format_process_table(&summaries)))
+}
+
+pub(super) async fn execute_kill_process(
+    args: &Value,
+    registry: &mut ProcessRegistry,
+) -> Result<ToolResult> {
+    let id = process_id(args)?;
+    match registry.kill(id).await {
+        Ok(summary) => Ok(ToolResult::ok(format!(
+            "Process {id} {}: {}",
+            summary.status.label(),
+            summary.command
+        ))),
+        Err(message) => Ok(ToolResult::err(message)),
+    }
+}
+
+pub fn format_process_table(summaries: &[ProcessSummary]) -> String {
+    summaries
+        .iter()
+        .map(|summary| {
+            format!(
+                "{:>3}  {:<16} {:>8}  {}",
+                summary.id,
+                summary.status.label(),
+                format_runtime(summary.runtime),
+                summary.command
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn process_id(args: &Value) -> Result<u32> {
+    let value = args.get("id").context("Missing 'id' argument")?;
+    value
+        .as_u64()
+        .or_else(|| value.as_str().and_then(|id| id.trim().parse().ok()))
+        .and_then(|id| u32::try_from(id).ok())
+        .context("'id' must be a process id number")
+}
+
+fn format_runtime(runtime: Duration) -> String {
+    let secs = runtime.as_secs();
+    if secs >= 3600 {
+        format!("{}h{:02}m", secs / 3600, (secs % 3600) / 60)
+    } else if secs >= 60 {
+        format!("{}m{:02}s", secs / 60, secs % 60)
+    } else {
+        format!("{secs}s")
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::{
+        ProcessRegistry, ProcessStatus, execute_bash_background, execute_kill_process,
+        execute_list_processes, execute_read_process_output, format_runtime,
+    };
+    use crate::tool_context::ToolContext;
+    use serde_json::json;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    #[tokio::test]
+    async fn background_process_output_is_read_incrementally_and_killed() {
+        let temp = TempDir::new("process-registry");
+        let context = ToolContext::new(temp.path().to_path_buf()).expect("tool context");
+        let mut registry = ProcessRegistry::default();
+
+        let started = execute_bash_background(
+            &json!({ "command": "echo ready; sleep 30" }),
+            &context,
+            &mut registry,
+        )
+        .await
+        .expect("start");
+        assert!(started.success, "{:?}", started.error);
+        let output = started.output.expect("output");
+        assert!(output.starts_with("Started background process 1 (pid "));
+        assert!(output.ends_with("ready"));
+
+        let again = execute_read_process_output(&json!({ "id": 1 }), &registry)
+            .await
+            .expect("read");
+        assert!(again.output.expect("output").ends_with("(no new output)"));
+
+        let listed = execute_list_processes(&registry).expect("list");
+        assert!(listed.output.expect("output").contains("running"));
+
+        let killed = execute_kill_process(&json!({ "id": "1" }), &mut registry)
+            .await
+            .expect("kill");
+        assert_eq!(
+            killed.output.as_deref(),
+            Some("Process 1 killed: echo ready; sleep 30")
+        );
+        assert_eq!(registry.summaries()[0].status, ProcessStatus::Killed);
+    }
+
+    #[tokio::test]
+    async fn reports_commands_that_exit_immediately_and_waits_for_output() {
+        let temp = TempDir::new("process-exit");
+        let context = ToolContext::new(temp.path().to_path_buf()).expect("tool context");
+        let mut registry = ProcessRegistry::default();
+
+        let failed = execute_bash_background(
+            &json!({ "command": "echo boom >&2; exit 3" }),
+            &context,
+            &mut registry,
+        )
+        .await
+        .expect("start");
+        assert!(!failed.success);
+        assert!(failed.error.expect("error").contains("(exited (3), 0s)"));
+
+        execute_bash_background(
+            &json!({ "command": "sleep 0.5; echo later" }),
+            &context,
+            &mut registry,
+        )
+        .await
+        .expect("start");
+        let waited = execute_read_process_output(&json!({ "id": 2, "wait_ms": 5000 }), &registry)
+            .await
+            .expect("read");
+        assert!(waited.output.expect("output").ends_with("later"));
+
+        let missing = execute_read_process_output(&json!({ "id": 9 }), &registry)
+            .await
+            .expect("read");
+        assert_eq!(
+            missing.error.as_deref(),
+            Some("No background process with id 9")
+        );
+    }
+
+    #[test]
+    fn runtime_is_compact() {
+        assert_eq!(format_runtime(Duration::from_secs(42)), "42s");
+        assert_eq!(format_runtime(Duration::from_secs(125)), "2m05s");
+        assert_eq!(format_runtime(Duration::from_secs(7260)), "2h01m");
+    }
+
+    struct TempDir {
+        path: PathBuf,
+    }
+
+    impl TempDir {
+        fn new(prefix: &str) -> Self {
+            let nonce = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .expect("clock after epoch")
+                .as_nanos();
+            let pid = std::process::id();
+            let path = std::env::temp_dir().join(format!("codetoad-{prefix}-{pid}-{nonce}"));
+            fs::create_dir_all(&path).expect("create temp dir");
+            Self { path }
+        }
+
+        fn path(&self) -> &Path {
+            &self.path
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.path);
+        }
+    }
+}
@@ -1,12 +1,22 @@
 use super::ToolResult;
+use crate::ignore_policy::IgnorePolicy;
+use crate::path_index::{PathIndex, score_path};
 use crate::tool_context::ToolContext;
 use anyhow::{Context, Result};
+use grep_matcher::Matcher;
+use grep_regex::{RegexMatcher, RegexMatcherBuilder};
+use grep_searcher::{
+    BinaryDetection, Searcher, SearcherBuilder, Sink, SinkContext, SinkContextKind, SinkMatch,
+};
+use ignore::overrides::{Override, OverrideBuilder};
+use ignore::{Walk, WalkBuilder};
 use serde::Deserialize;
 use serde_json::Value;
 use std::cmp::Ordering;
-use std::collections::{HashMap, HashSet};
-use std::path::PathBuf;
-use tokio::process::Command;
+use std::collections::{BTreeMap, HashSet};
+use std::path::Path;
+use std::sync::{Arc, Mutex, PoisonError};
+use tokio_util::sync::CancellationToken;
 
 #[derive(Debug, Clone, Deserialize)]
 struct SearchOptions {
@@ -29,11 +39,25 @@ struct SearchOptions {
     file_types: Option<Vec<String>>,
     #[serde(default)]
     include_hidden: Option<bool>,
+    #[serde(default)]
+    context_lines: Option<usize>,
 }
 
+/// One matching line, with the surrounding lines reported as context.
 #[derive(Debug, Clone)]
 struct SearchTextResult {
     file: String,
+    line_number: usize,
+    column: usize,
+    text: String,
+    context_before: Vec<ContextLine>,
+    context_after: Vec<ContextLine>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ContextLine {
+    line_number: usize,
+    text: String,
 }
 
 #[derive(Debug, Clone)]
@@ -42,10 +66,21 @@ struct FileSearchResult {
     score: i32,
 }
 
-pub(super) async fn execute_search(args: &Value, tool_context: &ToolContext) -> Result<ToolResult> {
+const DEFAULT_EXCLUDE_GLOBS: [&str; 4] = ["!.git/**", "!node_modules/**", "!.DS_Store", "!*.log"];
+const MAX_CONTEXT_LINES: usize = 10;
+/// Upper bound on the rendered result so a broad query can't flood the context.
+const MAX_OUTPUT_CHARS: usize = 12_000;
+const MAX_LINE_CHARS: usize = 300;
+
+pub(super) async fn execute_search(
+    args: &Value,
+    tool_context: &ToolContext,
+    path_index: &Arc<Mutex<PathIndex>>,
+    cancel_token: &CancellationToken,
+) -> Result<ToolResult> {
     let options: SearchOptions =
         serde_json::from_value(args.clone()).context("Invalid search arguments")?;
-    let query = options.query.trim();
+    let query = options.query.trim().to_string();
     if query.is_empty() {
         return Ok(ToolResult::err("Missing or empty 'query' argument"));
     }
@@ -61,141 +96,278 @@ pub(super) async fn execute_search(args: &Value, tool_context: &ToolContext) ->
         ));
     }
 
+    let matcher = if matches!(search_type.as_str(), "text" | "both") {
+        match build_matcher(&query, &options) {
+            Ok(matcher) => Some(matcher),
+            Err(err) => return Ok(ToolResult::err(format!("Invalid search pattern: {err}"))),
+        }
+    } else {
+        None
+    };
+    if let Err(err) = build_overrides(tool_context.current_dir(), &options) {
+        return Ok(ToolResult::err(format!("Invalid glob pattern: {err:#}")));
+    }
+
     let max_results = options.max_results.unwrap_or(50).clamp(1, 200);
-    let mut text_results = Vec::new();
-    let mut file_results = Vec::new();
+    let root = tool_context.current_dir().to_path_buf();
+    let ignore = tool_context.ignore_policy().clone();
+    let cancel = cancel_token.clone();
+    let path_index = Arc::clone(path_index);
+    let task_query = query.clone();
+    let (text_results, file_results) = tokio::task::spawn_blocking(move || -> Result<_> {
+        let mut text_results = Vec::new();
+        let mut file_results = Vec::new();
+        if let Some(matcher) = matcher {
+            text_results = search_text(&matcher, &options, max_results, &root, &ignore, &cancel)?;
+        }
+        if matches!(search_type.as_str(), "files" | "both") {
+            file_results = search_files(
+                &task_query,
+                &options,
+                max_results,
+                &root,
+                &ignore,
+                &path_index,
+                &cancel,
+            )?;
+        }
+        Ok((text_results, file_results))
+    })
+    .await
+    .context("Search task failed")??;
 
-    if matches!(search_type.as_str(), "text" | "both") {
-        text_results = search_text(query, &options, max_results, tool_context).await?;
-    }
-    if matches!(search_type.as_str(), "files" | "both") {
-        file_results = search_files(query, &options, max_results, tool_context).await?;
+    if cancel_token.is_cancelled() {
+        return Ok(ToolResult::err("Search cancelled"));
     }
 
     Ok(ToolResult::ok(format_search_results(
-        query,
+        &query,
         &text_results,
         &file_results,
     )))
 }
 
-async fn search_text(
-    query: &str,
-    options: &SearchOptions,
-    max_results: usize,
-    tool_context: &ToolContext,
-) -> Result<Vec<SearchTextResult>> {
-    let mut cmd = Command::new("rg");
-    cmd.arg("--json")
-        .arg("--with-filename")
-        .arg("--line-number")
-        .arg("--column")
-        .arg("--no-heading")
-        .arg("--color=never")
-        .arg("--max-count")
-        .arg(max_results.to_string())
-        .arg("--no-require-git")
-        .arg("--follow");
-
-    apply_default_exclude_globs(&mut cmd);
+fn build_matcher(query: &str, options: &SearchOptions) -> Result<RegexMatcher> {
+    Ok(RegexMatcherBuilder::new()
+        .case_insensitive(!options.case_sensitive.unwrap_or(false))
+        .word(options.whole_word.unwrap_or(false))
+        .fixed_strings(!options.regex.unwrap_or(false))
+        .line_terminator(Some(b'\n'))
+        .build(query)?)
+}
 
-    if !options.case_sensitive.unwrap_or(false) {
-        cmd.arg("--ignore-case");
+/// Builds the same filter set the `rg` invocation used to get from `--glob`:
+/// default excludes, the include/exclude patterns and `*.<ext>` per file type.
+fn build_overrides(root: &Path, options: &SearchOptions) -> Result<Override> {
+    let mut overrides = OverrideBuilder::new(root);
+    for pattern in DEFAULT_EXCLUDE_GLOBS {
+        overrides.add(pattern)?;
     }
-    if options.whole_word.unwrap_or(false) {
-        cmd.arg("--word-regexp");
+    if let Some(include_pattern) = options.include_pattern.as_deref() {
+        overrides
+            .add(include_pattern)
+            .with_context(|| format!("include_pattern '{include_pattern}'"))?;
     }
-    if !options.regex.unwrap_or(false) {
-        cmd.arg("--fixed-strings");
+    if let Some(exclude_pattern) = options.exclude_pattern.as_deref() {
+        overrides
+            .add(&format!("!{exclude_pattern}"))
+            .with_context(|| format!("exclude_pattern '{exclude_pattern}'"))?;
+    }
+    for file_type in options.file_types.as_deref().unwrap_or_default() {
+        let trimmed = file_type.trim().trim_start_matches('.');
+        if !trimmed.is_empty() {
+            overrides.add(&format!("*.{trimmed}"))?;
+        }
     }
-    apply_search_filters(&mut cmd, options);
+    Ok(overrides.build()?)
+}
 
-    cmd.arg(query).arg(".");
+fn build_walker(root: &Path, options: &SearchOptions, ignore: &IgnorePolicy) -> Result<Walk> {
+    let ignore = ignore.clone();
+    Ok(WalkBuilder::new(root)
+        .hidden(!options.include_hidden.unwrap_or(false))
+        .follow_links(true)
+        .require_git(false)
+        .overrides(build_overrides(root, options)?)
+        .sort_by_file_name(|a, b| a.cmp(b))
+        .filter_entry(move |entry| {
+            let is_dir = entry.file_type().is_some_and(|kind| kind.is_dir());
+            !ignore.is_ignored(entry.path(), is_dir)
+        })
+        .build())
+}
 
-    let output = cmd
-        .current_dir(tool_context.current_dir())
-        .output()
-        .await
-        .with_context(|| format!("Failed running search command for query '{query}'"))?;
-    let status_code = output.status.code().unwrap_or_default();
-    if !output.status.success() && status_code != 1 {
-        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
-        return Err(anyhow::anyhow!("Search command failed: {stderr}"));
-    }
+fn search_text(
+    matcher: &RegexMatcher,
+    options: &SearchOptions,
+    max_results: usize,
+    root: &Path,
+    ignore: &IgnorePolicy,
+    cancel: &CancellationToken,
+) -> Result<Vec<SearchTextResult>> {
+    let context_lines = context_lines(options);
+    let mut searcher = SearcherBuilder::new()
+        .line_number(true)
+        .binary_detection(BinaryDetection::quit(b'\x00'))
+        .before_context(context_lines)
+        .after_context(context_lines)
+        .build();
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
     let mut results = Vec::new();
-    for line in stdout.lines() {
-        let Ok(parsed) = serde_json::from_str::<Value>(line) else {
+    for entry in build_walker(root, options, ignore)? {
+        if cancel.is_cancelled() || results.len() >= max_results {
+            break;
+        }
+        let Ok(entry) = entry else {
             continue;
         };
-        if parsed.get("type").and_then(Value::as_str) != Some("match") {
+        if !entry.file_type().is_some_and(|kind| kind.is_file()) {
             continue;
         }
-
-        let data = parsed.get("data").unwrap_or(&Value::Null);
-        let file = data
-            .get("path")
-            .and_then(|v| v.get("text"))
-            .and_then(Value::as_str)
-            .unwrap_or_default()
-            .to_string();
-        if file.is_empty() {
-            continue;
+        let mut collector = MatchCollector {
+            matcher,
+            file: display_path(entry.path(), root),
+            context_lines,
+            limit: max_results - results.len(),
+            cancel,
+            results: Vec::new(),
+            pending_before: Vec::new(),
+        };
+        // Unreadable files are skipped, as ripgrep does.
+        if searcher
+            .search_path(matcher, entry.path(), &mut collector)
+            .is_ok()
+        {
+            results.extend(collector.results);
         }
+    }
+    Ok(results)
+}
 
-        results.push(SearchTextResult { file });
+fn context_lines(options: &SearchOptions) -> usize {
+    options.context_lines.unwrap_or(0).min(MAX_CONTEXT_LINES)
+}
 
-        if results.len() >= max_results {
-            break;
+/// Collects one file's matches. Context lines are held as "before" context for
+/// the next match and attached as "after" context to the previous one.
+struct MatchCollector<'a> {
+    matcher: &'a RegexMatcher,
+    file: String,
+    context_lines: usize,
+    limit: usize,
+    cancel: &'a CancellationToken,
+    results: Vec<SearchTextResult>,
+    pending_before: Vec<ContextLine>,
+}
+
+impl Sink for MatchCollector<'_> {
+    type Error = std::io::Error;
+
+    fn matched(&mut self, _searcher: &Searcher, mat: &SinkMatch<'_>) -> Result<bool, Self::Error> {
+        let line_number = mat.line_number().unwrap_or(0) as usize;
+        let column = self
+            .matcher
+            .find(mat.bytes())
+            .ok()
+            .flatten()
+            .map(|found| found.start() + 1)
+            .unwrap_or(1);
+        let context_lines = self.context_lines;
+        let context_before = self
+            .pending_before
+            .drain(..)
+            .filter(|ctx| ctx.line_number + context_lines >= line_number)
+            .collect();
+        self.results.push(SearchTextResult {
+            file: self.file.clone(),
+            line_number,
+            column,
+            text: line_text(mat.bytes()),
+            context_before,
+            context_after: Vec::new(),
+        });
+        Ok(self.results.len() < self.limit && !self.cancel.is_cancelled())
+    }
+
+    fn context(
+        &mut self,
+        _searcher: &Searcher,
+        context: &SinkContext<'_>,
+    ) -> Result<bool, Self::Error> {
+        let line = ContextLine {
+            line_number: context.line_number().unwrap_or(0) as usize,
+            text: line_text(context.bytes()),
+        };
+        if *context.kind() == SinkContextKind::After
+            && let Some(last) = self.results.last_mut()
+        {
+            last.context_after.push(line.clone());
         }
+        self.pending_before.push(line);
+        Ok(!self.cancel.is_cancelled())
     }
+}
 
-    Ok(results)
+fn line_text(bytes: &[u8]) -> String {
+    String::from_utf8_lossy(bytes)
+        .trim_end_matches(['\n', '\r'])
+        .to_string()
+}
+
+fn display_path(path: &Path, root: &Path) -> String {
+    path.strip_prefix(root)
+        .unwrap_or(path)
+        .to_string_lossy()
+        .to_string()
 }
 
-async fn search_files(
+/// Ranks indexed paths under `root` with the shared fuzzy scorer. Filters
+/// mirror the text walker: overrides apply to every ancestor directory, and
+/// hidden entries are skipped unless requested or explicitly whitelisted, and
+/// paths hidden by the ignore policy never show up.
+fn search_files(
     query: &str,
     options: &SearchOptions,
     max_results: usize,
-    tool_context: &ToolContext,
+    root: &Path,
+    ignore: &IgnorePolicy,
+    path_index: &Mutex<PathIndex>,
+    cancel: &CancellationToken,
 ) -> Result<Vec<FileSearchResult>> {
-    let mut cmd = Command::new("rg");
-    cmd.arg("--files").arg("--no-require-git").arg("--follow");
-
-    apply_default_exclude_globs(&mut cmd);
-    apply_search_filters(&mut cmd, options);
-
-    cmd.arg(".");
-
-    let output = cmd
-        .current_dir(tool_context.current_dir())
-        .output()
-        .await
-        .context("Failed running file search command")?;
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
-        return Err(anyhow::anyhow!("File search command failed: {stderr}"));
-    }
-
-    let query_lower = query.to_ascii_lowercase();
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let mut results = Vec::new();
-    for line in stdout.lines() {
-        let raw = line.trim();
-        if raw.is_empty() {
-            continue;
-        }
-        let path = normalize_file_path(raw);
-        let file_name = PathBuf::from(&path)
-            .file_name()
-            .map(|n| n.to_string_lossy().to_string())
-            .unwrap_or_else(|| path.clone());
-        let score = calculate_file_score(&file_name, &path, &query_lower);
-        if score > 0 {
-            results.push(FileSearchResult { path, score });
-        }
-    }
+    let overrides = build_overrides(root, options)?;
+    let include_hidden = options.include_hidden.unwrap_or(false);
+    let mut index = path_index.lock().unwrap_or_else(PoisonError::into_inner);
+    index.refresh();
+    if cancel.is_cancelled() {
+        return Ok(Vec::new());
+    }
+
+    let prefix = root
+        .strip_prefix(index.root())
+        .unwrap_or(Path::new(""))
+        .components()
+        .map(|part| part.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/");
+    let mut results = index
+        .entries()
+        .filter(|entry| !entry.is_dir)
+        .filter(|entry| !ignore.is_ignored(&index.root().join(&entry.path), false))
+        .filter_map(|entry| {
+            let path = if prefix.is_empty() {
+                entry.path.as_str()
+            } else {
+                entry.path.strip_prefix(&prefix)?.strip_prefix('/')?
+            };
+            if !passes_filters(&overrides, path, include_hidden) {
+                return None;
+            }
+            score_path(query, path).map(|score| FileSearchResult {
+                path: path.to_string(),
+                score,
+            })
+        })
+        .collect::<Vec<_>>();
 
     results.sort_by(|a, b| match b.score.cmp(&a.score) {
         Ordering::Equal => a.path.cmp(&b.path),
@@ -205,70 +377,23 @@ async fn search_files(
     Ok(results)
 }
 
-fn apply_default_exclude_globs(cmd: &mut Command) {
-    for pattern in ["!.git/**", "!node_modules/**", "!.DS_Store", "!*.log"] {
-        cmd.arg("--glob").arg(pattern);
-    }
-}
-
-fn apply_search_filters(cmd: &mut Command, options: &SearchOptions) {
-    if options.include_hidden.unwrap_or(false) {
-        cmd.arg("--hidden");
-    }
-    if let Some(include_pattern) = options.include_pattern.as_deref() {
-        cmd.arg("--glob").arg(include_pattern);
-    }
-    if let Some(exclude_pattern) = options.exclude_pattern.as_deref() {
-        cmd.arg("--glob").arg(format!("!{exclude_pattern}"));
-    }
-    append_file_type_globs(cmd, options.file_types.as_deref());
-}
-
-fn append_file_type_globs(cmd: &mut Command, file_types: Option<&[String]>) {
-    if let Some(file_types) = file_types {
-        for file_type in file_types {
-            let trimmed = file_type.trim().trim_start_matches('.');
-            if !trimmed.is_empty() {
-                cmd.arg("--glob").arg(format!("*.{trimmed}"));
-            }
+fn passes_filters(overrides: &Override, path: &str, include_hidden: bool) -> bool {
+    let parts = path.split('/').collect::<Vec<_>>();
+    for depth in 1..=parts.len() {
+        let is_dir = depth < parts.len();
+        let partial = parts[..depth].join("/");
+        let matched = overrides.matched(&partial, is_dir);
+        if matched.is_ignore() {
+            return false;
         }
-    }
-}
-
-fn normalize_file_path(path: &str) -> String {
-    path.strip_prefix("./")
-        .map(|trimmed| trimmed.to_string())
-        .unwrap_or_else(|| path.to_string())
-}
-
-fn calculate_file_score(file_name: &str, file_path: &str, pattern: &str) -> i32 {
-    let lower_name = file_name.to_ascii_lowercase();
-    let lower_path = file_path.to_ascii_lowercase();
-
-    if lower_name == pattern {
-        return 100;
-    }
-    if lower_name.contains(pattern) {
-        return 80;
-    }
-    if lower_path.contains(pattern) {
-        return 60;
-    }
-
-    let mut pattern_index = 0usize;
-    let pattern_chars = pattern.chars().collect::<Vec<_>>();
-    for ch in lower_name.chars() {
-        if pattern_index < pattern_chars.len() && ch == pattern_chars[pattern_index] {
-            pattern_index += 1;
+        if matched.is_whitelist() {
+            continue;
+        }
+        if !include_hidden && parts[depth - 1].starts_with('.') {
+            return false;
         }
     }
-
-    if pattern_index == pattern_chars.len() {
-        let length_penalty = lower_name.chars().count() as i32 - pattern_chars.len() as i32;
-        return (40 - length_penalty).max(10);
-    }
-
-    0
+    true
 }
 
 fn format_search_results(
@@ -280,83 +405,136 @@ fn format_search_results(
         return format!("No results found for \"{query}\"");
     }
 
-    let mut match_counts: HashMap<String, usize> = HashMap::new();
+    let mut grouped: Vec<(&str, Vec<&SearchTextResult>)> = Vec::new();
     for result in text_results {
-        *match_counts.entry(result.file.clone()).or_insert(0) += 1;
+        match grouped.iter_mut().find(|(file, _)| *file == result.file) {
+            Some((_, matches)) => matches.push(result),
+            None => grouped.push((result.file.as_str(), vec![result])),
+        }
     }
 
-    let mut ordered_files = Vec::new();
-    let mut seen: HashSet<String> = HashSet::new();
-    for result in text_results {
-        if seen.insert(result.file.clone()) {
-            ordered_files.push(result.file.clone());
+    let mut output = format!("Search results for \"{query}\":");
+    let mut shown_matches = 0usize;
+    let mut shown_files = 0usize;
+    let mut truncated = false;
+    for (file, matches) in &grouped {
+        let block = render_file_matches(file, matches);
+        if output.len() + block.len() + 1 > MAX_OUTPUT_CHARS {
+            truncated = true;
+            break;
         }
+        output.push('\n');
+        output.push_str(&block);
+        shown_matches += matches.len();
+        shown_files += 1;
+    }
+    if truncated {
+        output.push_str(&format!(
+            "\n... output limit reached: {} more matches in {} files not shown. Narrow the query or use include_pattern.",
+            text_results.len() - shown_matches,
+            grouped.len() - shown_files
+        ));
     }
-    for result in file_results {
-        if seen.insert(result.path.clone()) {
-            ordered_files.push(result.path.clone());
+
+    let seen: HashSet<&str> = grouped.iter().map(|(file, _)| *file).collect();
+    let name_matches = file_results
+        .iter()
+        .filter(|result| !seen.contains(result.path.as_str()))
+        .collect::<Vec<_>>();
+    if !name_matches.is_empty() && !truncated {
+        let display_limit = 8usize;
+        output.push_str("\nFiles with matching names:");
+        for result in name_matches.iter().take(display_limit) {
+            output.push_str(&format!("\n  {}", result.path));
+        }
+        if name_matches.len() > display_limit {
+            output.push_str(&format!(
+                "\n  ... +{} more",
+                name_matches.len() - display_limit
+            ));
         }
     }
 
-    let display_limit = 8usize;
-    let mut lines = vec![format!("Search results for \"{query}\":")];
-    for file in ordered_files.iter().take(display_limit) {
-        let match_count = match_counts.get(file).copied().unwrap_or(0);
-        if match_count > 0 {
-            lines.push(format!("  {file} ({match_count} matches)"));
-        } else {
-            lines.push(format!("  {file}"));
+    output
+}
+
+/// Renders one file's matches grep-style: `line:column: text` for matches,
+/// `line- text` for context, and `--` between non-adjacent blocks.
+fn render_file_matches(file: &str, matches: &[&SearchTextResult]) -> String {
+    let noun = if matches.len() == 1 {
+        "match"
+    } else {
+        "matches"
+    };
+    let mut lines: BTreeMap<usize, String> = BTreeMap::new();
+    for result in matches {
+        for ctx in result.context_before.iter().chain(&result.context_after) {
+            lines
+                .entry(ctx.line_number)
+                .or_insert_with(|| format!("{}- {}", ctx.line_number, truncate_line(&ctx.text)));
         }
+        lines.insert(
+            result.line_number,
+            format!(
+                "{}:{}: {}",
+                result.line_number,
+                result.column,
+                truncate_line(&result.text)
+            ),
+        );
     }
-    if ordered_files.len() > display_limit {
-        lines.push(format!(
-            "  ... +{} more",
-            ordered_files.len() - display_limit
-        ));
+
+    let mut rendered = format!("{file} ({} {noun})", matches.len());
+    let mut previous: Option<usize> = None;
+    for (line_number, line) in lines {
+        if previous.is_some_and(|previous| line_number > previous + 1) {
+            rendered.push_str("\n  --");
+        }
+        rendered.push_str("\n  ");
+        rendered.push_str(&line);
+        previous = Some(line_number);
     }
+    rendered
+}
 
-    lines.join("\n")
+fn truncate_line(text: &str) -> String {
+    let trimmed = text.trim_end();
+    match trimmed.char_indices().nth(MAX_LINE_CHARS) {
+        Some((cut, _)) => format!("{}...", &trimmed[..cut]),
+        None => trimmed.to_string(),
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{
-        FileSearchResult, SearchTextResult, calculate_file_score, execute_search,
-        format_search_results, normalize_file_path,
-    };
+    use super::{FileSearchResult, SearchTextResult, execute_search, format_search_results};
+    use crate::ignore_policy::IgnorePolicy;
+    use crate::path_index::PathIndex;
     use crate::tool_context::ToolContext;
     use serde_json::json;
     use std::fs;
     use std::path::{Path, PathBuf};
+    use std::sync::{Arc, Mutex};
     use std::time::{SystemTime, UNIX_EPOCH};
-
-    #[test]
-    fn normalize_file_path_trims_leading_dot_slash() {
-        assert_eq!(normalize_file_path("./src/main.rs"), "src/main.rs");
-        assert_eq!(normalize_file_path("src/lib.rs"), "src/lib.rs");
-    }
-
-    #[test]
-    fn calculate_file_score_prioritizes_exact_and_substring_matches() {
-        assert!(calculate_file_score("main.rs", "src/main.rs", "main.rs") >= 100);
-        assert!(
-            calculate_file_score("search_tool.rs", "src/tools/search_tool.rs", "search")
-                > calculate_file_score("tooling.rs", "src/tooling.rs", "search")
-        );
+    use tokio_util::sync::CancellationToken;
+
+    fn text_match(file: &str, line_number: usize, text: &str) -> SearchTextResult {
+        SearchTextResult {
+            file: file.to_string(),
+            line_number,
+            column: 1,
+            text: text.to_string(),
+            context_before: Vec::new(),
+            context_after: Vec::new(),
+        }
     }
 
     #[test]
-    fn format_search_results_deduplicates_and_limits_output() {
+    fn format_search_results_groups_matches_by_file() {
         let text_results = vec![
-            SearchTextResult {
-                file: "src/a.rs".to_string(),
-            },
-            SearchTextResult {
-                file: "src/a.rs".to_string(),
-            },
-            SearchTextResult {
-                file: "src/b.rs".to_string(),
-            },
+            text_match("src/a.rs", 3, "let abc = 1;"),
+            text_match("src/a.rs", 9, "abc();"),
+            text_match("src/b.rs", 1, "// abc"),
         ];
 
         let file_results = vec![
@@ -372,9 +550,226 @@ mod tests {
 
         let output = format_search_results("abc", &text_results, &file_results);
         assert!(output.contains("Search results for \"abc\":"));
-        assert!(output.contains("src/a.rs (2 matches)"));
-        assert!(output.contains("src/b.rs (1 matches)"));
-        assert!(output.contains("src/c.rs"));
+        assert!(output.contains("src/a.rs (2 matches)\n  3:1: let abc = 1;\n  --\n  9:1: abc();"));
+        assert!(output.contains("src/b.rs (1 match)\n  1:1: // abc"));
+        assert!(output.ends_with("Files with matching names:\n  src/c.rs"));
+    }
+
+    #[test]
+    fn format_search_results_caps_output_by_char_budget() {
+        let long_line = "x".repeat(250);
+        let text_results = (0..200)
+            .map(|index| text_match(&format!("src/file{index}.rs"), 1, &long_line))
+            .collect::<Vec<_>>();
+
+        let output = format_search_results("x", &text_results, &[]);
+        assert!(output.len() <= super::MAX_OUTPUT_CHARS + 200);
+        assert!(output.contains("... output limit reached:"));
+        assert!(output.contains("Narrow the query"));
+    }
+
+    #[tokio::test]
+    async fn execute_search_reports_lines_columns_and_context() {
+        let temp = TempDir::new("search-context");
+        fs::create_dir_all(temp.path().join("src")).expect("create src");
+        fs::write(
+            temp.path().join("src/lib.rs"),
+            "fn helper() {\n    needle();\n}\n\nfn other() {}\n",
+        )
+        .expect("write");
+        let context = ToolContext::new(temp.path().to_path_buf()).expect("tool context");
+
+        let result = execute_search(
+            &json!({ "query": "needle", "search_type": "text", "context_lines": 1 }),
+            &context,
+            &index_for(&context),
+            &CancellationToken::new(),
+        )
+        .await
+        .expect("search");
+        let output = result.output.expect("output");
+        assert!(
+            output
+                .contains("src/lib.rs (1 match)\n  1- fn helper() {\n  2:5:     needle();\n  3- }")
+        );
+    }
+
+    #[tokio::test]
+    async fn execute_search_honors_filters_and_gitignore() {
+        let temp = TempDir::new("search-filters");
+        let root = temp.path();
+        fs::create_dir_all(root.join("target")).expect("create target");
+        fs::write(root.join(".gitignore"), "target/\n").expect("write");
+        fs::write(root.join("a.rs"), "Token here\n").expect("write");
+        fs::write(root.join("b.ts"), "token there\n").expect("write");
+        fs::write(root.join("c.rs"), "tokens plural\n").expect("write");
+        fs::write(root.join("target/gen.rs"), "token generated\n").expect("write");
+        fs::write(root.join(".hidden.rs"), "token hidden\n").expect("write");
+        let context = ToolContext::new(root.to_path_buf()).expect("tool context");
+        let cancel = CancellationToken::new();
+
+        let output = execute_search(
+            &json!({ "query": "token", "search_type": "text", "file_types": ["rs"], "whole_word": true }),
+            &context,
+            &index_for(&context),
+            &cancel,
+        )
+        .await
+        .expect("search")
+        .output
+        .expect("output");
+        assert!(output.contains("a.rs (1 match)"));
+        assert!(!output.contains("b.ts"));
+        assert!(!output.contains("c.rs"));
+        assert!(!output.contains("gen.rs"));
+
+        let unfiltered = execute_search(
+            &json!({ "query": "token", "search_type": "text" }),
+            &context,
+            &index_for(&context),
+            &cancel,
+        )
+        .await
+        .expect("search")
+        .output
+        .expect("output");
+        assert!(unfiltered.contains("c.rs (1 match)"));
+        assert!(!unfiltered.contains(".hidden.rs"));
+
+        let case_sensitive = execute_search(
+            &json!({ "query": "tok.n", "search_type": "text", "regex": true, "case_sensitive": true, "include_hidden": true }),
+            &context,
+            &index_for(&context),
+            &cancel,
+        )
+        .await
+        .expect("search")
+        .output
+        .expect("output");
+        assert!(!case_sensitive.contains("a.rs"));
+        assert!(case_sensitive.contains("b.ts"));
+        assert!(case_sensitive.contains(".hidden.rs"));
+
+        let files = execute_search(
+            &json!({ "query": "b.ts", "search_type": "files" }),
+            &context,
+            &index_for(&context),
+            &cancel,
+        )
+        .await
+        .expect("search")
+        .output
+        .expect("output");
+        assert!(files.contains("Files with matching names:\n  b.ts"));
+    }
+
+    #[tokio::test]
+    async fn execute_search_ranks_files_fuzzily_within_current_dir() {
+        let temp = TempDir::new("search-files-fuzzy");
+        let root = temp.path();
+        fs::create_dir_all(root.join("src/tools")).expect("create dirs");
+        fs::create_dir_all(root.join("docs")).expect("create dirs");
+        fs::create_dir_all(root.join("target")).expect("create dirs");
+        fs::write(root.join(".gitignore"), "target/\n").expect("write");
+        fs::write(root.join("src/tools/search_tool.rs"), "").expect("write");
+        fs::write(root.join("src/session_store.rs"), "").expect("write");
+        fs::write(root.join("docs/search_tool.md"), "").expect("write");
+        fs::write(root.join("target/search_tool.rs"), "").expect("write");
+        let mut context = ToolContext::new(root.to_path_buf()).expect("tool context");
+        let index = index_for(&context);
+        let cancel = CancellationToken::new();
+
+        let output = execute_search(
+            &json!({ "query": "stool", "search_type": "files" }),
+            &context,
+            &index,
+            &cancel,
+        )
+        .await
+        .expect("search")
+        .output
+        .expect("output");
+        assert!(output.contains("src/tools/search_tool.rs"));
+        assert!(output.contains("docs/search_tool.md"));
+        assert!(!output.contains("target/"));
+        assert!(!output.contains("session_store.rs"));
+
+        context.set_current_dir("src").expect("cd src");
+        let output = execute_search(
+            &json!({ "query": "stool", "search_type": "files" }),
+            &context,
+            &index,
+            &cancel,
+        )
+        .await
+        .expect("search")
+        .output
+        .expect("output");
+        assert!(output.contains("Files with matching names:\n  tools/search_tool.rs"));
+        assert!(!output.contains("docs/"));
+    }
+
+    #[tokio::test]
+    async fn execute_search_skips_ignored_paths() {
+        let temp = TempDir::new("search-ignored");
+        let root = temp.path();
+        fs::create_dir_all(root.join("secrets")).expect("create dirs");
+        fs::write(root.join(".grokignore"), "secrets/\n").expect("write");
+        fs::write(root.join("secrets/token.txt"), "token hidden\n").expect("write");
+        fs::write(root.join("notes.txt"), "token visible\n").expect("write");
+        fs::write(root.join("dump.sql"), "token dumped\n").expect("write");
+        let mut context = ToolContext::new(root.to_path_buf()).expect("tool context");
+        context.set_ignore_policy(
+            IgnorePolicy::load(context.project_root(), &["*.sql".to_string()]).expect("policy"),
+        );
+
+        let output = execute_search(
+            &json!({ "query": "token" }),
+            &context,
+            &index_for(&context),
+            &CancellationToken::new(),
+        )
+        .await
+        .expect("search")
+        .output
+        .expect("output");
+        assert!(output.contains("notes.txt (1 match)"));
+        assert!(!output.contains("secrets"));
+        assert!(!output.contains("dump.sql"));
+    }
+
+    #[tokio::test]
+    async fn execute_search_reports_invalid_patterns_and_cancellation() {
+        let temp = TempDir::new("search-invalid");
+        fs::write(temp.path().join("a.txt"), "x\n").expect("write");
+        let context = ToolContext::new(temp.path().to_path_buf()).expect("tool context");
+
+        let invalid = execute_search(
+            &json!({ "query": "(", "regex": true }),
+            &context,
+            &index_for(&context),
+            &CancellationToken::new(),
+        )
+        .await
+        .expect("search");
+        assert!(
+            invalid
+                .error
+                .as_deref()
+                .is_some_and(|msg| msg.starts_with("Invalid search pattern"))
+        );
+
+        let cancel = CancellationToken::new();
+        cancel.cancel();
+        let cancelled = execute_search(
+            &json!({ "query": "x" }),
+            &context,
+            &index_for(&context),
+            &cancel,
+        )
+        .await
+        .expect("search");
+        assert_eq!(cancelled.error.as_deref(), Some("Search cancelled"));
     }
 
     #[tokio::test]
@@ -382,9 +777,14 @@ mod tests {
         let temp = TempDir::new("search-empty-query");
         let context = ToolContext::new(temp.path().to_path_buf()).expect("tool context");
 
-        let result = execute_search(&json!({ "query": "   " }), &context)
-            .await
-            .expect("search execution");
+        let result = execute_search(
+            &json!({ "query": "   " }),
+            &context,
+            &index_for(&context),
+            &CancellationToken::new(),
+        )
+        .await
+        .expect("search execution");
         assert!(!result.success);
         assert_eq!(
             result.error.as_deref(),
@@ -403,6 +803,8 @@ mod tests {
                 "search_type": "unsupported"
             }),
             &context,
+            &index_for(&context),
+            &CancellationToken::new(),
         )
         .await
         .expect("search execution");
@@ -415,6 +817,12 @@ mod tests {
         );
     }
 
+    fn index_for(context: &ToolContext) -> Arc<Mutex<PathIndex>> {
+        Arc::new(Mutex::new(PathIndex::new(
+            context.project_root().to_path_buf(),
+        )))
+    }
+
     struct TempDir {
         path: PathBuf,
     }
@@ -0,0 +1,490 @@
+use super::bash_tool::kill_process_group;
+#[cfg(unix)]
+use super::bash_tool::signal_process_group;
+use super::{ToolOutputStream, ToolProgress};
+use crate::sandbox::{PROXY_ENV_VARS, SandboxPolicy};
+use anyhow::{Context, Result};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWriteExt, BufReader};
+use tokio::process::{Child, ChildStdin, Command};
+use tokio::sync::mpsc;
+use tokio_util::sync::CancellationToken;
+
+/// Variables the shell maintains itself; never worth saving or restoring.
+const SHELL_MANAGED_VARS: &[&str] = &["PWD", "OLDPWD", "SHLVL", "_"];
+
+pub(super) enum ShellExit {
+    Finished {
+        status: i32,
+        cwd: Option<PathBuf>,
+    },
+    TimedOut(Duration),
+    Cancelled,
+    /// The shell itself exited, e.g. because the command ran `exit`.
+    ShellExited,
+}
+
+pub(super) struct ShellOutcome {
+    pub stdout: String,
+    pub stderr: String,
+    pub exit: ShellExit,
+}
+
+/// The opt-in long-lived shell shared by every bash call in a session, so
+/// `export`, `source venv/bin/activate` and shell functions carry over.
+#[derive(Default)]
+pub(crate) struct ShellSession {
+    enabled: bool,
+    shell: Option<PersistentShell>,
+    /// Exported variables that differ from codetoad's own environment. Kept
+    /// across shell restarts and saved in session snapshots.
+    env: BTreeMap<String, String>,
+}
+
+impl ShellSession {
+    pub(crate) fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub(crate) fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+        if !enabled {
+            self.shell = None;
+        }
+    }
+
+    pub(crate) fn env(&self) -> &BTreeMap<String, String> {
+        &self.env
+    }
+
+    /// Replaces the saved environment; a running shell is restarted with it.
+    pub(crate) fn restore_env(&mut self, env: BTreeMap<String, String>) {
+        self.env = env;
+        self.shell = None;
+    }
+
+    pub(super) async fn run(
+        &mut self,
+        command: &str,
+        cwd: &Path,
+        sandbox: &SandboxPolicy,
+        timeout: Duration,
+        cancel_token: &CancellationToken,
+        progress: Option<ToolProgress>,
+    ) -> Result<ShellOutcome> {
+        if self
+            .shell
+            .as_ref()
+            .is_some_and(|shell| &shell.sandbox != sandbox)
+        {
+            self.shell = None;
+        }
+        if self.shell.is_none() {
+            self.shell = Some(PersistentShell::start(cwd, &self.env, sandbox)?);
+        }
+        let Some(shell) = self.shell.as_mut() else {
+            unreachable!("shell was just started");
+        };
+
+        let outcome = shell
+            .run(command, cwd, timeout, cancel_token, progress)
+            .await;
+        match &outcome {
+            Ok(ShellOutcome {
+                exit: ShellExit::Finished { .. },
+                ..
+            }) => {
+                if let Some(env) = shell.read_env() {
+                    self.env = env;
+                }
+            }
+            _ => {
+                if let Some(mut shell) = self.shell.take() {
+                    kill_process_group(&mut shell.child).await;
+                }
+            }
+        }
+        outcome
+    }
+}
+
+struct PersistentShell {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: mpsc::UnboundedReceiver<Vec<u8>>,
+    stderr: mpsc::UnboundedReceiver<Vec<u8>>,
+    /// Marks the end of each command's output on both streams.
+    sentinel: String,
+    env_file: PathBuf,
+    /// The policy the shell was started under; a change needs a new shell.
+    sandbox: SandboxPolicy,
+}
+
+impl PersistentShell {
+    fn start(cwd: &Path, env: &BTreeMap<String, String>, sandbox: &SandboxPolicy) -> Result<Self> {
+        let mut process = if Path::new("/bin/bash").exists() {
+            let mut process = Command::new("bash");
+            process.args(["--noprofile", "--norc", "-s"]);
+            process
+        } else {
+            let mut process = Command::new("sh");
+            process.arg("-s");
+            process
+        };
+        process
+            .current_dir(cwd)
+            .envs(env)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+        #[cfg(unix)]
+        process.process_group(0);
+        sandbox.apply(&mut process)?;
+        let mut child = process
+            .spawn()
+            .context("Failed to start persistent shell")?;
+
+        let stdin = child.stdin.take().context("Shell stdin unavailable")?;
+        let stdout = spawn_line_reader(child.stdout.take().context("Shell stdout unavailable")?);
+        let stderr = spawn_line_reader(child.stderr.take().context("Shell stderr unavailable")?);
+        let nonce = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_nanos())
+            .unwrap_or_default();
+        let pid = std::process::id();
+        Ok(Self {
+            child,
+            stdin,
+            stdout,
+            stderr,
+            sentinel: format!("__CODETOAD_DONE_{pid}_{nonce}__"),
+            env_file: std::env::temp_dir().join(format!("codetoad-shell-env-{pid}-{nonce}")),
+            sandbox: sandbox.clone(),
+        })
+    }
+
+    async fn run(
+        &mut self,
+        command: &str,
+        cwd: &Path,
+        timeout: Duration,
+        cancel_token: &CancellationToken,
+        progress: Option<ToolProgress>,
+    ) -> Result<ShellOutcome> {
+        // `eval` keeps a syntax error in the command from desynchronizing the
+        // framing, and stdin is redirected so the command cannot eat the
+        // script that follows it.
+        let script = format!(
+            "cd {cwd} 2>/dev/null\n\
+             eval {command} </dev/null\n\
+             __codetoad_status=$?\n\
+             env -0 > {env_file} 2>/dev/null\n\
+             printf '\\n%s %s %s\\n' '{sentinel}' \"$__codetoad_status\" \"$PWD\"\n\
+             printf '\\n%s\\n' '{sentinel}' >&2\n",
+            cwd = shell_quote(&cwd.to_string_lossy()),
+            command = shell_quote(command),
+            env_file = shell_quote(&self.env_file.to_string_lossy()),
+            sentinel = self.sentinel,
+        );
+        if self.stdin.write_all(script.as_bytes()).await.is_err()
+            || self.stdin.flush().await.is_err()
+        {
+            return Ok(ShellOutcome {
+                stdout: String::new(),
+                stderr: String::new(),
+                exit: ShellExit::ShellExited,
+            });
+        }
+
+        let status_prefix = format!("{} ", self.sentinel);
+        let mut stdout = Vec::new();
+        let mut stderr = Vec::new();
+        let mut finished: Option<(i32, Option<PathBuf>)> = None;
+        let mut stderr_done = false;
+        let deadline = tokio::time::sleep(timeout);
+        tokio::pin!(deadline);
+
+        let exit = loop {
+            if let Some((status, cwd)) = &finished
+                && stderr_done
+            {
+                break ShellExit::Finished {
+                    status: *status,
+                    cwd: cwd.clone(),
+                };
+            }
+            tokio::select! {
+                line = self.stdout.recv(), if finished.is_none() => {
+                    let Some(line) = line else {
+                        break ShellExit::ShellExited;
+                    };
+                    let text = String::from_utf8_lossy(&line);
+                    if let Some(rest) = text.strip_prefix(&status_prefix) {
+                        let (status, pwd) = rest.split_once(' ').unwrap_or((rest, ""));
+                        finished = Some((
+                            status.trim().parse().unwrap_or(1),
+                            (!pwd.is_empty()).then(|| PathBuf::from(pwd)),
+                        ));
+                    } else {
+                        record_line(&mut stdout, &text, ToolOutputStream::Stdout, &progress);
+                    }
+                }
+                line = self.stderr.recv(), if !stderr_done => {
+                    let Some(line) = line else {
+                        break ShellExit::ShellExited;
+                    };
+                    let text = String::from_utf8_lossy(&line);
+                    if text == self.sentinel {
+                        stderr_done = true;
+                    } else {
+                        record_line(&mut stderr, &text, ToolOutputStream::Stderr, &progress);
+                    }
+                }
+                _ = &mut deadline => break ShellExit::TimedOut(timeout),
+                _ = cancel_token.cancelled() => break ShellExit::Cancelled,
+            }
+        };
+
+        Ok(ShellOutcome {
+            stdout: stdout.join("\n"),
+            stderr: stderr.join("\n"),
+            exit,
+        })
+    }
+
+    /// Exported variables after the last command, minus those unchanged from
+    /// codetoad's own environment.
+    fn read_env(&self) -> Option<BTreeMap<String, String>> {
+        let bytes = std::fs::read(&self.env_file).ok()?;
+        let inherited = std::env::vars_os()
+            .filter_map(|(key, value)| Some((key.into_string().ok()?, value.into_string().ok()?)))
+            .collect::<BTreeMap<_, _>>();
+        Some(
+            bytes
+                .split(|byte| *byte == 0)
+                .filter_map(|entry| {
+                    let entry = std::str::from_utf8(entry).ok()?;
+                    let (key, value) = entry.split_once('=')?;
+                    Some((key.to_string(), value.to_string()))
+                })
+                .filter(|(key, value)| {
+                    !key.is_empty()
+                        && !SHELL_MANAGED_VARS.contains(&key.as_str())
+                        && !PROXY_ENV_VARS.contains(&key.as_str())
+                        && inherited.get(key) != Some(value)
+                })
+                .collect(),
+        )
+    }
+}
+
+impl Drop for PersistentShell {
+    fn drop(&mut self) {
+        #[cfg(unix)]
+        if let Some(pid) = self.child.id() {
+            signal_process_group(pid);
+        }
+        let _ = std::fs::remove_file(&self.env_file);
+    }
+}
+
+fn record_line(
+    lines: &mut Vec<String>,
+    text: &str,
+    stream: ToolOutputStream,
+    progress: &Option<ToolProgress>,
+) {
+    let display = text.trim_end_matches('\r');
+    if let Some(progress) = progress
+        && !display.is_empty()
+    {
+        progress(stream, display.to_string());
+    }
+    lines.push(text.to_string());
+}
+
+fn spawn_line_reader<R: AsyncRead + Unpin + Send + 'static>(
+    pipe: R,
+) -> mpsc::UnboundedReceiver<Vec<u8>> {
+    let (sender, receiver) = mpsc::unbounded_channel();
+    tokio::spawn(async move {
+        let mut reader = BufReader::new(pipe);
+        loop {
+            let mut line = Vec::new();
+            match reader.read_until(b'\n', &mut line).await {
+                Ok(0) | Err(_) => break,
+                Ok(_) => {
+                    if line.last() == Some(&b'\n') {
+                        line.pop();
+                    }
+                    if sender.send(line).is_err() {
+                        break;
+                    }
+                }
+            }
+        }
+    });
+    receiver
+}
+
+fn shell_quote(text: &str) -> String {
+    format!("'{}'", text.replace('\'', "'\\''"))
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::{ShellExit, ShellSession, shell_quote};
+    use crate::sandbox::SandboxPolicy;
+    use std::collections::BTreeMap;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+    use tokio_util::sync::CancellationToken;
+
+    async fn run(session: &mut ShellSession, command: &str, cwd: &Path) -> (String, i32) {
+        let outcome = session
+            .run(
+                command,
+                cwd,
+                &SandboxPolicy::default(),
+                Duration::from_secs(10),
+                &CancellationToken::new(),
+                None,
+            )
+            .await
+            .expect("run");
+        match outcome.exit {
+            ShellExit::Finished { status, .. } => (outcome.stdout.trim().to_string(), status),
+            _ => panic!("command did not finish"),
+        }
+    }
+
+    #[tokio::test]
+    async fn state_carries_across_commands_and_env_is_captured() {
+        let temp = TempDir::new("shell-session");
+        let mut session = ShellSession::default();
+        session.set_enabled(true);
+
+        run(
+            &mut session,
+            "export CODETOAD_TEST_VAR='a b'; greet() { echo \"hi $1\"; }",
+            temp.path(),
+        )
+        .await;
+        assert_eq!(
+            run(&mut session, "greet toad", temp.path()).await,
+            ("hi toad".to_string(), 0)
+        );
+        assert_eq!(
+            run(
+                &mut session,
+                "echo \"$CODETOAD_TEST_VAR\"; false",
+                temp.path()
+            )
+            .await,
+            ("a b".to_string(), 1)
+        );
+        assert_eq!(
+            session.env().get("CODETOAD_TEST_VAR").map(String::as_str),
+            Some("a b")
+        );
+        assert!(!session.env().contains_key("PWD"));
+
+        let (_, status) = run(&mut session, "echo 'unterminated", temp.path()).await;
+        assert_ne!(status, 0);
+        assert_eq!(
+            run(&mut session, "echo still alive", temp.path()).await.0,
+            "still alive"
+        );
+    }
+
+    #[tokio::test]
+    async fn reports_cwd_and_restarts_with_restored_env() {
+        let temp = TempDir::new("shell-restore");
+        fs::create_dir_all(temp.path().join("nested")).expect("create nested");
+        let mut session = ShellSession::default();
+        session.set_enabled(true);
+
+        let outcome = session
+            .run(
+                "cd nested",
+                temp.path(),
+                &SandboxPolicy::default(),
+                Duration::from_secs(10),
+                &CancellationToken::new(),
+                None,
+            )
+            .await
+            .expect("run");
+        let ShellExit::Finished { cwd: Some(cwd), .. } = outcome.exit else {
+            panic!("expected cwd");
+        };
+        assert!(cwd.ends_with("nested"));
+
+        session.restore_env(BTreeMap::from([(
+            "CODETOAD_RESTORED".to_string(),
+            "yes".to_string(),
+        )]));
+        assert_eq!(
+            run(&mut session, "echo $CODETOAD_RESTORED", temp.path())
+                .await
+                .0,
+            "yes"
+        );
+
+        let outcome = session
+            .run(
+                "exit 4",
+                temp.path(),
+                &SandboxPolicy::default(),
+                Duration::from_secs(10),
+                &CancellationToken::new(),
+                None,
+            )
+            .await
+            .expect("run");
+        assert!(matches!(outcome.exit, ShellExit::ShellExited));
+        assert_eq!(
+            run(&mut session, "echo $CODETOAD_RESTORED", temp.path())
+                .await
+                .0,
+            "yes"
+        );
+    }
+
+    #[test]
+    fn quotes_single_quotes() {
+        assert_eq!(shell_quote("it's"), "'it'\\''s'");
+    }
+
+    struct TempDir {
+        path: PathBuf,
+    }
+
+    impl TempDir {
+        fn new(prefix: &str) -> Self {
+            let nonce = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .expect("clock after epoch")
+                .as_nanos();
+            let pid = std::process::id();
+            let path = std::env::temp_dir().join(format!("codetoad-{prefix}-{pid}-{nonce}"));
+            fs::create_dir_all(&path).expect("create temp dir");
+            Self { path }
+        }
+
+        fn path(&self) -> &Path {
+            &self.path
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.path);
+        }
+    }
+}
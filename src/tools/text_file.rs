@@ -0,0 +1,209 @@
+const UTF8_BOM: &[u8] = &[0xEF, 0xBB, 0xBF];
+const UTF16_LE_BOM: &[u8] = &[0xFF, 0xFE];
+const UTF16_BE_BOM: &[u8] = &[0xFE, 0xFF];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum TextEncoding {
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum LineEnding {
+    Lf,
+    Crlf,
+}
+
+/// On-disk shape of a text file, captured on read so writes can reproduce it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) struct TextFormat {
+    pub encoding: TextEncoding,
+    pub bom: bool,
+    pub line_ending: LineEnding,
+}
+
+#[derive(Debug, Clone)]
+pub(super) struct DecodedText {
+    /// Decoded content with the BOM removed and original line endings intact.
+    pub text: String,
+    pub format: TextFormat,
+}
+
+impl Default for TextFormat {
+    fn default() -> Self {
+        Self {
+            encoding: TextEncoding::Utf8,
+            bom: false,
+            line_ending: LineEnding::Lf,
+        }
+    }
+}
+
+impl TextFormat {
+    /// Rewrites LF or CRLF input so it uses this file's line ending style.
+    pub fn apply_line_endings(&self, text: &str) -> String {
+        let normalized = text.replace("\r\n", "\n");
+        match self.line_ending {
+            LineEnding::Lf => normalized,
+            LineEnding::Crlf => normalized.replace('\n', "\r\n"),
+        }
+    }
+
+    pub fn encode(&self, text: &str) -> Vec<u8> {
+        match self.encoding {
+            TextEncoding::Utf8 => {
+                let mut bytes = Vec::with_capacity(text.len() + UTF8_BOM.len());
+                if self.bom {
+                    bytes.extend_from_slice(UTF8_BOM);
+                }
+                bytes.extend_from_slice(text.as_bytes());
+                bytes
+            }
+            TextEncoding::Utf16Le => {
+                let mut bytes = UTF16_LE_BOM.to_vec();
+                for unit in text.encode_utf16() {
+                    bytes.extend_from_slice(&unit.to_le_bytes());
+                }
+                bytes
+            }
+            TextEncoding::Utf16Be => {
+                let mut bytes = UTF16_BE_BOM.to_vec();
+                for unit in text.encode_utf16() {
+                    bytes.extend_from_slice(&unit.to_be_bytes());
+                }
+                bytes
+            }
+        }
+    }
+
+    /// Short human-readable note for non-default formats, e.g. `CRLF, UTF-8 BOM`.
+    pub fn describe(&self) -> Option<String> {
+        let mut parts = Vec::new();
+        if self.line_ending == LineEnding::Crlf {
+            parts.push("CRLF");
+        }
+        match (self.encoding, self.bom) {
+            (TextEncoding::Utf8, true) => parts.push("UTF-8 BOM"),
+            (TextEncoding::Utf16Le, _) => parts.push("UTF-16LE"),
+            (TextEncoding::Utf16Be, _) => parts.push("UTF-16BE"),
+            (TextEncoding::Utf8, false) => {}
+        }
+        if parts.is_empty() {
+            None
+        } else {
+            Some(parts.join(", "))
+        }
+    }
+}
+
+/// Decodes UTF-8 (with or without BOM) and BOM-marked UTF-16. Anything else is
+/// refused with a message suitable for returning to the model.
+pub(super) fn decode_text(bytes: &[u8]) -> Result<DecodedText, String> {
+    let (text, encoding, bom) = if let Some(rest) = bytes.strip_prefix(UTF16_LE_BOM) {
+        (
+            decode_utf16(rest, u16::from_le_bytes)?,
+            TextEncoding::Utf16Le,
+            true,
+        )
+    } else if let Some(rest) = bytes.strip_prefix(UTF16_BE_BOM) {
+        (
+            decode_utf16(rest, u16::from_be_bytes)?,
+            TextEncoding::Utf16Be,
+            true,
+        )
+    } else {
+        let (rest, bom) = match bytes.strip_prefix(UTF8_BOM) {
+            Some(rest) => (rest, true),
+            None => (bytes, false),
+        };
+        let text = std::str::from_utf8(rest).map_err(|err| {
+            format!(
+                "file is not valid UTF-8 (invalid byte at offset {}). Legacy encodings such as Latin-1 or Windows-1252 are not supported; convert the file to UTF-8 first",
+                err.valid_up_to() + if bom { UTF8_BOM.len() } else { 0 }
+            )
+        })?;
+        (text.to_string(), TextEncoding::Utf8, bom)
+    };
+
+    let line_ending = detect_line_ending(&text);
+    Ok(DecodedText {
+        text,
+        format: TextFormat {
+            encoding,
+            bom,
+            line_ending,
+        },
+    })
+}
+
+fn decode_utf16(bytes: &[u8], to_unit: fn([u8; 2]) -> u16) -> Result<String, String> {
+    if !bytes.len().is_multiple_of(2) {
+        return Err("file has a UTF-16 byte order mark but an odd number of bytes".to_string());
+    }
+    let units = bytes
+        .chunks_exact(2)
+        .map(|pair| to_unit([pair[0], pair[1]]))
+        .collect::<Vec<_>>();
+    String::from_utf16(&units).map_err(|_| "file contains invalid UTF-16 data".to_string())
+}
+
+fn detect_line_ending(text: &str) -> LineEnding {
+    let crlf = text.matches("\r\n").count();
+    let lf_only = text.matches('\n').count() - crlf;
+    if crlf > lf_only {
+        LineEnding::Crlf
+    } else {
+        LineEnding::Lf
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LineEnding, TextEncoding, decode_text};
+
+    #[test]
+    fn detects_crlf_and_bom_and_round_trips_bytes() {
+        let bytes = b"\xEF\xBB\xBFone\r\ntwo\r\n";
+        let decoded = decode_text(bytes).expect("decode");
+        assert_eq!(decoded.text, "one\r\ntwo\r\n");
+        assert!(decoded.format.bom);
+        assert_eq!(decoded.format.line_ending, LineEnding::Crlf);
+        assert_eq!(decoded.format.encode(&decoded.text), bytes.to_vec());
+        assert_eq!(
+            decoded.format.describe().as_deref(),
+            Some("CRLF, UTF-8 BOM")
+        );
+    }
+
+    #[test]
+    fn apply_line_endings_normalizes_mixed_input() {
+        let decoded = decode_text(b"a\r\nb\r\n").expect("decode");
+        assert_eq!(
+            decoded.format.apply_line_endings("x\ny\r\nz"),
+            "x\r\ny\r\nz"
+        );
+        let lf = decode_text(b"a\nb\n").expect("decode");
+        assert_eq!(lf.format.apply_line_endings("x\r\ny"), "x\ny");
+        assert_eq!(lf.format.describe(), None);
+    }
+
+    #[test]
+    fn decodes_and_reencodes_utf16_with_bom() {
+        let mut bytes = vec![0xFF, 0xFE];
+        for unit in "hé\n".encode_utf16() {
+            bytes.extend_from_slice(&unit.to_le_bytes());
+        }
+        let decoded = decode_text(&bytes).expect("decode");
+        assert_eq!(decoded.text, "hé\n");
+        assert_eq!(decoded.format.encoding, TextEncoding::Utf16Le);
+        assert_eq!(decoded.format.encode(&decoded.text), bytes);
+    }
+
+    #[test]
+    fn refuses_legacy_single_byte_encodings() {
+        let err = decode_text(b"caf\xE9\n").expect_err("latin-1 must be refused");
+        assert!(err.contains("not valid UTF-8"));
+        assert!(err.contains("offset 3"));
+    }
+}
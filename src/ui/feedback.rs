@@ -1,5 +1,6 @@
 use crate::agent::{ConfirmationDecision, ToolCallSummary};
 use crate::confirmation::ConfirmationOperation;
+use crate::permissions::{PermissionRule, RuleSource};
 use crate::tool_catalog::tool_display_name;
 use crate::tools::ToolResult;
 use anyhow::Result;
@@ -44,7 +45,8 @@ pub fn tool_label(tool: &ToolCallSummary) -> String {
 
 pub fn prompt_tool_confirmation(
     tool_call: &ToolCallSummary,
-    operation: ConfirmationOperation,
+    operation: Option<ConfirmationOperation>,
+    suggested_rule: &PermissionRule,
 ) -> Result<ConfirmationDecision> {
     println!();
     println!(
@@ -57,17 +59,22 @@ pub fn prompt_tool_confirmation(
         )
         .yellow()
     );
-    println!(
-        "{}",
-        format!("  operation: {}", confirmation_operation_label(operation)).dark_grey()
-    );
+    if let Some(operation) = operation {
+        println!(
+            "{}",
+            format!("  operation: {}", confirmation_operation_label(operation)).dark_grey()
+        );
+    }
     println!(
         "{}",
         format!("  details: {}", confirmation_detail(tool_call)).dark_grey()
     );
     println!(
         "{}",
-        "  [y] approve once   [a] approve all for this session   [n]/[Esc] reject".dark_grey()
+        format!(
+            "  [y] approve once   [a] allow {suggested_rule} this session   [p] allow it in project settings   [n]/[Esc] reject"
+        )
+        .dark_grey()
     );
     io::stdout().flush()?;
 
@@ -84,21 +91,28 @@ pub fn prompt_tool_confirmation(
                 println!("{}", "  -> approved".dark_green());
                 return Ok(ConfirmationDecision::Approve {
                     tool_call_id: tool_call.id.clone(),
-                    remember_for_session: false,
+                    remember: None,
                 });
             }
             KeyCode::Char('a') | KeyCode::Char('A') => {
                 println!(
                     "{}",
-                    format!(
-                        "  -> approved and remembered for {}",
-                        confirmation_operation_label(operation)
-                    )
-                    .dark_green()
+                    format!("  -> approved; allowing {suggested_rule} this session").dark_green()
+                );
+                return Ok(ConfirmationDecision::Approve {
+                    tool_call_id: tool_call.id.clone(),
+                    remember: Some(RuleSource::Session),
+                });
+            }
+            KeyCode::Char('p') | KeyCode::Char('P') => {
+                println!(
+                    "{}",
+                    format!("  -> approved; saved {suggested_rule} to project settings")
+                        .dark_green()
                 );
                 return Ok(ConfirmationDecision::Approve {
                     tool_call_id: tool_call.id.clone(),
-                    remember_for_session: true,
+                    remember: Some(RuleSource::Project),
                 });
             }
             KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => {
@@ -126,8 +140,14 @@ fn confirmation_detail(tool_call: &ToolCallSummary) -> String {
             return format!("command: {command}");
         }
         if let Some(path) = value.get("path").and_then(serde_json::Value::as_str) {
+            if value.get("overwrite").and_then(serde_json::Value::as_bool) == Some(true) {
+                return format!("path: {path} (overwrites existing file)");
+            }
             return format!("path: {path}");
         }
+        if let Some((source, destination)) = source_and_destination(&value) {
+            return format!("from: {source} to: {destination}");
+        }
     }
     "operation details unavailable".to_string()
 }
@@ -138,9 +158,16 @@ fn pretty_tool_name(name: &str) -> &str {
 
 fn tool_target(tool: &ToolCallSummary) -> String {
     if let Ok(value) = serde_json::from_str::<serde_json::Value>(&tool.arguments) {
+        if let Some((source, destination)) = source_and_destination(&value) {
+            return format!("{source} -> {destination}");
+        }
+        if let Some(id) = value.get("id").and_then(serde_json::Value::as_u64) {
+            return id.to_string();
+        }
         return value
-            .get("path")
+            .get("symbol")
             .and_then(serde_json::Value::as_str)
+            .or_else(|| value.get("path").and_then(serde_json::Value::as_str))
             .or_else(|| value.get("command").and_then(serde_json::Value::as_str))
             .or_else(|| value.get("query").and_then(serde_json::Value::as_str))
             .or_else(|| value.get("id").and_then(serde_json::Value::as_str))
@@ -150,6 +177,14 @@ fn tool_target(tool: &ToolCallSummary) -> String {
     String::new()
 }
 
+fn source_and_destination(value: &serde_json::Value) -> Option<(&str, &str)> {
+    let source = value.get("source").and_then(serde_json::Value::as_str)?;
+    let destination = value
+        .get("destination")
+        .and_then(serde_json::Value::as_str)?;
+    Some((source, destination))
+}
+
 #[cfg(test)]
 mod tests {
     use super::{
@@ -186,6 +221,16 @@ mod tests {
             arguments: r#"{"path":"src/main.rs"}"#.to_string(),
         };
         assert_eq!(confirmation_detail(&path_call), "path: src/main.rs");
+
+        let overwrite_call = ToolCallSummary {
+            id: "3".to_string(),
+            name: "create_file".to_string(),
+            arguments: r#"{"path":"a.txt","content":"x","overwrite":true}"#.to_string(),
+        };
+        assert_eq!(
+            confirmation_detail(&overwrite_call),
+            "path: a.txt (overwrites existing file)"
+        );
     }
 
     #[test]
@@ -217,6 +262,28 @@ mod tests {
         };
         assert_eq!(tool_target(&from_command), "git status");
 
+        let from_move = ToolCallSummary {
+            id: "4".to_string(),
+            name: "move_file".to_string(),
+            arguments: r#"{"source":"a.rs","destination":"b.rs"}"#.to_string(),
+        };
+        assert_eq!(tool_target(&from_move), "a.rs -> b.rs");
+        assert_eq!(confirmation_detail(&from_move), "from: a.rs to: b.rs");
+
+        let from_symbol = ToolCallSummary {
+            id: "5".to_string(),
+            name: "find_definition".to_string(),
+            arguments: r#"{"symbol":"Store::open","path":"src"}"#.to_string(),
+        };
+        assert_eq!(tool_target(&from_symbol), "Store::open");
+
+        let from_process = ToolCallSummary {
+            id: "6".to_string(),
+            name: "kill_process".to_string(),
+            arguments: r#"{"id":3}"#.to_string(),
+        };
+        assert_eq!(tool_target(&from_process), "3");
+
         let invalid = ToolCallSummary {
             id: "3".to_string(),
             name: "search".to_string(),
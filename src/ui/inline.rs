@@ -7,11 +7,14 @@ use crate::git_ops::{
 };
 use crate::image_input::prepare_user_input;
 use crate::onboarding::{ensure_active_provider_api_key, run_add_or_update_provider};
+use crate::path_index::PathIndex;
+use crate::permissions::{PermissionAction, PermissionRule, RuleSource};
 use crate::session_store::{list_sessions, load_session};
 use crate::slash_commands::{
     CommandGroup, ParsedSlashCommand, append_help_section, parse_slash_command,
 };
-use crate::tools::ToolResult;
+use crate::tool_catalog::TOOL_BASH;
+use crate::tools::{ProcessStatus, ToolOutputStream, ToolResult, format_process_table};
 use crate::ui::feedback::{
     print_logo_and_tips, print_tool_result, prompt_tool_confirmation, tool_label,
 };
@@ -23,8 +26,8 @@ use crossterm::event::{
 };
 use crossterm::execute;
 use crossterm::style::Stylize;
-use crossterm::terminal::{disable_raw_mode, enable_raw_mode};
-use std::collections::HashMap;
+use crossterm::terminal::{self, disable_raw_mode, enable_raw_mode};
+use std::collections::{HashMap, VecDeque};
 use std::io::{self, Write};
 use std::time::Instant;
 use tokio::sync::mpsc;
@@ -35,6 +38,7 @@ const DIRECT_COMMANDS: &[&str] = &[
     "ls", "pwd", "cd", "cat", "mkdir", "touch", "echo", "grep", "find", "cp", "mv", "rm",
 ];
 const STATUS_FRAMES: &[&str] = &["-", "\\", "|", "/"];
+const TOOL_OUTPUT_TAIL_LINES: usize = 6;
 
 struct StreamRawModeGuard;
 
@@ -58,23 +62,26 @@ pub async fn run_inline(app: AppContext, initial_message: Option<String>) -> Res
     println!();
     let _ = app.autosave_session().await?;
     let mut history: Vec<String> = Vec::new();
-    let mut auto_edit = app.auto_edit_enabled().await;
-    let mut synced_auto_edit = auto_edit;
+    let mut permission_mode = app.permission_mode().await;
     let mut current_model = app.agent().lock().await.current_model().to_string();
+    let mut path_index = PathIndex::new(app.cwd().to_path_buf());
+    path_index.set_ignore_policy(app.agent().lock().await.ignore_policy().clone());
 
     if let Some(initial) = initial_message {
         history.push(initial.clone());
-        if auto_edit != synced_auto_edit {
-            app.set_auto_edit_enabled(auto_edit).await;
-            synced_auto_edit = auto_edit;
-        }
-        handle_input(&initial, auto_edit, app.clone()).await?;
+        handle_input(&initial, app.clone()).await?;
         let _ = app.autosave_session().await?;
         current_model = app.agent().lock().await.current_model().to_string();
     }
 
     loop {
-        let Some(input) = read_prompt_line(&history, &mut auto_edit, &current_model)? else {
+        let Some(input) = read_prompt_line(
+            &history,
+            &mut permission_mode,
+            &current_model,
+            &mut path_index,
+        )?
+        else {
             break;
         };
         let input = input.trim().to_string();
@@ -85,11 +92,11 @@ pub async fn run_inline(app: AppContext, initial_message: Option<String>) -> Res
             break;
         }
         history.push(input.clone());
-        if auto_edit != synced_auto_edit {
-            app.set_auto_edit_enabled(auto_edit).await;
-            synced_auto_edit = auto_edit;
+        if permission_mode != app.permission_mode().await {
+            app.set_permission_mode(permission_mode).await;
         }
-        handle_input(&input, auto_edit, app.clone()).await?;
+        handle_input(&input, app.clone()).await?;
+        permission_mode = app.permission_mode().await;
         let _ = app.autosave_session().await?;
         current_model = app.agent().lock().await.current_model().to_string();
     }
@@ -103,13 +110,13 @@ fn recover_terminal_state() {
     let _ = execute!(stdout, DisableMouseCapture);
 }
 
-async fn handle_input(input: &str, auto_edit_enabled: bool, app: AppContext) -> Result<()> {
+async fn handle_input(input: &str, app: AppContext) -> Result<()> {
     if let Some(command) = parse_slash_command(input) {
         return handle_slash_command(command, app).await;
     }
 
     if is_direct_command(input) {
-        return handle_direct_command(input, auto_edit_enabled, app).await;
+        return handle_direct_command(input, app).await;
     }
 
     let prepared = prepare_user_input(input, app.cwd());
@@ -186,10 +193,45 @@ async fn handle_slash_command(command: ParsedSlashCommand, app: AppContext) -> R
                 .lock()
                 .await
                 .restore_session_snapshot(snapshot)?;
-            app.sync_auto_edit_from_agent().await;
+            app.sync_permission_mode_from_agent().await;
             app.set_active_session_name(name.clone()).await;
             println!("Loaded session: {name}");
         }
+        ParsedSlashCommand::Processes => {
+            let processes = app.agent().lock().await.background_processes();
+            if processes.is_empty() {
+                println!("No background processes.");
+                return Ok(());
+            }
+            println!("{}", format_process_table(&processes));
+            let running = processes
+                .iter()
+                .filter(|process| process.status == ProcessStatus::Running)
+                .map(|process| format!("{}  {}", process.id, process.command))
+                .collect::<Vec<_>>();
+            if running.is_empty() {
+                return Ok(());
+            }
+            let Some(selected) = select_option_inline(
+                "Kill a process (Esc keeps them running)",
+                &running,
+                None,
+                "No running processes.",
+            )?
+            else {
+                return Ok(());
+            };
+            if let Some(id) = selected
+                .split_whitespace()
+                .next()
+                .and_then(|id| id.parse().ok())
+            {
+                kill_background_process(id, &app).await;
+            }
+        }
+        ParsedSlashCommand::KillProcess(id) => {
+            kill_background_process(id, &app).await;
+        }
         ParsedSlashCommand::Providers => {
             let settings = app.settings();
             let (provider_ids, current_provider) = {
@@ -247,6 +289,17 @@ async fn handle_slash_command(command: ParsedSlashCommand, app: AppContext) -> R
     Ok(())
 }
 
+async fn kill_background_process(id: u32, app: &AppContext) {
+    match app.agent().lock().await.kill_background_process(id).await {
+        Ok(summary) => println!(
+            "Process {id} {}: {}",
+            summary.status.label(),
+            summary.command
+        ),
+        Err(err) => println!("{}", format!("{err:#}").red()),
+    }
+}
+
 async fn set_active_model(model: String, app: AppContext) -> Result<()> {
     let agent = app.agent();
     let settings = app.settings();
@@ -256,11 +309,7 @@ async fn set_active_model(model: String, app: AppContext) -> Result<()> {
     Ok(())
 }
 
-async fn handle_direct_command(
-    input: &str,
-    auto_edit_enabled: bool,
-    app: AppContext,
-) -> Result<()> {
+async fn handle_direct_command(input: &str, app: AppContext) -> Result<()> {
     let agent = app.agent();
     let tool_call = ToolCallSummary {
         id: "bash_inline_direct".to_string(),
@@ -268,28 +317,42 @@ async fn handle_direct_command(
         arguments: format!(r#"{{"command":"{}"}}"#, input.replace('"', "\\\"")),
     };
 
-    let auto_approved = {
-        agent
-            .lock()
-            .await
-            .is_operation_auto_approved(ConfirmationOperation::Bash)
+    let args = serde_json::json!({ "command": input });
+    let (permission, suggested_rule) = {
+        let agent = agent.lock().await;
+        (
+            agent.permission_for(TOOL_BASH, &args),
+            agent.suggested_rule(TOOL_BASH, &args),
+        )
     };
-    if !auto_edit_enabled && !auto_approved {
-        match prompt_tool_confirmation(&tool_call, ConfirmationOperation::Bash)? {
-            ConfirmationDecision::Approve {
-                remember_for_session,
-                ..
-            } => {
-                if remember_for_session {
+    match permission.action {
+        PermissionAction::Allow => {}
+        PermissionAction::Deny => {
+            print_tool_result(tool_call, ToolResult::err(permission.denial_message()));
+            return Ok(());
+        }
+        PermissionAction::Ask => {
+            let decision = prompt_tool_confirmation(
+                &tool_call,
+                Some(ConfirmationOperation::Bash),
+                &suggested_rule,
+            )?;
+            save_remembered_rule(&decision, &suggested_rule, &app).await;
+            match decision {
+                ConfirmationDecision::Approve {
+                    remember: Some(source),
+                    ..
+                } => {
                     agent
                         .lock()
                         .await
-                        .remember_operation_for_session(ConfirmationOperation::Bash);
+                        .add_permission_rule(source, suggested_rule);
+                }
+                ConfirmationDecision::Approve { remember: None, .. } => {}
+                ConfirmationDecision::Reject { .. } => {
+                    print_tool_result(tool_call, ToolResult::err("Operation cancelled by user"));
+                    return Ok(());
                 }
-            }
-            ConfirmationDecision::Reject { .. } => {
-                print_tool_result(tool_call, ToolResult::err("Operation cancelled by user"));
-                return Ok(());
             }
         }
     }
@@ -299,6 +362,27 @@ async fn handle_direct_command(
     Ok(())
 }
 
+/// "Remember in project" also writes the rule to `.grok/settings.json`; the
+/// agent keeps its own copy for the rest of the session.
+async fn save_remembered_rule(
+    decision: &ConfirmationDecision,
+    rule: &PermissionRule,
+    app: &AppContext,
+) {
+    if let ConfirmationDecision::Approve {
+        remember: Some(RuleSource::Project),
+        ..
+    } = decision
+        && let Err(err) = app
+            .settings()
+            .lock()
+            .await
+            .add_project_permission_rule(rule)
+    {
+        println!("{}", format!("  -> could not save rule: {err:#}").red());
+    }
+}
+
 async fn stream_agent_message(
     message: String,
     attachments: Vec<crate::protocol::ChatImageAttachment>,
@@ -346,6 +430,7 @@ async fn stream_agent_message(
     let mut tool_failed = 0usize;
     let mut cancel_requested = false;
     let mut token_count = 0usize;
+    let mut output_tail = ToolOutputTail::default();
 
     loop {
         let event = tokio::select! {
@@ -384,10 +469,19 @@ async fn stream_agent_message(
         };
 
         let Some(event) = event else {
+            output_tail.finish()?;
             finalize_stream_output(&mut started_content, &mut renderer, &mut status_width)?;
             break;
         };
 
+        if !matches!(
+            event,
+            AgentEvent::ToolProgress { .. } | AgentEvent::TokenCount(_)
+        ) {
+            clear_status_line(&mut status_width)?;
+            output_tail.finish()?;
+        }
+
         match event {
             AgentEvent::Content(chunk) => {
                 if !started_content {
@@ -403,9 +497,11 @@ async fn stream_agent_message(
             AgentEvent::ConfirmationRequest {
                 tool_call,
                 operation,
+                suggested_rule,
             } => {
                 prepare_for_aux_output(&mut started_content, &mut renderer, &mut status_width)?;
-                let decision = prompt_tool_confirmation(&tool_call, operation)?;
+                let decision = prompt_tool_confirmation(&tool_call, operation, &suggested_rule)?;
+                save_remembered_rule(&decision, &suggested_rule, &app).await;
                 confirm_tx.send(decision).ok();
             }
             AgentEvent::ToolCalls(calls) => {
@@ -420,7 +516,19 @@ async fn stream_agent_message(
                     tool_started_at.insert(call.id.clone(), Instant::now());
                 }
             }
-            AgentEvent::ToolResult { tool_call, result } => {
+            AgentEvent::ToolProgress {
+                tool_call_id,
+                stream,
+                line,
+            } => {
+                clear_status_line(&mut status_width)?;
+                output_tail.push(tool_call_id, stream, line)?;
+            }
+            AgentEvent::ToolResult {
+                tool_call,
+                result,
+                redactions,
+            } => {
                 prepare_for_aux_output(&mut started_content, &mut renderer, &mut status_width)?;
                 tool_results_seen = tool_results_seen.saturating_add(1);
                 phase = if tool_calls_seen == 0 {
@@ -451,6 +559,14 @@ async fn stream_agent_message(
                     );
                 }
                 print_tool_result(tool_call, result);
+                if redactions > 0 {
+                    let noun = if redactions == 1 { "secret" } else { "secrets" };
+                    println!(
+                        "{}",
+                        format!("  -> {redactions} {noun} masked in the copy sent to the model")
+                            .dark_grey()
+                    );
+                }
             }
             AgentEvent::Done => {
                 finalize_stream_output(&mut started_content, &mut renderer, &mut status_width)?;
@@ -498,6 +614,82 @@ async fn stream_agent_message(
     Ok(())
 }
 
+/// The last few output lines of the running tool, redrawn in place under its
+/// timeline entry and erased once the tool reports its result.
+#[derive(Default)]
+struct ToolOutputTail {
+    tool_call_id: String,
+    lines: VecDeque<(ToolOutputStream, String)>,
+    rendered: usize,
+}
+
+impl ToolOutputTail {
+    fn push(
+        &mut self,
+        tool_call_id: String,
+        stream: ToolOutputStream,
+        line: String,
+    ) -> io::Result<()> {
+        if self.tool_call_id != tool_call_id {
+            self.finish()?;
+            self.tool_call_id = tool_call_id;
+        }
+        self.lines.push_back((stream, line));
+        while self.lines.len() > TOOL_OUTPUT_TAIL_LINES {
+            self.lines.pop_front();
+        }
+
+        self.erase()?;
+        let width = terminal::size()
+            .map(|(columns, _)| usize::from(columns))
+            .unwrap_or(100);
+        for (stream, line) in &self.lines {
+            let text = tail_line_text(line, width);
+            match stream {
+                ToolOutputStream::Stdout => print!("{}\r\n", text.dark_grey()),
+                ToolOutputStream::Stderr => print!("{}\r\n", text.dark_red()),
+            }
+        }
+        self.rendered = self.lines.len();
+        io::stdout().flush()
+    }
+
+    fn erase(&mut self) -> io::Result<()> {
+        if self.rendered > 0 {
+            print!("\x1b[{}A\r\x1b[J", self.rendered);
+            io::stdout().flush()?;
+            self.rendered = 0;
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.erase()?;
+        self.lines.clear();
+        self.tool_call_id.clear();
+        Ok(())
+    }
+}
+
+/// One tail row: control characters dropped and cut to the terminal width so
+/// the row never wraps and the in-place redraw stays aligned.
+fn tail_line_text(line: &str, width: usize) -> String {
+    let max_chars = width.saturating_sub(5).max(10);
+    let cleaned = line
+        .replace('\t', "    ")
+        .chars()
+        .filter(|ch| !ch.is_control())
+        .collect::<String>();
+    let mut text = String::from("  │ ");
+    if cleaned.chars().count() > max_chars {
+        text.extend(cleaned.chars().take(max_chars - 1));
+        text.push('…');
+    } else {
+        text.push_str(&cleaned);
+    }
+    text
+}
+
 fn prepare_for_aux_output(
     started_content: &mut bool,
     renderer: &mut MarkdownStreamRenderer,
@@ -586,7 +778,7 @@ fn help_text() -> String {
     append_help_section(&mut output, "Git Commands", CommandGroup::Git);
     output.push_str(
         "\nDirect Commands:\n  ls, pwd, cd, cat, mkdir, touch, echo, grep, find, cp, mv, rm\n\n\
-Input Controls:\n  Up/Down       History (or command suggestion selection)\n  Left/Right    Move cursor\n  Tab           Accept command suggestion\n  Shift+Tab     Toggle auto-edit mode (bypass confirmations)\n  Enter         Submit input (or accept suggestion when / command hints are visible)\n  Ctrl+A/E      Start/end of line\n  Ctrl+U/W      Delete to start / delete previous word\n  Ctrl+C        Clear input (press twice on empty input to exit)\n\n\
+Input Controls:\n  Up/Down       History (or command suggestion selection)\n  Left/Right    Move cursor\n  Tab           Accept command suggestion\n  Shift+Tab     Cycle permission mode (default, accept edits, plan, bypass)\n  Enter         Submit input (or accept suggestion when / command hints are visible)\n  Ctrl+A/E      Start/end of line\n  Ctrl+U/W      Delete to start / delete previous word\n  Ctrl+C        Clear input (press twice on empty input to exit)\n\n\
 Image Input:\n  Drag/drop image paths or include markdown image links (`![alt](path/to/image.png)`)\n  Detected images are attached automatically and listed before submit\n\n\
 Confirmation Controls:\n  y             Approve operation once\n  a             Approve this operation type for session\n  n / Esc       Reject operation\n\n\
 Active Generation Controls:\n  Esc or Ctrl+C Cancel the current generation/tool loop\n\n\
@@ -662,7 +854,10 @@ fn poll_cancel_request() -> Result<bool> {
 
 #[cfg(test)]
 mod tests {
-    use super::{format_bytes, format_elapsed, format_token_count, help_text, is_direct_command};
+    use super::{
+        format_bytes, format_elapsed, format_token_count, help_text, is_direct_command,
+        tail_line_text,
+    };
     use std::time::Duration;
 
     #[test]
@@ -691,6 +886,15 @@ mod tests {
         assert_eq!(format_token_count(1_750_000), "1.8M");
     }
 
+    #[test]
+    fn tail_lines_fit_the_terminal_width() {
+        assert_eq!(tail_line_text("ok\tdone", 80), "  │ ok    done");
+        assert_eq!(tail_line_text("\x1b[32mgreen", 80), "  │ [32mgreen");
+        let long = tail_line_text(&"x".repeat(200), 40);
+        assert_eq!(long.chars().count(), 4 + 35);
+        assert!(long.ends_with("x…"));
+    }
+
     #[test]
     fn byte_formatter_scales_values() {
         assert_eq!(format_bytes(999), "999 B");
@@ -1,4 +1,6 @@
-use crate::slash_commands::filtered_command_suggestions;
+use crate::path_index::{PathIndex, PathMatch};
+use crate::permissions::PermissionMode;
+use crate::slash_commands::{SlashCommand, filtered_command_suggestions};
 use anyhow::Result;
 use crossterm::cursor::{MoveDown, MoveToColumn, MoveUp};
 use crossterm::event::{self, Event as CEvent, KeyCode, KeyEventKind, KeyModifiers};
@@ -7,11 +9,115 @@ use crossterm::style::Stylize;
 use crossterm::terminal::{Clear, ClearType, disable_raw_mode, enable_raw_mode, size};
 use std::io::{self, Write};
 
+const MENTION_SUGGESTION_LIMIT: usize = 6;
+
+/// Autocomplete candidates for the text at the cursor: slash commands while
+/// the input starts with `/`, project paths while the cursor is in an `@` word.
+enum PromptSuggestions {
+    None,
+    Commands(Vec<&'static SlashCommand>),
+    Files {
+        start: usize,
+        end: usize,
+        matches: Vec<PathMatch>,
+    },
+}
+
+impl PromptSuggestions {
+    fn for_input(input: &str, cursor: usize, path_index: &PathIndex) -> Self {
+        if input.starts_with('/') {
+            return Self::Commands(filtered_command_suggestions(input));
+        }
+        let Some((start, end)) = active_mention(input, cursor) else {
+            return Self::None;
+        };
+        let query = &input[start + 1..cursor];
+        let show_hidden = query.starts_with('.') || query.contains("/.");
+        let matches = path_index.find_where(query, MENTION_SUGGESTION_LIMIT, |entry| {
+            show_hidden || !entry.hidden
+        });
+        Self::Files {
+            start,
+            end,
+            matches,
+        }
+    }
+
+    fn len(&self) -> usize {
+        match self {
+            Self::None => 0,
+            Self::Commands(commands) => commands.len(),
+            Self::Files { matches, .. } => matches.len(),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Applies the selected suggestion, returning the new input and cursor, or
+    /// `None` when the input already holds the selected slash command.
+    fn complete(&self, input: &str, selected_index: usize) -> Option<(String, usize)> {
+        let safe = selected_index.min(self.len().checked_sub(1)?);
+        match self {
+            Self::None => None,
+            Self::Commands(commands) => {
+                let selected = commands[safe].command;
+                if input.trim() == selected {
+                    return None;
+                }
+                let completed = format!("{selected} ");
+                let cursor = completed.len();
+                Some((completed, cursor))
+            }
+            Self::Files {
+                start,
+                end,
+                matches,
+            } => {
+                let selected = &matches[safe];
+                let mut mention = format!("@{}", selected.path);
+                if selected.is_dir {
+                    mention.push('/');
+                } else if !input[*end..].starts_with(char::is_whitespace) {
+                    mention.push(' ');
+                }
+                let mut completed = input.to_string();
+                completed.replace_range(*start..*end, &mention);
+                let mut cursor = start + mention.len();
+                if !selected.is_dir && !mention.ends_with(' ') {
+                    cursor = next_boundary(&completed, cursor);
+                }
+                Some((completed, cursor))
+            }
+        }
+    }
+}
+
+/// Finds the `@`-prefixed word under the cursor and returns its byte range.
+fn active_mention(input: &str, cursor: usize) -> Option<(usize, usize)> {
+    let start = input[..cursor]
+        .char_indices()
+        .rev()
+        .find(|(_, ch)| ch.is_whitespace())
+        .map_or(0, |(index, ch)| index + ch.len_utf8());
+    if !input[start..].starts_with('@') {
+        return None;
+    }
+    let end = input[cursor..]
+        .char_indices()
+        .find(|(_, ch)| ch.is_whitespace())
+        .map_or(input.len(), |(index, _)| cursor + index);
+    Some((start, end))
+}
+
 pub fn read_prompt_line(
     history: &[String],
-    auto_edit: &mut bool,
+    permission_mode: &mut PermissionMode,
     current_model: &str,
+    path_index: &mut PathIndex,
 ) -> Result<Option<String>> {
+    path_index.refresh();
     enable_raw_mode()?;
     let mut input = String::new();
     let mut cursor = 0usize;
@@ -23,8 +129,9 @@ pub fn read_prompt_line(
         &input,
         cursor,
         selected_suggestion_idx,
-        *auto_edit,
+        *permission_mode,
         current_model,
+        path_index,
         &mut rendered_panel_lines,
     )?;
 
@@ -39,27 +146,25 @@ pub fn read_prompt_line(
 
         match key.code {
             KeyCode::Enter => {
-                let suggestions = filtered_command_suggestions(&input);
-                if !suggestions.is_empty() {
-                    let safe = selected_suggestion_idx.min(suggestions.len().saturating_sub(1));
-                    let selected = suggestions[safe].command;
-                    let trimmed = input.trim();
-                    if trimmed != selected {
-                        input = format!("{selected} ");
-                        cursor = input.len();
-                        ctrl_c_armed = false;
-                        history_idx = None;
-                        selected_suggestion_idx = 0;
-                        rerender_prompt_input(
-                            &input,
-                            cursor,
-                            selected_suggestion_idx,
-                            *auto_edit,
-                            current_model,
-                            &mut rendered_panel_lines,
-                        )?;
-                        continue;
-                    }
+                let suggestions = PromptSuggestions::for_input(&input, cursor, path_index);
+                if let Some((completed, completed_cursor)) =
+                    suggestions.complete(&input, selected_suggestion_idx)
+                {
+                    input = completed;
+                    cursor = completed_cursor;
+                    ctrl_c_armed = false;
+                    history_idx = None;
+                    selected_suggestion_idx = 0;
+                    rerender_prompt_input(
+                        &input,
+                        cursor,
+                        selected_suggestion_idx,
+                        *permission_mode,
+                        current_model,
+                        path_index,
+                        &mut rendered_panel_lines,
+                    )?;
+                    continue;
                 }
                 clear_prompt_panel(&mut rendered_panel_lines)?;
                 disable_raw_mode()?;
@@ -68,7 +173,7 @@ pub fn read_prompt_line(
                 return Ok(Some(input));
             }
             KeyCode::BackTab => {
-                *auto_edit = !*auto_edit;
+                *permission_mode = permission_mode.next();
                 ctrl_c_armed = false;
             }
             KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
@@ -164,7 +269,7 @@ pub fn read_prompt_line(
                 ctrl_c_armed = false;
             }
             KeyCode::Up => {
-                let suggestions = filtered_command_suggestions(&input);
+                let suggestions = PromptSuggestions::for_input(&input, cursor, path_index);
                 if !suggestions.is_empty() {
                     selected_suggestion_idx = if selected_suggestion_idx == 0 {
                         suggestions.len().saturating_sub(1)
@@ -183,7 +288,7 @@ pub fn read_prompt_line(
                 ctrl_c_armed = false;
             }
             KeyCode::Down => {
-                let suggestions = filtered_command_suggestions(&input);
+                let suggestions = PromptSuggestions::for_input(&input, cursor, path_index);
                 if !suggestions.is_empty() {
                     selected_suggestion_idx = (selected_suggestion_idx + 1) % suggestions.len();
                 } else if !history.is_empty() {
@@ -206,11 +311,12 @@ pub fn read_prompt_line(
                 ctrl_c_armed = false;
             }
             KeyCode::Tab => {
-                let suggestions = filtered_command_suggestions(&input);
-                if !suggestions.is_empty() {
-                    let safe = selected_suggestion_idx.min(suggestions.len().saturating_sub(1));
-                    input = format!("{} ", suggestions[safe].command);
-                    cursor = input.len();
+                let suggestions = PromptSuggestions::for_input(&input, cursor, path_index);
+                if let Some((completed, completed_cursor)) =
+                    suggestions.complete(&input, selected_suggestion_idx)
+                {
+                    input = completed;
+                    cursor = completed_cursor;
                     history_idx = None;
                     selected_suggestion_idx = 0;
                 }
@@ -236,8 +342,9 @@ pub fn read_prompt_line(
             &input,
             cursor,
             selected_suggestion_idx,
-            *auto_edit,
+            *permission_mode,
             current_model,
+            path_index,
             &mut rendered_panel_lines,
         )?;
     }
@@ -339,11 +446,18 @@ fn rerender_prompt_input(
     input: &str,
     cursor: usize,
     selected_suggestion_idx: usize,
-    auto_edit: bool,
+    permission_mode: PermissionMode,
     current_model: &str,
+    path_index: &PathIndex,
     rendered_panel_lines: &mut usize,
 ) -> io::Result<()> {
-    let panel = build_prompt_panel(input, selected_suggestion_idx, auto_edit, current_model);
+    let suggestions = PromptSuggestions::for_input(input, cursor, path_index);
+    let panel = build_prompt_panel(
+        &suggestions,
+        selected_suggestion_idx,
+        permission_mode,
+        current_model,
+    );
     render_prompt_with_suggestions(input, cursor, &panel, rendered_panel_lines)
 }
 
@@ -485,50 +599,78 @@ fn previous_word_start(input: &str, cursor: usize) -> usize {
 }
 
 fn build_prompt_panel(
-    input: &str,
+    suggestions: &PromptSuggestions,
     selected_index: usize,
-    auto_edit: bool,
+    permission_mode: PermissionMode,
     current_model: &str,
 ) -> Vec<String> {
     let status = format!(
-        "{} auto-edit: {} (shift + tab)   ~= {}",
-        if auto_edit { "▶" } else { "⏸" },
-        if auto_edit { "on" } else { "off" },
+        "{} mode: {} (shift + tab)   ~= {}",
+        match permission_mode {
+            PermissionMode::Default => "⏸",
+            PermissionMode::AcceptEdits => "▶",
+            PermissionMode::Plan => "◇",
+            PermissionMode::Bypass => "⏵⏵",
+        },
+        permission_mode.label(),
         current_model
     );
     let mut lines = vec![status];
+    let safe = selected_index.min(suggestions.len().saturating_sub(1));
+    let marker = |idx: usize| if idx == safe { ">" } else { " " };
+
+    match suggestions {
+        PromptSuggestions::None => return lines,
+        PromptSuggestions::Commands(matches) => {
+            if matches.is_empty() {
+                lines.push("slash commands: (no matches)".to_string());
+                return lines;
+            }
 
-    if !input.starts_with('/') {
-        return lines;
-    }
-
-    let matches = filtered_command_suggestions(input);
-    if matches.is_empty() {
-        lines.push("slash commands: (no matches)".to_string());
-        return lines;
-    }
+            lines.push("slash commands:".to_string());
+            let display_limit = 6usize;
+            for (idx, command) in matches.iter().take(display_limit).enumerate() {
+                lines.push(format!(
+                    "  {} {:<18} {}",
+                    marker(idx),
+                    command.command,
+                    command.description
+                ));
+            }
+            if matches.len() > display_limit {
+                lines.push(format!("    ... +{} more", matches.len() - display_limit));
+            }
+            lines.push("    ↑/↓ navigate  Tab autocomplete  Enter run".to_string());
+        }
+        PromptSuggestions::Files { matches, .. } => {
+            if matches.is_empty() {
+                lines.push("files: (no matches)".to_string());
+                return lines;
+            }
 
-    lines.push("slash commands:".to_string());
-    let safe = selected_index.min(matches.len().saturating_sub(1));
-    let display_limit = 6usize;
-    for (idx, command) in matches.iter().take(display_limit).enumerate() {
-        let marker = if idx == safe { ">" } else { " " };
-        lines.push(format!(
-            "  {marker} {:<18} {}",
-            command.command, command.description
-        ));
-    }
-    if matches.len() > display_limit {
-        lines.push(format!("    ... +{} more", matches.len() - display_limit));
+            lines.push("files:".to_string());
+            for (idx, found) in matches.iter().enumerate() {
+                let suffix = if found.is_dir { "/" } else { "" };
+                lines.push(format!("  {} {}{suffix}", marker(idx), found.path));
+            }
+            lines.push("    ↑/↓ navigate  Tab/Enter insert path".to_string());
+        }
     }
-    lines.push("    ↑/↓ navigate  Tab autocomplete  Enter run".to_string());
 
     lines
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{build_prompt_panel, next_boundary, prev_boundary, previous_word_start};
+    use super::{
+        PromptSuggestions, active_mention, build_prompt_panel, next_boundary, prev_boundary,
+        previous_word_start,
+    };
+    use crate::path_index::PathIndex;
+    use crate::permissions::PermissionMode;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+    use std::time::{SystemTime, UNIX_EPOCH};
 
     #[test]
     fn boundaries_handle_utf8_characters() {
@@ -552,17 +694,97 @@ mod tests {
 
     #[test]
     fn prompt_panel_without_slash_only_contains_status_line() {
-        let panel = build_prompt_panel("hello", 0, false, "grok-code-fast-1");
+        let panel = build_prompt_panel(
+            &suggestions_for("hello"),
+            0,
+            PermissionMode::Default,
+            "grok-code-fast-1",
+        );
         assert_eq!(panel.len(), 1);
-        assert!(panel[0].contains("auto-edit: off"));
+        assert!(panel[0].contains("mode: default"));
         assert!(panel[0].contains("grok-code-fast-1"));
     }
 
     #[test]
     fn prompt_panel_with_slash_includes_commands_section() {
-        let panel = build_prompt_panel("/", 0, true, "grok-4");
+        let panel = build_prompt_panel(
+            &suggestions_for("/"),
+            0,
+            PermissionMode::AcceptEdits,
+            "grok-4",
+        );
         assert!(panel.len() >= 3);
-        assert!(panel[0].contains("auto-edit: on"));
+        assert!(panel[0].contains("mode: accept edits"));
         assert!(panel.iter().any(|line| line.contains("slash commands:")));
     }
+
+    #[test]
+    fn active_mention_finds_at_word_under_cursor() {
+        let input = "explain @src/ma and more";
+        assert_eq!(active_mention(input, 15), Some((8, 15)));
+        assert_eq!(active_mention(input, 10), Some((8, 15)));
+        assert_eq!(active_mention(input, 5), None);
+        assert_eq!(active_mention(input, input.len()), None);
+        assert_eq!(active_mention("user@host", 9), None);
+    }
+
+    #[test]
+    fn file_mentions_suggest_and_complete_paths() {
+        let temp = TempDir::new("prompt-mentions");
+        fs::create_dir_all(temp.path().join("src")).expect("create src");
+        fs::write(temp.path().join("src/main.rs"), "").expect("write");
+        fs::write(temp.path().join(".env"), "").expect("write");
+        let mut index = PathIndex::new(temp.path().to_path_buf());
+        index.refresh();
+
+        let input = "look at @mainrs please";
+        let suggestions = PromptSuggestions::for_input(input, 15, &index);
+        let panel = build_prompt_panel(&suggestions, 0, PermissionMode::Default, "grok-4");
+        assert!(panel.iter().any(|line| line == "files:"));
+        assert!(panel.iter().any(|line| line == "  > src/main.rs"));
+        let (completed, cursor) = suggestions.complete(input, 0).expect("completion");
+        assert_eq!(completed, "look at @src/main.rs please");
+        assert_eq!(&completed[..cursor], "look at @src/main.rs ");
+
+        let suggestions = PromptSuggestions::for_input("@sr", 3, &index);
+        let (completed, cursor) = suggestions.complete("@sr", 0).expect("completion");
+        assert_eq!(completed, "@src/");
+        assert_eq!(cursor, completed.len());
+
+        let hidden = PromptSuggestions::for_input("@env", 4, &index);
+        assert!(hidden.is_empty());
+        let hidden = PromptSuggestions::for_input("@.env", 5, &index);
+        assert_eq!(hidden.len(), 1);
+    }
+
+    fn suggestions_for(input: &str) -> PromptSuggestions {
+        PromptSuggestions::for_input(input, input.len(), &PathIndex::new(PathBuf::new()))
+    }
+
+    struct TempDir {
+        path: PathBuf,
+    }
+
+    impl TempDir {
+        fn new(prefix: &str) -> Self {
+            let nonce = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .expect("clock after epoch")
+                .as_nanos();
+            let pid = std::process::id();
+            let path = std::env::temp_dir().join(format!("codetoad-{prefix}-{pid}-{nonce}"));
+            fs::create_dir_all(&path).expect("create temp dir");
+            Self { path }
+        }
+
+        fn path(&self) -> &Path {
+            &self.path
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.path);
+        }
+    }
 }
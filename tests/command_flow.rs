@@ -103,7 +103,7 @@ async fn streaming_flow_emits_confirmation_then_rejection_result_then_done() {
                     _type: Some("function".to_string()),
                     function: Some(ChatCompletionToolCallFunctionDelta {
                         name: Some("bash".to_string()),
-                        arguments: Some("{\"command\":\"echo hi\"}".to_string()),
+                        arguments: Some("{\"command\":\"touch hi\"}".to_string()),
                     }),
                 }]),
             },
@@ -144,9 +144,11 @@ async fn streaming_flow_emits_confirmation_then_rejection_result_then_done() {
         if let AgentEvent::ConfirmationRequest {
             tool_call,
             operation,
+            suggested_rule,
         } = &event
         {
-            assert_eq!(*operation, ConfirmationOperation::Bash);
+            assert_eq!(*operation, Some(ConfirmationOperation::Bash));
+            assert_eq!(suggested_rule.to_string(), "bash(touch hi:*)");
             confirm_tx
                 .send(ConfirmationDecision::Reject {
                     tool_call_id: tool_call.id.clone(),
@@ -225,6 +227,10 @@ fn slash_command_parser_and_help_are_consistent() {
         parse_slash_command("/resume"),
         Some(ParsedSlashCommand::Resume)
     ));
+    assert!(matches!(
+        parse_slash_command("/ps"),
+        Some(ParsedSlashCommand::Processes)
+    ));
     assert!(matches!(
         parse_slash_command("/providers"),
         Some(ParsedSlashCommand::Providers)
@@ -248,6 +254,7 @@ fn slash_command_parser_and_help_are_consistent() {
     assert!(built_in.contains("/clear"));
     assert!(built_in.contains("/models"));
     assert!(built_in.contains("/exit"));
+    assert!(built_in.contains("/ps"));
 
     let mut providers = String::new();
     append_help_section(&mut providers, "Provider Commands", CommandGroup::Provider);